@@ -163,7 +163,8 @@ cargo build --release \
   --bin zenbook-duo-daemon \
   --bin zenbook-duo-session-agent \
   --bin zenbook-duo-lifecycle \
-  --bin zenbook-duo-usb-remap-helper
+  --bin zenbook-duo-usb-remap-helper \
+  --bin zenbook-duoctl
 
 echo "Installing Rust runtime binaries..."
 sudo mkdir -p "${RUNTIME_INSTALL_DIR}"
@@ -179,13 +180,18 @@ sudo install -m 0755 \
 sudo install -m 0755 \
   "${TAURI_DIR}/target/release/zenbook-duo-usb-remap-helper" \
   "${RUNTIME_INSTALL_DIR}/zenbook-duo-usb-remap-helper"
+sudo install -m 0755 \
+  "${TAURI_DIR}/target/release/zenbook-duoctl" \
+  "${RUNTIME_INSTALL_DIR}/zenbook-duoctl"
+sudo ln -sfn "${RUNTIME_INSTALL_DIR}/zenbook-duoctl" /usr/local/bin/zenbook-duoctl
 
 echo "Installed Rust runtime versions:"
 for binary in \
   zenbook-duo-daemon \
   zenbook-duo-session-agent \
   zenbook-duo-lifecycle \
-  zenbook-duo-usb-remap-helper; do
+  zenbook-duo-usb-remap-helper \
+  zenbook-duoctl; do
   "${RUNTIME_INSTALL_DIR}/${binary}" --version || true
 done
 
@@ -248,6 +254,15 @@ Environment=DBUS_SESSION_BUS_ADDRESS=unix:path=%t/bus
 WantedBy=default.target
 EOF
 
+echo "Installing D-Bus service policy..."
+sudo install -D -m 0644 \
+  "${TAURI_DIR}/linux/org.zenbook.Duo1.conf" \
+  /usr/share/dbus-1/system.d/org.zenbook.Duo1.conf
+sudo install -D -m 0644 \
+  "${TAURI_DIR}/linux/org.zenbook.Duo1.policy" \
+  /usr/share/polkit-1/actions/org.zenbook.Duo1.policy
+sudo systemctl reload dbus.service 2>/dev/null || true
+
 sudo mkdir -p "${SYSTEM_SLEEP_HOOK_DIR}"
 sudo rm -f "${SYSTEM_SLEEP_HOOK_PATH}"
 sudo ln -sfn "${RUNTIME_INSTALL_DIR}/zenbook-duo-lifecycle" "${SYSTEM_SLEEP_HOOK_PATH}"
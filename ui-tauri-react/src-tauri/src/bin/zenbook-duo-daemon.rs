@@ -3,7 +3,14 @@ async fn main() {
     zenbook_duo_control_lib::runtime::version::print_and_exit_if_requested("zenbook-duo-daemon");
     env_logger::init();
 
-    if let Err(err) = zenbook_duo_control_lib::runtime::daemon::run().await {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.split_first() {
+        Some((flag, rest)) if flag == "--simulate" => {
+            zenbook_duo_control_lib::runtime::simulation::run(rest).await
+        }
+        _ => zenbook_duo_control_lib::runtime::daemon::run().await,
+    };
+    if let Err(err) = result {
         eprintln!("{err}");
         std::process::exit(1);
     }
@@ -0,0 +1,6 @@
+fn main() {
+    zenbook_duo_control_lib::runtime::version::print_and_exit_if_requested("zenbook-duoctl");
+    env_logger::init();
+
+    std::process::exit(zenbook_duo_control_lib::duoctl::run_from_env());
+}
@@ -1,13 +1,39 @@
-use crate::hardware::sysfs;
 use crate::ipc::protocol::{DaemonRequest, DaemonResponse};
+use crate::models::{LogQuery, LogRecord};
 use crate::runtime::client;
-use crate::runtime::logger;
+use crate::runtime::{daemon, logger};
 
 #[tauri::command]
 pub fn read_log(lines: usize) -> Vec<String> {
+    read_log_records(lines)
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Newest log records, oldest first. Falls back to reading the log files
+/// directly when the daemon is unavailable.
+pub fn read_log_records(lines: usize) -> Vec<LogRecord> {
     match client::request(DaemonRequest::TailLogs { lines }) {
-        Ok(DaemonResponse::Logs { lines }) => lines,
-        _ => sysfs::read_log_lines(lines),
+        Ok(DaemonResponse::LogRecords { records }) => records,
+        Ok(DaemonResponse::Logs { lines }) => lines
+            .iter()
+            .filter_map(|line| LogRecord::parse_line(line))
+            .collect(),
+        _ => logger::tail(lines),
+    }
+}
+
+/// Filtered search over the daemon, session agent and USB remap logs.
+#[tauri::command]
+pub fn query_logs(query: LogQuery) -> Result<Vec<LogRecord>, String> {
+    match client::request(DaemonRequest::query_logs(query.clone(), false)) {
+        Ok(DaemonResponse::LogRecords { records }) => Ok(records),
+        Ok(DaemonResponse::Error { message }) => Err(message),
+        Ok(other) => Err(format!(
+            "Unexpected daemon response while querying logs: {other:?}"
+        )),
+        Err(_) => logger::query(&daemon::log_sinks(), &query.compile()?),
     }
 }
 
@@ -16,7 +42,6 @@ pub fn clear_log() -> Result<(), String> {
     match client::request(DaemonRequest::ClearLogs) {
         Ok(DaemonResponse::Ack) => Ok(()),
         Ok(DaemonResponse::Error { message }) => Err(message),
-        Ok(_) => logger::clear().or_else(|_| sysfs::clear_log()),
-        Err(_) => logger::clear().or_else(|_| sysfs::clear_log()),
+        Ok(_) | Err(_) => logger::clear(),
     }
 }
@@ -4,6 +4,7 @@ use crate::hardware::display_layout;
 use crate::ipc::protocol::{DaemonRequest, DaemonResponse};
 use crate::models::{Profile, ProfileList};
 use crate::runtime::client;
+use crate::runtime::schema::{self, Document};
 
 fn profiles_path() -> PathBuf {
     let config_dir = crate::commands::settings::config_base_dir().join("zenbook-duo");
@@ -16,14 +17,19 @@ fn load_profile_list() -> ProfileList {
     fs::read_to_string(&path)
         .ok()
         .and_then(|s| serde_json::from_str(&s).ok())
+        .and_then(|value| schema::upgrade(Document::Profiles, value).ok())
+        .and_then(|upgraded| serde_json::from_value(upgraded.value).ok())
         .unwrap_or_else(|| ProfileList {
             profiles: Profile::default_profiles(),
+            ..ProfileList::default()
         })
 }
 
 fn save_profile_list(list: &ProfileList) -> Result<(), String> {
     let path = profiles_path();
-    let json = serde_json::to_string_pretty(list).map_err(|e| format!("Serialize error: {e}"))?;
+    let value = serde_json::to_value(list).map_err(|e| format!("Serialize error: {e}"))?;
+    schema::guard_overwrite(Document::Profiles, &path, &value)?;
+    let json = serde_json::to_string_pretty(&value).map_err(|e| format!("Serialize error: {e}"))?;
     fs::write(&path, json).map_err(|e| format!("Write error: {e}"))
 }
 
@@ -63,15 +69,17 @@ fn daemon_response_result(
     }
 }
 
-#[tauri::command]
-pub fn activate_profile(id: String) -> Result<(), String> {
-    let list = load_profile_list();
-    let profile = list
+pub(crate) fn find_profile(id: &str) -> Result<Profile, String> {
+    load_profile_list()
         .profiles
-        .iter()
+        .into_iter()
         .find(|p| p.id == id)
-        .ok_or_else(|| format!("Profile '{id}' not found"))?
-        .clone();
+        .ok_or_else(|| format!("Profile '{id}' not found"))
+}
+
+#[tauri::command]
+pub fn activate_profile(id: String) -> Result<(), String> {
+    let profile = find_profile(&id)?;
 
     daemon_response_result(
         client::request(DaemonRequest::SetBacklight {
@@ -190,6 +198,28 @@ mod tests {
         assert!(profiles.iter().any(|profile| profile.id == "presentation"));
     }
 
+    #[test]
+    fn unversioned_profiles_file_is_migrated_and_saved_with_version() {
+        let _guard = crate::commands::settings::test_env_lock()
+            .lock()
+            .expect("profiles env lock");
+        let _home = TestHome::new();
+        let legacy = serde_json::json!({ "profiles": [test_profile("legacy")] });
+        fs::write(profiles_path(), legacy.to_string()).expect("write legacy profiles");
+
+        save_profile(test_profile("custom")).expect("save profile");
+
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(profiles_path()).expect("read profiles"))
+                .expect("parse profiles");
+        assert_eq!(saved["schemaVersion"], serde_json::json!(1));
+        let ids: Vec<_> = list_profiles()
+            .into_iter()
+            .map(|profile| profile.id)
+            .collect();
+        assert_eq!(ids, vec!["legacy", "custom"]);
+    }
+
     #[test]
     fn profile_activation_surfaces_daemon_errors_and_unexpected_responses() {
         let daemon_error = daemon_response_result(
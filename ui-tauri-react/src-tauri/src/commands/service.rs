@@ -1,8 +1,8 @@
 use std::process::Command;
 
 use crate::hardware::sysfs;
-use crate::ipc::protocol::{DaemonRequest, DaemonResponse, PROTOCOL_VERSION};
-use crate::models::VersionInfo;
+use crate::ipc::protocol::{parse_protocol_mismatch, DaemonRequest, DaemonResponse, PROTOCOL_VERSION};
+use crate::models::{MetricsSnapshot, VersionInfo};
 use crate::runtime::client;
 
 const LEGACY_DAEMON_RESTART_ERROR: &str = "Service restart not yet owned by rust-daemon";
@@ -19,11 +19,17 @@ pub fn is_service_active() -> bool {
 pub fn get_version_info() -> VersionInfo {
     let app_version = env!("CARGO_PKG_VERSION");
 
+    // Daemons that predate the handshake reject `Hello`; the plain
+    // `GetVersion` path below still describes them.
+    if let Ok(handshake) = client::hello() {
+        return VersionInfo::from_handshake(app_version, PROTOCOL_VERSION, handshake);
+    }
+
     match client::request(DaemonRequest::GetVersion) {
         Ok(DaemonResponse::Version { version }) => {
             VersionInfo::from_daemon(app_version, PROTOCOL_VERSION, version)
         }
-        Ok(DaemonResponse::Error { message }) => parse_daemon_protocol_version(&message)
+        Ok(DaemonResponse::Error { message }) => parse_protocol_mismatch(&message)
             .map(|daemon_protocol_version| {
                 VersionInfo::protocol_mismatch(
                     app_version,
@@ -36,13 +42,17 @@ pub fn get_version_info() -> VersionInfo {
     }
 }
 
-fn parse_daemon_protocol_version(message: &str) -> Option<u32> {
-    message
-        .strip_prefix("Protocol mismatch: expected ")?
-        .split_once(',')?
-        .0
-        .parse()
-        .ok()
+/// Runtime counters and latency histograms. Only the daemon collects them, so
+/// there is no local fallback.
+#[tauri::command]
+pub fn get_metrics() -> Result<MetricsSnapshot, String> {
+    match client::request(DaemonRequest::GetMetrics)? {
+        DaemonResponse::Metrics { metrics } => Ok(metrics),
+        DaemonResponse::Error { message } => Err(message),
+        other => Err(format!(
+            "Unexpected daemon response while reading metrics: {other:?}"
+        )),
+    }
 }
 
 #[tauri::command]
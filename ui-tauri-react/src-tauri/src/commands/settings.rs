@@ -5,6 +5,7 @@ use std::path::PathBuf;
 use crate::ipc::protocol::{DaemonRequest, DaemonResponse};
 use crate::models::DuoSettings;
 use crate::runtime::client;
+use crate::runtime::schema::{self, Document};
 
 const AUTOSTART_FILE_NAME: &str = "zenbook-duo-control.desktop";
 
@@ -91,24 +92,21 @@ pub fn load_settings_local() -> DuoSettings {
         Err(_) => return DuoSettings::default(), // no settings file => show setup
     };
 
-    // Merge defaults + file contents.
-    // Important behavior: when upgrading an existing install, we don't want to force the setup
-    // screen to appear just because `setupCompleted` is a new field.
-    let mut settings: DuoSettings = serde_json::from_str(&raw).unwrap_or_default();
-
-    if let Ok(v) = serde_json::from_str::<serde_json::Value>(&raw) {
-        if v.get("setupCompleted").is_none() {
-            settings.setup_completed = true;
-        }
-    }
-
-    settings
+    // Merge defaults + file contents after bringing older files up to the
+    // current schema. Files from a newer release are read as far as we
+    // understand them; `save_settings_local` refuses to drop the rest.
+    serde_json::from_str(&raw)
+        .map_err(|e| e.to_string())
+        .and_then(|value| schema::upgrade(Document::Settings, value))
+        .and_then(|upgraded| serde_json::from_value(upgraded.value).map_err(|e| e.to_string()))
+        .unwrap_or_default()
 }
 
 pub fn save_settings_local(settings: DuoSettings) -> Result<(), String> {
     let path = settings_path();
-    let json =
-        serde_json::to_string_pretty(&settings).map_err(|e| format!("Serialize error: {e}"))?;
+    let value = serde_json::to_value(&settings).map_err(|e| format!("Serialize error: {e}"))?;
+    schema::guard_overwrite(Document::Settings, &path, &value)?;
+    let json = serde_json::to_string_pretty(&value).map_err(|e| format!("Serialize error: {e}"))?;
     fs::write(&path, json).map_err(|e| format!("Write error: {e}"))
 }
 
@@ -235,4 +233,38 @@ mod tests {
         assert!(loaded.start_on_boot_minimized);
         assert!(loaded.invert_sensor_rotation);
     }
+
+    #[test]
+    fn legacy_settings_file_is_migrated_without_reopening_setup() {
+        let _guard = test_env_lock().lock().expect("settings env lock");
+        let _home = TestHome::new();
+        fs::write(settings_path(), r#"{"defaultBacklight": 2}"#).expect("write legacy settings");
+
+        let loaded = load_settings_local();
+
+        assert!(loaded.setup_completed);
+        assert_eq!(loaded.default_backlight, 2);
+        assert_eq!(
+            loaded.schema_version,
+            crate::models::SETTINGS_SCHEMA_VERSION
+        );
+    }
+
+    #[test]
+    fn saving_over_newer_settings_refuses_to_drop_their_fields() {
+        let _guard = test_env_lock().lock().expect("settings env lock");
+        let _home = TestHome::new();
+        let newer = r#"{"schemaVersion": 99, "defaultBacklight": 1, "accentColor": "teal"}"#;
+        fs::write(settings_path(), newer).expect("write newer settings");
+
+        let loaded = load_settings_local();
+        assert_eq!(loaded.default_backlight, 1);
+
+        let error = save_settings_local(loaded).expect_err("save must be refused");
+        assert!(error.contains("accentColor"));
+        assert_eq!(
+            fs::read_to_string(settings_path()).expect("read settings"),
+            newer
+        );
+    }
 }
@@ -1,6 +1,6 @@
 use crate::hardware::sysfs;
 use crate::ipc::protocol::{DaemonRequest, DaemonResponse};
-use crate::models::DuoStatus;
+use crate::models::{DuoStatus, PostureReport};
 use crate::runtime::client;
 
 #[tauri::command]
@@ -12,3 +12,15 @@ pub fn get_status() -> Result<DuoStatus, String> {
         Err(_) => Ok(sysfs::get_full_status()),
     }
 }
+
+/// Posture tracking lives in the daemon, so there is no local fallback.
+#[tauri::command]
+pub fn get_posture() -> Result<PostureReport, String> {
+    match client::request(DaemonRequest::GetPosture)? {
+        DaemonResponse::Posture { posture } => Ok(posture),
+        DaemonResponse::Error { message } => Err(message),
+        other => Err(format!(
+            "Unexpected daemon response while reading posture: {other:?}"
+        )),
+    }
+}
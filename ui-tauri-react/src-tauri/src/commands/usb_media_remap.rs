@@ -1,14 +1,14 @@
-use chrono::Local;
 use serde::{Deserialize, Serialize};
-use std::fs::{self, OpenOptions};
-use std::io::Write;
+use std::fs;
 use std::process::Command;
 use std::time::Duration;
 
 use nix::unistd::{Uid, User};
 
 use crate::ipc::protocol::{DaemonRequest, DaemonResponse};
+use crate::models::{LogLevel, LogRecord, ProtocolFeature};
 use crate::runtime::client;
+use crate::runtime::logger::LogSink;
 use crate::runtime::paths;
 
 const HELPER_BINARY_NAME: &str = "zenbook-duo-usb-remap-helper";
@@ -222,12 +222,23 @@ pub fn pause_file_path() -> String {
 }
 
 pub fn toggle_pause() -> Result<(), String> {
+    let paused = std::path::Path::new(&pause_file_path()).exists();
+    set_paused(!paused)
+}
+
+/// Creates or removes the pause file; already being in the requested state
+/// is not an error.
+pub fn set_paused(paused: bool) -> Result<(), String> {
     let path = pause_file_path();
-    if std::path::Path::new(&path).exists() {
-        fs::remove_file(&path).map_err(|e| format!("Failed to remove pause file: {e}"))?;
-    } else {
+    if paused {
         ensure_duo_dir_for_pid(&pid_path())?;
         fs::write(&path, "").map_err(|e| format!("Failed to create pause file: {e}"))?;
+    } else {
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to remove pause file: {e}")),
+        }
     }
     Ok(())
 }
@@ -295,6 +306,52 @@ pub fn daemon_first_toggle_pause() -> Result<(), String> {
     result
 }
 
+pub fn daemon_first_set_paused(paused: bool) -> Result<(), String> {
+    let result = match client::request(DaemonRequest::UsbMediaRemapSetPaused { paused }) {
+        Ok(DaemonResponse::Ack) => Ok(()),
+        // Daemons older than the explicit request only understand the toggle.
+        Ok(DaemonResponse::Error { .. })
+            if !client::daemon_supports(ProtocolFeature::RemapPause) =>
+        {
+            toggle_pause_to(paused)
+        }
+        Ok(DaemonResponse::Error { message }) => Err(message),
+        Ok(_) => Err("Unexpected daemon response while setting USB remap pause".into()),
+        Err(_) => set_paused(paused),
+    };
+
+    if result.is_ok() {
+        let msg = if paused {
+            "USB Media Remap paused"
+        } else {
+            "USB Media Remap resumed"
+        };
+        let _ = send_desktop_notification(msg);
+    }
+
+    result
+}
+
+/// Reaches `paused` through the shared toggle and re-reads the status, so a
+/// concurrent toggle is reported instead of silently leaving the wrong state.
+fn toggle_pause_to(paused: bool) -> Result<(), String> {
+    if daemon_first_status().paused != paused {
+        match client::request(DaemonRequest::UsbMediaRemapTogglePause) {
+            Ok(DaemonResponse::Ack) => {}
+            Ok(DaemonResponse::Error { message }) => return Err(message),
+            Ok(_) => return Err("Unexpected daemon response while toggling USB remap pause".into()),
+            Err(e) => return Err(e),
+        }
+    }
+    if daemon_first_status().paused != paused {
+        let wanted = if paused { "paused" } else { "resumed" };
+        return Err(format!(
+            "USB remap was not left {wanted}: its pause state changed concurrently"
+        ));
+    }
+    Ok(())
+}
+
 fn running_pid_files() -> Vec<String> {
     let p1 = pid_path();
     if get_status().running {
@@ -311,12 +368,7 @@ fn target_uid() -> u32 {
     std::env::var("SUDO_UID")
         .ok()
         .and_then(|value| value.parse::<u32>().ok())
-        .or_else(|| {
-            std::env::var("ZENBOOK_DUO_UID")
-                .ok()
-                .and_then(|value| value.parse::<u32>().ok())
-        })
-        .unwrap_or_else(|| Uid::current().as_raw())
+        .unwrap_or_else(paths::session_owner_uid)
 }
 
 fn send_desktop_notification(message: &str) -> Result<(), String> {
@@ -424,21 +476,17 @@ fn ensure_duo_dir_for_pid(pid_file: &str) -> Result<(), String> {
     crate::runtime::runtime_dir::ensure_dir_owned_like_parent(dir)
 }
 
-fn log_error<T: Into<String>>(message: T) -> String {
-    let message = message.into();
-    let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S");
-    let log_path = std::path::Path::new(&pid_path())
+/// The helper logs next to its pid file.
+pub fn log_path() -> std::path::PathBuf {
+    std::path::Path::new(&pid_path())
         .parent()
         .map(|p| p.join("duo.log"))
-        .unwrap_or_else(|| std::env::temp_dir().join("zenbook-duo-usb-remap.log"));
-
-    let _ = fs::create_dir_all(
-        log_path
-            .parent()
-            .unwrap_or_else(|| std::path::Path::new("/tmp")),
-    );
-    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(log_path) {
-        let _ = writeln!(file, "{} - USB-REMAP - ERROR: {}", timestamp, message);
-    }
+        .unwrap_or_else(|| std::env::temp_dir().join("zenbook-duo-usb-remap.log"))
+}
+
+fn log_error<T: Into<String>>(message: T) -> String {
+    let message = message.into();
+    let record = LogRecord::new(LogLevel::Error, "usb-remap", message.as_str());
+    let _ = LogSink::at(log_path()).write(&record);
     message
 }
@@ -0,0 +1,934 @@
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+
+use crate::commands::{backlight, display, logs, service, status, touchscreen, usb_media_remap};
+use crate::ipc::protocol::{DaemonEvent, DaemonRequest, DaemonResponse, SubscriptionCategory};
+use crate::models::{
+    ConnectionType, DisplayLayout, DuoStatus, EventCategory, EventQuery, EventSeverity,
+    HardwareEvent, LogLevel, LogQuery, LogRecord, Orientation,
+};
+use crate::runtime::client;
+
+pub const EXIT_OK: i32 = 0;
+/// The daemon or the hardware fallback reported an error.
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+/// The daemon is unreachable and the command has no local fallback.
+pub const EXIT_UNAVAILABLE: i32 = 3;
+/// The daemon refused the request for the caller's tier.
+pub const EXIT_DENIED: i32 = 4;
+
+const DEFAULT_EVENT_LIMIT: usize = 20;
+const DEFAULT_LOG_LINES: usize = 50;
+
+const USAGE: &str = "\
+usage: zenbook-duoctl [--json] <command>
+
+commands:
+  status                              show keyboard, display and radio state
+  version                             show client and daemon versions
+  backlight get                       show the keyboard backlight level
+  backlight set <0-3>                 set the keyboard backlight level
+  layout get                          show the display layout (JSON with --json)
+  layout apply <FILE|->               apply a display layout from JSON
+  rotate <normal|left|right|inverted> rotate the displays
+  touchscreen list                    list touchscreens and their connectors
+  touchscreen <enable|disable> <CONNECTOR>
+  remap <status|start|stop|pause|resume>
+                                      control the USB media-key remap helper
+  events [--limit N] [--follow]       show recent hardware events
+         [--since TIME] [--until TIME]
+         [--category C]... [--severity S]
+                                      search the event journal; TIME is
+                                      RFC 3339 or YYYY-MM-DD (local time)
+  logs [--tail N] [--follow]          show the last log lines
+       [--since TIME] [--until TIME]
+       [--component C]... [--level L]
+       [--grep TEXT] [--regex RE]
+                                      search the daemon, session agent
+                                      and USB remap logs
+  logs clear                          clear the daemon log
+  metrics                             show runtime counters and latency
+                                      histograms (Prometheus text format)
+  posture                             show the device posture and how it
+                                      got there
+
+exit codes: 0 ok, 1 failed, 2 usage, 3 daemon unavailable, 4 permission denied";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    Help,
+    Status,
+    Version,
+    BacklightGet,
+    BacklightSet(u8),
+    LayoutGet,
+    LayoutApply(PathBuf),
+    Rotate(Orientation),
+    TouchscreenList,
+    TouchscreenSet { connector: String, enabled: bool },
+    Remap(RemapAction),
+    Events { query: EventQuery, follow: bool },
+    Logs { query: LogQuery, follow: bool },
+    LogsClear,
+    Metrics,
+    Posture,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RemapAction {
+    Status,
+    Start,
+    Stop,
+    Pause,
+    Resume,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Invocation {
+    command: Command,
+    json: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CliError {
+    Usage(String),
+    Unavailable(String),
+    Denied(String),
+    Failed(String),
+}
+
+impl CliError {
+    /// Sorts an error string from the daemon-first helpers into an exit code.
+    fn from_message(message: String) -> Self {
+        if message.starts_with("Permission denied") || message.starts_with("Not authorized") {
+            CliError::Denied(message)
+        } else if message.starts_with("Failed to connect to daemon socket") {
+            CliError::Unavailable(message)
+        } else {
+            CliError::Failed(message)
+        }
+    }
+
+    fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => EXIT_USAGE,
+            CliError::Failed(_) => EXIT_FAILURE,
+            CliError::Unavailable(_) => EXIT_UNAVAILABLE,
+            CliError::Denied(_) => EXIT_DENIED,
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            CliError::Usage(message)
+            | CliError::Unavailable(message)
+            | CliError::Denied(message)
+            | CliError::Failed(message) => message,
+        }
+    }
+}
+
+pub fn run_from_env() -> i32 {
+    run_with_args(env::args().skip(1))
+}
+
+/// Runs one command and returns the process exit code.
+pub fn run_with_args<I>(args: I) -> i32
+where
+    I: Iterator<Item = String>,
+{
+    let invocation = match parse_args(args) {
+        Ok(invocation) => invocation,
+        Err(err) => {
+            eprintln!("{}\n\n{USAGE}", err.message());
+            return err.exit_code();
+        }
+    };
+
+    match execute(&invocation) {
+        Ok(()) => EXIT_OK,
+        Err(err) => {
+            if invocation.json {
+                let body = serde_json::json!({ "error": err.message() });
+                eprintln!("{body}");
+            } else {
+                eprintln!("zenbook-duoctl: {}", err.message());
+            }
+            err.exit_code()
+        }
+    }
+}
+
+fn parse_args<I>(args: I) -> Result<Invocation, CliError>
+where
+    I: Iterator<Item = String>,
+{
+    let mut json = false;
+    let mut words = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "-h" | "--help" => {
+                return Ok(Invocation {
+                    command: Command::Help,
+                    json,
+                })
+            }
+            _ => words.push(arg),
+        }
+    }
+
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let command = match words.as_slice() {
+        [] | ["help"] => Command::Help,
+        ["status"] => Command::Status,
+        ["version"] => Command::Version,
+        ["backlight"] | ["backlight", "get"] => Command::BacklightGet,
+        ["backlight", "set", level] => Command::BacklightSet(parse_backlight_level(level)?),
+        ["layout"] | ["layout", "get"] => Command::LayoutGet,
+        ["layout", "apply", path] => Command::LayoutApply(PathBuf::from(path)),
+        ["rotate", orientation] => Command::Rotate(parse_orientation(orientation)?),
+        ["touchscreen"] | ["touchscreen", "list"] => Command::TouchscreenList,
+        ["touchscreen", action @ ("enable" | "disable"), connector] => Command::TouchscreenSet {
+            connector: connector.to_string(),
+            enabled: *action == "enable",
+        },
+        ["remap", action] => Command::Remap(parse_remap_action(action)?),
+        ["events", rest @ ..] => parse_events(rest)?,
+        ["logs", "clear"] => Command::LogsClear,
+        ["logs", rest @ ..] => parse_logs(rest)?,
+        ["metrics"] => Command::Metrics,
+        ["posture"] => Command::Posture,
+        _ => {
+            return Err(CliError::Usage(format!(
+                "unknown command: {}",
+                words.join(" ")
+            )))
+        }
+    };
+
+    Ok(Invocation { command, json })
+}
+
+fn parse_backlight_level(raw: &str) -> Result<u8, CliError> {
+    match raw.parse::<u8>() {
+        Ok(level) if level <= 3 => Ok(level),
+        _ => Err(CliError::Usage(format!(
+            "backlight level must be 0-3, got {raw}"
+        ))),
+    }
+}
+
+fn parse_orientation(raw: &str) -> Result<Orientation, CliError> {
+    match raw {
+        "normal" => Ok(Orientation::Normal),
+        "left" => Ok(Orientation::Left),
+        "right" => Ok(Orientation::Right),
+        "inverted" => Ok(Orientation::Inverted),
+        _ => Err(CliError::Usage(format!("unsupported orientation: {raw}"))),
+    }
+}
+
+fn parse_remap_action(raw: &str) -> Result<RemapAction, CliError> {
+    match raw {
+        "status" => Ok(RemapAction::Status),
+        "start" => Ok(RemapAction::Start),
+        "stop" => Ok(RemapAction::Stop),
+        "pause" => Ok(RemapAction::Pause),
+        "resume" => Ok(RemapAction::Resume),
+        _ => Err(CliError::Usage(format!("unsupported remap action: {raw}"))),
+    }
+}
+
+fn parse_events(rest: &[&str]) -> Result<Command, CliError> {
+    let mut query = EventQuery {
+        limit: Some(DEFAULT_EVENT_LIMIT),
+        ..EventQuery::default()
+    };
+    let mut follow = false;
+    let mut rest = rest.iter();
+    while let Some(arg) = rest.next() {
+        match *arg {
+            "--follow" | "-f" => follow = true,
+            "--limit" | "-n" => query.limit = Some(parse_count(rest.next(), "--limit")?),
+            "--since" => query.since = Some(parse_time(rest.next(), "--since")?),
+            "--until" => query.until = Some(parse_time(rest.next(), "--until")?),
+            "--category" | "-c" => query.categories.push(parse_category(rest.next())?),
+            "--severity" | "-s" => query.min_severity = Some(parse_severity(rest.next())?),
+            other => {
+                return Err(CliError::Usage(format!(
+                    "unexpected events argument: {other}"
+                )))
+            }
+        }
+    }
+    Ok(Command::Events { query, follow })
+}
+
+fn parse_time(raw: Option<&&str>, flag: &str) -> Result<DateTime<Utc>, CliError> {
+    let raw = raw.ok_or_else(|| CliError::Usage(format!("{flag} needs a time")))?;
+    if let Ok(time) = DateTime::parse_from_rfc3339(raw) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or_else(|| CliError::Usage(format!("invalid {flag} time: {raw}")))
+}
+
+fn parse_category(raw: Option<&&str>) -> Result<EventCategory, CliError> {
+    let raw = raw.ok_or_else(|| CliError::Usage("--category needs a value".into()))?;
+    serde_json::from_value(serde_json::Value::String(raw.to_ascii_uppercase()))
+        .map_err(|_| CliError::Usage(format!("unknown event category: {raw}")))
+}
+
+fn parse_severity(raw: Option<&&str>) -> Result<EventSeverity, CliError> {
+    let raw = raw.ok_or_else(|| CliError::Usage("--severity needs a value".into()))?;
+    serde_json::from_value(serde_json::Value::String(raw.to_ascii_lowercase()))
+        .map_err(|_| CliError::Usage(format!("unknown event severity: {raw}")))
+}
+
+fn parse_logs(rest: &[&str]) -> Result<Command, CliError> {
+    let mut query = LogQuery {
+        limit: Some(DEFAULT_LOG_LINES),
+        ..LogQuery::default()
+    };
+    let mut follow = false;
+    let mut rest = rest.iter();
+    while let Some(arg) = rest.next() {
+        match *arg {
+            "--follow" | "-f" => follow = true,
+            "--tail" | "-n" => query.limit = Some(parse_count(rest.next(), "--tail")?),
+            "--since" => query.since = Some(parse_time(rest.next(), "--since")?),
+            "--until" => query.until = Some(parse_time(rest.next(), "--until")?),
+            "--component" | "-c" => query
+                .components
+                .push(parse_value(rest.next(), "--component")?),
+            "--level" | "-l" => query.min_level = Some(parse_level(rest.next())?),
+            "--grep" | "-g" => query.contains = Some(parse_value(rest.next(), "--grep")?),
+            "--regex" | "-e" => query.pattern = Some(parse_value(rest.next(), "--regex")?),
+            other => {
+                return Err(CliError::Usage(format!(
+                    "unexpected logs argument: {other}"
+                )))
+            }
+        }
+    }
+    Ok(Command::Logs { query, follow })
+}
+
+fn parse_level(raw: Option<&&str>) -> Result<LogLevel, CliError> {
+    let raw = raw.ok_or_else(|| CliError::Usage("--level needs a value".into()))?;
+    LogLevel::parse(raw).ok_or_else(|| CliError::Usage(format!("unknown log level: {raw}")))
+}
+
+fn parse_value(raw: Option<&&str>, flag: &str) -> Result<String, CliError> {
+    raw.map(|value| value.to_string())
+        .ok_or_else(|| CliError::Usage(format!("{flag} needs a value")))
+}
+
+fn parse_count(raw: Option<&&str>, flag: &str) -> Result<usize, CliError> {
+    raw.and_then(|value| value.parse::<usize>().ok())
+        .ok_or_else(|| CliError::Usage(format!("{flag} needs a number")))
+}
+
+fn execute(invocation: &Invocation) -> Result<(), CliError> {
+    let json = invocation.json;
+    match &invocation.command {
+        Command::Help => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        Command::Status => {
+            let status = status::get_status().map_err(CliError::from_message)?;
+            emit(json, &status, || print_status(&status))
+        }
+        Command::Version => show_version(json),
+        Command::BacklightGet => {
+            let level = backlight::get_backlight();
+            emit(json, &serde_json::json!({ "level": level }), || {
+                println!("{level}")
+            })
+        }
+        Command::BacklightSet(level) => {
+            backlight::set_backlight_daemon_first(*level).map_err(CliError::from_message)?;
+            acknowledge(json)
+        }
+        Command::LayoutGet => {
+            let layout = display::get_display_layout().map_err(CliError::from_message)?;
+            if json {
+                print_json(&layout)
+            } else {
+                print_layout(&layout);
+                Ok(())
+            }
+        }
+        Command::LayoutApply(path) => {
+            let layout = read_layout(path)?;
+            display::apply_display_layout(layout).map_err(CliError::from_message)?;
+            acknowledge(json)
+        }
+        Command::Rotate(orientation) => {
+            display::set_orientation(orientation.clone()).map_err(CliError::from_message)?;
+            acknowledge(json)
+        }
+        Command::TouchscreenList => {
+            let devices = touchscreen::list_touchscreens().map_err(CliError::from_message)?;
+            emit(json, &devices, || {
+                for device in &devices {
+                    let state = if device.enabled {
+                        "enabled"
+                    } else {
+                        "disabled"
+                    };
+                    println!("{}\t{}\t{}", device.connector, state, device.name);
+                }
+            })
+        }
+        Command::TouchscreenSet { connector, enabled } => {
+            touchscreen::set_touchscreen_enabled(connector.clone(), *enabled)
+                .map_err(CliError::from_message)?;
+            acknowledge(json)
+        }
+        Command::Remap(action) => run_remap(*action, json),
+        Command::Events { query, follow } => {
+            // Subscribe before reading the backlog so nothing published in
+            // between is lost.
+            let subscription = if *follow {
+                Some(
+                    client::subscribe(vec![SubscriptionCategory::Hardware])
+                        .map_err(CliError::from_message)?,
+                )
+            } else {
+                None
+            };
+            let backlog = show_events(query, json)?;
+            match subscription {
+                Some(subscription) => follow_events(subscription, &backlog, query, json),
+                None => Ok(()),
+            }
+        }
+        Command::Logs { query, follow } => {
+            if *follow {
+                follow_logs(query, json)
+            } else {
+                show_logs(query, json)
+            }
+        }
+        Command::LogsClear => {
+            logs::clear_log().map_err(CliError::from_message)?;
+            acknowledge(json)
+        }
+        Command::Metrics => {
+            let metrics = service::get_metrics().map_err(CliError::from_message)?;
+            emit(json, &metrics, || print!("{}", metrics.to_prometheus()))
+        }
+        Command::Posture => {
+            let report = status::get_posture().map_err(CliError::from_message)?;
+            emit(json, &report, || {
+                println!(
+                    "{} since {}",
+                    report.posture.label(),
+                    report.since.format("%Y-%m-%d %H:%M:%S")
+                );
+                for transition in &report.history {
+                    println!(
+                        "{}\t{} -> {}\t{}",
+                        transition.at.format("%Y-%m-%d %H:%M:%S"),
+                        transition.from.label(),
+                        transition.to.label(),
+                        transition.trigger.label()
+                    );
+                }
+            })
+        }
+    }
+}
+
+fn show_version(json: bool) -> Result<(), CliError> {
+    let cli_version = env!("CARGO_PKG_VERSION");
+    let daemon = match client::request(DaemonRequest::GetVersion) {
+        Ok(DaemonResponse::Version { version }) => Some(version),
+        Ok(DaemonResponse::Error { message }) => return Err(CliError::from_message(message)),
+        Ok(other) => {
+            return Err(CliError::Failed(format!(
+                "Unexpected daemon response while reading version: {other:?}"
+            )))
+        }
+        Err(_) => None,
+    };
+
+    let body = serde_json::json!({
+        "cliVersion": cli_version,
+        "daemon": daemon,
+    });
+    emit(json, &body, || {
+        println!("zenbook-duoctl {cli_version}");
+        match &daemon {
+            Some(daemon) => println!(
+                "zenbook-duo-daemon {} (protocol {})",
+                daemon.version, daemon.protocol_version
+            ),
+            None => println!("zenbook-duo-daemon unavailable"),
+        }
+    })
+}
+
+fn run_remap(action: RemapAction, json: bool) -> Result<(), CliError> {
+    let result = match action {
+        RemapAction::Status => {
+            let current = usb_media_remap::daemon_first_status();
+            return emit(json, &current, || {
+                let state = match (current.running, current.paused) {
+                    (false, _) => "stopped",
+                    (true, true) => "paused",
+                    (true, false) => "running",
+                };
+                println!("{state}");
+            });
+        }
+        RemapAction::Start => usb_media_remap::daemon_first_start(),
+        RemapAction::Stop => usb_media_remap::daemon_first_stop(),
+        RemapAction::Pause => usb_media_remap::daemon_first_set_paused(true),
+        RemapAction::Resume => usb_media_remap::daemon_first_set_paused(false),
+    };
+    result.map_err(CliError::from_message)?;
+    acknowledge(json)
+}
+
+/// Plain listings use `GetRecentEvents`, which every daemon understands;
+/// filters need the event journal. Returns the events it printed.
+fn show_events(query: &EventQuery, json: bool) -> Result<Vec<HardwareEvent>, CliError> {
+    let filtered = query.since.is_some()
+        || query.until.is_some()
+        || !query.categories.is_empty()
+        || query.min_severity.is_some();
+    let request = if filtered {
+        DaemonRequest::query_events(query.clone())
+    } else {
+        DaemonRequest::GetRecentEvents {
+            limit: query.limit.unwrap_or(DEFAULT_EVENT_LIMIT),
+        }
+    };
+    let events = match client::request(request) {
+        Ok(DaemonResponse::Events { events }) => events,
+        Ok(DaemonResponse::Error { message }) => return Err(CliError::from_message(message)),
+        Ok(other) => {
+            return Err(CliError::Failed(format!(
+                "Unexpected daemon response while reading events: {other:?}"
+            )))
+        }
+        Err(message) => return Err(CliError::from_message(message)),
+    };
+
+    for event in &events {
+        print_event(event, json)?;
+    }
+    Ok(events)
+}
+
+/// Streams hardware events until the daemon goes away, skipping those the
+/// `backlog` already showed. JSON mode prints one object per line so the
+/// output can be piped into `jq`.
+fn follow_events(
+    subscription: client::EventSubscription,
+    backlog: &[HardwareEvent],
+    query: &EventQuery,
+    json: bool,
+) -> Result<(), CliError> {
+    for event in subscription {
+        match event.map_err(CliError::Failed)? {
+            DaemonEvent::HardwareEvent { event }
+                if query.matches(&event) && !backlog.contains(&event) =>
+            {
+                print_event(&event, json)?
+            }
+            DaemonEvent::HardwareEvent { .. } => {}
+            DaemonEvent::StatusChanged { .. } | DaemonEvent::SessionAgentChanged { .. } => {}
+        }
+    }
+    Err(CliError::Unavailable(
+        "Daemon closed the event stream".into(),
+    ))
+}
+
+/// Plain listings use `TailLogs`, which every daemon understands; filters
+/// need `QueryLogs`.
+fn show_logs(query: &LogQuery, json: bool) -> Result<(), CliError> {
+    let filtered = query.since.is_some()
+        || query.until.is_some()
+        || !query.components.is_empty()
+        || query.min_level.is_some()
+        || query.contains.is_some()
+        || query.pattern.is_some();
+    let records = if filtered {
+        logs::query_logs(query.clone()).map_err(CliError::from_message)?
+    } else {
+        logs::read_log_records(query.limit.unwrap_or(DEFAULT_LOG_LINES))
+    };
+    emit(json, &records, || {
+        for record in &records {
+            println!("{record}");
+        }
+    })
+}
+
+/// Prints the matching tail of the logs, then new matching lines as they are
+/// written, until the daemon goes away. JSON mode prints one record per line.
+fn follow_logs(query: &LogQuery, json: bool) -> Result<(), CliError> {
+    let (records, stream) = client::follow_logs(query.clone()).map_err(CliError::Unavailable)?;
+    for record in &records {
+        print_record(record, json)?;
+    }
+    for batch in stream {
+        for record in &batch.map_err(CliError::Failed)? {
+            print_record(record, json)?;
+        }
+    }
+    Err(CliError::Unavailable("Daemon closed the log stream".into()))
+}
+
+fn print_record(record: &LogRecord, json: bool) -> Result<(), CliError> {
+    let line = if json {
+        serde_json::to_string(record)
+            .map_err(|e| CliError::Failed(format!("Failed to encode log record: {e}")))?
+    } else {
+        record.to_string()
+    };
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{line}")
+        .and_then(|()| stdout.flush())
+        .map_err(|e| CliError::Failed(format!("Failed to write log record: {e}")))
+}
+
+fn read_layout(path: &Path) -> Result<DisplayLayout, CliError> {
+    let raw = if path.as_os_str() == "-" {
+        let mut raw = String::new();
+        io::stdin()
+            .read_to_string(&mut raw)
+            .map_err(|e| CliError::Failed(format!("Failed to read layout from stdin: {e}")))?;
+        raw
+    } else {
+        fs::read_to_string(path)
+            .map_err(|e| CliError::Failed(format!("Failed to read {}: {e}", path.display())))?
+    };
+    serde_json::from_str(&raw)
+        .map_err(|e| CliError::Usage(format!("Invalid display layout JSON: {e}")))
+}
+
+fn emit<T: Serialize>(json: bool, value: &T, human: impl FnOnce()) -> Result<(), CliError> {
+    if json {
+        print_json(value)
+    } else {
+        human();
+        Ok(())
+    }
+}
+
+fn acknowledge(json: bool) -> Result<(), CliError> {
+    if json {
+        println!("{}", serde_json::json!({ "ok": true }));
+    }
+    Ok(())
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), CliError> {
+    let body = serde_json::to_string_pretty(value)
+        .map_err(|e| CliError::Failed(format!("Failed to encode JSON output: {e}")))?;
+    println!("{body}");
+    Ok(())
+}
+
+fn print_event(event: &HardwareEvent, json: bool) -> Result<(), CliError> {
+    let line = if json {
+        serde_json::to_string(event)
+            .map_err(|e| CliError::Failed(format!("Failed to encode event: {e}")))?
+    } else {
+        format_event(event)
+    };
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{line}")
+        .and_then(|()| stdout.flush())
+        .map_err(|e| CliError::Failed(format!("Failed to write event: {e}")))
+}
+
+fn format_event(event: &HardwareEvent) -> String {
+    let severity = match event.severity {
+        EventSeverity::Info => "info",
+        EventSeverity::Warning => "warning",
+        EventSeverity::Error => "error",
+    };
+    let category = match event.category {
+        EventCategory::Usb => "usb",
+        EventCategory::Display => "display",
+        EventCategory::Keyboard => "keyboard",
+        EventCategory::Network => "network",
+        EventCategory::Rotation => "rotation",
+        EventCategory::Bluetooth => "bluetooth",
+        EventCategory::Service => "service",
+    };
+    format!(
+        "{} {severity:<7} {category:<9} {}",
+        event.timestamp.format("%Y-%m-%d %H:%M:%S"),
+        event.message
+    )
+}
+
+fn print_status(status: &DuoStatus) {
+    for (label, value) in status_lines(status) {
+        println!("{label:<20}{value}");
+    }
+}
+
+fn status_lines(status: &DuoStatus) -> Vec<(&'static str, String)> {
+    let keyboard = match (status.keyboard_attached, &status.connection_type) {
+        (true, ConnectionType::Usb) => "attached (usb)".to_string(),
+        (_, ConnectionType::Bluetooth) => "bluetooth".to_string(),
+        (true, ConnectionType::None) => "attached".to_string(),
+        (false, _) => "detached".to_string(),
+    };
+    let on_off = |enabled: bool| if enabled { "on" } else { "off" }.to_string();
+    let battery = match (
+        status.keyboard_battery_percent,
+        status.keyboard_battery_charging,
+    ) {
+        (Some(percent), Some(true)) => format!("{percent}% (charging)"),
+        (Some(percent), _) => format!("{percent}%"),
+        (None, _) => "n/a".to_string(),
+    };
+    vec![
+        ("Keyboard", keyboard),
+        ("Monitors", status.monitor_count.to_string()),
+        ("Wi-Fi", on_off(status.wifi_enabled)),
+        ("Bluetooth", on_off(status.bluetooth_enabled)),
+        ("Keyboard backlight", status.backlight_level.to_string()),
+        ("Keyboard battery", battery),
+        (
+            "Display brightness",
+            format!("{}/{}", status.display_brightness, status.max_brightness),
+        ),
+        (
+            "Orientation",
+            format!("{:?}", status.orientation).to_lowercase(),
+        ),
+        (
+            "Daemon",
+            if status.service_active {
+                "active".to_string()
+            } else {
+                "unavailable (local readings)".to_string()
+            },
+        ),
+    ]
+}
+
+fn print_layout(layout: &DisplayLayout) {
+    for display in &layout.displays {
+        println!(
+            "{}{}\t{}x{}@{:.2}Hz\tscale {}\tat {},{}\ttransform {}",
+            display.connector,
+            if display.primary { " (primary)" } else { "" },
+            display.width,
+            display.height,
+            display.refresh_rate,
+            display.scale,
+            display.x,
+            display.y,
+            display.transform
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Invocation, CliError> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_subcommands_and_global_json_flag() {
+        assert_eq!(
+            parse(&["backlight", "set", "2", "--json"]).expect("backlight"),
+            Invocation {
+                command: Command::BacklightSet(2),
+                json: true,
+            }
+        );
+        assert_eq!(
+            parse(&["touchscreen", "disable", "eDP-2"])
+                .expect("touchscreen")
+                .command,
+            Command::TouchscreenSet {
+                connector: "eDP-2".into(),
+                enabled: false,
+            }
+        );
+        assert_eq!(
+            parse(&["rotate", "left"]).expect("rotate").command,
+            Command::Rotate(Orientation::Left)
+        );
+        assert_eq!(
+            parse(&["events", "--follow", "--limit", "5"])
+                .expect("events")
+                .command,
+            Command::Events {
+                query: EventQuery {
+                    limit: Some(5),
+                    ..EventQuery::default()
+                },
+                follow: true,
+            }
+        );
+        assert_eq!(
+            parse(&[
+                "events",
+                "--since",
+                "2026-03-10T08:00:00Z",
+                "--category",
+                "display",
+                "--severity",
+                "warning",
+            ])
+            .expect("events query")
+            .command,
+            Command::Events {
+                query: EventQuery {
+                    since: Some(Utc.with_ymd_and_hms(2026, 3, 10, 8, 0, 0).unwrap()),
+                    categories: vec![EventCategory::Display],
+                    min_severity: Some(EventSeverity::Warning),
+                    limit: Some(DEFAULT_EVENT_LIMIT),
+                    ..EventQuery::default()
+                },
+                follow: false,
+            }
+        );
+        assert_eq!(
+            parse(&["logs", "--tail", "10"]).expect("logs").command,
+            Command::Logs {
+                query: LogQuery {
+                    limit: Some(10),
+                    ..LogQuery::default()
+                },
+                follow: false,
+            }
+        );
+        assert_eq!(
+            parse(&[
+                "logs",
+                "-c",
+                "session-agent",
+                "--level",
+                "warn",
+                "--since",
+                "2026-03-10T08:00:00Z",
+                "--regex",
+                "^lost",
+                "-f",
+            ])
+            .expect("logs query")
+            .command,
+            Command::Logs {
+                query: LogQuery {
+                    since: Some(Utc.with_ymd_and_hms(2026, 3, 10, 8, 0, 0).unwrap()),
+                    components: vec!["session-agent".into()],
+                    min_level: Some(LogLevel::Warn),
+                    pattern: Some("^lost".into()),
+                    limit: Some(DEFAULT_LOG_LINES),
+                    ..LogQuery::default()
+                },
+                follow: true,
+            }
+        );
+        assert_eq!(
+            parse(&["remap", "pause"]).expect("remap").command,
+            Command::Remap(RemapAction::Pause)
+        );
+        assert_eq!(
+            parse(&["metrics", "--json"]).expect("metrics"),
+            Invocation {
+                command: Command::Metrics,
+                json: true,
+            }
+        );
+        assert_eq!(
+            parse(&["posture"]).expect("posture").command,
+            Command::Posture
+        );
+    }
+
+    #[test]
+    fn rejects_bad_arguments_as_usage_errors() {
+        for args in [
+            &["backlight", "set", "7"][..],
+            &["rotate", "sideways"],
+            &["logs", "--tail"],
+            &["logs", "--level", "loud"],
+            &["events", "--since", "last tuesday"],
+            &["events", "--category", "toaster"],
+            &["frobnicate"],
+        ] {
+            let err = parse(args).expect_err("invalid arguments");
+            assert_eq!(err.exit_code(), EXIT_USAGE, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn error_messages_map_to_exit_codes() {
+        assert_eq!(
+            CliError::from_message(
+                "Permission denied: clear_logs requires privileged access".into()
+            )
+            .exit_code(),
+            EXIT_DENIED
+        );
+        assert_eq!(
+            CliError::from_message("Failed to connect to daemon socket: No such file".into())
+                .exit_code(),
+            EXIT_UNAVAILABLE
+        );
+        assert_eq!(
+            CliError::from_message("No touchscreen found for eDP-9".into()).exit_code(),
+            EXIT_FAILURE
+        );
+    }
+
+    #[test]
+    fn status_lines_describe_keyboard_connection() {
+        let mut status = DuoStatus {
+            keyboard_attached: true,
+            connection_type: ConnectionType::Usb,
+            ..DuoStatus::default()
+        };
+        assert_eq!(status_lines(&status)[0].1, "attached (usb)");
+
+        status.keyboard_attached = false;
+        status.connection_type = ConnectionType::None;
+        assert_eq!(status_lines(&status)[0].1, "detached");
+    }
+
+    #[test]
+    fn status_lines_show_keyboard_battery_when_reported() {
+        let battery = |status: &DuoStatus| {
+            status_lines(status)
+                .into_iter()
+                .find(|(label, _)| *label == "Keyboard battery")
+                .map(|(_, value)| value)
+        };
+        let mut status = DuoStatus::default();
+        assert_eq!(battery(&status).as_deref(), Some("n/a"));
+
+        status.keyboard_battery_percent = Some(64);
+        status.keyboard_battery_charging = Some(true);
+        assert_eq!(battery(&status).as_deref(), Some("64% (charging)"));
+    }
+}
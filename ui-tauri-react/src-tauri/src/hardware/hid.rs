@@ -1,153 +1,681 @@
 use std::fs;
 use std::os::unix::io::AsRawFd;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use rusb::UsbContext;
 
-/// USB HID SET_REPORT for keyboard backlight control using rusb.
-///
-/// Protocol:
-///   Report ID: 0x5A
-///   Data: [0x5A, 0xBA, 0xC5, 0xC4, level, 0x00 x 11]
-///   wValue: 0x035A, wIndex: 4, wLength: 16
-pub fn set_backlight_usb(level: u8) -> Result<(), String> {
-    let level = level.min(3);
-
-    let context = rusb::Context::new().map_err(|e| format!("USB context error: {e}"))?;
-    let devices = context
-        .devices()
-        .map_err(|e| format!("USB device list error: {e}"))?;
-
-    for device in devices.iter() {
-        let desc: rusb::DeviceDescriptor = match device.device_descriptor() {
-            Ok(d) => d,
-            Err(_) => continue,
-        };
+use crate::runtime::metrics;
 
-        // ASUS Zenbook Duo keyboard: vendor 0x0B05
-        if desc.vendor_id() != 0x0B05 {
-            continue;
+/// Feature report that carries the keyboard backlight level.
+pub const BACKLIGHT_REPORT_ID: u8 = 0x5A;
+/// USB interface of the keyboard that takes the backlight report.
+const BACKLIGHT_INTERFACE: u8 = 4;
+const ASUS_VENDOR_ID: u16 = 0x0B05;
+const REPORT_LEN: usize = 16;
+
+const BACKLIGHT_MAGIC: [u8; 3] = [0xBA, 0xC5, 0xC4];
+
+/// The 0x5A backlight feature report: `[0x5A, 0xBA, 0xC5, 0xC4, level, 0 x 11]`.
+pub fn backlight_report(level: u8) -> [u8; REPORT_LEN] {
+    let mut data = [0u8; REPORT_LEN];
+    data[0] = BACKLIGHT_REPORT_ID;
+    data[1..4].copy_from_slice(&BACKLIGHT_MAGIC);
+    data[4] = level.min(3);
+    data
+}
+
+/// The level in the keyboard's answer to GET_FEATURE 0x5A. The firmware
+/// echoes the layout it is written with; any other answer means it does
+/// not report the level.
+pub fn parse_backlight_report(report: &[u8]) -> Option<u8> {
+    match report {
+        [BACKLIGHT_REPORT_ID, a, b, c, level, ..] if [*a, *b, *c] == BACKLIGHT_MAGIC => {
+            (*level <= 3).then_some(*level)
         }
+        _ => None,
+    }
+}
 
-        let handle: rusb::DeviceHandle<rusb::Context> = match device.open() {
-            Ok(h) => h,
-            Err(_) => continue,
-        };
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportKind {
+    UsbControl,
+    UsbHidraw,
+    BluetoothHidraw,
+}
+
+impl TransportKind {
+    /// Used in errors and as the `transport` metrics label.
+    pub fn label(self) -> &'static str {
+        match self {
+            TransportKind::UsbControl => "usb",
+            TransportKind::UsbHidraw => "usb_hidraw",
+            TransportKind::BluetoothHidraw => "bluetooth",
+        }
+    }
+}
+
+/// A route to the keyboard's HID feature reports. Opening one is the
+/// expensive part (a USB bus scan or a hidraw search), so an open transport
+/// is kept in a [`TransportCache`] and reused until it fails or the
+/// keyboard is replugged.
+pub trait KeyboardTransport: Send {
+    fn kind(&self) -> TransportKind;
+    fn send_feature_report(&self, report: &[u8]) -> Result<(), String>;
+    /// Fills `report`, whose first byte is the report id, with the device's
+    /// answer and returns how many bytes it sent.
+    fn get_feature_report(&self, report: &mut [u8]) -> Result<usize, String>;
+}
+
+/// HID SET_REPORT control transfers to interface 4 through libusb.
+pub struct UsbControl {
+    handle: rusb::DeviceHandle<rusb::Context>,
+}
+
+impl UsbControl {
+    pub fn open() -> Result<Self, String> {
+        let context = rusb::Context::new().map_err(|e| format!("USB context error: {e}"))?;
+        let devices = context
+            .devices()
+            .map_err(|e| format!("USB device list error: {e}"))?;
 
-        // Check if this is the keyboard by reading product string
-        if let Ok(product) = handle.read_product_string_ascii(&desc) {
-            if !product.contains("Zenbook Duo Keyboard") && !product.contains("ASUS_DUO") {
+        for device in devices.iter() {
+            let Ok(desc) = device.device_descriptor() else {
+                continue;
+            };
+            if desc.vendor_id() != ASUS_VENDOR_ID {
                 continue;
             }
-        } else {
-            continue;
+            let Ok(handle) = device.open() else {
+                continue;
+            };
+            // Check if this is the keyboard by reading product string
+            let Ok(product) = handle.read_product_string_ascii(&desc) else {
+                continue;
+            };
+            if product.contains("Zenbook Duo Keyboard") || product.contains("ASUS_DUO") {
+                return Ok(Self { handle });
+            }
         }
+        Err("Zenbook Duo keyboard not found via USB".into())
+    }
+}
 
-        // Detach kernel driver if needed
-        let interface = 4u8;
-        let _ = handle.set_auto_detach_kernel_driver(true);
-        let _ = handle.claim_interface(interface as u8);
+impl KeyboardTransport for UsbControl {
+    fn kind(&self) -> TransportKind {
+        TransportKind::UsbControl
+    }
+
+    fn send_feature_report(&self, report: &[u8]) -> Result<(), String> {
+        // The kernel driver is detached only for the transfer and gets the
+        // interface back afterwards.
+        let _ = self.handle.set_auto_detach_kernel_driver(true);
+        let _ = self.handle.claim_interface(BACKLIGHT_INTERFACE);
 
-        // Build the HID SET_REPORT payload
-        let mut data = [0u8; 16];
-        data[0] = 0x5A; // Report ID
-        data[1] = 0xBA;
-        data[2] = 0xC5;
-        data[3] = 0xC4;
-        data[4] = level;
+        // HID SET_REPORT: bmRequestType=0x21 (host-to-device, class,
+        // interface), bRequest=0x09, wValue=0x0300 | report id (feature).
+        let value = 0x0300 | u16::from(report[0]);
+        let result = self
+            .handle
+            .write_control(
+                0x21,
+                0x09,
+                value,
+                u16::from(BACKLIGHT_INTERFACE),
+                report,
+                Duration::from_secs(2),
+            )
+            .map(|_| ())
+            .map_err(|e| format!("USB write error: {e}"));
+
+        let _ = self.handle.release_interface(BACKLIGHT_INTERFACE);
+        result
+    }
 
-        // HID SET_REPORT: bmRequestType=0x21, bRequest=0x09
-        // wValue = 0x0300 | report_id = 0x035A
-        // wIndex = interface number
-        let request_type = 0x21; // Host-to-device, class, interface
-        let request = 0x09; // SET_REPORT
-        let value = 0x035A; // Feature report, ID 0x5A
-        let index = interface as u16;
-        let timeout = std::time::Duration::from_secs(2);
+    fn get_feature_report(&self, report: &mut [u8]) -> Result<usize, String> {
+        let _ = self.handle.set_auto_detach_kernel_driver(true);
+        let _ = self.handle.claim_interface(BACKLIGHT_INTERFACE);
 
-        handle
-            .write_control(request_type, request, value, index, &data, timeout)
-            .map_err(|e| format!("USB write error: {e}"))?;
+        // HID GET_REPORT: bmRequestType=0xA1 (device-to-host, class,
+        // interface), bRequest=0x01, wValue=0x0300 | report id (feature).
+        let value = 0x0300 | u16::from(report[0]);
+        let result = self
+            .handle
+            .read_control(
+                0xA1,
+                0x01,
+                value,
+                u16::from(BACKLIGHT_INTERFACE),
+                report,
+                Duration::from_secs(2),
+            )
+            .map_err(|e| format!("USB read error: {e}"));
 
-        return Ok(());
+        let _ = self.handle.release_interface(BACKLIGHT_INTERFACE);
+        result
     }
+}
 
-    Err("Zenbook Duo keyboard not found via USB".into())
+/// HIDIOCSFEATURE/HIDIOCGFEATURE on the keyboard's hidraw node, over USB or
+/// Bluetooth.
+pub struct Hidraw {
+    kind: TransportKind,
+    path: PathBuf,
+    file: fs::File,
 }
 
-/// Bluetooth HID Feature Report for keyboard backlight using ioctl HIDIOCSFEATURE.
-pub fn set_backlight_bluetooth(level: u8) -> Result<(), String> {
-    let level = level.min(3);
+impl Hidraw {
+    pub fn open(kind: TransportKind) -> Result<Self, String> {
+        let path = find_hidraw(Path::new("/sys/class/hidraw"), kind)?;
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+        Ok(Self { kind, path, file })
+    }
+}
 
-    // Find the hidraw device for the Zenbook keyboard
-    let hidraw_path = find_bt_hidraw()?;
+impl KeyboardTransport for Hidraw {
+    fn kind(&self) -> TransportKind {
+        self.kind
+    }
 
-    let file = fs::OpenOptions::new()
-        .read(true)
-        .write(true)
-        .open(&hidraw_path)
-        .map_err(|e| format!("Failed to open {hidraw_path}: {e}"))?;
+    fn send_feature_report(&self, report: &[u8]) -> Result<(), String> {
+        let mut data = report.to_vec();
+        // HIDIOCSFEATURE(len) = _IOC(_IOC_WRITE|_IOC_READ, 'H', 0x06, len)
+        let request = 0xC000_4806 | ((data.len() as libc::c_ulong) << 16);
 
-    let fd = file.as_raw_fd();
+        // SAFETY: `data` outlives the call and the request encodes its length.
+        let ret = unsafe { libc::ioctl(self.file.as_raw_fd(), request, data.as_mut_ptr()) };
+        if ret < 0 {
+            return Err(format!(
+                "ioctl HIDIOCSFEATURE on {} failed: {}",
+                self.path.display(),
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
 
-    // Build the same payload
-    let mut data = [0u8; 16];
-    data[0] = 0x5A;
-    data[1] = 0xBA;
-    data[2] = 0xC5;
-    data[3] = 0xC4;
-    data[4] = level;
+    fn get_feature_report(&self, report: &mut [u8]) -> Result<usize, String> {
+        // HIDIOCGFEATURE(len) = _IOC(_IOC_WRITE|_IOC_READ, 'H', 0x07, len)
+        let request = 0xC000_4807 | ((report.len() as libc::c_ulong) << 16);
 
-    // HIDIOCSFEATURE = 0xC0104806 + len
-    // This is _IOC(_IOC_WRITE|_IOC_READ, 'H', 0x06, len)
-    // For 16 bytes: 0xC0104806
-    let hidiocsfeature: libc::c_ulong = 0xC010_4806;
+        // SAFETY: `report` outlives the call and the request encodes its length.
+        let ret = unsafe { libc::ioctl(self.file.as_raw_fd(), request, report.as_mut_ptr()) };
+        if ret < 0 {
+            return Err(format!(
+                "ioctl HIDIOCGFEATURE on {} failed: {}",
+                self.path.display(),
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(ret as usize)
+    }
+}
 
-    let ret = unsafe { libc::ioctl(fd, hidiocsfeature, data.as_mut_ptr()) };
+fn find_hidraw(class_dir: &Path, kind: TransportKind) -> Result<PathBuf, String> {
+    let entries = fs::read_dir(class_dir)
+        .map_err(|e| format!("Failed to read {}: {e}", class_dir.display()))?;
+    for entry in entries.flatten() {
+        let Ok(uevent) = fs::read_to_string(entry.path().join("device/uevent")) else {
+            continue;
+        };
+        if hidraw_matches(&uevent, kind) {
+            return Ok(Path::new("/dev").join(entry.file_name()));
+        }
+    }
+    Err(match kind {
+        TransportKind::BluetoothHidraw => "Bluetooth hidraw device not found".into(),
+        _ => "USB hidraw device not found".into(),
+    })
+}
 
-    if ret < 0 {
-        return Err(format!(
-            "ioctl HIDIOCSFEATURE failed: {}",
-            std::io::Error::last_os_error()
-        ));
+/// Whether a hidraw node's HID uevent is the keyboard on `kind`'s bus. Over
+/// USB only the backlight interface qualifies.
+fn hidraw_matches(uevent: &str, kind: TransportKind) -> bool {
+    let field = |key: &str| {
+        uevent
+            .lines()
+            .find_map(|line| line.strip_prefix(key))
+            .unwrap_or_default()
+    };
+    let name = field("HID_NAME=");
+    if !name.contains("Zenbook Duo Keyboard") && !name.contains("ASUS_DUO") {
+        return false;
+    }
+    let id = field("HID_ID=");
+    match kind {
+        TransportKind::BluetoothHidraw => id.starts_with("0005:"),
+        TransportKind::UsbHidraw => {
+            id.starts_with("0003:")
+                && field("HID_PHYS=").ends_with(&format!("/input{BACKLIGHT_INTERFACE}"))
+        }
+        TransportKind::UsbControl => false,
     }
+}
+
+pub type OpenResult = Result<Box<dyn KeyboardTransport>, String>;
+/// A transport kind and how to discover it.
+pub type TransportOpener<'a> = (TransportKind, &'a dyn Fn() -> OpenResult);
+
+/// Transports in the order they are tried: libusb first, as it has always
+/// been, then hidraw over USB and Bluetooth.
+const TRANSPORTS: [(TransportKind, fn() -> OpenResult); 3] = [
+    (TransportKind::UsbControl, || {
+        UsbControl::open().map(|t| Box::new(t) as _)
+    }),
+    (TransportKind::UsbHidraw, || {
+        Hidraw::open(TransportKind::UsbHidraw).map(|t| Box::new(t) as _)
+    }),
+    (TransportKind::BluetoothHidraw, || {
+        Hidraw::open(TransportKind::BluetoothHidraw).map(|t| Box::new(t) as _)
+    }),
+];
 
-    Ok(())
+/// Keeps the transport that last reached the keyboard.
+#[derive(Default)]
+pub struct TransportCache {
+    transport: Option<Box<dyn KeyboardTransport>>,
+    /// The cached transport could not answer a read and nothing else could
+    /// either, so reads fail straight away until it is replaced.
+    reads_unsupported: bool,
 }
 
-fn find_bt_hidraw() -> Result<String, String> {
-    let hidraw_dir = Path::new("/sys/class/hidraw");
-    if let Ok(entries) = fs::read_dir(hidraw_dir) {
-        for entry in entries.flatten() {
-            let uevent_path = entry.path().join("device/uevent");
-            if let Ok(contents) = fs::read_to_string(&uevent_path) {
-                if (contents.contains("Zenbook Duo Keyboard") || contents.contains("ASUS_DUO"))
-                    && contents.contains("0005:")
-                {
-                    let name = entry.file_name();
-                    return Ok(format!("/dev/{}", name.to_string_lossy()));
+impl TransportCache {
+    pub const fn new() -> Self {
+        Self {
+            transport: None,
+            reads_unsupported: false,
+        }
+    }
+
+    pub fn invalidate(&mut self) {
+        self.transport = None;
+        self.reads_unsupported = false;
+    }
+
+    pub fn cached_kind(&self) -> Option<TransportKind> {
+        self.transport.as_ref().map(|transport| transport.kind())
+    }
+
+    /// Sends `report` on the cached transport. When there is none, or it
+    /// fails, each of `openers` is tried in turn and the first that delivers
+    /// the report is kept.
+    pub fn send(&mut self, report: &[u8], openers: &[TransportOpener]) -> Result<(), String> {
+        self.with_transport(openers, |transport| {
+            observed(metrics::HID_WRITE_DURATION, transport, || {
+                transport.send_feature_report(report)
+            })
+        })
+        .map_err(|errors| {
+            format!(
+                "Failed to set keyboard backlight natively ({})",
+                errors.join("; ")
+            )
+        })
+    }
+
+    /// Asks for feature report `report_id`, falling back to the other
+    /// transports the same way [`TransportCache::send`] does.
+    pub fn query(&mut self, report_id: u8, openers: &[TransportOpener]) -> Result<Vec<u8>, String> {
+        if let Some(kind) = self.cached_kind().filter(|_| self.reads_unsupported) {
+            return Err(format!(
+                "Keyboard over {} does not answer feature report {report_id:#04x}",
+                kind.label()
+            ));
+        }
+        let result = self.with_transport(openers, |transport| {
+            observed(metrics::HID_READ_DURATION, transport, || {
+                let mut report = vec![0u8; REPORT_LEN];
+                report[0] = report_id;
+                let len = transport.get_feature_report(&mut report)?;
+                report.truncate(len);
+                Ok(report)
+            })
+        });
+        // Still holding a transport after a failed read means the one we had
+        // was kept for writes, and it cannot answer.
+        self.reads_unsupported = result.is_err() && self.transport.is_some();
+        result.map_err(|errors| {
+            format!(
+                "Failed to read keyboard feature report {report_id:#04x} ({})",
+                errors.join("; ")
+            )
+        })
+    }
+
+    fn with_transport<T>(
+        &mut self,
+        openers: &[TransportOpener],
+        operation: impl Fn(&dyn KeyboardTransport) -> Result<T, String>,
+    ) -> Result<T, Vec<String>> {
+        let mut errors = Vec::new();
+        let previous = self.transport.take();
+        if let Some(transport) = &previous {
+            match operation(transport.as_ref()) {
+                Ok(value) => {
+                    self.transport = previous;
+                    return Ok(value);
+                }
+                Err(err) => errors.push(format!("cached {}: {err}", transport.kind().label())),
+            }
+        }
+
+        for (kind, open) in openers {
+            let result =
+                open().and_then(|transport| operation(transport.as_ref()).map(|v| (transport, v)));
+            match result {
+                Ok((transport, value)) => {
+                    self.transport = Some(transport);
+                    self.reads_unsupported = false;
+                    return Ok(value);
                 }
+                Err(err) => errors.push(format!("{}: {err}", kind.label())),
             }
         }
+        // A transport that cannot answer one request may still take
+        // another, e.g. writes on firmware without GET_FEATURE.
+        self.transport = previous;
+        Err(errors)
     }
-    Err("Bluetooth hidraw device not found".into())
 }
 
-/// Set backlight, trying USB first then Bluetooth.
+fn observed<T>(
+    name: &'static str,
+    transport: &dyn KeyboardTransport,
+    operation: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+    let started = Instant::now();
+    let result = operation();
+    metrics::observe_result(
+        name,
+        &[("transport", transport.kind().label())],
+        started,
+        &result,
+    );
+    result
+}
+
+static TRANSPORT: Mutex<TransportCache> = Mutex::new(TransportCache::new());
+
+/// Forgets the cached transport. Called on keyboard hotplug, after which the
+/// old USB handle or hidraw node no longer reaches the keyboard.
+pub fn invalidate_transport() {
+    TRANSPORT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .invalidate();
+}
+
+fn system_openers() -> Vec<TransportOpener<'static>> {
+    TRANSPORTS
+        .iter()
+        .map(|(kind, open)| (*kind, open as &dyn Fn() -> OpenResult))
+        .collect()
+}
+
+/// Sets the keyboard backlight (0-3) over whichever transport reaches it.
 pub fn set_backlight(level: u8) -> Result<(), String> {
-    // Try USB first
-    let usb_err = match set_backlight_usb(level) {
-        Ok(()) => return Ok(()),
-        Err(e) => e,
-    };
+    TRANSPORT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .send(&backlight_report(level), &system_openers())
+}
 
-    // Try Bluetooth
-    let bt_err = match set_backlight_bluetooth(level) {
-        Ok(()) => return Ok(()),
-        Err(e) => e,
-    };
+/// The backlight level the keyboard itself reports. Callers keep their
+/// cached level for when this fails: the keyboard is gone or its firmware
+/// does not answer GET_FEATURE.
+pub fn read_backlight() -> Result<u8, String> {
+    let report = TRANSPORT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .query(BACKLIGHT_REPORT_ID, &system_openers())?;
+    parse_backlight_report(&report).ok_or_else(|| {
+        format!("Keyboard answered feature report {BACKLIGHT_REPORT_ID:#04x} without a backlight level: {report:02x?}")
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::sync::Arc;
+
+    /// Records every feature report it is sent and, like the firmware,
+    /// answers GET_FEATURE with the last one (level 0 before any). Fails
+    /// everything when `broken`, and reads when built `without_reads`.
+    #[derive(Clone)]
+    pub struct FakeTransport {
+        kind: TransportKind,
+        broken: bool,
+        reads: bool,
+        reports: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    impl FakeTransport {
+        pub fn new(kind: TransportKind) -> Self {
+            Self {
+                kind,
+                broken: false,
+                reads: true,
+                reports: Arc::default(),
+            }
+        }
+
+        pub fn broken(kind: TransportKind) -> Self {
+            Self {
+                broken: true,
+                ..Self::new(kind)
+            }
+        }
+
+        pub fn without_reads(kind: TransportKind) -> Self {
+            Self {
+                reads: false,
+                ..Self::new(kind)
+            }
+        }
+
+        pub fn reports(&self) -> Vec<Vec<u8>> {
+            self.reports.lock().expect("reports lock").clone()
+        }
+    }
+
+    impl KeyboardTransport for FakeTransport {
+        fn kind(&self) -> TransportKind {
+            self.kind
+        }
+
+        fn send_feature_report(&self, report: &[u8]) -> Result<(), String> {
+            if self.broken {
+                return Err("device disconnected".into());
+            }
+            self.reports
+                .lock()
+                .expect("reports lock")
+                .push(report.to_vec());
+            Ok(())
+        }
+
+        fn get_feature_report(&self, report: &mut [u8]) -> Result<usize, String> {
+            if self.broken {
+                return Err("device disconnected".into());
+            }
+            if !self.reads {
+                return Err("Broken pipe".into());
+            }
+            let answer = self
+                .reports()
+                .last()
+                .cloned()
+                .unwrap_or_else(|| backlight_report(0).to_vec());
+            let len = answer.len().min(report.len());
+            report[..len].copy_from_slice(&answer[..len]);
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn discovers_once_and_reuses_the_transport() {
+        let usb = FakeTransport::new(TransportKind::UsbHidraw);
+        let opened = Cell::new(0);
+        let open_usb = || {
+            opened.set(opened.get() + 1);
+            Ok(Box::new(usb.clone()) as Box<dyn KeyboardTransport>)
+        };
+        let no_libusb = || Err("Zenbook Duo keyboard not found via USB".to_string());
+        let openers: [TransportOpener; 2] = [
+            (TransportKind::UsbControl, &no_libusb),
+            (TransportKind::UsbHidraw, &open_usb),
+        ];
+        let mut cache = TransportCache::new();
+
+        cache.send(&backlight_report(2), &openers).expect("first");
+        cache.send(&backlight_report(9), &openers).expect("second");
+
+        assert_eq!(opened.get(), 1);
+        assert_eq!(cache.cached_kind(), Some(TransportKind::UsbHidraw));
+        assert_eq!(
+            usb.reports(),
+            vec![
+                vec![0x5A, 0xBA, 0xC5, 0xC4, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                vec![0x5A, 0xBA, 0xC5, 0xC4, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            ]
+        );
+    }
+
+    #[test]
+    fn failed_or_invalidated_transport_is_rediscovered() {
+        let stale = FakeTransport::broken(TransportKind::UsbControl);
+        let bluetooth = FakeTransport::new(TransportKind::BluetoothHidraw);
+        let open_stale = || Ok(Box::new(stale.clone()) as Box<dyn KeyboardTransport>);
+        let open_bluetooth = || Ok(Box::new(bluetooth.clone()) as Box<dyn KeyboardTransport>);
+        let mut cache = TransportCache {
+            transport: Some(Box::new(stale.clone())),
+            ..TransportCache::new()
+        };
+
+        cache
+            .send(
+                &backlight_report(1),
+                &[
+                    (TransportKind::UsbControl, &open_stale),
+                    (TransportKind::BluetoothHidraw, &open_bluetooth),
+                ],
+            )
+            .expect("falls back to bluetooth");
+        assert_eq!(cache.cached_kind(), Some(TransportKind::BluetoothHidraw));
+        assert_eq!(bluetooth.reports().len(), 1);
+
+        cache.invalidate();
+        assert_eq!(cache.cached_kind(), None);
+        let err = cache
+            .send(
+                &backlight_report(1),
+                &[(TransportKind::UsbControl, &open_stale)],
+            )
+            .expect_err("nothing reaches the keyboard");
+        assert!(err.contains("usb: device disconnected"));
+    }
+
+    #[test]
+    fn matches_keyboard_hidraw_nodes_by_bus_and_interface() {
+        let usb = |phys: &str| {
+            format!(
+                "DRIVER=hid-generic\nHID_ID=0003:00000B05:00001B2C\nHID_NAME=ASUSTeK Computer Inc. ASUS Zenbook Duo Keyboard\nHID_PHYS={phys}\n"
+            )
+        };
+        let bluetooth = "HID_ID=0005:00000B05:00001B2D\nHID_NAME=ASUS Zenbook Duo Keyboard\nHID_PHYS=a0:b1:c2:d3:e4:f5\n";
+
+        assert!(hidraw_matches(
+            &usb("usb-0000:00:14.0-7/input4"),
+            TransportKind::UsbHidraw
+        ));
+        assert!(!hidraw_matches(
+            &usb("usb-0000:00:14.0-7/input0"),
+            TransportKind::UsbHidraw
+        ));
+        assert!(!hidraw_matches(
+            &usb("usb-0000:00:14.0-7/input4"),
+            TransportKind::BluetoothHidraw
+        ));
+        assert!(hidraw_matches(bluetooth, TransportKind::BluetoothHidraw));
+        assert!(!hidraw_matches(
+            "HID_ID=0005:0000046D:0000B023\nHID_NAME=MX Keys\n",
+            TransportKind::BluetoothHidraw
+        ));
+    }
+
+    #[test]
+    fn reads_back_the_level_the_keyboard_reports() {
+        let usb = FakeTransport::new(TransportKind::UsbControl);
+        let mut cache = TransportCache {
+            transport: Some(Box::new(usb.clone())),
+            ..TransportCache::new()
+        };
+
+        let before = cache.query(BACKLIGHT_REPORT_ID, &[]).expect("read");
+        assert_eq!(parse_backlight_report(&before), Some(0));
+        cache.send(&backlight_report(2), &[]).expect("write");
+        let after = cache.query(BACKLIGHT_REPORT_ID, &[]).expect("read");
+        assert_eq!(parse_backlight_report(&after), Some(2));
+
+        assert_eq!(parse_backlight_report(&[0x5A, 0, 0, 0, 2]), None);
+        assert_eq!(parse_backlight_report(&[0x5A, 0xBA, 0xC5, 0xC4, 7]), None);
+        assert_eq!(parse_backlight_report(&[0x5A]), None);
+    }
+
+    #[test]
+    fn failed_read_keeps_the_transport_for_writes() {
+        let bluetooth = FakeTransport::without_reads(TransportKind::BluetoothHidraw);
+        let no_usb = || Err("Zenbook Duo keyboard not found via USB".to_string());
+        let mut cache = TransportCache {
+            transport: Some(Box::new(bluetooth.clone())),
+            ..TransportCache::new()
+        };
 
-    Err(format!(
-        "Failed to set keyboard backlight natively (usb: {usb_err}; bt: {bt_err})"
-    ))
+        let err = cache
+            .query(BACKLIGHT_REPORT_ID, &[(TransportKind::UsbControl, &no_usb)])
+            .expect_err("firmware does not answer");
+        assert!(err.contains("cached bluetooth: Broken pipe"));
+        assert!(err.contains("usb: Zenbook Duo keyboard not found via USB"));
+        assert_eq!(cache.cached_kind(), Some(TransportKind::BluetoothHidraw));
+
+        cache.send(&backlight_report(1), &[]).expect("write");
+        assert_eq!(bluetooth.reports().len(), 1);
+    }
+
+    #[test]
+    fn transport_that_cannot_read_is_not_asked_again_until_invalidated() {
+        let bluetooth = FakeTransport::without_reads(TransportKind::BluetoothHidraw);
+        let opened = Cell::new(0);
+        let no_usb = || {
+            opened.set(opened.get() + 1);
+            Err("Zenbook Duo keyboard not found via USB".to_string())
+        };
+        let openers: [TransportOpener; 1] = [(TransportKind::UsbControl, &no_usb)];
+        let mut cache = TransportCache {
+            transport: Some(Box::new(bluetooth.clone())),
+            ..TransportCache::new()
+        };
+
+        cache
+            .query(BACKLIGHT_REPORT_ID, &openers)
+            .expect_err("firmware does not answer");
+        let err = cache
+            .query(BACKLIGHT_REPORT_ID, &openers)
+            .expect_err("still unanswered");
+        assert!(err.contains("bluetooth does not answer"));
+        assert_eq!(opened.get(), 1);
+        cache.send(&backlight_report(1), &openers).expect("write");
+
+        cache.invalidate();
+        cache
+            .query(BACKLIGHT_REPORT_ID, &openers)
+            .expect_err("no transport left");
+        assert_eq!(opened.get(), 2);
+    }
 }
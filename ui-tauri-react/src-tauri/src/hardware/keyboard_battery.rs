@@ -0,0 +1,192 @@
+use std::fs;
+use std::path::Path;
+
+const POWER_SUPPLY_ROOT: &str = "/sys/class/power_supply";
+const BLUEZ_SERVICE: &str = "org.bluez";
+const BLUEZ_DEVICE: &str = "org.bluez.Device1";
+const BLUEZ_BATTERY: &str = "org.bluez.Battery1";
+
+/// The detached keyboard's battery, as BlueZ or the kernel reports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyboardBattery {
+    pub percent: u8,
+    /// `None` when the source does not say, as `Battery1` never does.
+    pub charging: Option<bool>,
+}
+
+/// BlueZ `Battery1` gives the percentage; the HID `power_supply` node the
+/// kernel creates for the keyboard adds the charging state and stands in
+/// when BlueZ has no battery for it. `None` when the keyboard is not
+/// connected over Bluetooth.
+pub fn read() -> Option<KeyboardBattery> {
+    let node = read_power_supply(Path::new(POWER_SUPPLY_ROOT));
+    match bluez_percent() {
+        Ok(Some(percent)) => Some(KeyboardBattery {
+            percent,
+            charging: node.and_then(|node| node.charging),
+        }),
+        Ok(None) | Err(_) => node,
+    }
+}
+
+fn is_keyboard(name: &str) -> bool {
+    name.contains("Zenbook Duo Keyboard") || name.contains("ASUS_DUO")
+}
+
+/// Device-scoped supplies are peripherals; the laptop's own battery and AC
+/// adapter are `System`.
+fn read_power_supply(root: &Path) -> Option<KeyboardBattery> {
+    let read = |dir: &Path, name: &str| {
+        fs::read_to_string(dir.join(name))
+            .ok()
+            .map(|value| value.trim().to_string())
+    };
+    fs::read_dir(root).ok()?.flatten().find_map(|entry| {
+        let dir = entry.path();
+        if read(&dir, "scope").as_deref() != Some("Device")
+            || !read(&dir, "model_name").is_some_and(|name| is_keyboard(&name))
+        {
+            return None;
+        }
+        let percent = read(&dir, "capacity")?.parse::<u8>().ok()?.min(100);
+        let charging = match read(&dir, "status").as_deref() {
+            Some("Charging") => Some(true),
+            Some("Discharging" | "Not charging" | "Full") => Some(false),
+            _ => None,
+        };
+        Some(KeyboardBattery { percent, charging })
+    })
+}
+
+/// A BlueZ device as far as the battery lookup cares.
+#[derive(Debug, Default)]
+struct BluezDevice {
+    name: String,
+    connected: bool,
+    battery: Option<u8>,
+}
+
+fn bluez_percent() -> Result<Option<u8>, String> {
+    let connection = crate::runtime::radio::system_bus()
+        .map_err(|e| format!("Failed to connect to the system bus: {e}"))?;
+    let objects = zbus::blocking::fdo::ObjectManagerProxy::builder(&connection)
+        .destination(BLUEZ_SERVICE)
+        .and_then(|builder| builder.path("/"))
+        .and_then(|builder| builder.build())
+        .and_then(|proxy| proxy.get_managed_objects().map_err(Into::into))
+        .map_err(|e| format!("Failed to list BlueZ devices: {e}"))?;
+
+    let devices: Vec<BluezDevice> = objects
+        .values()
+        .filter_map(|interfaces| {
+            let property = |interface: &str, name: &str| {
+                interfaces
+                    .iter()
+                    .find(|(key, _)| key.as_str() == interface)
+                    .and_then(|(_, properties)| properties.get(name))
+            };
+            let device = property(BLUEZ_DEVICE, "Connected")?;
+            Some(BluezDevice {
+                name: property(BLUEZ_DEVICE, "Name")
+                    .or_else(|| property(BLUEZ_DEVICE, "Alias"))
+                    .and_then(|value| value.downcast_ref::<&str>().ok())
+                    .unwrap_or_default()
+                    .to_string(),
+                connected: device.downcast_ref::<bool>().unwrap_or(false),
+                battery: property(BLUEZ_BATTERY, "Percentage")
+                    .and_then(|value| value.downcast_ref::<u8>().ok()),
+            })
+        })
+        .collect();
+    Ok(keyboard_percent(&devices))
+}
+
+fn keyboard_percent(devices: &[BluezDevice]) -> Option<u8> {
+    devices
+        .iter()
+        .find(|device| device.connected && is_keyboard(&device.name))
+        .and_then(|device| device.battery)
+        .map(|percent| percent.min(100))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn supply(root: &Path, name: &str, files: &[(&str, &str)]) {
+        let dir = root.join(name);
+        fs::create_dir_all(&dir).expect("create supply");
+        for (file, value) in files {
+            fs::write(dir.join(file), format!("{value}\n")).expect("write supply file");
+        }
+    }
+
+    fn scratch() -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        std::env::temp_dir().join(format!("zenbook-duo-power-supply-{unique}"))
+    }
+
+    #[test]
+    fn reads_the_keyboard_power_supply_and_skips_system_batteries() {
+        let root = scratch();
+        supply(
+            &root,
+            "BAT0",
+            &[
+                ("scope", "System"),
+                ("capacity", "80"),
+                ("status", "Charging"),
+            ],
+        );
+        supply(&root, "AC0", &[("online", "1")]);
+        assert_eq!(read_power_supply(&root), None);
+
+        supply(
+            &root,
+            "hid-a0:b1:c2:d3:e4:f5-battery",
+            &[
+                ("scope", "Device"),
+                ("model_name", "ASUS Zenbook Duo Keyboard"),
+                ("capacity", "42"),
+                ("status", "Discharging"),
+            ],
+        );
+        assert_eq!(
+            read_power_supply(&root),
+            Some(KeyboardBattery {
+                percent: 42,
+                charging: Some(false),
+            })
+        );
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn only_a_connected_keyboard_reports_a_bluez_battery() {
+        let mouse = BluezDevice {
+            name: "MX Master 3".into(),
+            connected: true,
+            battery: Some(90),
+        };
+        let keyboard = |connected| BluezDevice {
+            name: "ASUS Zenbook Duo Keyboard".into(),
+            connected,
+            battery: Some(17),
+        };
+
+        assert_eq!(keyboard_percent(&[mouse, keyboard(false)]), None);
+        assert_eq!(keyboard_percent(&[keyboard(true)]), Some(17));
+        assert_eq!(
+            keyboard_percent(&[BluezDevice {
+                battery: None,
+                ..keyboard(true)
+            }]),
+            None
+        );
+    }
+}
@@ -1,5 +1,7 @@
 pub mod display_layout;
 pub mod duo;
 pub mod hid;
+pub mod keyboard_battery;
+pub mod rfkill;
 pub mod sysfs;
 pub mod touchscreen;
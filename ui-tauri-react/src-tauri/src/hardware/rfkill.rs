@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+
+const RFKILL_PATH: &str = "/dev/rfkill";
+/// `struct rfkill_event`: idx (u32), type, op, soft, hard. Kernels since 5.11
+/// append a `hard_block_reasons` byte, which we read past and never write.
+const RFKILL_EVENT_SIZE: usize = 8;
+const RFKILL_READ_SIZE: usize = 32;
+
+/// Switch types from `linux/rfkill.h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RfkillType {
+    All,
+    Wlan,
+    Bluetooth,
+    Other(u8),
+}
+
+impl RfkillType {
+    fn from_raw(raw: u8) -> Self {
+        match raw {
+            0 => RfkillType::All,
+            1 => RfkillType::Wlan,
+            2 => RfkillType::Bluetooth,
+            other => RfkillType::Other(other),
+        }
+    }
+
+    fn raw(self) -> u8 {
+        match self {
+            RfkillType::All => 0,
+            RfkillType::Wlan => 1,
+            RfkillType::Bluetooth => 2,
+            RfkillType::Other(raw) => raw,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RfkillOp {
+    Add,
+    Del,
+    Change,
+    /// Only valid when writing: applies to every switch of the type.
+    ChangeAll,
+    Other(u8),
+}
+
+impl RfkillOp {
+    fn from_raw(raw: u8) -> Self {
+        match raw {
+            0 => RfkillOp::Add,
+            1 => RfkillOp::Del,
+            2 => RfkillOp::Change,
+            3 => RfkillOp::ChangeAll,
+            other => RfkillOp::Other(other),
+        }
+    }
+
+    fn raw(self) -> u8 {
+        match self {
+            RfkillOp::Add => 0,
+            RfkillOp::Del => 1,
+            RfkillOp::Change => 2,
+            RfkillOp::ChangeAll => 3,
+            RfkillOp::Other(raw) => raw,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RfkillEvent {
+    pub idx: u32,
+    pub kind: RfkillType,
+    pub op: RfkillOp,
+    pub soft_blocked: bool,
+    pub hard_blocked: bool,
+}
+
+impl RfkillEvent {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let bytes = bytes.get(..RFKILL_EVENT_SIZE)?;
+        Some(Self {
+            idx: u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            kind: RfkillType::from_raw(bytes[4]),
+            op: RfkillOp::from_raw(bytes[5]),
+            soft_blocked: bytes[6] != 0,
+            hard_blocked: bytes[7] != 0,
+        })
+    }
+
+    pub fn encode(&self) -> [u8; RFKILL_EVENT_SIZE] {
+        let idx = self.idx.to_ne_bytes();
+        [
+            idx[0],
+            idx[1],
+            idx[2],
+            idx[3],
+            self.kind.raw(),
+            self.op.raw(),
+            self.soft_blocked as u8,
+            self.hard_blocked as u8,
+        ]
+    }
+}
+
+/// One rfkill switch as the kernel last reported it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RfkillDevice {
+    pub idx: u32,
+    pub kind: RfkillType,
+    pub soft_blocked: bool,
+    pub hard_blocked: bool,
+}
+
+/// Narrow interface over `/dev/rfkill` so radio logic can be tested without
+/// touching the host's switches.
+pub trait RfkillControl {
+    fn devices(&self) -> Result<Vec<RfkillDevice>, String>;
+    fn set_soft_blocked(&self, kind: RfkillType, blocked: bool) -> Result<(), String>;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DevRfkill;
+
+impl RfkillControl for DevRfkill {
+    /// Opening `/dev/rfkill` queues an `Add` event per existing switch, so a
+    /// non-blocking read until `EAGAIN` yields the current state.
+    fn devices(&self) -> Result<Vec<RfkillDevice>, String> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(RFKILL_PATH)
+            .map_err(|e| format!("Failed to open {RFKILL_PATH}: {e}"))?;
+
+        let mut events = Vec::new();
+        loop {
+            match read_event(&mut file) {
+                Ok(Some(event)) => events.push(event),
+                Ok(None) => break,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(format!("Failed to read {RFKILL_PATH}: {err}")),
+            }
+        }
+        Ok(apply_events(events))
+    }
+
+    fn set_soft_blocked(&self, kind: RfkillType, blocked: bool) -> Result<(), String> {
+        let event = RfkillEvent {
+            idx: 0,
+            kind,
+            op: RfkillOp::ChangeAll,
+            soft_blocked: blocked,
+            hard_blocked: false,
+        };
+        OpenOptions::new()
+            .write(true)
+            .open(RFKILL_PATH)
+            .and_then(|mut file| file.write_all(&event.encode()))
+            .map_err(|e| format!("Failed to write {RFKILL_PATH}: {e}"))
+    }
+}
+
+/// Folds the event stream read from `/dev/rfkill` into the switches that
+/// still exist.
+pub fn apply_events(events: impl IntoIterator<Item = RfkillEvent>) -> Vec<RfkillDevice> {
+    let mut devices = BTreeMap::new();
+    for event in events {
+        match event.op {
+            RfkillOp::Add | RfkillOp::Change => {
+                devices.insert(
+                    event.idx,
+                    RfkillDevice {
+                        idx: event.idx,
+                        kind: event.kind,
+                        soft_blocked: event.soft_blocked,
+                        hard_blocked: event.hard_blocked,
+                    },
+                );
+            }
+            RfkillOp::Del => {
+                devices.remove(&event.idx);
+            }
+            RfkillOp::ChangeAll | RfkillOp::Other(_) => {}
+        }
+    }
+    devices.into_values().collect()
+}
+
+/// Whether any switch of `kind` lets the radio transmit, or `None` when the
+/// machine has no such switch.
+pub fn radio_unblocked(devices: &[RfkillDevice], kind: RfkillType) -> Option<bool> {
+    let mut matching = devices
+        .iter()
+        .filter(|device| device.kind == kind)
+        .peekable();
+    matching.peek()?;
+    Some(matching.any(|device| !device.soft_blocked && !device.hard_blocked))
+}
+
+/// Blocking reader for switch changes, used by the daemon's radio watcher.
+pub struct RfkillMonitor {
+    file: File,
+}
+
+impl RfkillMonitor {
+    pub fn open() -> Result<Self, String> {
+        File::open(RFKILL_PATH)
+            .map(|file| Self { file })
+            .map_err(|e| format!("Failed to open {RFKILL_PATH}: {e}"))
+    }
+
+    pub fn next_event(&mut self) -> io::Result<RfkillEvent> {
+        loop {
+            match read_event(&mut self.file) {
+                Ok(Some(event)) => return Ok(event),
+                Ok(None) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "rfkill device closed",
+                    ))
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+fn read_event(file: &mut File) -> io::Result<Option<RfkillEvent>> {
+    let mut buffer = [0u8; RFKILL_READ_SIZE];
+    let read = file.read(&mut buffer)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    RfkillEvent::parse(&buffer[..read])
+        .map(Some)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("short rfkill event ({read} bytes)"),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(idx: u32, kind: RfkillType, op: RfkillOp, soft_blocked: bool) -> RfkillEvent {
+        RfkillEvent {
+            idx,
+            kind,
+            op,
+            soft_blocked,
+            hard_blocked: false,
+        }
+    }
+
+    #[test]
+    fn event_round_trips_and_ignores_extended_reason_byte() {
+        let written = event(3, RfkillType::Bluetooth, RfkillOp::ChangeAll, true);
+        let mut extended = written.encode().to_vec();
+        extended.push(0x01);
+
+        assert_eq!(RfkillEvent::parse(&extended), Some(written));
+        assert_eq!(RfkillEvent::parse(&extended[..7]), None);
+    }
+
+    #[test]
+    fn folds_add_change_and_delete_events() {
+        let devices = apply_events([
+            event(0, RfkillType::Wlan, RfkillOp::Add, false),
+            event(1, RfkillType::Bluetooth, RfkillOp::Add, false),
+            event(2, RfkillType::Bluetooth, RfkillOp::Add, true),
+            event(1, RfkillType::Bluetooth, RfkillOp::Change, true),
+            event(0, RfkillType::Wlan, RfkillOp::Del, false),
+        ]);
+
+        assert_eq!(devices.len(), 2);
+        assert_eq!(radio_unblocked(&devices, RfkillType::Wlan), None);
+        assert_eq!(
+            radio_unblocked(&devices, RfkillType::Bluetooth),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn hard_block_keeps_radio_off() {
+        let devices = [RfkillDevice {
+            idx: 0,
+            kind: RfkillType::Wlan,
+            soft_blocked: false,
+            hard_blocked: true,
+        }];
+
+        assert_eq!(radio_unblocked(&devices, RfkillType::Wlan), Some(false));
+    }
+}
@@ -5,6 +5,8 @@ use crate::models::{ConnectionType, DuoStatus, Orientation};
 use crate::runtime::{paths, state::RuntimeState};
 
 const BACKLIGHT_ROOT: &str = "/sys/class/backlight";
+const POWER_SUPPLY_ROOT: &str = "/sys/class/power_supply";
+const DRM_ROOT: &str = "/sys/class/drm";
 
 fn load_runtime_state() -> Option<RuntimeState> {
     let path = paths::state_file_path();
@@ -70,6 +72,9 @@ fn read_backlight_value(path: &Path) -> Option<u32> {
         .and_then(|s| s.trim().parse().ok())
 }
 
+/// The level the daemon last recorded in its state file. Prefer
+/// [`hid::read_backlight`](crate::hardware::hid::read_backlight) where the
+/// keyboard can be asked.
 pub fn read_backlight_level() -> u8 {
     load_runtime_state()
         .map(|state| state.status.backlight_level)
@@ -88,6 +93,74 @@ pub fn read_max_brightness() -> u32 {
         .unwrap_or(1)
 }
 
+/// A share of `max`, never below 1 so a dark room cannot blank the panel.
+fn brightness_for_percent(percent: u8, max: u32) -> u32 {
+    (u64::from(max) * u64::from(percent.min(100)) / 100).max(1) as u32
+}
+
+fn write_brightness_percent(dir: &Path, percent: u8) -> Result<u32, String> {
+    let max = read_backlight_value(&dir.join("max_brightness")).unwrap_or(1);
+    let value = brightness_for_percent(percent, max);
+    fs::write(dir.join("brightness"), value.to_string())
+        .map_err(|e| format!("Failed to write brightness for {}: {e}", dir.display()))?;
+    Ok(value)
+}
+
+fn set_panel_brightness_from(root: &Path, percent: u8) -> Result<u32, String> {
+    let primary = primary_backlight_dir_from(root).ok_or("no primary backlight device found")?;
+    let value = write_brightness_percent(&primary, percent)?;
+    if let Some(secondary) = secondary_backlight_dir_from(root) {
+        write_brightness_percent(&secondary, percent)?;
+    }
+    Ok(value)
+}
+
+/// Sets both panels to `percent` of their own maximum and returns the
+/// primary panel's new raw brightness.
+pub fn set_panel_brightness(percent: u8) -> Result<u32, String> {
+    set_panel_brightness_from(Path::new(BACKLIGHT_ROOT), percent)
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn on_ac_power_from(root: &Path) -> Option<bool> {
+    let mut adapters = fs::read_dir(root)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| read_trimmed(&path.join("type")).as_deref() == Some("Mains"))
+        .peekable();
+    adapters.peek()?;
+    Some(adapters.any(|path| read_trimmed(&path.join("online")).as_deref() == Some("1")))
+}
+
+/// Whether a mains adapter is plugged in, or `None` when the machine reports
+/// no adapter at all.
+pub fn on_ac_power() -> Option<bool> {
+    on_ac_power_from(Path::new(POWER_SUPPLY_ROOT))
+}
+
+fn external_display_connected_from(root: &Path) -> bool {
+    fs::read_dir(root)
+        .into_iter()
+        .flat_map(|entries| entries.flatten())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = backlight_name(path);
+            // Connectors are `cardN-<connector>`; eDP panels are built in.
+            name.contains('-') && !name.contains("-edp-")
+        })
+        .any(|path| read_trimmed(&path.join("status")).as_deref() == Some("connected"))
+}
+
+/// Whether any DRM connector other than the built-in eDP panels reports a
+/// connected display.
+pub fn external_display_connected() -> bool {
+    external_display_connected_from(Path::new(DRM_ROOT))
+}
+
 pub fn detect_connection_type() -> ConnectionType {
     let hidraw_dir = Path::new("/sys/class/hidraw");
     if !hidraw_dir.exists() {
@@ -171,32 +244,11 @@ pub fn get_full_status() -> DuoStatus {
         max_brightness: read_max_brightness(),
         service_active: is_service_active(),
         orientation: Orientation::Normal,
+        keyboard_battery_percent: None,
+        keyboard_battery_charging: None,
     }
 }
 
-pub fn clear_log() -> Result<(), String> {
-    let runtime_path = paths::log_file_path();
-    if let Some(parent) = runtime_path.parent() {
-        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-    }
-    fs::write(runtime_path, "").map_err(|e| e.to_string())
-}
-
-pub fn read_log_lines(count: usize) -> Vec<String> {
-    let runtime_path = paths::log_file_path();
-    let contents = fs::read_to_string(&runtime_path).unwrap_or_default();
-
-    contents
-        .lines()
-        .rev()
-        .take(count)
-        .map(String::from)
-        .collect::<Vec<_>>()
-        .into_iter()
-        .rev()
-        .collect()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -239,6 +291,67 @@ mod tests {
         fs::remove_dir_all(root).expect("remove temp dir");
     }
 
+    #[test]
+    fn panel_brightness_scales_each_panel_to_its_own_maximum() {
+        let root = unique_temp_dir("panel-brightness");
+        create_backlight(&root, "intel_backlight");
+        create_backlight(&root, "card1-eDP-2-backlight");
+        fs::write(
+            root.join("card1-eDP-2-backlight").join("max_brightness"),
+            "255",
+        )
+        .expect("write secondary max brightness");
+
+        assert_eq!(set_panel_brightness_from(&root, 50), Ok(200));
+        let read = |name: &str| read_backlight_value(&root.join(name).join("brightness"));
+        assert_eq!(read("intel_backlight"), Some(200));
+        assert_eq!(read("card1-eDP-2-backlight"), Some(127));
+
+        assert_eq!(set_panel_brightness_from(&root, 0), Ok(1));
+        assert_eq!(set_panel_brightness_from(&root, 150), Ok(400));
+
+        fs::remove_dir_all(root).expect("remove temp dir");
+    }
+
+    #[test]
+    fn reads_mains_adapter_state_and_ignores_batteries() {
+        let root = unique_temp_dir("power");
+        assert_eq!(on_ac_power_from(&root), None);
+
+        create_node(&root.join("BAT0"), &[("type", "Battery"), ("online", "1")]);
+        assert_eq!(on_ac_power_from(&root), None);
+
+        create_node(&root.join("ADP0"), &[("type", "Mains"), ("online", "0")]);
+        assert_eq!(on_ac_power_from(&root), Some(false));
+
+        fs::write(root.join("ADP0").join("online"), "1\n").expect("plug adapter");
+        assert_eq!(on_ac_power_from(&root), Some(true));
+
+        fs::remove_dir_all(root).expect("remove temp dir");
+    }
+
+    #[test]
+    fn only_non_edp_connectors_count_as_external_displays() {
+        let root = unique_temp_dir("drm");
+        create_node(&root.join("card1"), &[]);
+        create_node(&root.join("card1-eDP-1"), &[("status", "connected")]);
+        create_node(&root.join("card1-eDP-2"), &[("status", "connected")]);
+        create_node(&root.join("card1-DP-1"), &[("status", "disconnected")]);
+        assert!(!external_display_connected_from(&root));
+
+        create_node(&root.join("card1-HDMI-A-1"), &[("status", "connected")]);
+        assert!(external_display_connected_from(&root));
+
+        fs::remove_dir_all(root).expect("remove temp dir");
+    }
+
+    fn create_node(dir: &Path, attributes: &[(&str, &str)]) {
+        fs::create_dir_all(dir).expect("create sysfs node");
+        for (name, value) in attributes {
+            fs::write(dir.join(name), format!("{value}\n")).expect("write sysfs attribute");
+        }
+    }
+
     fn create_backlight(root: &Path, name: &str) {
         let dir = root.join(name);
         fs::create_dir_all(&dir).expect("create backlight dir");
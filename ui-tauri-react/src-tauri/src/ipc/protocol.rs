@@ -1,32 +1,92 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::commands::usb_media_remap::UsbMediaRemapStatus;
 use crate::models::{
-    DaemonVersionInfo, DisplayLayout, DuoSettings, DuoStatus, HardwareEvent, Orientation,
+    DaemonVersionInfo, DisplayLayout, DuoSettings, DuoStatus, EventCategory, EventQuery,
+    EventSeverity, HardwareEvent, HookInvocation, HookOutcome, LogLevel, LogQuery, LogRecord,
+    MetricsSnapshot, Orientation, PostureReport, ProtocolFeature, MAX_HOOKS_PER_RUN,
 };
 
-pub const PROTOCOL_VERSION: u32 = 1;
+/// Newest protocol dialect this build speaks.
+pub const PROTOCOL_VERSION: u32 = 11;
+/// Oldest dialect this build still accepts and answers in.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+/// How long the daemon waits for most session-agent replies.
+pub const SESSION_REPLY_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub fn is_supported_version(version: u32) -> bool {
+    (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version)
+}
+
+/// Picks the newest version both sides speak, if their ranges overlap.
+pub fn negotiate_version(peer_min: u32, peer_max: u32) -> Option<u32> {
+    let highest = peer_max.min(PROTOCOL_VERSION);
+    let lowest = peer_min.max(MIN_PROTOCOL_VERSION);
+    (lowest <= highest).then_some(highest)
+}
+
+/// Error text for rejected envelopes. The `expected N,` prefix is what
+/// version-1 clients parse, so it must stay stable.
+pub fn protocol_mismatch_message(got: u32) -> String {
+    format!(
+        "Protocol mismatch: expected {PROTOCOL_VERSION}, got {got} (supported {MIN_PROTOCOL_VERSION}-{PROTOCOL_VERSION})"
+    )
+}
+
+pub fn parse_protocol_mismatch(message: &str) -> Option<u32> {
+    message
+        .strip_prefix("Protocol mismatch: expected ")?
+        .split_once(',')?
+        .0
+        .parse()
+        .ok()
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Envelope<T> {
     pub protocol_version: u32,
+    /// Caller-chosen correlation id. Requests that carry one may be answered
+    /// out of order; the response repeats the id so the caller can match it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
     pub payload: T,
 }
 
 impl<T> Envelope<T> {
     pub fn new(payload: T) -> Self {
+        Self::with_version(PROTOCOL_VERSION, payload)
+    }
+
+    pub fn with_version(protocol_version: u32, payload: T) -> Self {
         Self {
-            protocol_version: PROTOCOL_VERSION,
+            protocol_version,
+            id: None,
             payload,
         }
     }
+
+    pub fn with_id(mut self, id: Option<u64>) -> Self {
+        self.id = id;
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum DaemonRequest {
     Ping,
+    /// Version handshake: the client states the range it speaks and the
+    /// features it knows; the daemon answers with the negotiated dialect.
+    Hello {
+        min_version: u32,
+        max_version: u32,
+        #[serde(default)]
+        features: Vec<ProtocolFeature>,
+    },
     HandleLifecycle {
         phase: LifecyclePhase,
     },
@@ -50,6 +110,11 @@ pub enum DaemonRequest {
     UsbMediaRemapStart,
     UsbMediaRemapStop,
     UsbMediaRemapTogglePause,
+    /// Pauses or resumes the remap helper; a no-op when it is already in
+    /// that state, so concurrent callers cannot undo each other.
+    UsbMediaRemapSetPaused {
+        paused: bool,
+    },
     RestartService,
     RegisterSessionAgent {
         session_id: String,
@@ -66,11 +131,141 @@ pub enum DaemonRequest {
     GetRecentEvents {
         limit: usize,
     },
+    /// Searches the on-disk event journal; see `EventQuery` for the filters.
+    QueryEvents {
+        #[serde(default)]
+        since: Option<DateTime<Utc>>,
+        #[serde(default)]
+        until: Option<DateTime<Utc>>,
+        #[serde(default)]
+        categories: Vec<EventCategory>,
+        #[serde(default)]
+        min_severity: Option<EventSeverity>,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// Searches the component logs; see `LogQuery` for the filters. With
+    /// `follow`, the connection keeps receiving `LogRecords` batches (tagged
+    /// with the request's `id`) as matching lines are appended, until the
+    /// client hangs up.
+    QueryLogs {
+        #[serde(default)]
+        since: Option<DateTime<Utc>>,
+        #[serde(default)]
+        until: Option<DateTime<Utc>>,
+        #[serde(default)]
+        components: Vec<String>,
+        #[serde(default)]
+        min_level: Option<LogLevel>,
+        #[serde(default)]
+        contains: Option<String>,
+        #[serde(default)]
+        pattern: Option<String>,
+        #[serde(default)]
+        limit: Option<usize>,
+        #[serde(default)]
+        follow: bool,
+    },
+    /// Counters and latency histograms from the daemon, merged with the
+    /// session agent's when one is connected.
+    GetMetrics,
+    /// Current device posture and the transitions that led to it.
+    GetPosture,
+    /// A key went down on the docked keyboard; sent by the remap helper so
+    /// the idle timeout can restart and wake the backlight.
+    KeyboardActivity,
+    /// The panel brightness was stepped by hand; the ambient light sensor
+    /// leaves it alone for the cool-down.
+    ManualBrightness,
     ListTouchscreens,
     SetTouchscreenEnabled {
         connector: String,
         enabled: bool,
     },
+    /// Turns the connection into a stream of `Envelope<DaemonEvent>` lines
+    /// after the daemon acknowledges. An empty category list means everything.
+    Subscribe {
+        #[serde(default)]
+        categories: Vec<SubscriptionCategory>,
+    },
+}
+
+impl DaemonRequest {
+    /// Wire name of the request, matching its serde tag.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DaemonRequest::Ping => "ping",
+            DaemonRequest::Hello { .. } => "hello",
+            DaemonRequest::HandleLifecycle { .. } => "handle_lifecycle",
+            DaemonRequest::GetStatus => "get_status",
+            DaemonRequest::GetVersion => "get_version",
+            DaemonRequest::GetDisplayLayout => "get_display_layout",
+            DaemonRequest::GetSettings => "get_settings",
+            DaemonRequest::SaveSettings { .. } => "save_settings",
+            DaemonRequest::SetBacklight { .. } => "set_backlight",
+            DaemonRequest::SetOrientation { .. } => "set_orientation",
+            DaemonRequest::ApplyDisplayLayout { .. } => "apply_display_layout",
+            DaemonRequest::UsbMediaRemapStatus => "usb_media_remap_status",
+            DaemonRequest::UsbMediaRemapStart => "usb_media_remap_start",
+            DaemonRequest::UsbMediaRemapStop => "usb_media_remap_stop",
+            DaemonRequest::UsbMediaRemapTogglePause => "usb_media_remap_toggle_pause",
+            DaemonRequest::UsbMediaRemapSetPaused { .. } => "usb_media_remap_set_paused",
+            DaemonRequest::RestartService => "restart_service",
+            DaemonRequest::RegisterSessionAgent { .. } => "register_session_agent",
+            DaemonRequest::AppendLog { .. } => "append_log",
+            DaemonRequest::TailLogs { .. } => "tail_logs",
+            DaemonRequest::ClearLogs => "clear_logs",
+            DaemonRequest::GetRecentEvents { .. } => "get_recent_events",
+            DaemonRequest::QueryEvents { .. } => "query_events",
+            DaemonRequest::QueryLogs { .. } => "query_logs",
+            DaemonRequest::GetMetrics => "get_metrics",
+            DaemonRequest::GetPosture => "get_posture",
+            DaemonRequest::KeyboardActivity => "keyboard_activity",
+            DaemonRequest::ManualBrightness => "manual_brightness",
+            DaemonRequest::ListTouchscreens => "list_touchscreens",
+            DaemonRequest::SetTouchscreenEnabled { .. } => "set_touchscreen_enabled",
+            DaemonRequest::Subscribe { .. } => "subscribe",
+        }
+    }
+
+    pub fn query_events(query: EventQuery) -> Self {
+        DaemonRequest::QueryEvents {
+            since: query.since,
+            until: query.until,
+            categories: query.categories,
+            min_severity: query.min_severity,
+            limit: query.limit,
+        }
+    }
+
+    pub fn query_logs(query: LogQuery, follow: bool) -> Self {
+        DaemonRequest::QueryLogs {
+            since: query.since,
+            until: query.until,
+            components: query.components,
+            min_level: query.min_level,
+            contains: query.contains,
+            pattern: query.pattern,
+            limit: query.limit,
+            follow,
+        }
+    }
+
+    /// Feature a request belongs to when it is newer than the baseline dialect.
+    pub fn required_feature(&self) -> Option<ProtocolFeature> {
+        match self {
+            DaemonRequest::Hello { .. } => Some(ProtocolFeature::Handshake),
+            DaemonRequest::Subscribe { .. } => Some(ProtocolFeature::EventSubscription),
+            DaemonRequest::QueryEvents { .. } => Some(ProtocolFeature::EventJournal),
+            DaemonRequest::QueryLogs { .. } => Some(ProtocolFeature::LogQuery),
+            DaemonRequest::GetMetrics => Some(ProtocolFeature::Metrics),
+            DaemonRequest::GetPosture => Some(ProtocolFeature::Posture),
+            DaemonRequest::KeyboardActivity => Some(ProtocolFeature::KeyboardActivity),
+            DaemonRequest::ManualBrightness => Some(ProtocolFeature::AmbientLight),
+            DaemonRequest::UsbMediaRemapSetPaused { .. } => Some(ProtocolFeature::RemapPause),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,6 +273,9 @@ pub enum DaemonRequest {
 pub enum DaemonResponse {
     Pong,
     Ack,
+    Hello {
+        handshake: Handshake,
+    },
     Status {
         status: DuoStatus,
     },
@@ -96,9 +294,18 @@ pub enum DaemonResponse {
     Logs {
         lines: Vec<String>,
     },
+    LogRecords {
+        records: Vec<LogRecord>,
+    },
     Events {
         events: Vec<HardwareEvent>,
     },
+    Metrics {
+        metrics: MetricsSnapshot,
+    },
+    Posture {
+        posture: PostureReport,
+    },
     Touchscreens {
         devices: Vec<crate::hardware::touchscreen::TouchscreenDevice>,
     },
@@ -107,6 +314,50 @@ pub enum DaemonResponse {
     },
 }
 
+impl DaemonResponse {
+    /// Rewrites responses that did not exist in `version` into the shape an
+    /// older client still understands.
+    pub fn for_version(self, version: u32) -> Self {
+        match self {
+            DaemonResponse::LogRecords { records }
+                if version < ProtocolFeature::StructuredLogs.introduced_in() =>
+            {
+                DaemonResponse::Logs {
+                    lines: records.iter().map(ToString::to_string).collect(),
+                }
+            }
+            other => other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Handshake {
+    pub daemon_version: String,
+    pub negotiated_version: u32,
+    pub min_version: u32,
+    pub max_version: u32,
+    pub features: Vec<ProtocolFeature>,
+}
+
+impl Handshake {
+    /// Features offered at `negotiated_version`, narrowed to the ones the
+    /// client listed when it listed any.
+    pub fn negotiated(negotiated_version: u32, client_features: &[ProtocolFeature]) -> Self {
+        Self {
+            daemon_version: env!("CARGO_PKG_VERSION").to_string(),
+            negotiated_version,
+            min_version: MIN_PROTOCOL_VERSION,
+            max_version: PROTOCOL_VERSION,
+            features: ProtocolFeature::available_in(negotiated_version)
+                .into_iter()
+                .filter(|feature| client_features.is_empty() || client_features.contains(feature))
+                .collect(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum LifecyclePhase {
@@ -133,6 +384,28 @@ pub enum DaemonEvent {
     },
 }
 
+impl DaemonEvent {
+    pub fn category(&self) -> SubscriptionCategory {
+        match self {
+            DaemonEvent::StatusChanged { .. } => SubscriptionCategory::Status,
+            DaemonEvent::HardwareEvent { .. } => SubscriptionCategory::Hardware,
+            DaemonEvent::SessionAgentChanged { .. } => SubscriptionCategory::SessionAgent,
+        }
+    }
+
+    pub fn matches(&self, categories: &[SubscriptionCategory]) -> bool {
+        categories.is_empty() || categories.contains(&self.category())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionCategory {
+    Status,
+    Hardware,
+    SessionAgent,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum SessionBackend {
@@ -164,6 +437,40 @@ pub enum SessionCommand {
         urgent: bool,
     },
     OpenEmojiPicker,
+    GetMetrics,
+    /// Runs the user's hook scripts for a transition.
+    RunHooks {
+        invocation: HookInvocation,
+        timeout_ms: u64,
+    },
+}
+
+impl SessionCommand {
+    /// Wire name of the command, matching its serde tag.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SessionCommand::GetDisplayLayout => "get_display_layout",
+            SessionCommand::SetDockMode { .. } => "set_dock_mode",
+            SessionCommand::ApplyDisplayLayout { .. } => "apply_display_layout",
+            SessionCommand::SetOrientation { .. } => "set_orientation",
+            SessionCommand::ShowNotification { .. } => "show_notification",
+            SessionCommand::OpenEmojiPicker => "open_emoji_picker",
+            SessionCommand::GetMetrics => "get_metrics",
+            SessionCommand::RunHooks { .. } => "run_hooks",
+        }
+    }
+
+    /// How long the daemon waits for the reply. Hook runs can legitimately
+    /// take as long as their scripts' combined timeouts.
+    pub fn reply_timeout(&self) -> Duration {
+        match self {
+            SessionCommand::RunHooks { timeout_ms, .. } => {
+                SESSION_REPLY_TIMEOUT
+                    + Duration::from_millis(*timeout_ms) * MAX_HOOKS_PER_RUN as u32
+            }
+            _ => SESSION_REPLY_TIMEOUT,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -171,5 +478,97 @@ pub enum SessionCommand {
 pub enum SessionResponse {
     Ack,
     DisplayLayout { layout: DisplayLayout },
+    Metrics { metrics: MetricsSnapshot },
+    HookOutcomes { outcomes: Vec<HookOutcome> },
     Error { message: String },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiation_picks_newest_shared_version() {
+        assert_eq!(negotiate_version(1, 1), Some(1));
+        assert_eq!(negotiate_version(1, 12), Some(PROTOCOL_VERSION));
+        assert_eq!(negotiate_version(PROTOCOL_VERSION + 1, PROTOCOL_VERSION + 3), None);
+        assert_eq!(negotiate_version(0, 0), None);
+    }
+
+    #[test]
+    fn mismatch_message_stays_parseable_by_version_one_clients() {
+        let message = protocol_mismatch_message(7);
+        assert_eq!(parse_protocol_mismatch(&message), Some(PROTOCOL_VERSION));
+        assert_eq!(
+            parse_protocol_mismatch("Protocol mismatch: expected 1, got 2"),
+            Some(1)
+        );
+        assert_eq!(parse_protocol_mismatch("No session agent registered"), None);
+    }
+
+    #[test]
+    fn handshake_narrows_features_to_client_list() {
+        let handshake = Handshake::negotiated(2, &[ProtocolFeature::Handshake]);
+        assert_eq!(handshake.features, vec![ProtocolFeature::Handshake]);
+
+        let handshake = Handshake::negotiated(1, &[]);
+        assert!(handshake.features.is_empty());
+    }
+
+    #[test]
+    fn log_records_fall_back_to_lines_for_older_dialects() {
+        let records = vec![LogRecord::new(
+            crate::models::LogLevel::Warn,
+            "rust-daemon",
+            "lost compositor",
+        )];
+        let line = records[0].to_string();
+        let response = DaemonResponse::LogRecords { records };
+
+        assert!(matches!(
+            response.clone().for_version(PROTOCOL_VERSION),
+            DaemonResponse::LogRecords { .. }
+        ));
+        match response.for_version(4) {
+            DaemonResponse::Logs { lines } => assert_eq!(lines, vec![line]),
+            other => panic!("unexpected response: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn request_kind_matches_serde_tag() {
+        let requests = [
+            DaemonRequest::Ping,
+            DaemonRequest::GetRecentEvents { limit: 1 },
+            DaemonRequest::query_events(EventQuery::default()),
+            DaemonRequest::query_logs(LogQuery::default(), true),
+            DaemonRequest::UsbMediaRemapTogglePause,
+            DaemonRequest::UsbMediaRemapSetPaused { paused: true },
+            DaemonRequest::SetTouchscreenEnabled {
+                connector: "eDP-2".into(),
+                enabled: true,
+            },
+        ];
+        for request in requests {
+            let encoded = serde_json::to_value(&request).expect("encode request");
+            assert_eq!(encoded["type"], request.kind());
+        }
+    }
+
+    #[test]
+    fn envelope_id_is_optional_on_the_wire() {
+        let untagged = serde_json::to_string(&Envelope::new(DaemonRequest::Ping)).expect("encode");
+        assert!(!untagged.contains("\"id\""));
+
+        let tagged: Envelope<DaemonRequest> = serde_json::from_str(
+            r#"{"protocolVersion":3,"id":42,"payload":{"type":"ping"}}"#,
+        )
+        .expect("decode tagged");
+        assert_eq!(tagged.id, Some(42));
+
+        let legacy: Envelope<DaemonRequest> =
+            serde_json::from_str(r#"{"protocolVersion":1,"payload":{"type":"ping"}}"#)
+                .expect("decode legacy");
+        assert_eq!(legacy.id, None);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod commands;
+pub mod duoctl;
 pub mod hardware;
 pub mod ipc;
 pub mod models;
@@ -61,6 +62,7 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             commands::status::get_status,
+            commands::status::get_posture,
             commands::backlight::get_backlight,
             commands::backlight::set_backlight,
             commands::display::get_display_layout,
@@ -69,11 +71,13 @@ pub fn run() {
             commands::service::is_service_active,
             commands::service::get_version_info,
             commands::service::restart_service,
+            commands::service::get_metrics,
             commands::settings::load_settings,
             commands::settings::save_settings,
             commands::theme::get_system_theme,
             commands::logs::read_log,
             commands::logs::clear_log,
+            commands::logs::query_logs,
             commands::profiles::list_profiles,
             commands::profiles::save_profile,
             commands::profiles::delete_profile,
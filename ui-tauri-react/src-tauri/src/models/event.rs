@@ -1,7 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct HardwareEvent {
     pub timestamp: DateTime<Utc>,
@@ -11,7 +11,7 @@ pub struct HardwareEvent {
     pub source: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum EventCategory {
     Usb,
@@ -23,7 +23,8 @@ pub enum EventCategory {
     Service,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Ordered from least to most severe, so `min_severity` filters compare.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "lowercase")]
 pub enum EventSeverity {
     Info,
@@ -31,6 +32,34 @@ pub enum EventSeverity {
     Error,
 }
 
+/// Filter over the event journal. Unset bounds are open and an empty
+/// category list matches every category.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EventQuery {
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub until: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub categories: Vec<EventCategory>,
+    #[serde(default)]
+    pub min_severity: Option<EventSeverity>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl EventQuery {
+    pub fn matches(&self, event: &HardwareEvent) -> bool {
+        self.since.is_none_or(|since| event.timestamp >= since)
+            && self.until.is_none_or(|until| event.timestamp <= until)
+            && (self.categories.is_empty() || self.categories.contains(&event.category))
+            && self
+                .min_severity
+                .is_none_or(|min_severity| event.severity >= min_severity)
+    }
+}
+
 impl HardwareEvent {
     pub fn info(
         category: EventCategory,
@@ -76,3 +105,46 @@ impl HardwareEvent {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn event_at(hour: u32, category: EventCategory, severity: EventSeverity) -> HardwareEvent {
+        HardwareEvent {
+            timestamp: Utc.with_ymd_and_hms(2026, 3, 10, hour, 0, 0).unwrap(),
+            category,
+            severity,
+            message: "test".into(),
+            source: "test".into(),
+        }
+    }
+
+    #[test]
+    fn query_filters_by_time_category_and_severity() {
+        let query = EventQuery {
+            since: Some(Utc.with_ymd_and_hms(2026, 3, 10, 8, 0, 0).unwrap()),
+            until: Some(Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap()),
+            categories: vec![EventCategory::Display],
+            min_severity: Some(EventSeverity::Warning),
+            limit: None,
+        };
+
+        assert!(query.matches(&event_at(9, EventCategory::Display, EventSeverity::Error)));
+        assert!(query.matches(&event_at(
+            12,
+            EventCategory::Display,
+            EventSeverity::Warning
+        )));
+        assert!(!query.matches(&event_at(7, EventCategory::Display, EventSeverity::Error)));
+        assert!(!query.matches(&event_at(13, EventCategory::Display, EventSeverity::Error)));
+        assert!(!query.matches(&event_at(9, EventCategory::Usb, EventSeverity::Error)));
+        assert!(!query.matches(&event_at(9, EventCategory::Display, EventSeverity::Info)));
+        assert!(EventQuery::default().matches(&event_at(
+            1,
+            EventCategory::Usb,
+            EventSeverity::Info
+        )));
+    }
+}
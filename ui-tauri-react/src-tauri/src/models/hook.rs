@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+use super::{ConnectionType, DevicePosture};
+
+/// Hooks run per directory and transition at most this many scripts; the
+/// rest are skipped so one run stays within a known time budget.
+pub const MAX_HOOKS_PER_RUN: usize = 16;
+
+/// Transition announced to hook scripts as `DUO_EVENT`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    Attach,
+    Detach,
+    LidClosed,
+    LidOpened,
+    Resume,
+}
+
+impl HookEvent {
+    pub fn label(self) -> &'static str {
+        match self {
+            HookEvent::Attach => "attach",
+            HookEvent::Detach => "detach",
+            HookEvent::LidClosed => "lid_closed",
+            HookEvent::LidOpened => "lid_opened",
+            HookEvent::Resume => "resume",
+        }
+    }
+}
+
+/// What a hook is told about the transition, taken after the policy actions
+/// for it have been applied.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HookInvocation {
+    pub event: HookEvent,
+    pub posture: DevicePosture,
+    pub keyboard_attached: bool,
+    pub connection_type: ConnectionType,
+    pub monitor_count: u32,
+    pub external_monitor: bool,
+    pub lid_closed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_ac_power: Option<bool>,
+    pub wifi_enabled: bool,
+    pub bluetooth_enabled: bool,
+}
+
+impl HookInvocation {
+    /// The `DUO_*` variables a hook runs with. `DUO_AC_POWER` is left out
+    /// when the machine reports no mains supply.
+    pub fn environment(&self) -> Vec<(&'static str, String)> {
+        let flag = |value: bool| if value { "1" } else { "0" }.to_string();
+        let connection = match self.connection_type {
+            ConnectionType::Usb => "usb",
+            ConnectionType::Bluetooth => "bluetooth",
+            ConnectionType::None => "none",
+        };
+
+        let mut env = vec![
+            ("DUO_EVENT", self.event.label().to_string()),
+            ("DUO_POSTURE", self.posture.label().to_string()),
+            ("DUO_KEYBOARD_ATTACHED", flag(self.keyboard_attached)),
+            ("DUO_CONNECTION", connection.to_string()),
+            ("DUO_MONITORS", self.monitor_count.to_string()),
+            ("DUO_EXTERNAL_MONITOR", flag(self.external_monitor)),
+            ("DUO_LID_CLOSED", flag(self.lid_closed)),
+            ("DUO_WIFI", flag(self.wifi_enabled)),
+            ("DUO_BLUETOOTH", flag(self.bluetooth_enabled)),
+        ];
+        if let Some(on_ac_power) = self.on_ac_power {
+            env.push(("DUO_AC_POWER", flag(on_ac_power)));
+        }
+        env
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum HookStatus {
+    Succeeded,
+    /// Non-zero exit, or `None` when a signal ended it.
+    Failed {
+        code: Option<i32>,
+    },
+    TimedOut,
+    SpawnFailed {
+        message: String,
+    },
+    /// Over [`MAX_HOOKS_PER_RUN`]; not started.
+    Skipped,
+}
+
+impl HookStatus {
+    pub fn is_failure(&self) -> bool {
+        !matches!(self, HookStatus::Succeeded | HookStatus::Skipped)
+    }
+
+    pub fn describe(&self, timeout_ms: u64) -> String {
+        match self {
+            HookStatus::Succeeded => "succeeded".into(),
+            HookStatus::Failed { code: Some(code) } => format!("exited with status {code}"),
+            HookStatus::Failed { code: None } => "was killed by a signal".into(),
+            HookStatus::TimedOut => format!("timed out after {timeout_ms} ms"),
+            HookStatus::SpawnFailed { message } => message.clone(),
+            HookStatus::Skipped => format!("skipped, over {MAX_HOOKS_PER_RUN} hooks"),
+        }
+    }
+}
+
+/// One hook script's run. `output` is stdout followed by stderr, cut to the
+/// last few KiB.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HookOutcome {
+    pub hook: String,
+    pub status: HookStatus,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub output: String,
+    pub duration_ms: u64,
+}
@@ -0,0 +1,420 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::{DateTime, Local, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub fn label(self) -> &'static str {
+        match self {
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "debug" | "trace" => Some(LogLevel::Debug),
+            "info" => Some(LogLevel::Info),
+            "warn" | "warning" => Some(LogLevel::Warn),
+            "error" => Some(LogLevel::Error),
+            _ => None,
+        }
+    }
+}
+
+/// One line of a component log.
+///
+/// `timestamp` is missing only for lines written before logs were
+/// structured, which carried no time at all.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LogRecord {
+    #[serde(default)]
+    pub timestamp: Option<DateTime<Utc>>,
+    pub level: LogLevel,
+    pub component: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+}
+
+impl LogRecord {
+    pub fn new(level: LogLevel, component: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            timestamp: Some(Utc::now()),
+            level,
+            component: component.into(),
+            message: message.into(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    pub fn field(mut self, key: impl Into<String>, value: impl fmt::Display) -> Self {
+        self.fields.insert(key.into(), value.to_string());
+        self
+    }
+
+    /// Parses a log line in any format the logger has written: JSON lines,
+    /// the text format produced by `Display`, the remap helper's old
+    /// `YYYY-MM-DD HH:MM:SS - USB-REMAP - LEVEL: message` lines, or a legacy
+    /// `component: message` line.
+    pub fn parse_line(line: &str) -> Option<Self> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.trim().is_empty() {
+            return None;
+        }
+        if line.starts_with('{') {
+            if let Ok(record) = serde_json::from_str(line) {
+                return Some(record);
+            }
+        }
+        Some(
+            parse_text_line(line)
+                .or_else(|| parse_helper_line(line))
+                .unwrap_or_else(|| parse_legacy_line(line)),
+        )
+    }
+}
+
+/// Filter over the component logs. Unset bounds are open, an empty
+/// component list matches every component, and `pattern` is a regular
+/// expression searched for in the message.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LogQuery {
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub until: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub components: Vec<String>,
+    #[serde(default)]
+    pub min_level: Option<LogLevel>,
+    #[serde(default)]
+    pub contains: Option<String>,
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl LogQuery {
+    pub fn compile(self) -> Result<LogFilter, String> {
+        let pattern = self
+            .pattern
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| format!("Invalid log pattern: {e}"))?;
+        Ok(LogFilter {
+            query: self,
+            pattern,
+        })
+    }
+}
+
+/// A `LogQuery` with its pattern compiled.
+#[derive(Debug, Clone)]
+pub struct LogFilter {
+    query: LogQuery,
+    pattern: Option<Regex>,
+}
+
+impl LogFilter {
+    pub fn query(&self) -> &LogQuery {
+        &self.query
+    }
+
+    /// Lines written before logs carried timestamps only match queries
+    /// without a time window.
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        let query = &self.query;
+        let in_window = match record.timestamp {
+            Some(timestamp) => {
+                query.since.is_none_or(|since| timestamp >= since)
+                    && query.until.is_none_or(|until| timestamp <= until)
+            }
+            None => query.since.is_none() && query.until.is_none(),
+        };
+        in_window
+            && (query.components.is_empty()
+                || query
+                    .components
+                    .iter()
+                    .any(|component| component.eq_ignore_ascii_case(&record.component)))
+            && query
+                .min_level
+                .is_none_or(|min_level| record.level >= min_level)
+            && query
+                .contains
+                .as_deref()
+                .is_none_or(|needle| record.message.contains(needle))
+            && self
+                .pattern
+                .as_ref()
+                .is_none_or(|pattern| pattern.is_match(&record.message))
+    }
+
+    /// True once a backwards scan has passed the start of the window, so
+    /// nothing older can match either.
+    pub fn is_before_window(&self, record: &LogRecord) -> bool {
+        match (self.query.since, record.timestamp) {
+            (Some(since), Some(timestamp)) => timestamp < since,
+            _ => false,
+        }
+    }
+}
+
+/// `2026-03-10T09:00:00.123Z WARN rust-daemon: message<TAB>key=value ...`
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(timestamp) = self.timestamp {
+            write!(
+                f,
+                "{} ",
+                timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+            )?;
+        }
+        write!(
+            f,
+            "{} {}: {}",
+            self.level.label(),
+            self.component,
+            escape_message(&self.message)
+        )?;
+        let mut separator = "\t";
+        for (key, value) in &self.fields {
+            write!(f, "{separator}{key}={}", quote_value(value))?;
+            separator = " ";
+        }
+        Ok(())
+    }
+}
+
+fn escape_message(message: &str) -> String {
+    message
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+fn unescape_message(message: &str) -> String {
+    let mut out = String::with_capacity(message.len());
+    let mut chars = message.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn quote_value(value: &str) -> String {
+    let plain = !value.is_empty()
+        && !value
+            .chars()
+            .any(|c| c.is_whitespace() || c == '"' || c == '=' || c == '\\');
+    if plain {
+        value.to_string()
+    } else {
+        serde_json::to_string(value).unwrap_or_default()
+    }
+}
+
+fn parse_text_line(line: &str) -> Option<LogRecord> {
+    let (timestamp, rest) = line.split_once(' ')?;
+    let timestamp = DateTime::parse_from_rfc3339(timestamp)
+        .ok()?
+        .with_timezone(&Utc);
+    let (level, rest) = rest.trim_start().split_once(' ')?;
+    let level = LogLevel::parse(level)?;
+    let (body, fields) = match rest.split_once('\t') {
+        Some((body, fields)) => (body, parse_fields(fields)),
+        None => (rest, BTreeMap::new()),
+    };
+    let (component, message) = split_component(body);
+    Some(LogRecord {
+        timestamp: Some(timestamp),
+        level,
+        component,
+        message: unescape_message(&message),
+        fields,
+    })
+}
+
+fn parse_helper_line(line: &str) -> Option<LogRecord> {
+    let (timestamp, rest) = line.split_once(" - ")?;
+    let timestamp = NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S").ok()?;
+    let (component, rest) = rest.split_once(" - ")?;
+    let (level, message) = rest.split_once(": ")?;
+    Some(LogRecord {
+        timestamp: Local
+            .from_local_datetime(&timestamp)
+            .earliest()
+            .map(|time| time.with_timezone(&Utc)),
+        level: LogLevel::parse(level)?,
+        component: component.to_ascii_lowercase(),
+        message: message.to_string(),
+        fields: BTreeMap::new(),
+    })
+}
+
+fn parse_legacy_line(line: &str) -> LogRecord {
+    let (component, message) = split_component(line);
+    LogRecord {
+        timestamp: None,
+        level: LogLevel::Info,
+        component,
+        message,
+        fields: BTreeMap::new(),
+    }
+}
+
+/// Components are single words, so anything else before the first `: ` is
+/// part of the message.
+fn split_component(body: &str) -> (String, String) {
+    match body.split_once(": ") {
+        Some((component, message))
+            if !component.is_empty() && !component.contains(char::is_whitespace) =>
+        {
+            (component.to_string(), message.to_string())
+        }
+        _ => (String::new(), body.to_string()),
+    }
+}
+
+fn parse_fields(raw: &str) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    let mut rest = raw.trim_start();
+    while let Some((key, tail)) = rest.split_once('=') {
+        let (value, tail) = if tail.starts_with('"') {
+            let mut stream = serde_json::Deserializer::from_str(tail).into_iter::<String>();
+            match stream.next() {
+                Some(Ok(value)) => (value, &tail[stream.byte_offset()..]),
+                _ => break,
+            }
+        } else {
+            let end = tail.find(' ').unwrap_or(tail.len());
+            (tail[..end].to_string(), &tail[end..])
+        };
+        fields.insert(key.to_string(), value);
+        rest = tail.trim_start();
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn record() -> LogRecord {
+        LogRecord {
+            timestamp: Some(Utc.with_ymd_and_hms(2026, 3, 10, 9, 0, 0).unwrap()),
+            level: LogLevel::Warn,
+            component: "rust-daemon".into(),
+            message: "failed to apply layout: no session\nretrying".into(),
+            fields: BTreeMap::new(),
+        }
+        .field("attempt", 2)
+        .field("path", "/run/user/1000/zenbook duo")
+    }
+
+    #[test]
+    fn text_format_round_trips() {
+        let line = record().to_string();
+        assert_eq!(
+            line,
+            "2026-03-10T09:00:00.000Z WARN rust-daemon: failed to apply layout: no session\\nretrying\tattempt=2 path=\"/run/user/1000/zenbook duo\""
+        );
+        assert_eq!(LogRecord::parse_line(&line), Some(record()));
+    }
+
+    #[test]
+    fn json_lines_parse() {
+        let line = serde_json::to_string(&record()).expect("encode record");
+        assert_eq!(LogRecord::parse_line(&line), Some(record()));
+    }
+
+    #[test]
+    fn legacy_lines_keep_component_and_message() {
+        let parsed =
+            LogRecord::parse_line("rust-daemon: set backlight request -> 2").expect("legacy line");
+        assert_eq!(parsed.timestamp, None);
+        assert_eq!(parsed.level, LogLevel::Info);
+        assert_eq!(parsed.component, "rust-daemon");
+        assert_eq!(parsed.message, "set backlight request -> 2");
+
+        let helper = LogRecord::parse_line("2026-03-10 09:00:00 - USB-REMAP - ERROR: no device")
+            .expect("helper line");
+        assert!(helper.timestamp.is_some());
+        assert_eq!(helper.level, LogLevel::Error);
+        assert_eq!(helper.component, "usb-remap");
+        assert_eq!(helper.message, "no device");
+
+        let unprefixed = LogRecord::parse_line("something odd: happened").expect("legacy line");
+        assert_eq!(unprefixed.component, "");
+        assert_eq!(unprefixed.message, "something odd: happened");
+        assert_eq!(LogRecord::parse_line("   "), None);
+    }
+
+    #[test]
+    fn filter_combines_window_component_level_and_text() {
+        let filter = LogQuery {
+            since: Some(Utc.with_ymd_and_hms(2026, 3, 10, 8, 0, 0).unwrap()),
+            components: vec!["SESSION-AGENT".into()],
+            min_level: Some(LogLevel::Warn),
+            pattern: Some("^lost (compositor|display)".into()),
+            ..LogQuery::default()
+        }
+        .compile()
+        .expect("compile query");
+
+        let mut candidate = record();
+        candidate.component = "session-agent".into();
+        candidate.message = "lost compositor connection".into();
+        assert!(filter.matches(&candidate));
+
+        candidate.level = LogLevel::Info;
+        assert!(!filter.matches(&candidate));
+        candidate.level = LogLevel::Error;
+        candidate.message = "compositor lost".into();
+        assert!(!filter.matches(&candidate));
+
+        candidate.message = "lost display".into();
+        candidate.timestamp = Some(Utc.with_ymd_and_hms(2026, 3, 10, 7, 0, 0).unwrap());
+        assert!(!filter.matches(&candidate));
+        assert!(filter.is_before_window(&candidate));
+
+        candidate.timestamp = None;
+        assert!(!filter.matches(&candidate));
+        assert!(!filter.is_before_window(&candidate));
+
+        assert!(LogQuery {
+            pattern: Some("(".into()),
+            ..LogQuery::default()
+        }
+        .compile()
+        .is_err());
+    }
+}
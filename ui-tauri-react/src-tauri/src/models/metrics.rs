@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use serde::{Deserialize, Serialize};
+
+/// Upper bounds, in seconds, shared by every latency histogram. They span a
+/// fast HID write up to a dock replay that waits on a slow compositor.
+pub const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Point-in-time copy of a process's counters and histograms. Series are
+/// identified by name plus labels and kept sorted so output is stable.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsSnapshot {
+    #[serde(default)]
+    pub counters: Vec<CounterSample>,
+    #[serde(default)]
+    pub histograms: Vec<HistogramSample>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CounterSample {
+    pub name: String,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    pub value: u64,
+}
+
+/// Latency distribution. `bucket_counts[i]` counts observations no larger
+/// than `LATENCY_BUCKETS[i]` and above the previous bound; anything slower
+/// than the last bound only shows up in `count` and `sum_seconds`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HistogramSample {
+    pub name: String,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    pub bucket_counts: Vec<u64>,
+    pub count: u64,
+    pub sum_seconds: f64,
+}
+
+impl HistogramSample {
+    pub fn empty(name: impl Into<String>, labels: BTreeMap<String, String>) -> Self {
+        Self {
+            name: name.into(),
+            labels,
+            bucket_counts: vec![0; LATENCY_BUCKETS.len()],
+            count: 0,
+            sum_seconds: 0.0,
+        }
+    }
+
+    pub fn observe(&mut self, seconds: f64) {
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.bucket_counts[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum_seconds += seconds;
+    }
+
+    fn absorb(&mut self, other: &HistogramSample) {
+        for (count, extra) in self.bucket_counts.iter_mut().zip(&other.bucket_counts) {
+            *count += extra;
+        }
+        self.count += other.count;
+        self.sum_seconds += other.sum_seconds;
+    }
+}
+
+impl MetricsSnapshot {
+    /// Adds `label=value` to every series, e.g. to tell the session agent's
+    /// compositor calls apart from the daemon's own fallbacks.
+    pub fn with_label(mut self, label: &str, value: &str) -> Self {
+        for counter in &mut self.counters {
+            counter.labels.insert(label.to_string(), value.to_string());
+        }
+        for histogram in &mut self.histograms {
+            histogram
+                .labels
+                .insert(label.to_string(), value.to_string());
+        }
+        self
+    }
+
+    /// Folds `other` in, summing series that share a name and label set.
+    pub fn merge(&mut self, other: MetricsSnapshot) {
+        for counter in other.counters {
+            match self
+                .counters
+                .iter_mut()
+                .find(|existing| existing.name == counter.name && existing.labels == counter.labels)
+            {
+                Some(existing) => existing.value += counter.value,
+                None => self.counters.push(counter),
+            }
+        }
+        for histogram in other.histograms {
+            match self.histograms.iter_mut().find(|existing| {
+                existing.name == histogram.name && existing.labels == histogram.labels
+            }) {
+                Some(existing) => existing.absorb(&histogram),
+                None => self.histograms.push(histogram),
+            }
+        }
+        self.counters
+            .sort_by(|a, b| (&a.name, &a.labels).cmp(&(&b.name, &b.labels)));
+        self.histograms
+            .sort_by(|a, b| (&a.name, &a.labels).cmp(&(&b.name, &b.labels)));
+    }
+
+    /// Renders the Prometheus text exposition format, as read by the
+    /// node_exporter textfile collector.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut last_name = None;
+        for counter in &self.counters {
+            if last_name != Some(counter.name.as_str()) {
+                let _ = writeln!(out, "# TYPE {} counter", counter.name);
+                last_name = Some(counter.name.as_str());
+            }
+            let _ = writeln!(
+                out,
+                "{}{} {}",
+                counter.name,
+                render_labels(&counter.labels, None),
+                counter.value
+            );
+        }
+
+        last_name = None;
+        for histogram in &self.histograms {
+            if last_name != Some(histogram.name.as_str()) {
+                let _ = writeln!(out, "# TYPE {} histogram", histogram.name);
+                last_name = Some(histogram.name.as_str());
+            }
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(&histogram.bucket_counts) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{} {cumulative}",
+                    histogram.name,
+                    render_labels(&histogram.labels, Some(&bound.to_string()))
+                );
+            }
+            let labels = render_labels(&histogram.labels, None);
+            let _ = writeln!(
+                out,
+                "{}_bucket{} {}",
+                histogram.name,
+                render_labels(&histogram.labels, Some("+Inf")),
+                histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "{}_sum{labels} {}",
+                histogram.name, histogram.sum_seconds
+            );
+            let _ = writeln!(out, "{}_count{labels} {}", histogram.name, histogram.count);
+        }
+        out
+    }
+}
+
+fn render_labels(labels: &BTreeMap<String, String>, le: Option<&str>) -> String {
+    let mut pairs: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| format!("{key}=\"{}\"", escape_label(value)))
+        .collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{le}\""));
+    }
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn histogram_places_observations_in_the_first_fitting_bucket() {
+        let mut histogram = HistogramSample::empty("latency", BTreeMap::new());
+        histogram.observe(0.001);
+        histogram.observe(0.3);
+        histogram.observe(60.0);
+
+        assert_eq!(histogram.bucket_counts[0], 1);
+        assert_eq!(histogram.bucket_counts[6], 1);
+        assert_eq!(histogram.bucket_counts.iter().sum::<u64>(), 2);
+        assert_eq!(histogram.count, 3);
+        assert!((histogram.sum_seconds - 60.301).abs() < 1e-9);
+    }
+
+    #[test]
+    fn merge_sums_matching_series_and_keeps_the_rest() {
+        let mut daemon = MetricsSnapshot {
+            counters: vec![CounterSample {
+                name: "retries_total".into(),
+                labels: labels(&[("kind", "lid")]),
+                value: 2,
+            }],
+            histograms: vec![HistogramSample::empty("latency", BTreeMap::new())],
+        };
+        let mut agent_histogram = HistogramSample::empty("latency", BTreeMap::new());
+        agent_histogram.observe(0.02);
+        daemon.merge(MetricsSnapshot {
+            counters: vec![
+                CounterSample {
+                    name: "retries_total".into(),
+                    labels: labels(&[("kind", "lid")]),
+                    value: 3,
+                },
+                CounterSample {
+                    name: "retries_total".into(),
+                    labels: labels(&[("kind", "lifecycle")]),
+                    value: 1,
+                },
+            ],
+            histograms: vec![agent_histogram],
+        });
+
+        assert_eq!(daemon.counters.len(), 2);
+        assert_eq!(daemon.counters[0].value, 5);
+        assert_eq!(daemon.counters[1].value, 1);
+        assert_eq!(daemon.histograms[0].count, 1);
+    }
+
+    #[test]
+    fn prometheus_output_uses_cumulative_buckets() {
+        let mut histogram = HistogramSample::empty("cmd_seconds", labels(&[("program", "gdctl")]));
+        histogram.observe(0.004);
+        histogram.observe(0.04);
+        let snapshot = MetricsSnapshot {
+            counters: vec![CounterSample {
+                name: "retries_total".into(),
+                labels: labels(&[("kind", "lid")]),
+                value: 4,
+            }],
+            histograms: vec![histogram],
+        };
+
+        let text = snapshot.to_prometheus();
+        assert!(text.contains("# TYPE retries_total counter\nretries_total{kind=\"lid\"} 4\n"));
+        assert!(text.contains("cmd_seconds_bucket{program=\"gdctl\",le=\"0.005\"} 1\n"));
+        assert!(text.contains("cmd_seconds_bucket{program=\"gdctl\",le=\"0.05\"} 2\n"));
+        assert!(text.contains("cmd_seconds_bucket{program=\"gdctl\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("cmd_seconds_count{program=\"gdctl\"} 2\n"));
+    }
+}
@@ -1,10 +1,20 @@
 pub mod event;
+pub mod hook;
+pub mod log_record;
+pub mod metrics;
+pub mod policy_rule;
+pub mod posture;
 pub mod profile;
 pub mod settings;
 pub mod status;
 pub mod version;
 
 pub use event::*;
+pub use hook::*;
+pub use log_record::*;
+pub use metrics::*;
+pub use policy_rule::*;
+pub use posture::*;
 pub use profile::*;
 pub use settings::*;
 pub use status::*;
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+
+use super::ConnectionType;
+
+/// What the daemon was reacting to when it evaluates the rule set.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyTrigger {
+    /// The keyboard docked over USB.
+    Attach,
+    /// The keyboard left USB, whether or not it stayed on Bluetooth.
+    Detach,
+    /// The lid opened or closed; narrow it with the `lidClosed` condition.
+    Lid,
+    Resume,
+}
+
+impl PolicyTrigger {
+    pub fn label(self) -> &'static str {
+        match self {
+            PolicyTrigger::Attach => "attach",
+            PolicyTrigger::Detach => "detach",
+            PolicyTrigger::Lid => "lid",
+            PolicyTrigger::Resume => "resume",
+        }
+    }
+}
+
+/// Every condition that is set must hold for the rule to fire; an empty set
+/// always matches.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyConditions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_type: Option<ConnectionType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_ac_power: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_monitor: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lid_closed: Option<bool>,
+    /// Radio state before the rule's actions run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wifi_enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bluetooth_enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RadioSetting {
+    On,
+    Off,
+    /// Whatever the radio was set to the last time the keyboard was docked.
+    Restore,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PolicyRuleAction {
+    Wifi {
+        state: RadioSetting,
+    },
+    Bluetooth {
+        state: RadioSetting,
+    },
+    /// Keyboard backlight level; without one, `defaultBacklight` is used.
+    Backlight {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        level: Option<u8>,
+    },
+    /// Replays the dual/single-screen layout for the current dock state.
+    DisplayMode,
+    Profile {
+        id: String,
+    },
+    /// Starts or stops the USB media-key remap helper, overriding
+    /// `usbMediaRemapEnabled` until another rule changes it.
+    Remap {
+        enabled: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyRule {
+    /// Shown in logs and events; optional.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    pub trigger: PolicyTrigger,
+    #[serde(default)]
+    pub when: PolicyConditions,
+    pub actions: Vec<PolicyRuleAction>,
+}
+
+impl PolicyRule {
+    /// The behaviour the daemon always had: restore the docked radios, the
+    /// default backlight and the dual layout on attach; force Bluetooth on so
+    /// the keyboard can reconnect, and switch layouts, on detach.
+    pub fn default_rules() -> Vec<PolicyRule> {
+        vec![
+            PolicyRule {
+                name: "Restore docked state".into(),
+                trigger: PolicyTrigger::Attach,
+                when: PolicyConditions::default(),
+                actions: vec![
+                    PolicyRuleAction::Wifi {
+                        state: RadioSetting::Restore,
+                    },
+                    PolicyRuleAction::Bluetooth {
+                        state: RadioSetting::Restore,
+                    },
+                    PolicyRuleAction::Backlight { level: None },
+                    PolicyRuleAction::DisplayMode,
+                ],
+            },
+            PolicyRule {
+                name: "Keep the keyboard reachable".into(),
+                trigger: PolicyTrigger::Detach,
+                when: PolicyConditions::default(),
+                actions: vec![
+                    PolicyRuleAction::Bluetooth {
+                        state: RadioSetting::On,
+                    },
+                    PolicyRuleAction::DisplayMode,
+                ],
+            },
+        ]
+    }
+
+    pub fn label(&self) -> String {
+        if self.name.is_empty() {
+            format!("{} rule", self.trigger.label())
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hand_written_rules() {
+        let rules: Vec<PolicyRule> = serde_json::from_str(
+            r#"[
+                {
+                    "trigger": "detach",
+                    "when": { "bluetoothEnabled": false, "onAcPower": false },
+                    "actions": [
+                        { "type": "bluetooth", "state": "on" },
+                        { "type": "backlight", "level": 1 },
+                        { "type": "profile", "id": "tablet" }
+                    ]
+                },
+                { "trigger": "lid", "when": { "lidClosed": true }, "actions": [{ "type": "remap", "enabled": false }] }
+            ]"#,
+        )
+        .expect("rules");
+
+        assert_eq!(rules[0].trigger, PolicyTrigger::Detach);
+        assert_eq!(rules[0].when.bluetooth_enabled, Some(false));
+        assert_eq!(rules[0].when.connection_type, None);
+        assert_eq!(
+            rules[0].actions[1],
+            PolicyRuleAction::Backlight { level: Some(1) }
+        );
+        assert_eq!(rules[0].label(), "detach rule");
+        assert_eq!(
+            rules[1].actions,
+            vec![PolicyRuleAction::Remap { enabled: false }]
+        );
+    }
+
+    #[test]
+    fn default_rules_round_trip() {
+        let rules = PolicyRule::default_rules();
+        let json = serde_json::to_value(&rules).expect("serialize");
+        let parsed: Vec<PolicyRule> = serde_json::from_value(json).expect("parse");
+
+        assert_eq!(parsed, rules);
+    }
+}
@@ -0,0 +1,189 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::ConnectionType;
+
+/// What the machine physically looks like right now, derived from the
+/// keyboard connection, the lid switch and the sleep lifecycle.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DevicePosture {
+    /// Keyboard seated on the lower screen over the pogo pins.
+    Docked,
+    /// Keyboard off the screen and paired over Bluetooth.
+    DetachedBluetooth,
+    #[default]
+    DetachedNoKeyboard,
+    /// Lid closed, driving external displays only.
+    Clamshell,
+    /// Lid closed with the keyboard still seated.
+    ClamshellDocked,
+    Suspended,
+}
+
+/// Everything a posture is derived from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostureInputs {
+    pub keyboard_attached: bool,
+    pub connection_type: ConnectionType,
+    pub lid_closed: bool,
+    pub suspended: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PostureTrigger {
+    Startup,
+    Keyboard,
+    Lid,
+    Suspend,
+    Resume,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PostureTransition {
+    pub from: DevicePosture,
+    pub to: DevicePosture,
+    pub trigger: PostureTrigger,
+    pub at: DateTime<Utc>,
+}
+
+/// Answer to `GetPosture`: the current posture and how the daemon got there,
+/// oldest transition first.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PostureReport {
+    pub posture: DevicePosture,
+    pub since: DateTime<Utc>,
+    pub history: Vec<PostureTransition>,
+}
+
+impl PostureTrigger {
+    pub fn label(self) -> &'static str {
+        match self {
+            PostureTrigger::Startup => "startup",
+            PostureTrigger::Keyboard => "keyboard",
+            PostureTrigger::Lid => "lid",
+            PostureTrigger::Suspend => "suspend",
+            PostureTrigger::Resume => "resume",
+        }
+    }
+}
+
+impl DevicePosture {
+    pub fn derive(inputs: &PostureInputs) -> Self {
+        if inputs.suspended {
+            return DevicePosture::Suspended;
+        }
+        match (inputs.lid_closed, inputs.keyboard_attached) {
+            (true, true) => DevicePosture::ClamshellDocked,
+            (true, false) => DevicePosture::Clamshell,
+            (false, true) => DevicePosture::Docked,
+            (false, false) if inputs.connection_type == ConnectionType::Bluetooth => {
+                DevicePosture::DetachedBluetooth
+            }
+            (false, false) => DevicePosture::DetachedNoKeyboard,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DevicePosture::Docked => "docked",
+            DevicePosture::DetachedBluetooth => "detached_bluetooth",
+            DevicePosture::DetachedNoKeyboard => "detached_no_keyboard",
+            DevicePosture::Clamshell => "clamshell",
+            DevicePosture::ClamshellDocked => "clamshell_docked",
+            DevicePosture::Suspended => "suspended",
+        }
+    }
+
+    pub fn lid_closed(self) -> bool {
+        matches!(
+            self,
+            DevicePosture::Clamshell | DevicePosture::ClamshellDocked
+        )
+    }
+
+    pub fn keyboard_docked(self) -> bool {
+        matches!(self, DevicePosture::Docked | DevicePosture::ClamshellDocked)
+    }
+
+    /// Whether `next` is one step away: suspend and resume reach anything,
+    /// otherwise only the lid or only the keyboard may change at once.
+    pub fn allows(self, next: DevicePosture) -> bool {
+        if self == next {
+            return false;
+        }
+        if self == DevicePosture::Suspended || next == DevicePosture::Suspended {
+            return true;
+        }
+        self.lid_closed() == next.lid_closed() || self.keyboard_docked() == next.keyboard_docked()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(
+        keyboard_attached: bool,
+        connection_type: ConnectionType,
+        lid_closed: bool,
+    ) -> PostureInputs {
+        PostureInputs {
+            keyboard_attached,
+            connection_type,
+            lid_closed,
+            suspended: false,
+        }
+    }
+
+    #[test]
+    fn derives_posture_from_lid_and_keyboard() {
+        assert_eq!(
+            DevicePosture::derive(&inputs(true, ConnectionType::Usb, false)),
+            DevicePosture::Docked
+        );
+        assert_eq!(
+            DevicePosture::derive(&inputs(false, ConnectionType::Bluetooth, false)),
+            DevicePosture::DetachedBluetooth
+        );
+        assert_eq!(
+            DevicePosture::derive(&inputs(false, ConnectionType::None, false)),
+            DevicePosture::DetachedNoKeyboard
+        );
+        assert_eq!(
+            DevicePosture::derive(&inputs(false, ConnectionType::Bluetooth, true)),
+            DevicePosture::Clamshell
+        );
+        assert_eq!(
+            DevicePosture::derive(&inputs(true, ConnectionType::Usb, true)),
+            DevicePosture::ClamshellDocked
+        );
+        assert_eq!(
+            DevicePosture::derive(&PostureInputs {
+                suspended: true,
+                ..inputs(true, ConnectionType::Usb, false)
+            }),
+            DevicePosture::Suspended
+        );
+    }
+
+    #[test]
+    fn only_single_axis_moves_are_direct_transitions() {
+        use DevicePosture::*;
+
+        assert!(Docked.allows(DetachedBluetooth));
+        assert!(DetachedBluetooth.allows(DetachedNoKeyboard));
+        assert!(Docked.allows(ClamshellDocked));
+        assert!(DetachedNoKeyboard.allows(Clamshell));
+        assert!(Clamshell.allows(ClamshellDocked));
+        assert!(Clamshell.allows(Suspended));
+        assert!(Suspended.allows(Docked));
+
+        assert!(!Docked.allows(Clamshell));
+        assert!(!DetachedBluetooth.allows(ClamshellDocked));
+        assert!(!Docked.allows(Docked));
+    }
+}
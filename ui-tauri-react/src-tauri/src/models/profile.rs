@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 
 use super::{DisplayLayout, Orientation};
 
+pub const PROFILES_SCHEMA_VERSION: u32 = 1;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Profile {
@@ -14,12 +16,27 @@ pub struct Profile {
     pub display_layout: Option<DisplayLayout>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProfileList {
+    #[serde(default = "profiles_schema_version")]
+    pub schema_version: u32,
     pub profiles: Vec<Profile>,
 }
 
+impl Default for ProfileList {
+    fn default() -> Self {
+        Self {
+            schema_version: PROFILES_SCHEMA_VERSION,
+            profiles: Vec::new(),
+        }
+    }
+}
+
+fn profiles_schema_version() -> u32 {
+    PROFILES_SCHEMA_VERSION
+}
+
 impl Profile {
     pub fn default_profiles() -> Vec<Profile> {
         vec![
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use super::DisplayLayout;
+use super::{DisplayLayout, PolicyRule};
 
 pub const DEFAULT_BACKLIGHT_LEVEL: u8 = 0;
 pub const DEFAULT_SCALE_FACTOR: f64 = 1.66;
@@ -8,10 +8,20 @@ pub const DEFAULT_USB_MEDIA_REMAP_ENABLED: bool = true;
 pub const DEFAULT_START_ON_BOOT_MINIMIZED: bool = false;
 pub const DEFAULT_INVERT_SENSOR_ROTATION: bool = false;
 pub const DEFAULT_SETUP_COMPLETED: bool = false;
+pub const DEFAULT_ATTACH_SETTLE_MS: u64 = 1000;
+pub const DEFAULT_HOOK_TIMEOUT_MS: u64 = 10_000;
+pub const DEFAULT_KEYBOARD_BATTERY_THRESHOLDS: [u8; 2] = [20, 10];
+pub const DEFAULT_BACKLIGHT_IDLE_TIMEOUT_USB_SECS: u64 = 0;
+pub const DEFAULT_BACKLIGHT_IDLE_TIMEOUT_BLUETOOTH_SECS: u64 = 60;
+pub const DEFAULT_AMBIENT_LIGHT_ENABLED: bool = false;
+pub const DEFAULT_AMBIENT_OVERRIDE_COOLDOWN_SECS: u64 = 300;
+pub const SETTINGS_SCHEMA_VERSION: u32 = 1;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DuoSettings {
+    #[serde(default = "settings_schema_version")]
+    pub schema_version: u32,
     #[serde(default = "default_backlight")]
     pub default_backlight: u8,
     #[serde(default = "default_scale")]
@@ -34,11 +44,49 @@ pub struct DuoSettings {
     pub touchscreen_disabled: Vec<String>,
     #[serde(default)]
     pub saved_display_layout: Option<DisplayLayout>,
+    /// How long a keyboard attach/detach must hold before policy reacts to
+    /// it. Zero reacts to every raw transition.
+    #[serde(default = "default_attach_settle_ms")]
+    pub attach_settle_ms: u64,
+    /// What the daemon does on attach, detach, lid and resume. Every rule
+    /// whose trigger and conditions match runs, in order.
+    #[serde(default = "PolicyRule::default_rules")]
+    pub policy_rules: Vec<PolicyRule>,
+    /// Each hook script is killed if it runs longer than this.
+    #[serde(default = "default_hook_timeout_ms")]
+    pub hook_timeout_ms: u64,
+    /// Battery percentages at which a draining Bluetooth keyboard warns.
+    #[serde(default = "default_keyboard_battery_thresholds")]
+    pub keyboard_battery_thresholds: Vec<u8>,
+    /// Seconds without a keypress before the backlight of a docked keyboard
+    /// turns off. Zero keeps it on.
+    #[serde(default = "default_backlight_idle_timeout_usb_secs")]
+    pub backlight_idle_timeout_usb_secs: u64,
+    /// The same for the keyboard on Bluetooth, where the backlight costs
+    /// battery.
+    #[serde(default = "default_backlight_idle_timeout_bluetooth_secs")]
+    pub backlight_idle_timeout_bluetooth_secs: u64,
+    /// Follow the ambient light sensor with the panels and the keyboard
+    /// backlight.
+    #[serde(default = "default_ambient_light_enabled")]
+    pub ambient_light_enabled: bool,
+    /// Panel brightness in percent by lux, interpolated between points.
+    #[serde(default = "default_ambient_brightness_curve")]
+    pub ambient_brightness_curve: Vec<LightCurvePoint>,
+    /// Keyboard backlight level (0–3) by lux; each point holds until the
+    /// next one.
+    #[serde(default = "default_ambient_keyboard_curve")]
+    pub ambient_keyboard_curve: Vec<LightCurvePoint>,
+    /// How long a brightness or backlight key keeps the sensor's hands off
+    /// what it changed.
+    #[serde(default = "default_ambient_override_cooldown_secs")]
+    pub ambient_override_cooldown_secs: u64,
 }
 
 impl Default for DuoSettings {
     fn default() -> Self {
         Self {
+            schema_version: SETTINGS_SCHEMA_VERSION,
             default_backlight: default_backlight(),
             default_scale: default_scale(),
             auto_dual_screen: true,
@@ -50,10 +98,32 @@ impl Default for DuoSettings {
             setup_completed: DEFAULT_SETUP_COMPLETED,
             touchscreen_disabled: Vec::new(),
             saved_display_layout: None,
+            attach_settle_ms: default_attach_settle_ms(),
+            policy_rules: PolicyRule::default_rules(),
+            hook_timeout_ms: default_hook_timeout_ms(),
+            keyboard_battery_thresholds: default_keyboard_battery_thresholds(),
+            backlight_idle_timeout_usb_secs: default_backlight_idle_timeout_usb_secs(),
+            backlight_idle_timeout_bluetooth_secs: default_backlight_idle_timeout_bluetooth_secs(),
+            ambient_light_enabled: default_ambient_light_enabled(),
+            ambient_brightness_curve: default_ambient_brightness_curve(),
+            ambient_keyboard_curve: default_ambient_keyboard_curve(),
+            ambient_override_cooldown_secs: default_ambient_override_cooldown_secs(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct LightCurvePoint {
+    pub lux: f64,
+    pub value: u8,
+}
+
+impl LightCurvePoint {
+    const fn new(lux: f64, value: u8) -> Self {
+        Self { lux, value }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum ThemePreference {
@@ -63,6 +133,10 @@ pub enum ThemePreference {
     Dark,
 }
 
+fn settings_schema_version() -> u32 {
+    SETTINGS_SCHEMA_VERSION
+}
+
 fn default_backlight() -> u8 {
     DEFAULT_BACKLIGHT_LEVEL
 }
@@ -83,6 +157,53 @@ fn default_invert_sensor_rotation() -> bool {
     DEFAULT_INVERT_SENSOR_ROTATION
 }
 
+fn default_attach_settle_ms() -> u64 {
+    DEFAULT_ATTACH_SETTLE_MS
+}
+
+fn default_hook_timeout_ms() -> u64 {
+    DEFAULT_HOOK_TIMEOUT_MS
+}
+
+fn default_keyboard_battery_thresholds() -> Vec<u8> {
+    DEFAULT_KEYBOARD_BATTERY_THRESHOLDS.to_vec()
+}
+
+fn default_backlight_idle_timeout_usb_secs() -> u64 {
+    DEFAULT_BACKLIGHT_IDLE_TIMEOUT_USB_SECS
+}
+
+fn default_backlight_idle_timeout_bluetooth_secs() -> u64 {
+    DEFAULT_BACKLIGHT_IDLE_TIMEOUT_BLUETOOTH_SECS
+}
+
+fn default_ambient_light_enabled() -> bool {
+    DEFAULT_AMBIENT_LIGHT_ENABLED
+}
+
+fn default_ambient_brightness_curve() -> Vec<LightCurvePoint> {
+    vec![
+        LightCurvePoint::new(0.0, 10),
+        LightCurvePoint::new(50.0, 30),
+        LightCurvePoint::new(300.0, 60),
+        LightCurvePoint::new(1000.0, 85),
+        LightCurvePoint::new(5000.0, 100),
+    ]
+}
+
+fn default_ambient_keyboard_curve() -> Vec<LightCurvePoint> {
+    vec![
+        LightCurvePoint::new(0.0, 3),
+        LightCurvePoint::new(10.0, 2),
+        LightCurvePoint::new(60.0, 1),
+        LightCurvePoint::new(250.0, 0),
+    ]
+}
+
+fn default_ambient_override_cooldown_secs() -> u64 {
+    DEFAULT_AMBIENT_OVERRIDE_COOLDOWN_SECS
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,6 +227,37 @@ mod tests {
             DEFAULT_INVERT_SENSOR_ROTATION
         );
         assert_eq!(settings.setup_completed, DEFAULT_SETUP_COMPLETED);
+        assert_eq!(settings.attach_settle_ms, DEFAULT_ATTACH_SETTLE_MS);
+        assert_eq!(settings.policy_rules, PolicyRule::default_rules());
+        assert_eq!(settings.hook_timeout_ms, DEFAULT_HOOK_TIMEOUT_MS);
+        assert_eq!(
+            settings.keyboard_battery_thresholds,
+            DEFAULT_KEYBOARD_BATTERY_THRESHOLDS
+        );
+        assert_eq!(
+            settings.backlight_idle_timeout_usb_secs,
+            DEFAULT_BACKLIGHT_IDLE_TIMEOUT_USB_SECS
+        );
+        assert_eq!(
+            settings.backlight_idle_timeout_bluetooth_secs,
+            DEFAULT_BACKLIGHT_IDLE_TIMEOUT_BLUETOOTH_SECS
+        );
+        assert_eq!(
+            settings.ambient_light_enabled,
+            DEFAULT_AMBIENT_LIGHT_ENABLED
+        );
+        assert_eq!(
+            settings.ambient_brightness_curve,
+            default_ambient_brightness_curve()
+        );
+        assert_eq!(
+            settings.ambient_keyboard_curve,
+            default_ambient_keyboard_curve()
+        );
+        assert_eq!(
+            settings.ambient_override_cooldown_secs,
+            DEFAULT_AMBIENT_OVERRIDE_COOLDOWN_SECS
+        );
         assert!(settings.auto_dual_screen);
         assert!(settings.sync_brightness);
         assert_eq!(settings.theme, ThemePreference::System);
@@ -13,6 +13,11 @@ pub struct DuoStatus {
     pub max_brightness: u32,
     pub service_active: bool,
     pub orientation: Orientation,
+    /// The keyboard's own battery while it runs on Bluetooth; `None` when
+    /// it is docked or does not report one.
+    pub keyboard_battery_percent: Option<u8>,
+    /// `None` when the battery source does not say.
+    pub keyboard_battery_charging: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
@@ -1,5 +1,63 @@
 use serde::{Deserialize, Serialize};
 
+use crate::ipc::protocol::MIN_PROTOCOL_VERSION;
+
+/// Protocol features a client may rely on, keyed by the protocol version that
+/// introduced them. Baseline requests from version 1 are not listed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolFeature {
+    Handshake,
+    EventSubscription,
+    Pipelining,
+    EventJournal,
+    StructuredLogs,
+    LogQuery,
+    Metrics,
+    Posture,
+    KeyboardActivity,
+    AmbientLight,
+    RemapPause,
+}
+
+impl ProtocolFeature {
+    pub const ALL: [ProtocolFeature; 11] = [
+        ProtocolFeature::Handshake,
+        ProtocolFeature::EventSubscription,
+        ProtocolFeature::Pipelining,
+        ProtocolFeature::EventJournal,
+        ProtocolFeature::StructuredLogs,
+        ProtocolFeature::LogQuery,
+        ProtocolFeature::Metrics,
+        ProtocolFeature::Posture,
+        ProtocolFeature::KeyboardActivity,
+        ProtocolFeature::AmbientLight,
+        ProtocolFeature::RemapPause,
+    ];
+
+    pub fn introduced_in(self) -> u32 {
+        match self {
+            ProtocolFeature::Handshake | ProtocolFeature::EventSubscription => 2,
+            ProtocolFeature::Pipelining => 3,
+            ProtocolFeature::EventJournal => 4,
+            ProtocolFeature::StructuredLogs => 5,
+            ProtocolFeature::LogQuery => 6,
+            ProtocolFeature::Metrics => 7,
+            ProtocolFeature::Posture => 8,
+            ProtocolFeature::KeyboardActivity => 9,
+            ProtocolFeature::AmbientLight => 10,
+            ProtocolFeature::RemapPause => 11,
+        }
+    }
+
+    pub fn available_in(version: u32) -> Vec<ProtocolFeature> {
+        Self::ALL
+            .into_iter()
+            .filter(|feature| feature.introduced_in() <= version)
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct DaemonVersionInfo {
@@ -15,6 +73,10 @@ pub struct VersionInfo {
     pub daemon_version: Option<String>,
     pub daemon_protocol_version: Option<u32>,
     pub service_available: bool,
+    #[serde(default)]
+    pub negotiated_protocol_version: Option<u32>,
+    #[serde(default)]
+    pub unavailable_features: Vec<ProtocolFeature>,
 }
 
 impl VersionInfo {
@@ -23,26 +85,55 @@ impl VersionInfo {
         app_protocol_version: u32,
         daemon: DaemonVersionInfo,
     ) -> Self {
+        let negotiated = daemon.protocol_version.min(app_protocol_version);
         Self {
             app_version: app_version.into(),
             app_protocol_version,
             daemon_version: Some(daemon.version),
             daemon_protocol_version: Some(daemon.protocol_version),
             service_available: true,
+            negotiated_protocol_version: Some(negotiated),
+            unavailable_features: unavailable_features(Some(negotiated)),
         }
     }
 
+    pub fn from_handshake(
+        app_version: impl Into<String>,
+        app_protocol_version: u32,
+        handshake: crate::ipc::protocol::Handshake,
+    ) -> Self {
+        Self {
+            app_version: app_version.into(),
+            app_protocol_version,
+            daemon_version: Some(handshake.daemon_version),
+            daemon_protocol_version: Some(handshake.max_version),
+            service_available: true,
+            negotiated_protocol_version: Some(handshake.negotiated_version),
+            unavailable_features: ProtocolFeature::ALL
+                .into_iter()
+                .filter(|feature| !handshake.features.contains(feature))
+                .collect(),
+        }
+    }
+
+    /// The daemon rejected our envelope. If its version is one we can still
+    /// speak, only the newer features are missing; otherwise nothing works.
     pub fn protocol_mismatch(
         app_version: impl Into<String>,
         app_protocol_version: u32,
         daemon_protocol_version: u32,
     ) -> Self {
+        let negotiated = (MIN_PROTOCOL_VERSION..=app_protocol_version)
+            .contains(&daemon_protocol_version)
+            .then_some(daemon_protocol_version);
         Self {
             app_version: app_version.into(),
             app_protocol_version,
             daemon_version: None,
             daemon_protocol_version: Some(daemon_protocol_version),
             service_available: true,
+            negotiated_protocol_version: negotiated,
+            unavailable_features: unavailable_features(negotiated),
         }
     }
 
@@ -53,6 +144,66 @@ impl VersionInfo {
             daemon_version: None,
             daemon_protocol_version: None,
             service_available: false,
+            negotiated_protocol_version: None,
+            unavailable_features: Vec::new(),
         }
     }
 }
+
+fn unavailable_features(negotiated: Option<u32>) -> Vec<ProtocolFeature> {
+    let version = negotiated.unwrap_or(0);
+    ProtocolFeature::ALL
+        .into_iter()
+        .filter(|feature| feature.introduced_in() > version)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn older_daemon_in_range_reports_only_newer_features_unavailable() {
+        let info = VersionInfo::from_daemon(
+            "0.4.0",
+            2,
+            DaemonVersionInfo {
+                version: "0.3.7".into(),
+                protocol_version: 1,
+            },
+        );
+
+        assert_eq!(info.negotiated_protocol_version, Some(1));
+        assert_eq!(info.unavailable_features, ProtocolFeature::ALL.to_vec());
+    }
+
+    #[test]
+    fn protocol_mismatch_outside_supported_range_disables_everything() {
+        let info = VersionInfo::protocol_mismatch("0.4.0", 2, 9);
+
+        assert!(info.service_available);
+        assert_eq!(info.negotiated_protocol_version, None);
+        assert_eq!(info.unavailable_features, ProtocolFeature::ALL.to_vec());
+    }
+
+    #[test]
+    fn protocol_mismatch_inside_supported_range_keeps_shared_features() {
+        let info = VersionInfo::protocol_mismatch("0.4.0", 3, 2);
+
+        assert_eq!(info.negotiated_protocol_version, Some(2));
+        assert_eq!(
+            info.unavailable_features,
+            vec![
+                ProtocolFeature::Pipelining,
+                ProtocolFeature::EventJournal,
+                ProtocolFeature::StructuredLogs,
+                ProtocolFeature::LogQuery,
+                ProtocolFeature::Metrics,
+                ProtocolFeature::Posture,
+                ProtocolFeature::KeyboardActivity,
+                ProtocolFeature::AmbientLight,
+                ProtocolFeature::RemapPause,
+            ]
+        );
+    }
+}
@@ -0,0 +1,428 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures_util::StreamExt;
+use tokio::sync::RwLock;
+
+use crate::models::{ConnectionType, DuoSettings, LightCurvePoint};
+use crate::runtime::{logger, state::RuntimeState};
+
+const SENSOR_PROXY: &str = "net.hadess.SensorProxy";
+const SENSOR_PROXY_PATH: &str = "/net/hadess/SensorProxy";
+/// Until the sensor shows up, or after iio-sensor-proxy restarts.
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+/// Smaller steps than this are sensor noise, not a change of room.
+const PANEL_HYSTERESIS_PERCENT: u8 = 5;
+
+/// Manual brightness and backlight changes the sensor has to leave alone
+/// for a while.
+///
+/// Like the keyboard idle clock it travels with `RuntimeState`: the
+/// Bluetooth hotkeys and the `SetBacklight` and `ManualBrightness` requests
+/// stamp it, and the sensor watcher consults it.
+#[derive(Clone, Default)]
+pub struct AmbientLight {
+    overrides: Arc<Mutex<Overrides>>,
+}
+
+impl std::fmt::Debug for AmbientLight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AmbientLight").finish_non_exhaustive()
+    }
+}
+
+impl AmbientLight {
+    fn overrides(&self) -> std::sync::MutexGuard<'_, Overrides> {
+        self.overrides.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The panel brightness was set by hand.
+    pub fn manual_display_change(&self) {
+        self.overrides().manual_display_change(Instant::now());
+    }
+
+    /// The keyboard backlight was set by hand.
+    pub fn manual_keyboard_change(&self) {
+        self.overrides().keyboard = Some(Instant::now());
+    }
+}
+
+#[derive(Debug, Default)]
+struct Overrides {
+    display: Option<Instant>,
+    keyboard: Option<Instant>,
+    /// The panel percentage the sensor last set.
+    applied_percent: Option<u8>,
+}
+
+/// What a light reading should change; `None` leaves it alone.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Targets {
+    panel_percent: Option<u8>,
+    keyboard_level: Option<u8>,
+}
+
+impl Overrides {
+    fn manual_display_change(&mut self, now: Instant) {
+        self.display = Some(now);
+        // Whatever the sensor set last is gone; set the curve again once
+        // the cool-down ends.
+        self.applied_percent = None;
+    }
+
+    /// `keyboard` is the backlight level now, or `None` when the sensor
+    /// must not touch it.
+    fn plan(
+        &self,
+        settings: &DuoSettings,
+        lux: f64,
+        keyboard: Option<u8>,
+        now: Instant,
+    ) -> Targets {
+        let cooldown = Duration::from_secs(settings.ambient_override_cooldown_secs);
+        let cooling_down =
+            |since: Option<Instant>| since.is_some_and(|at| now.duration_since(at) < cooldown);
+
+        let panel_percent = if cooling_down(self.display) {
+            None
+        } else {
+            panel_percent(&settings.ambient_brightness_curve, lux).filter(|target| {
+                self.applied_percent
+                    .is_none_or(|applied| applied.abs_diff(*target) >= PANEL_HYSTERESIS_PERCENT)
+            })
+        };
+        let keyboard_level = match keyboard {
+            Some(current) if !cooling_down(self.keyboard) => {
+                keyboard_level(&settings.ambient_keyboard_curve, lux)
+                    .filter(|target| *target != current)
+            }
+            _ => None,
+        };
+        Targets {
+            panel_percent,
+            keyboard_level,
+        }
+    }
+}
+
+fn sorted(curve: &[LightCurvePoint]) -> Vec<LightCurvePoint> {
+    let mut points = curve.to_vec();
+    points.sort_by(|a, b| a.lux.total_cmp(&b.lux));
+    points
+}
+
+/// Straight lines between the curve's points, flat beyond its ends.
+fn panel_percent(curve: &[LightCurvePoint], lux: f64) -> Option<u8> {
+    let points = sorted(curve);
+    let first = points.first()?;
+    if lux <= first.lux {
+        return Some(first.value.min(100));
+    }
+    let value = points
+        .windows(2)
+        .find(|pair| lux <= pair[1].lux)
+        .map(|pair| {
+            let (low, high) = (pair[0], pair[1]);
+            let share = (lux - low.lux) / (high.lux - low.lux);
+            f64::from(low.value) + share * (f64::from(high.value) - f64::from(low.value))
+        })
+        .unwrap_or_else(|| f64::from(points[points.len() - 1].value));
+    Some(value.round().clamp(0.0, 100.0) as u8)
+}
+
+/// The level of the last point at or below `lux`.
+fn keyboard_level(curve: &[LightCurvePoint], lux: f64) -> Option<u8> {
+    let points = sorted(curve);
+    let level = points
+        .iter()
+        .rev()
+        .find(|point| point.lux <= lux)
+        .or(points.first())?
+        .value;
+    Some(level.min(3))
+}
+
+pub fn start(state: Arc<RwLock<RuntimeState>>) {
+    tokio::spawn(async move {
+        let mut reported = false;
+        loop {
+            let enabled = state.read().await.settings.ambient_light_enabled;
+            if enabled {
+                if let Err(err) = watch_light(&state).await {
+                    if reported {
+                        logger::debug("rust-daemon", format!("ambient light watcher: {err}"));
+                    } else {
+                        logger::warn("rust-daemon", format!("ambient light watcher: {err}"));
+                        reported = true;
+                    }
+                }
+            }
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+    });
+}
+
+/// Claims the light sensor from iio-sensor-proxy and follows its readings
+/// until the setting is switched off. The claim goes with the connection.
+async fn watch_light(state: &Arc<RwLock<RuntimeState>>) -> Result<(), String> {
+    let connection = zbus::Connection::system()
+        .await
+        .map_err(|e| format!("Failed to connect to the system bus: {e}"))?;
+    let properties = zbus::fdo::PropertiesProxy::builder(&connection)
+        .destination(SENSOR_PROXY)
+        .and_then(|builder| builder.path(SENSOR_PROXY_PATH))
+        .map_err(|e| format!("Failed to address iio-sensor-proxy: {e}"))?
+        .build()
+        .await
+        .map_err(|e| format!("Failed to reach iio-sensor-proxy: {e}"))?;
+    let interface = zbus::names::InterfaceName::from_static_str(SENSOR_PROXY)
+        .expect("sensor proxy interface name should be valid");
+
+    let has_light = properties
+        .get(interface.clone(), "HasAmbientLight")
+        .await
+        .ok()
+        .and_then(|value| value.downcast_ref::<bool>().ok())
+        .unwrap_or(false);
+    if !has_light {
+        return Err("iio-sensor-proxy reports no ambient light sensor".into());
+    }
+
+    let mut changes = properties
+        .receive_properties_changed()
+        .await
+        .map_err(|e| format!("Failed to watch the light sensor: {e}"))?;
+    connection
+        .call_method(
+            Some(SENSOR_PROXY),
+            SENSOR_PROXY_PATH,
+            Some(SENSOR_PROXY),
+            "ClaimLight",
+            &(),
+        )
+        .await
+        .map_err(|e| format!("Failed to claim the light sensor: {e}"))?;
+    logger::info(
+        "rust-daemon",
+        "ambient light watcher claimed the light sensor",
+    );
+
+    if let Some(lux) = properties
+        .get(interface, "LightLevel")
+        .await
+        .ok()
+        .and_then(|value| value.downcast_ref::<f64>().ok())
+    {
+        apply(state, lux).await;
+    }
+
+    while let Some(signal) = changes.next().await {
+        if !state.read().await.settings.ambient_light_enabled {
+            logger::info(
+                "rust-daemon",
+                "ambient light turned off; releasing the sensor",
+            );
+            return Ok(());
+        }
+        let Some(lux) = signal.args().ok().and_then(|args| {
+            args.changed_properties()
+                .get("LightLevel")
+                .and_then(|value| value.downcast_ref::<f64>().ok())
+        }) else {
+            continue;
+        };
+        apply(state, lux).await;
+    }
+
+    Err("iio-sensor-proxy went away".into())
+}
+
+async fn apply(state: &Arc<RwLock<RuntimeState>>, lux: f64) {
+    let (settings, hardware, ambient, keyboard) = {
+        let guard = state.read().await;
+        // A backlight the idle timeout turned off stays off until a keypress.
+        let keyboard = (guard.status.connection_type != ConnectionType::None
+            && !guard.keyboard_idle.dimmed())
+        .then_some(guard.status.backlight_level);
+        (
+            guard.settings.clone(),
+            guard.hardware.clone(),
+            guard.ambient_light.clone(),
+            keyboard,
+        )
+    };
+    if !settings.ambient_light_enabled {
+        return;
+    }
+    let targets = ambient
+        .overrides()
+        .plan(&settings, lux, keyboard, Instant::now());
+    if targets == Targets::default() {
+        return;
+    }
+
+    let panel =
+        targets
+            .panel_percent
+            .and_then(|percent| match hardware.set_panel_brightness(percent) {
+                Ok(raw) => {
+                    ambient.overrides().applied_percent = Some(percent);
+                    Some((percent, raw))
+                }
+                Err(err) => {
+                    logger::warn(
+                        "rust-daemon",
+                        format!("failed to set panel brightness from ambient light: {err}"),
+                    );
+                    None
+                }
+            });
+    let keyboard = targets
+        .keyboard_level
+        .and_then(|level| match hardware.set_backlight(level) {
+            Ok(()) => Some(level),
+            Err(err) => {
+                logger::warn(
+                    "rust-daemon",
+                    format!("failed to set keyboard backlight from ambient light: {err}"),
+                );
+                None
+            }
+        });
+    if panel.is_none() && keyboard.is_none() {
+        return;
+    }
+
+    let mut guard = state.write().await;
+    let mut changes = Vec::new();
+    if let Some((percent, raw)) = panel {
+        guard.status.display_brightness = raw;
+        changes.push(format!("panels {percent}%"));
+    }
+    if let Some(level) = keyboard {
+        guard.status.backlight_level = level;
+        changes.push(format!("keyboard backlight {level}"));
+    }
+    guard.touch();
+    guard.persist();
+    logger::info(
+        "rust-daemon",
+        format!("ambient light {lux:.0} lux: {}", changes.join(", ")),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> DuoSettings {
+        DuoSettings {
+            ambient_light_enabled: true,
+            ambient_brightness_curve: vec![
+                LightCurvePoint {
+                    lux: 0.0,
+                    value: 10,
+                },
+                LightCurvePoint {
+                    lux: 100.0,
+                    value: 50,
+                },
+                LightCurvePoint {
+                    lux: 1000.0,
+                    value: 100,
+                },
+            ],
+            ambient_keyboard_curve: vec![
+                LightCurvePoint {
+                    lux: 50.0,
+                    value: 1,
+                },
+                LightCurvePoint { lux: 0.0, value: 3 },
+                LightCurvePoint {
+                    lux: 500.0,
+                    value: 0,
+                },
+            ],
+            ambient_override_cooldown_secs: 60,
+            ..DuoSettings::default()
+        }
+    }
+
+    #[test]
+    fn curves_interpolate_the_panels_and_step_the_keyboard() {
+        let settings = settings();
+        let panel = |lux| panel_percent(&settings.ambient_brightness_curve, lux);
+        let keyboard = |lux| keyboard_level(&settings.ambient_keyboard_curve, lux);
+
+        assert_eq!(panel(-5.0), Some(10));
+        assert_eq!(panel(50.0), Some(30));
+        assert_eq!(panel(550.0), Some(75));
+        assert_eq!(panel(20_000.0), Some(100));
+        assert_eq!(panel_percent(&[], 50.0), None);
+
+        assert_eq!(keyboard(0.0), Some(3));
+        assert_eq!(keyboard(49.0), Some(3));
+        assert_eq!(keyboard(50.0), Some(1));
+        assert_eq!(keyboard(900.0), Some(0));
+        assert_eq!(
+            keyboard_level(&[LightCurvePoint { lux: 0.0, value: 9 }], 10.0),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn small_changes_in_light_leave_the_panels_alone() {
+        let settings = settings();
+        let now = Instant::now();
+        let mut overrides = Overrides::default();
+
+        let first = overrides.plan(&settings, 100.0, Some(1), now);
+        assert_eq!(
+            first,
+            Targets {
+                panel_percent: Some(50),
+                keyboard_level: None,
+            }
+        );
+        overrides.applied_percent = first.panel_percent;
+
+        assert_eq!(
+            overrides.plan(&settings, 110.0, Some(1), now),
+            Targets::default()
+        );
+        assert_eq!(
+            overrides.plan(&settings, 200.0, Some(1), now).panel_percent,
+            Some(56)
+        );
+    }
+
+    #[test]
+    fn manual_changes_hold_off_the_sensor_for_the_cool_down() {
+        let settings = settings();
+        let start = Instant::now();
+        let mut overrides = Overrides {
+            applied_percent: Some(50),
+            ..Overrides::default()
+        };
+        overrides.manual_display_change(start);
+        overrides.keyboard = Some(start);
+
+        assert_eq!(
+            overrides.plan(&settings, 100.0, Some(3), start + Duration::from_secs(59)),
+            Targets::default()
+        );
+        assert_eq!(
+            overrides.plan(&settings, 100.0, Some(3), start + Duration::from_secs(60)),
+            Targets {
+                panel_percent: Some(50),
+                keyboard_level: Some(1),
+            }
+        );
+        assert_eq!(
+            overrides
+                .plan(&settings, 100.0, None, start + Duration::from_secs(60))
+                .keyboard_level,
+            None
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Crash-safe file replacement.
+///
+/// The new contents go to a temp file in the same directory, are fsynced and
+/// then renamed over the target, so readers only ever see the old or the new
+/// file. The previous file is kept as `<name>.bak`; if power is lost between
+/// the two renames, the backup is the newest complete copy.
+pub fn write_with_backup(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+
+    let temp = temp_path(path);
+    if let Err(err) = write_synced(&temp, contents) {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("Failed to write {}: {err}", temp.display()));
+    }
+
+    match fs::rename(path, backup_path(path)) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => {
+            let _ = fs::remove_file(&temp);
+            return Err(format!("Failed to rotate {}: {err}", path.display()));
+        }
+    }
+    fs::rename(&temp, path).map_err(|e| format!("Failed to replace {}: {e}", path.display()))?;
+
+    // Persist the renames themselves; without this a crash can roll the
+    // directory entry back even though the file data made it to disk.
+    File::open(parent)
+        .and_then(|dir| dir.sync_all())
+        .map_err(|e| format!("Failed to sync {}: {e}", parent.display()))
+}
+
+/// Like `write_with_backup`, but without keeping the previous copy. For
+/// files that are cheap to regenerate and only need readers never to see a
+/// partial write.
+pub fn replace(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let temp = temp_path(path);
+    if let Err(err) = write_synced(&temp, contents) {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("Failed to write {}: {err}", temp.display()));
+    }
+    fs::rename(&temp, path).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        format!("Failed to replace {}: {e}", path.display())
+    })
+}
+
+pub fn backup_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".bak")
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let unique = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    with_suffix(path, &format!(".{}.{unique}.tmp", std::process::id()))
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn unique_temp_dir(label: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        std::env::temp_dir().join(format!("zenbook-duo-atomic-{label}-{nanos}"))
+    }
+
+    #[test]
+    fn replaces_file_and_keeps_previous_copy_as_backup() {
+        let dir = unique_temp_dir("rotate");
+        let path = dir.join("state.json");
+
+        write_with_backup(&path, b"first").expect("first write");
+        assert_eq!(fs::read(&path).expect("read first"), b"first");
+        assert!(!backup_path(&path).exists());
+
+        write_with_backup(&path, b"second").expect("second write");
+        assert_eq!(fs::read(&path).expect("read second"), b"second");
+        assert_eq!(fs::read(backup_path(&path)).expect("read backup"), b"first");
+
+        let leftovers: Vec<_> = fs::read_dir(&dir)
+            .expect("list dir")
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn backup_sits_next_to_the_file() {
+        assert_eq!(
+            backup_path(Path::new("/var/lib/zenbook-duo/state.json")),
+            PathBuf::from("/var/lib/zenbook-duo/state.json.bak")
+        );
+    }
+}
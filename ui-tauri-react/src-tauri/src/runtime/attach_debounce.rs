@@ -0,0 +1,307 @@
+use std::time::{Duration, Instant};
+
+use crate::models::{ConnectionType, DuoStatus};
+
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// The keyboard fields of `DuoStatus` that go through the settle window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttachState {
+    pub keyboard_attached: bool,
+    pub connection_type: ConnectionType,
+}
+
+impl AttachState {
+    pub fn of(status: &DuoStatus) -> Self {
+        Self {
+            keyboard_attached: status.keyboard_attached,
+            connection_type: status.connection_type.clone(),
+        }
+    }
+
+    pub fn apply_to(&self, status: &mut DuoStatus) {
+        status.keyboard_attached = self.keyboard_attached;
+        status.connection_type = self.connection_type.clone();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Debounced {
+    /// Nothing changed, or a flap already being timed is still settling.
+    Unchanged,
+    /// A new state was seen; check again at `deadline`.
+    Pending { deadline: Instant },
+    /// The new state held for the whole window and is now the stable one.
+    /// `suppressed` counts the raw transitions swallowed on the way.
+    Settled { state: AttachState, suppressed: u32 },
+    /// The keyboard went back to the stable state before the window ran
+    /// out, so every transition seen in between was dropped.
+    Reverted { suppressed: u32 },
+}
+
+struct Pending {
+    state: AttachState,
+    since: Instant,
+    /// Raw transitions seen since leaving the stable state.
+    transitions: u32,
+}
+
+/// Holds back keyboard attach/detach changes until they stay put for the
+/// settle window, so a keyboard seated slowly on the pogo pins produces one
+/// transition instead of a burst of display replays and radio toggles.
+pub struct AttachDebouncer<C: Clock> {
+    clock: C,
+    stable: AttachState,
+    pending: Option<Pending>,
+}
+
+impl<C: Clock> AttachDebouncer<C> {
+    pub fn new(stable: AttachState, clock: C) -> Self {
+        Self {
+            clock,
+            stable,
+            pending: None,
+        }
+    }
+
+    pub fn stable(&self) -> &AttachState {
+        &self.stable
+    }
+
+    /// Adopts `live` as the stable state when nothing is settling, so a
+    /// change made outside the debouncer (a resume re-probe, say) is not
+    /// undone by the next observation.
+    pub fn resync(&mut self, live: AttachState) {
+        if self.pending.is_none() {
+            self.stable = live;
+        }
+    }
+
+    pub fn observe(&mut self, observed: AttachState, window: Duration) -> Debounced {
+        let now = self.clock.now();
+
+        if observed == self.stable {
+            return match self.pending.take() {
+                // Leaving and returning are both swallowed.
+                Some(pending) => Debounced::Reverted {
+                    suppressed: pending.transitions + 1,
+                },
+                None => Debounced::Unchanged,
+            };
+        }
+
+        let (pending, restarted) = match self.pending.take() {
+            Some(pending) if pending.state == observed => (pending, false),
+            Some(pending) => (
+                Pending {
+                    state: observed,
+                    since: now,
+                    transitions: pending.transitions + 1,
+                },
+                true,
+            ),
+            None => (
+                Pending {
+                    state: observed,
+                    since: now,
+                    transitions: 1,
+                },
+                true,
+            ),
+        };
+
+        let deadline = pending.since + window;
+        if now >= deadline {
+            self.stable = pending.state.clone();
+            return Debounced::Settled {
+                state: pending.state,
+                suppressed: pending.transitions - 1,
+            };
+        }
+
+        self.pending = Some(pending);
+        if restarted {
+            Debounced::Pending { deadline }
+        } else {
+            Debounced::Unchanged
+        }
+    }
+
+    /// When the pending state, if any, is due to be checked again.
+    pub fn deadline(&self, window: Duration) -> Option<Instant> {
+        self.pending.as_ref().map(|pending| pending.since + window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[derive(Clone)]
+    struct FakeClock(Rc<Cell<Instant>>);
+
+    impl FakeClock {
+        fn new() -> Self {
+            Self(Rc::new(Cell::new(Instant::now())))
+        }
+
+        fn advance(&self, millis: u64) {
+            self.0.set(self.0.get() + Duration::from_millis(millis));
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.0.get()
+        }
+    }
+
+    const WINDOW: Duration = Duration::from_millis(1000);
+
+    fn detached() -> AttachState {
+        AttachState {
+            keyboard_attached: false,
+            connection_type: ConnectionType::None,
+        }
+    }
+
+    fn usb() -> AttachState {
+        AttachState {
+            keyboard_attached: true,
+            connection_type: ConnectionType::Usb,
+        }
+    }
+
+    fn bluetooth() -> AttachState {
+        AttachState {
+            keyboard_attached: false,
+            connection_type: ConnectionType::Bluetooth,
+        }
+    }
+
+    #[test]
+    fn attach_settles_only_after_the_window() {
+        let clock = FakeClock::new();
+        let mut debouncer = AttachDebouncer::new(detached(), clock.clone());
+        let start = clock.now();
+
+        assert_eq!(
+            debouncer.observe(usb(), WINDOW),
+            Debounced::Pending {
+                deadline: start + WINDOW
+            }
+        );
+        clock.advance(400);
+        assert_eq!(debouncer.observe(usb(), WINDOW), Debounced::Unchanged);
+        assert_eq!(debouncer.stable(), &detached());
+
+        clock.advance(600);
+        assert_eq!(
+            debouncer.observe(usb(), WINDOW),
+            Debounced::Settled {
+                state: usb(),
+                suppressed: 0
+            }
+        );
+        assert_eq!(debouncer.stable(), &usb());
+        assert_eq!(debouncer.deadline(WINDOW), None);
+    }
+
+    #[test]
+    fn resync_follows_outside_changes_but_not_while_settling() {
+        let clock = FakeClock::new();
+        let mut debouncer = AttachDebouncer::new(usb(), clock.clone());
+
+        debouncer.resync(bluetooth());
+        assert_eq!(debouncer.observe(bluetooth(), WINDOW), Debounced::Unchanged);
+
+        assert!(matches!(
+            debouncer.observe(detached(), WINDOW),
+            Debounced::Pending { .. }
+        ));
+        debouncer.resync(usb());
+        assert_eq!(debouncer.stable(), &bluetooth());
+    }
+
+    #[test]
+    fn rapid_attach_detach_never_reaches_policy() {
+        let clock = FakeClock::new();
+        let mut debouncer = AttachDebouncer::new(detached(), clock.clone());
+
+        for _ in 0..3 {
+            assert!(matches!(
+                debouncer.observe(usb(), WINDOW),
+                Debounced::Pending { .. }
+            ));
+            clock.advance(150);
+            assert!(matches!(
+                debouncer.observe(detached(), WINDOW),
+                Debounced::Reverted { suppressed: 2 }
+            ));
+            clock.advance(150);
+        }
+
+        assert_eq!(debouncer.stable(), &detached());
+        assert_eq!(debouncer.deadline(WINDOW), None);
+    }
+
+    #[test]
+    fn each_new_state_restarts_the_window() {
+        let clock = FakeClock::new();
+        let mut debouncer = AttachDebouncer::new(detached(), clock.clone());
+
+        debouncer.observe(bluetooth(), WINDOW);
+        clock.advance(800);
+        let restarted = clock.now();
+        assert_eq!(
+            debouncer.observe(usb(), WINDOW),
+            Debounced::Pending {
+                deadline: restarted + WINDOW
+            }
+        );
+
+        clock.advance(800);
+        assert_eq!(debouncer.observe(usb(), WINDOW), Debounced::Unchanged);
+        clock.advance(200);
+        assert_eq!(
+            debouncer.observe(usb(), WINDOW),
+            Debounced::Settled {
+                state: usb(),
+                suppressed: 1
+            }
+        );
+    }
+
+    #[test]
+    fn zero_window_passes_transitions_straight_through() {
+        let clock = FakeClock::new();
+        let mut debouncer = AttachDebouncer::new(detached(), clock);
+
+        assert_eq!(
+            debouncer.observe(usb(), Duration::ZERO),
+            Debounced::Settled {
+                state: usb(),
+                suppressed: 0
+            }
+        );
+        assert_eq!(
+            debouncer.observe(detached(), Duration::ZERO),
+            Debounced::Settled {
+                state: detached(),
+                suppressed: 0
+            }
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use crate::hardware::touchscreen::{self, TouchscreenDevice};
+use crate::models::{DisplayLayout, DuoStatus};
+use crate::runtime::policy::PolicyContext;
+use crate::runtime::probe::{self, StatusRefresh};
+use crate::runtime::radio::{Radio, Radios};
+use crate::runtime::simulation::{SimHardware, TranscriptEntry};
+
+/// The machine the daemon reads and drives.
+///
+/// Like the persister it travels with `RuntimeState`, so the monitor, the
+/// lid and lifecycle handlers and the policy reach the hardware through the
+/// state they already hold. The default handle is the real machine;
+/// `--simulate` swaps in [`SimHardware`] and everything it is asked to do
+/// lands in the simulation transcript instead.
+#[derive(Clone, Default)]
+pub struct HardwareBackend {
+    simulated: Option<Arc<SimHardware>>,
+}
+
+impl std::fmt::Debug for HardwareBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HardwareBackend")
+            .field("simulated", &self.simulated.is_some())
+            .finish()
+    }
+}
+
+impl HardwareBackend {
+    pub fn simulated(hardware: Arc<SimHardware>) -> Self {
+        Self {
+            simulated: Some(hardware),
+        }
+    }
+
+    pub fn is_simulated(&self) -> bool {
+        self.simulated.is_some()
+    }
+
+    pub fn current_status(&self) -> DuoStatus {
+        match &self.simulated {
+            Some(sim) => sim.status(),
+            None => probe::current_status(),
+        }
+    }
+
+    /// See [`probe::refresh_status`]; displays are still left to the caller.
+    pub fn refresh_status(&self, status: &mut DuoStatus, refresh: StatusRefresh) {
+        match &self.simulated {
+            Some(sim) => sim.refresh_status(status, refresh),
+            None => probe::refresh_status(status, refresh),
+        }
+    }
+
+    /// The compositor's layout, for when the session agent cannot be asked.
+    pub fn display_layout(&self) -> Result<DisplayLayout, String> {
+        match &self.simulated {
+            Some(sim) => Ok(sim.display_layout()),
+            None => crate::hardware::display_layout::get_display_layout(),
+        }
+    }
+
+    pub fn set_radio(&self, radio: Radio, enabled: bool) -> Result<(), String> {
+        match &self.simulated {
+            Some(sim) => Radios::new(&**sim, &**sim, &**sim).set_enabled(radio, enabled),
+            None => Radios::system().set_enabled(radio, enabled),
+        }
+    }
+
+    pub fn set_backlight(&self, level: u8) -> Result<(), String> {
+        match &self.simulated {
+            Some(sim) => sim.set_backlight(level),
+            None => crate::hardware::hid::set_backlight(level),
+        }
+    }
+
+    /// The level the keyboard reports, not the one last written.
+    pub fn read_backlight(&self) -> Result<u8, String> {
+        match &self.simulated {
+            Some(sim) => sim.read_backlight(),
+            None => crate::hardware::hid::read_backlight(),
+        }
+    }
+
+    /// Both panels to `percent` of their maximum; returns the primary's new
+    /// raw brightness.
+    pub fn set_panel_brightness(&self, percent: u8) -> Result<u32, String> {
+        match &self.simulated {
+            Some(sim) => sim.set_panel_brightness(percent),
+            None => crate::hardware::sysfs::set_panel_brightness(percent),
+        }
+    }
+
+    pub fn policy_context(&self) -> PolicyContext {
+        match &self.simulated {
+            Some(sim) => sim.policy_context(),
+            None => PolicyContext::probe(),
+        }
+    }
+
+    pub fn usb_media_remap_running(&self) -> bool {
+        match &self.simulated {
+            Some(sim) => sim.remap_running(),
+            None => crate::commands::usb_media_remap::get_status().running,
+        }
+    }
+
+    pub fn start_usb_media_remap(&self) -> Result<(), String> {
+        match &self.simulated {
+            Some(sim) => sim.set_remap_running(true),
+            None => crate::commands::usb_media_remap::start_remap(),
+        }
+    }
+
+    pub fn stop_usb_media_remap(&self) -> Result<(), String> {
+        match &self.simulated {
+            Some(sim) => sim.set_remap_running(false),
+            None => crate::commands::usb_media_remap::stop_remap(),
+        }
+    }
+
+    pub fn touchscreens(&self) -> Vec<TouchscreenDevice> {
+        match &self.simulated {
+            Some(_) => Vec::new(),
+            None => touchscreen::list_touchscreens(),
+        }
+    }
+
+    pub fn set_touchscreen_enabled(&self, i2c_id: &str, enabled: bool) -> Result<(), String> {
+        match &self.simulated {
+            Some(_) => Err(format!("No simulated touchscreen {i2c_id}")),
+            None => touchscreen::set_touchscreen_enabled(i2c_id, enabled),
+        }
+    }
+
+    /// Adds to the simulation transcript; a no-op on real hardware.
+    pub fn record(&self, entry: TranscriptEntry) {
+        if let Some(sim) = &self.simulated {
+            sim.record(entry);
+        }
+    }
+}
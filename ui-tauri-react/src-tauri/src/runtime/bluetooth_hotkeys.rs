@@ -7,7 +7,7 @@ use std::time::Duration;
 use tokio::sync::RwLock;
 
 use crate::models::{ConnectionType, EventCategory, HardwareEvent};
-use crate::runtime::{logger, state::RuntimeState};
+use crate::runtime::{keyboard_idle, logger, state::RuntimeState};
 
 const HIDRAW_ROOT: &str = "/sys/class/hidraw";
 const REPORT_ID: u8 = 0x5a;
@@ -30,10 +30,10 @@ pub(crate) fn start(state: Arc<RwLock<RuntimeState>>) {
     {
         Ok(_) => {}
         Err(err) => {
-            log::warn!("failed to spawn Bluetooth hotkey watcher: {err}");
-            let _ = logger::append_line(format!(
-                "rust-daemon: failed to spawn Bluetooth hotkey watcher: {err}"
-            ));
+            logger::warn(
+                "rust-daemon",
+                format!("failed to spawn Bluetooth hotkey watcher: {err}"),
+            );
         }
     }
 }
@@ -47,16 +47,19 @@ fn watch_loop(state: Arc<RwLock<RuntimeState>>) {
 
         match fs::OpenOptions::new().read(true).open(&path) {
             Ok(file) => {
-                let _ = logger::append_line(format!(
-                    "rust-daemon: Bluetooth hotkey watcher opened {}",
-                    path.display()
-                ));
-                if let Err(err) = watch_device(file, state.clone()) {
-                    log::warn!("Bluetooth hotkey watcher lost {}: {err}", path.display());
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: Bluetooth hotkey watcher lost {}: {err}",
-                        path.display()
-                    ));
+                logger::info(
+                    "rust-daemon",
+                    format!("Bluetooth hotkey watcher opened {}", path.display()),
+                );
+                let idle = state.blocking_read().keyboard_idle.clone();
+                idle.set_bluetooth_watched(true);
+                let result = watch_device(file, state.clone());
+                idle.set_bluetooth_watched(false);
+                if let Err(err) = result {
+                    logger::warn(
+                        "rust-daemon",
+                        format!("Bluetooth hotkey watcher lost {}: {err}", path.display()),
+                    );
                 }
             }
             Err(err) => {
@@ -74,11 +77,13 @@ fn watch_loop(state: Arc<RwLock<RuntimeState>>) {
 fn watch_device(mut file: fs::File, state: Arc<RwLock<RuntimeState>>) -> Result<(), String> {
     let mut active_action: Option<BluetoothHotkeyAction> = None;
     let mut buffer = [0_u8; 64];
+    let idle = state.blocking_read().keyboard_idle.clone();
 
     loop {
         match file.read(&mut buffer) {
             Ok(0) => return Err("hidraw read returned EOF".into()),
             Ok(count) => {
+                keyboard_idle::wake_blocking(&state, &idle);
                 let report = &buffer[..count];
                 if is_release_report(report) {
                     active_action = None;
@@ -95,10 +100,10 @@ fn watch_device(mut file: fs::File, state: Arc<RwLock<RuntimeState>>) -> Result<
                 active_action = Some(action);
 
                 if let Err(err) = handle_action(action, &state) {
-                    log::warn!("failed to handle Bluetooth hotkey {action:?}: {err}");
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: failed to handle Bluetooth hotkey {action:?}: {err}"
-                    ));
+                    logger::warn(
+                        "rust-daemon",
+                        format!("failed to handle Bluetooth hotkey {action:?}: {err}"),
+                    );
                 }
             }
             Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
@@ -169,22 +174,25 @@ fn handle_action(
 }
 
 fn cycle_backlight(state: &Arc<RwLock<RuntimeState>>) -> Result<(), String> {
-    let current = state.blocking_read().status.backlight_level;
+    let current = crate::hardware::hid::read_backlight()
+        .unwrap_or_else(|_| state.blocking_read().status.backlight_level);
     let next = (current + 1) % 4;
     crate::hardware::hid::set_backlight(next)?;
 
     let mut guard = state.blocking_write();
     guard.status.backlight_level = next;
+    guard.ambient_light.manual_keyboard_change();
     guard.push_recent_event(HardwareEvent::info(
         EventCategory::Keyboard,
         format!("Backlight set to {next}"),
         "rust-daemon",
     ));
     guard.touch();
-    guard.save()?;
-    let _ = logger::append_line(format!(
-        "rust-daemon: Bluetooth hotkey cycled backlight -> {next}"
-    ));
+    guard.persist();
+    logger::info(
+        "rust-daemon",
+        format!("Bluetooth hotkey cycled backlight -> {next}"),
+    );
     Ok(())
 }
 
@@ -213,16 +221,18 @@ fn step_brightness(
 
     let mut guard = state.blocking_write();
     guard.status.display_brightness = next;
+    guard.ambient_light.manual_display_change();
     guard.push_recent_event(HardwareEvent::info(
         EventCategory::Display,
         format!("Display brightness set to {next}"),
         "rust-daemon",
     ));
     guard.touch();
-    guard.save()?;
-    let _ = logger::append_line(format!(
-        "rust-daemon: Bluetooth hotkey adjusted brightness -> {next}"
-    ));
+    guard.persist();
+    logger::info(
+        "rust-daemon",
+        format!("Bluetooth hotkey adjusted brightness -> {next}"),
+    );
     Ok(())
 }
 
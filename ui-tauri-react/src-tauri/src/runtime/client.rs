@@ -1,21 +1,445 @@
+use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Write};
+use std::net::Shutdown;
 use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::Duration;
 
-use crate::ipc::protocol::{DaemonRequest, DaemonResponse, Envelope};
+use crate::ipc::protocol::{
+    is_supported_version, parse_protocol_mismatch, DaemonEvent, DaemonRequest, DaemonResponse,
+    Envelope, Handshake, SubscriptionCategory, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
+use crate::models::{LogQuery, LogRecord, ProtocolFeature};
 use crate::runtime::paths;
 
+/// Protocol dialect last agreed with the daemon. Starts optimistic and drops
+/// to an older supported version when the daemon says it can't speak ours.
+static DAEMON_DIALECT: AtomicU32 = AtomicU32::new(PROTOCOL_VERSION);
+
+/// Process-wide pipelined connection shared by every `request` caller.
+static SHARED_CONNECTION: Mutex<Option<DaemonConnection>> = Mutex::new(None);
+
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(3);
+/// Layout requests round-trip through the session agent and the compositor.
+const SESSION_RESPONSE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Sends one request to the daemon.
+///
+/// Daemons that support pipelining share a single multiplexed connection, so
+/// concurrent callers don't queue behind each other. Older daemons get a
+/// fresh connection per request in whatever dialect they speak.
 pub fn request(request: DaemonRequest) -> Result<DaemonResponse, String> {
-    let mut stream = UnixStream::connect(paths::daemon_socket_path())
+    if DAEMON_DIALECT.load(Ordering::Relaxed) >= ProtocolFeature::Pipelining.introduced_in() {
+        if let Ok(connection) = shared_connection() {
+            match connection.try_call(request.clone(), response_timeout(&request)) {
+                Ok(response) => return Ok(response),
+                Err(CallError::Failed(message)) => return Err(message),
+                // Never reached the daemon, so the one-shot path can safely retry.
+                Err(CallError::NotSent(_)) => {}
+            }
+        }
+    }
+
+    let version = DAEMON_DIALECT.load(Ordering::Relaxed);
+    let response = request_with_version(version, request.clone())?;
+    match downgrade_target(version, &response) {
+        Some(older) => {
+            DAEMON_DIALECT.store(older, Ordering::Relaxed);
+            request_with_version(older, request)
+        }
+        None => Ok(response),
+    }
+}
+
+/// Whether the daemon, as far as this process has negotiated with it,
+/// understands `feature`.
+pub fn daemon_supports(feature: ProtocolFeature) -> bool {
+    DAEMON_DIALECT.load(Ordering::Relaxed) >= feature.introduced_in()
+}
+
+/// Negotiates the protocol version with the daemon and remembers the result
+/// for later requests on this process.
+pub fn hello() -> Result<Handshake, String> {
+    let stream = connect()?;
+    let mut reader = send_request(stream, PROTOCOL_VERSION, hello_request())?;
+    read_handshake(&mut reader)
+}
+
+fn hello_request() -> DaemonRequest {
+    DaemonRequest::Hello {
+        min_version: MIN_PROTOCOL_VERSION,
+        max_version: PROTOCOL_VERSION,
+        features: ProtocolFeature::ALL.to_vec(),
+    }
+}
+
+fn read_handshake(reader: &mut BufReader<UnixStream>) -> Result<Handshake, String> {
+    match read_response(reader)? {
+        DaemonResponse::Hello { handshake } => {
+            DAEMON_DIALECT.store(handshake.negotiated_version, Ordering::Relaxed);
+            Ok(handshake)
+        }
+        DaemonResponse::Error { message } => {
+            if let Some(older) =
+                parse_protocol_mismatch(&message).filter(|v| is_supported_version(*v))
+            {
+                DAEMON_DIALECT.store(older, Ordering::Relaxed);
+            }
+            Err(message)
+        }
+        other => Err(format!("Unexpected daemon hello response: {other:?}")),
+    }
+}
+
+fn request_with_version(version: u32, request: DaemonRequest) -> Result<DaemonResponse, String> {
+    let stream = connect()?;
+    let mut reader = send_request(stream, version, request)?;
+    read_response(&mut reader)
+}
+
+fn response_timeout(request: &DaemonRequest) -> Duration {
+    match request {
+        DaemonRequest::GetDisplayLayout | DaemonRequest::ApplyDisplayLayout { .. } => {
+            SESSION_RESPONSE_TIMEOUT
+        }
+        _ => RESPONSE_TIMEOUT,
+    }
+}
+
+fn downgrade_target(sent: u32, response: &DaemonResponse) -> Option<u32> {
+    let DaemonResponse::Error { message } = response else {
+        return None;
+    };
+    parse_protocol_mismatch(message)
+        .filter(|daemon| *daemon != sent && is_supported_version(*daemon))
+}
+
+/// Opens a long-lived event stream from the daemon.
+///
+/// The read timeout only covers the subscription ack; afterwards reads block
+/// until the daemon publishes something or the connection drops.
+pub fn subscribe(categories: Vec<SubscriptionCategory>) -> Result<EventSubscription, String> {
+    let stream = connect()?;
+    let version = DAEMON_DIALECT.load(Ordering::Relaxed);
+    let mut reader = send_request(stream, version, DaemonRequest::Subscribe { categories })?;
+    match read_response(&mut reader)? {
+        DaemonResponse::Ack => {}
+        DaemonResponse::Error { message } => return Err(message),
+        other => {
+            return Err(format!(
+                "Unexpected daemon subscription response: {other:?}"
+            ))
+        }
+    }
+    reader
+        .get_ref()
+        .set_read_timeout(None)
+        .map_err(|e| format!("Failed to clear daemon read timeout: {e}"))?;
+    Ok(EventSubscription { reader })
+}
+
+pub struct EventSubscription {
+    reader: BufReader<UnixStream>,
+}
+
+impl Iterator for EventSubscription {
+    type Item = Result<DaemonEvent, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => Some(
+                serde_json::from_str::<Envelope<DaemonEvent>>(line.trim_end())
+                    .map(|envelope| envelope.payload)
+                    .map_err(|e| format!("Invalid daemon event JSON: {e}")),
+            ),
+            Err(e) => Some(Err(format!("Failed to read daemon event: {e}"))),
+        }
+    }
+}
+
+/// Runs a `QueryLogs` in follow mode: returns the matches so far, then a
+/// stream of record batches as matching lines are appended. As with
+/// `subscribe`, only the first answer is subject to the read timeout.
+pub fn follow_logs(query: LogQuery) -> Result<(Vec<LogRecord>, LogStream), String> {
+    let stream = connect()?;
+    let version = DAEMON_DIALECT.load(Ordering::Relaxed);
+    let mut reader = send_request(stream, version, DaemonRequest::query_logs(query, true))?;
+    let records = match read_response(&mut reader)? {
+        DaemonResponse::LogRecords { records } => records,
+        DaemonResponse::Error { message } => return Err(message),
+        other => return Err(format!("Unexpected daemon log query response: {other:?}")),
+    };
+    reader
+        .get_ref()
+        .set_read_timeout(None)
+        .map_err(|e| format!("Failed to clear daemon read timeout: {e}"))?;
+    Ok((records, LogStream { reader }))
+}
+
+pub struct LogStream {
+    reader: BufReader<UnixStream>,
+}
+
+impl Iterator for LogStream {
+    type Item = Result<Vec<LogRecord>, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match read_response(&mut self.reader) {
+            Ok(DaemonResponse::LogRecords { records }) => Some(Ok(records)),
+            Ok(DaemonResponse::Error { message }) => Some(Err(message)),
+            Ok(other) => Some(Err(format!(
+                "Unexpected daemon log stream response: {other:?}"
+            ))),
+            // The daemon hung up.
+            Err(_) => None,
+        }
+    }
+}
+
+fn shared_connection() -> Result<DaemonConnection, String> {
+    let mut slot = SHARED_CONNECTION
+        .lock()
+        .map_err(|_| "Daemon connection lock poisoned".to_string())?;
+    if let Some(connection) = slot.as_ref().filter(|connection| connection.is_open()) {
+        return Ok(connection.clone());
+    }
+    let connection = DaemonConnection::open()?;
+    *slot = Some(connection.clone());
+    Ok(connection)
+}
+
+type PendingReplies = HashMap<u64, mpsc::Sender<Result<DaemonResponse, String>>>;
+
+/// Multiplexed daemon connection.
+///
+/// Every call is tagged with a fresh envelope id and may be issued from any
+/// thread; a reader thread hands each response to the caller waiting on its
+/// id, so a slow request doesn't block the ones sent after it.
+#[derive(Clone)]
+pub struct DaemonConnection {
+    inner: Arc<ConnectionInner>,
+}
+
+struct ConnectionInner {
+    writer: Mutex<UnixStream>,
+    replies: Arc<ReplyTable>,
+    next_id: AtomicU64,
+    version: u32,
+}
+
+#[derive(Default)]
+struct ReplyTable {
+    pending: Mutex<PendingReplies>,
+    closed: AtomicBool,
+}
+
+enum CallError {
+    /// The request was never written, so retrying cannot run it twice.
+    NotSent(String),
+    Failed(String),
+}
+
+impl DaemonConnection {
+    pub fn open() -> Result<Self, String> {
+        Self::open_at(&paths::daemon_socket_path())
+    }
+
+    fn open_at(path: &Path) -> Result<Self, String> {
+        let stream = connect_to(path)?;
+        let writer = stream
+            .try_clone()
+            .map_err(|e| format!("Failed to clone daemon socket: {e}"))?;
+        let mut reader = send_request(stream, PROTOCOL_VERSION, hello_request())?;
+        let handshake = read_handshake(&mut reader)?;
+        if !handshake.features.contains(&ProtocolFeature::Pipelining) {
+            return Err(format!(
+                "Daemon protocol version {} does not support pipelined requests",
+                handshake.negotiated_version
+            ));
+        }
+        reader
+            .get_ref()
+            .set_read_timeout(None)
+            .map_err(|e| format!("Failed to clear daemon read timeout: {e}"))?;
+
+        let replies = Arc::new(ReplyTable::default());
+        let reader_replies = replies.clone();
+        thread::Builder::new()
+            .name("daemon-client-reader".into())
+            .spawn(move || read_replies(reader, &reader_replies))
+            .map_err(|e| format!("Failed to start daemon reply reader: {e}"))?;
+
+        Ok(Self {
+            inner: Arc::new(ConnectionInner {
+                writer: Mutex::new(writer),
+                replies,
+                next_id: AtomicU64::new(1),
+                version: handshake.negotiated_version,
+            }),
+        })
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.inner.replies.closed.load(Ordering::Acquire)
+    }
+
+    pub fn call(&self, request: DaemonRequest) -> Result<DaemonResponse, String> {
+        let timeout = response_timeout(&request);
+        self.call_with_timeout(request, timeout)
+    }
+
+    pub fn call_with_timeout(
+        &self,
+        request: DaemonRequest,
+        timeout: Duration,
+    ) -> Result<DaemonResponse, String> {
+        self.try_call(request, timeout).map_err(|err| match err {
+            CallError::NotSent(message) | CallError::Failed(message) => message,
+        })
+    }
+
+    fn try_call(
+        &self,
+        request: DaemonRequest,
+        timeout: Duration,
+    ) -> Result<DaemonResponse, CallError> {
+        let inner = &self.inner;
+        let id = inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut line = serde_json::to_string(
+            &Envelope::with_version(inner.version, request).with_id(Some(id)),
+        )
+        .map_err(|e| CallError::NotSent(format!("Failed to encode daemon request: {e}")))?;
+        line.push('\n');
+
+        let (sender, receiver) = mpsc::channel();
+        {
+            // Checked under the same lock the reader drains with, so a reply
+            // slot can't be registered after the connection is torn down.
+            let mut pending = inner
+                .replies
+                .pending
+                .lock()
+                .expect("daemon reply table lock");
+            if inner.replies.closed.load(Ordering::Acquire) {
+                return Err(CallError::NotSent("Daemon connection closed".into()));
+            }
+            pending.insert(id, sender);
+        }
+
+        let written = inner
+            .writer
+            .lock()
+            .expect("daemon writer lock")
+            .write_all(line.as_bytes());
+        if let Err(e) = written {
+            inner.replies.forget(id);
+            return Err(CallError::NotSent(format!(
+                "Failed to write daemon request: {e}"
+            )));
+        }
+
+        match receiver.recv_timeout(timeout) {
+            Ok(reply) => reply.map_err(CallError::Failed),
+            Err(RecvTimeoutError::Timeout) => {
+                inner.replies.forget(id);
+                Err(CallError::Failed(
+                    "Timed out waiting for daemon response".into(),
+                ))
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                Err(CallError::Failed("Daemon connection closed".into()))
+            }
+        }
+    }
+}
+
+impl Drop for ConnectionInner {
+    fn drop(&mut self) {
+        // Unblocks the reader thread so it can exit.
+        if let Ok(writer) = self.writer.get_mut() {
+            let _ = writer.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+impl ReplyTable {
+    fn forget(&self, id: u64) {
+        self.pending
+            .lock()
+            .expect("daemon reply table lock")
+            .remove(&id);
+    }
+
+    fn deliver(&self, id: u64, reply: Result<DaemonResponse, String>) {
+        let sender = self
+            .pending
+            .lock()
+            .expect("daemon reply table lock")
+            .remove(&id);
+        // A missing slot means the caller already timed out.
+        if let Some(sender) = sender {
+            let _ = sender.send(reply);
+        }
+    }
+
+    fn close(&self, reason: &str) {
+        let mut pending = self.pending.lock().expect("daemon reply table lock");
+        self.closed.store(true, Ordering::Release);
+        for (_, sender) in pending.drain() {
+            let _ = sender.send(Err(reason.to_string()));
+        }
+    }
+}
+
+fn read_replies(mut reader: BufReader<UnixStream>, replies: &ReplyTable) {
+    let reason = loop {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) => break "Daemon connection closed".to_string(),
+            Ok(_) => {}
+            Err(e) => break format!("Failed to read daemon response: {e}"),
+        }
+        match serde_json::from_str::<Envelope<DaemonResponse>>(line.trim_end()) {
+            Ok(Envelope {
+                id: Some(id),
+                payload,
+                ..
+            }) => replies.deliver(id, Ok(payload)),
+            Ok(_) => log::warn!("ignoring untagged daemon response on pipelined connection"),
+            Err(e) => break format!("Invalid daemon response JSON: {e}"),
+        }
+    };
+    replies.close(&reason);
+}
+
+fn connect() -> Result<UnixStream, String> {
+    connect_to(&paths::daemon_socket_path())
+}
+
+fn connect_to(path: &Path) -> Result<UnixStream, String> {
+    let stream = UnixStream::connect(path)
         .map_err(|e| format!("Failed to connect to daemon socket: {e}"))?;
     stream
-        .set_read_timeout(Some(Duration::from_secs(3)))
+        .set_read_timeout(Some(RESPONSE_TIMEOUT))
         .map_err(|e| format!("Failed to set daemon read timeout: {e}"))?;
     stream
-        .set_write_timeout(Some(Duration::from_secs(3)))
+        .set_write_timeout(Some(RESPONSE_TIMEOUT))
         .map_err(|e| format!("Failed to set daemon write timeout: {e}"))?;
+    Ok(stream)
+}
 
-    let line = serde_json::to_string(&Envelope::new(request))
+fn send_request(
+    mut stream: UnixStream,
+    version: u32,
+    request: DaemonRequest,
+) -> Result<BufReader<UnixStream>, String> {
+    let line = serde_json::to_string(&Envelope::with_version(version, request))
         .map_err(|e| format!("Failed to encode daemon request: {e}"))?;
     stream
         .write_all(line.as_bytes())
@@ -23,8 +447,10 @@ pub fn request(request: DaemonRequest) -> Result<DaemonResponse, String> {
     stream
         .write_all(b"\n")
         .map_err(|e| format!("Failed to terminate daemon request: {e}"))?;
+    Ok(BufReader::new(stream))
+}
 
-    let mut reader = BufReader::new(stream);
+fn read_response(reader: &mut BufReader<UnixStream>) -> Result<DaemonResponse, String> {
     let mut reply = String::new();
     reader
         .read_line(&mut reply)
@@ -37,3 +463,107 @@ pub fn request(request: DaemonRequest) -> Result<DaemonResponse, String> {
         .map_err(|e| format!("Invalid daemon response JSON: {e}"))?;
     Ok(envelope.payload)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{router, state::RuntimeState};
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use tokio::sync::RwLock;
+
+    fn unique_socket_path(label: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        std::env::temp_dir().join(format!("zenbook-duo-client-{label}-{nanos}.sock"))
+    }
+
+    fn spawn_router(path: PathBuf) -> thread::JoinHandle<()> {
+        let listener = std::os::unix::net::UnixListener::bind(&path).expect("bind test socket");
+        thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().expect("test runtime");
+            runtime.block_on(async move {
+                listener
+                    .set_nonblocking(true)
+                    .expect("nonblocking listener");
+                let listener =
+                    tokio::net::UnixListener::from_std(listener).expect("tokio listener");
+                let (stream, _) = listener.accept().await.expect("accept client");
+                let state = Arc::new(RwLock::new(RuntimeState::default()));
+                router::handle_client(stream, state)
+                    .await
+                    .expect("router result");
+            });
+        })
+    }
+
+    #[test]
+    fn concurrent_calls_share_one_pipelined_connection() {
+        let path = unique_socket_path("pipelined");
+        let server = spawn_router(path.clone());
+        let connection = DaemonConnection::open_at(&path).expect("open connection");
+
+        let callers: Vec<_> = (0..8)
+            .map(|index| {
+                let connection = connection.clone();
+                thread::spawn(move || {
+                    let request = if index % 2 == 0 {
+                        DaemonRequest::Ping
+                    } else {
+                        DaemonRequest::GetStatus
+                    };
+                    (index, connection.call(request).expect("daemon reply"))
+                })
+            })
+            .collect();
+
+        for caller in callers {
+            match caller.join().expect("join caller") {
+                (index, DaemonResponse::Pong) => assert_eq!(index % 2, 0),
+                (index, DaemonResponse::Status { status }) => {
+                    assert_eq!(index % 2, 1);
+                    assert!(status.service_active);
+                }
+                (_, other) => panic!("unexpected response: {other:?}"),
+            }
+        }
+
+        drop(connection);
+        server.join().expect("join server");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn pending_calls_fail_when_the_daemon_hangs_up() {
+        let path = unique_socket_path("hangup");
+        let listener = std::os::unix::net::UnixListener::bind(&path).expect("bind test socket");
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept client");
+            let mut reader = BufReader::new(stream.try_clone().expect("clone stream"));
+            let mut line = String::new();
+            reader.read_line(&mut line).expect("read hello");
+            let reply = Envelope::new(DaemonResponse::Hello {
+                handshake: Handshake::negotiated(PROTOCOL_VERSION, &[]),
+            });
+            let mut stream = stream;
+            writeln!(
+                stream,
+                "{}",
+                serde_json::to_string(&reply).expect("encode hello")
+            )
+            .expect("write hello");
+            line.clear();
+            reader.read_line(&mut line).expect("read request");
+        });
+
+        let connection = DaemonConnection::open_at(&path).expect("open connection");
+        let result = connection.call_with_timeout(DaemonRequest::Ping, Duration::from_secs(5));
+        assert_eq!(result.unwrap_err(), "Daemon connection closed");
+        assert!(!connection.is_open());
+
+        server.join().expect("join server");
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -1,14 +1,18 @@
 use std::process::{Command, Output};
+use std::time::Instant;
 
 use serde_json::Value;
 
-use crate::runtime::session;
+use crate::runtime::{metrics, session};
 
 pub fn command_output<S: AsRef<str>>(program: &str, args: &[S]) -> Result<Output, String> {
-    Command::new(program)
+    let started = Instant::now();
+    let output = Command::new(program)
         .args(args.iter().map(|arg| arg.as_ref()))
         .output()
-        .map_err(|e| format!("Failed to run {program}: {e}"))
+        .map_err(|e| format!("Failed to run {program}: {e}"));
+    record_command(program, started, &output);
+    output
 }
 
 pub fn command_succeeds<S: AsRef<str>>(program: &str, args: &[S]) -> bool {
@@ -23,9 +27,27 @@ pub fn run_command<S: AsRef<str>>(program: &str, args: &[S]) -> Result<(), Strin
 }
 
 pub fn niri_command_output(args: &[&str]) -> Result<Output, String> {
-    session::build_niri_command(args)
+    let started = Instant::now();
+    let output = session::build_niri_command(args)
         .output()
-        .map_err(|e| format!("Failed to run niri msg: {e}"))
+        .map_err(|e| format!("Failed to run niri msg: {e}"));
+    record_command("niri", started, &output);
+    output
+}
+
+/// A command that ran but exited non-zero is `failed`; one that could not be
+/// spawned at all is `error`.
+fn record_command(program: &str, started: Instant, output: &Result<Output, String>) {
+    let outcome = match output {
+        Ok(output) if output.status.success() => "ok",
+        Ok(_) => "failed",
+        Err(_) => "error",
+    };
+    metrics::observe(
+        metrics::COMMAND_DURATION,
+        &[("program", program), ("outcome", outcome)],
+        started.elapsed(),
+    );
 }
 
 pub fn niri_command_succeeds(args: &[&str]) -> bool {
@@ -8,25 +8,38 @@ use std::sync::Arc;
 
 mod session_bridge;
 mod notification_sink;
+mod hook_runner;
+pub(crate) use hook_runner::run_transition_hooks;
 pub(crate) use notification_sink::notify_runtime_error;
-use notification_sink::NotificationSink;
+pub(crate) use notification_sink::NotificationSink;
 use session_bridge::SessionBridge;
 
 use chrono::{Duration as ChronoDuration, Utc};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
+use tokio::signal::unix::{signal, SignalKind};
 use tokio::sync::RwLock;
 use tokio::time::{timeout, Duration};
 
 use crate::ipc::protocol::{
-    DaemonRequest, DaemonResponse, Envelope, LifecyclePhase, SessionCommand, SessionResponse,
-    PROTOCOL_VERSION,
+    negotiate_version, protocol_mismatch_message, DaemonRequest, DaemonResponse, Envelope,
+    Handshake, LifecyclePhase, SessionCommand, SessionResponse, PROTOCOL_VERSION,
 };
 use crate::models::DaemonVersionInfo;
-use crate::runtime::{logger, paths, router, service_control::ServiceController, state::RuntimeState};
+use crate::runtime::{
+    keyboard_idle, logger, metrics, paths, router, service_control::ServiceController,
+    state::RuntimeState,
+};
+use crate::runtime::event_journal::EventJournal;
+use crate::runtime::logger::LogSink;
+use crate::runtime::policy::{self, PolicyAction};
+use crate::runtime::posture::PostureAction;
 use crate::{
     commands, hardware,
-    models::{DisplayLayout, EventCategory, HardwareEvent, Orientation},
+    models::{
+        DisplayLayout, EventCategory, EventQuery, HardwareEvent, HookEvent, LogQuery,
+        MetricsSnapshot, Orientation, PolicyTrigger, PostureTrigger,
+    },
 };
 
 pub async fn run() -> Result<(), String> {
@@ -40,15 +53,30 @@ pub async fn run() -> Result<(), String> {
     configure_daemon_socket(paths::daemon_socket_path().as_path())
         .map_err(|e| format!("Failed to configure daemon socket: {e}"))?;
     let state = Arc::new(RwLock::new(initialize_state()));
+    crate::runtime::persistence::start(&state).await;
+    crate::runtime::event_journal::start(&state, EventJournal::system()).await;
     crate::runtime::bluetooth_hotkeys::start(state.clone());
     crate::runtime::monitor::start(state.clone());
     crate::runtime::logind::start(state.clone());
+    crate::runtime::keyboard_idle::start(state.clone());
+    crate::runtime::ambient_light::start(state.clone());
+    crate::runtime::dbus_service::start(state.clone());
+    crate::runtime::metrics::start(state.clone());
+
+    let mut terminate = signal(SignalKind::terminate())
+        .map_err(|e| format!("Failed to install SIGTERM handler: {e}"))?;
 
     loop {
-        let (stream, _) = listener
-            .accept()
-            .await
-            .map_err(|e| format!("Failed to accept daemon client: {e}"))?;
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = terminate.recv() => {
+                logger::info("rust-daemon", "stopping on SIGTERM");
+                let persister = state.read().await.persister.clone();
+                persister.flush().await;
+                return Ok(());
+            }
+        };
+        let (stream, _) = accepted.map_err(|e| format!("Failed to accept daemon client: {e}"))?;
         let state = state.clone();
         tokio::spawn(async move {
             if let Err(err) = router::handle_client(stream, state).await {
@@ -59,14 +87,10 @@ pub async fn run() -> Result<(), String> {
 }
 
 fn configure_daemon_socket(path: &Path) -> Result<(), String> {
-    let uid = std::env::var("ZENBOOK_DUO_UID")
-        .ok()
-        .and_then(|value| value.parse::<u32>().ok());
-    let gid = std::env::var("ZENBOOK_DUO_GID")
-        .ok()
-        .and_then(|value| value.parse::<u32>().ok());
-
-    if let (Some(uid), Some(gid)) = (uid, gid) {
+    if let (Some(uid), Some(gid)) = (
+        paths::configured_session_uid(),
+        paths::configured_session_gid(),
+    ) {
         let path_cstr = std::ffi::CString::new(path.as_os_str().as_encoded_bytes())
             .map_err(|_| format!("Socket path contains interior NUL: {}", path.display()))?;
         let result = unsafe { libc::chown(path_cstr.as_ptr(), uid, gid) };
@@ -93,9 +117,10 @@ fn initialize_state() -> RuntimeState {
     state.status.service_active = false;
     state.settings = commands::settings::load_settings_local();
     state.session_agent = Default::default();
+    state.reset_posture();
     state.touch();
-    persist_state(&state);
-    let _ = logger::append_line("rust-daemon: initialized runtime state");
+    state.persist();
+    logger::info("rust-daemon", "initialized runtime state");
     state
 }
 
@@ -105,6 +130,18 @@ pub(super) async fn dispatch_request(
 ) -> DaemonResponse {
 match payload {
     DaemonRequest::Ping => DaemonResponse::Pong,
+    DaemonRequest::Hello {
+        min_version,
+        max_version,
+        features,
+    } => match negotiate_version(min_version, max_version) {
+        Some(negotiated) => DaemonResponse::Hello {
+            handshake: Handshake::negotiated(negotiated, &features),
+        },
+        None => DaemonResponse::Error {
+            message: protocol_mismatch_message(max_version),
+        },
+    },
     DaemonRequest::HandleLifecycle { phase } => match DisplayReplayPolicy::handle_lifecycle(&state, phase).await
     {
         Ok(()) => DaemonResponse::Ack,
@@ -151,27 +188,29 @@ match payload {
         let mut guard = state.write().await;
         guard.settings = settings;
         guard.touch();
-        persist_state(&guard);
+        guard.persist();
         DaemonResponse::Ack
     }
-    DaemonRequest::SetBacklight { level } => match hardware::hid::set_backlight(level) {
-        Ok(()) => {
-            let mut guard = state.write().await;
-            guard.status.backlight_level = level;
-            let _ = logger::append_line(format!(
-                "rust-daemon: set backlight request -> {level}"
-            ));
-            guard.push_recent_event(HardwareEvent::info(
-                EventCategory::Keyboard,
-                format!("Backlight set to {level}"),
-                "rust-daemon",
-            ));
-            guard.touch();
-            persist_state(&guard);
-            DaemonResponse::Ack
+    DaemonRequest::SetBacklight { level } => {
+        let hardware = state.read().await.hardware.clone();
+        match hardware.set_backlight(level) {
+            Ok(()) => {
+                let mut guard = state.write().await;
+                guard.status.backlight_level = level;
+                guard.ambient_light.manual_keyboard_change();
+                logger::info("rust-daemon", format!("set backlight request -> {level}"));
+                guard.push_recent_event(HardwareEvent::info(
+                    EventCategory::Keyboard,
+                    format!("Backlight set to {level}"),
+                    "rust-daemon",
+                ));
+                guard.touch();
+                guard.persist();
+                DaemonResponse::Ack
+            }
+            Err(message) => DaemonResponse::Error { message },
         }
-        Err(message) => DaemonResponse::Error { message },
-    },
+    }
     DaemonRequest::SetOrientation { orientation } => {
         apply_orientation(&state, orientation).await
     }
@@ -179,26 +218,36 @@ match payload {
         apply_display_layout_request(&state, layout).await
     }
     DaemonRequest::UsbMediaRemapStart => {
-        let _ = logger::append_line("rust-daemon: start usb media remap request");
+        logger::info("rust-daemon", "start usb media remap request");
         match commands::usb_media_remap::start_remap() {
             Ok(()) => DaemonResponse::Ack,
             Err(message) => DaemonResponse::Error { message },
         }
     }
     DaemonRequest::UsbMediaRemapStop => {
-        let _ = logger::append_line("rust-daemon: stop usb media remap request");
+        logger::info("rust-daemon", "stop usb media remap request");
         match commands::usb_media_remap::stop_remap() {
             Ok(()) => DaemonResponse::Ack,
             Err(message) => DaemonResponse::Error { message },
         }
     }
     DaemonRequest::UsbMediaRemapTogglePause => {
-        let _ = logger::append_line("rust-daemon: toggle usb media remap pause request");
+        logger::info("rust-daemon", "toggle usb media remap pause request");
         match commands::usb_media_remap::toggle_pause() {
             Ok(()) => DaemonResponse::Ack,
             Err(message) => DaemonResponse::Error { message },
         }
     }
+    DaemonRequest::UsbMediaRemapSetPaused { paused } => {
+        logger::info(
+            "rust-daemon",
+            format!("set usb media remap paused={paused} request"),
+        );
+        match commands::usb_media_remap::set_paused(paused) {
+            Ok(()) => DaemonResponse::Ack,
+            Err(message) => DaemonResponse::Error { message },
+        }
+    }
     DaemonRequest::RestartService => match ServiceController::restart_owned_services() {
         Ok(()) => DaemonResponse::Ack,
         Err(message) => DaemonResponse::Error { message },
@@ -217,9 +266,49 @@ match payload {
         Ok(()) => DaemonResponse::Ack,
         Err(message) => DaemonResponse::Error { message },
     },
-    DaemonRequest::TailLogs { lines } => DaemonResponse::Logs {
-        lines: hardware::sysfs::read_log_lines(lines),
+    DaemonRequest::TailLogs { lines } => {
+        match tokio::task::spawn_blocking(move || logger::tail(lines)).await {
+            Ok(records) => DaemonResponse::LogRecords { records },
+            Err(err) => DaemonResponse::Error {
+                message: format!("Failed to read logs: {err}"),
+            },
+        }
+    }
+    DaemonRequest::QueryLogs {
+        since,
+        until,
+        components,
+        min_level,
+        contains,
+        pattern,
+        limit,
+        follow: _,
+    } => {
+        let query = LogQuery {
+            since,
+            until,
+            components,
+            min_level,
+            contains,
+            pattern,
+            limit,
+        };
+        query_logs(query).await
+    }
+    DaemonRequest::GetMetrics => DaemonResponse::Metrics {
+        metrics: collect_metrics(&state).await,
+    },
+    DaemonRequest::GetPosture => DaemonResponse::Posture {
+        posture: state.read().await.posture.report(),
     },
+    DaemonRequest::KeyboardActivity => {
+        keyboard_idle::wake(&state).await;
+        DaemonResponse::Ack
+    }
+    DaemonRequest::ManualBrightness => {
+        state.read().await.ambient_light.manual_display_change();
+        DaemonResponse::Ack
+    }
     DaemonRequest::ClearLogs => match logger::clear() {
         Ok(()) => DaemonResponse::Ack,
         Err(message) => DaemonResponse::Error { message },
@@ -238,6 +327,29 @@ match payload {
             .collect();
         DaemonResponse::Events { events }
     }
+    DaemonRequest::QueryEvents {
+        since,
+        until,
+        categories,
+        min_severity,
+        limit,
+    } => {
+        let query = EventQuery {
+            since,
+            until,
+            categories,
+            min_severity,
+            limit,
+        };
+        let journal = EventJournal::system();
+        match tokio::task::spawn_blocking(move || journal.query(&query)).await {
+            Ok(Ok(events)) => DaemonResponse::Events { events },
+            Ok(Err(message)) => DaemonResponse::Error { message },
+            Err(err) => DaemonResponse::Error {
+                message: format!("Failed to query event journal: {err}"),
+            },
+        }
+    }
     DaemonRequest::ListTouchscreens => {
         let devices = hardware::touchscreen::list_touchscreens();
         DaemonResponse::Touchscreens { devices }
@@ -256,6 +368,9 @@ match payload {
             },
         }
     }
+    DaemonRequest::Subscribe { .. } => DaemonResponse::Error {
+        message: "Subscribe must be the first request on a dedicated connection".into(),
+    },
 }
 }
 
@@ -274,17 +389,13 @@ fn remove_stale_socket(path: &Path) {
     }
 }
 
-fn persist_state(state: &RuntimeState) {
-    if let Err(err) = state.save() {
-        log::warn!("failed to persist runtime state: {err}");
-        let _ = logger::append_line(format!("rust-daemon: failed to persist state: {err}"));
-    }
-}
-
 pub(crate) struct DisplayReplayPolicy;
 
 impl DisplayReplayPolicy {
-    async fn handle_lifecycle(state: &Arc<RwLock<RuntimeState>>, phase: LifecyclePhase) -> Result<(), String> {
+    pub(crate) async fn handle_lifecycle(
+        state: &Arc<RwLock<RuntimeState>>,
+        phase: LifecyclePhase,
+    ) -> Result<(), String> {
         handle_lifecycle(state, phase).await
     }
 
@@ -296,27 +407,66 @@ impl DisplayReplayPolicy {
     }
 }
 
+pub(crate) async fn query_logs(query: LogQuery) -> DaemonResponse {
+    let filter = match query.compile() {
+        Ok(filter) => filter,
+        Err(message) => return DaemonResponse::Error { message },
+    };
+    match tokio::task::spawn_blocking(move || logger::query(&log_sinks(), &filter)).await {
+        Ok(Ok(records)) => DaemonResponse::LogRecords { records },
+        Ok(Err(message)) => DaemonResponse::Error { message },
+        Err(err) => DaemonResponse::Error {
+            message: format!("Failed to query logs: {err}"),
+        },
+    }
+}
+
+/// The daemon's own metrics plus the session agent's, labelled
+/// `process="session_agent"`. The agent is only asked when it is connected,
+/// and a failed round trip just leaves its series out.
+pub(crate) async fn collect_metrics(state: &Arc<RwLock<RuntimeState>>) -> MetricsSnapshot {
+    let agent_connected = state.read().await.session_agent.connected;
+    let agent = if agent_connected {
+        session_bridge::request_session(state.clone(), SessionCommand::GetMetrics, false)
+            .await
+            .ok()
+    } else {
+        None
+    };
+
+    let mut snapshot = metrics::snapshot();
+    if let Some(SessionResponse::Metrics { metrics }) = agent {
+        snapshot.merge(metrics.with_label("process", "session_agent"));
+    }
+    snapshot
+}
+
+/// Logs searched by `QueryLogs`: the runtime log shared by the daemon and
+/// session agent, and the USB remap helper's own log.
+pub(crate) fn log_sinks() -> Vec<LogSink> {
+    vec![
+        LogSink::system(),
+        LogSink::at(commands::usb_media_remap::log_path()),
+    ]
+}
+
 async fn handle_lifecycle(
     state: &Arc<RwLock<RuntimeState>>,
     phase: LifecyclePhase,
 ) -> Result<(), String> {
+    let hardware = state.read().await.hardware.clone();
     match phase {
         LifecyclePhase::Pre | LifecyclePhase::Hibernate | LifecyclePhase::Shutdown => {
-            logger::append_line(format!("rust-daemon: lifecycle -> {:?}", phase)).ok();
+            logger::info("rust-daemon", format!("lifecycle -> {:?}", phase));
             if lifecycle_should_stop_usb_media_remap(&phase) {
-                if let Err(err) = commands::usb_media_remap::stop_remap() {
-                    log::warn!(
-                        "failed to stop usb media remap for lifecycle {:?}: {err}",
-                        phase
+                if let Err(err) = hardware.stop_usb_media_remap() {
+                    logger::warn(
+                        "rust-daemon",
+                        format!("lifecycle usb media remap stop skipped: {}", err),
                     );
-                    logger::append_line(format!(
-                        "rust-daemon: lifecycle usb media remap stop skipped: {}",
-                        err
-                    ))
-                    .ok();
                 }
             }
-            hardware::hid::set_backlight(0)?;
+            hardware.set_backlight(0)?;
 
             let mut guard = state.write().await;
             guard.push_recent_event(HardwareEvent::info(
@@ -324,51 +474,73 @@ async fn handle_lifecycle(
                 format!("Lifecycle event: {:?}", phase),
                 "rust-daemon",
             ));
+            guard.sync_posture(PostureTrigger::Suspend);
             guard.touch();
-            persist_state(&guard);
+            guard.persist();
+            let persister = guard.persister.clone();
+            drop(guard);
+            // The machine may be gone before the write-behind delay expires.
+            persister.flush().await;
             Ok(())
         }
         LifecyclePhase::Post | LifecyclePhase::Thaw | LifecyclePhase::Boot => {
-            logger::append_line(format!("rust-daemon: lifecycle -> {:?}", phase)).ok();
+            logger::info("rust-daemon", format!("lifecycle -> {:?}", phase));
             if lifecycle_should_queue_usb_media_remap_retry(&phase) {
                 crate::runtime::monitor::queue_usb_media_remap_resume_retry(state.clone());
             }
 
             let (restore_level, scale) = {
                 let guard = state.read().await;
-                (guard.status.backlight_level, guard.settings.default_scale)
+                // Waking up counts as activity, so an idle-dimmed backlight
+                // comes back at the level it had.
+                let level = guard
+                    .keyboard_idle
+                    .activity()
+                    .unwrap_or(guard.status.backlight_level);
+                (level, guard.settings.default_scale)
             };
-            hardware::hid::set_backlight(restore_level)?;
+            // Written rather than read back: suspend left the keyboard at 0,
+            // and a keyboard swapped while asleep takes the level from here.
+            hardware.set_backlight(restore_level)?;
 
-            let refreshed = crate::runtime::probe::current_status();
+            let refreshed = hardware.current_status();
             let attached = refreshed.keyboard_attached;
+            let context = hardware.policy_context();
 
-            {
+            let policy = {
                 let mut guard = state.write().await;
-                let previous_level = guard.status.backlight_level;
                 guard.status = refreshed;
-                guard.status.backlight_level = previous_level;
+                guard.status.backlight_level = restore_level;
                 guard.status.service_active = true;
                 guard.push_recent_event(HardwareEvent::info(
                     EventCategory::Service,
                     format!("Lifecycle event: {:?}", phase),
                     "rust-daemon",
                 ));
+                // The display replay below covers whatever posture we woke in.
+                guard.sync_posture(if matches!(phase, LifecyclePhase::Boot) {
+                    PostureTrigger::Startup
+                } else {
+                    PostureTrigger::Resume
+                });
+                let policy = if matches!(phase, LifecyclePhase::Boot) {
+                    Vec::new()
+                } else {
+                    policy::evaluate_rules(&mut guard, PolicyTrigger::Resume, &context)
+                };
                 guard.touch();
-                persist_state(&guard);
-            }
-
+                guard.persist();
+                policy
+            };
             // Restore touchscreen disabled state
             {
                 let guard = state.read().await;
                 let disabled = guard.settings.touchscreen_disabled.clone();
                 drop(guard);
                 for connector in &disabled {
-                    let devices = hardware::touchscreen::list_touchscreens();
+                    let devices = hardware.touchscreens();
                     if let Some(dev) = devices.iter().find(|d| &d.connector == connector) {
-                        if let Err(e) =
-                            hardware::touchscreen::set_touchscreen_enabled(&dev.i2c_id, false)
-                        {
+                        if let Err(e) = hardware.set_touchscreen_enabled(&dev.i2c_id, false) {
                             eprintln!(
                                 "rust-daemon: failed to restore touchscreen disabled for {}: {}",
                                 connector, e
@@ -378,7 +550,12 @@ async fn handle_lifecycle(
                 }
             }
 
-            refresh_lifecycle_display_mode(state, attached, scale).await
+            let result = refresh_lifecycle_display_mode(state, attached, scale).await;
+            crate::runtime::monitor::apply_policy_actions(state.clone(), policy).await;
+            if !matches!(phase, LifecyclePhase::Boot) {
+                run_transition_hooks(state, HookEvent::Resume).await;
+            }
+            result
         }
     }
 }
@@ -402,11 +579,10 @@ async fn refresh_lifecycle_display_mode(
     match replay_current_display_mode_with_disconnect(state, attached, scale, false).await {
         Ok(()) => Ok(()),
         Err(err) if is_display_session_deferral(&err) => {
-            logger::append_line(format!(
-                "rust-daemon: lifecycle dock refresh deferred: {}",
-                err
-            ))
-            .ok();
+            logger::info(
+                "rust-daemon",
+                format!("lifecycle dock refresh deferred: {}", err),
+            );
             queue_lifecycle_display_retry(state.clone());
             Ok(())
         }
@@ -417,11 +593,10 @@ async fn refresh_lifecycle_display_mode(
                 &format!("Lifecycle dock refresh skipped: {err}"),
             )
             .await;
-            logger::append_line(format!(
-                "rust-daemon: lifecycle dock refresh skipped: {}",
-                err
-            ))
-            .ok();
+            logger::warn(
+                "rust-daemon",
+                format!("lifecycle dock refresh skipped: {}", err),
+            );
             Ok(())
         }
     }
@@ -440,22 +615,26 @@ async fn handle_session_registration(
         guard.session_agent.backend = Some(backend);
         guard.session_agent.socket_path = Some(socket_path);
         guard.status.service_active = true;
-        let _ = logger::append_line(format!(
-            "rust-daemon: session agent registered ({:?})",
-            guard.session_agent.backend
-        ));
+        logger::info(
+            "rust-daemon",
+            format!(
+                "session agent registered ({:?})",
+                guard.session_agent.backend
+            ),
+        );
+        guard.publish_session_agent_change();
         guard.touch();
-        persist_state(&guard);
+        guard.persist();
     }
 
     let state = Arc::clone(state);
     tokio::spawn(async move {
         if let Err(err) = replay_current_display_mode_from_state(&state).await {
             if is_display_session_deferral(&err) {
-                let _ = logger::append_line(format!(
-                    "rust-daemon: session registration dock replay deferred: {}",
-                    err
-                ));
+                logger::info(
+                    "rust-daemon",
+                    format!("session registration dock replay deferred: {}", err),
+                );
                 queue_lifecycle_display_retry(state.clone());
                 return;
             }
@@ -467,10 +646,10 @@ async fn handle_session_registration(
                 &format!("Session registration dock replay skipped: {err}"),
             )
             .await;
-            let _ = logger::append_line(format!(
-                "rust-daemon: session registration dock replay skipped: {}",
-                err
-            ));
+            logger::warn(
+                "rust-daemon",
+                format!("session registration dock replay skipped: {}", err),
+            );
         }
     });
 
@@ -502,15 +681,29 @@ async fn replay_current_display_mode_with_disconnect(
     attached: bool,
     scale: f64,
     disconnect_on_failure: bool,
+) -> Result<(), String> {
+    let started = std::time::Instant::now();
+    let result = replay_display_mode(state, attached, scale, disconnect_on_failure).await;
+    metrics::observe_result(metrics::DOCK_REPLAY_DURATION, &[], started, &result);
+    result
+}
+
+async fn replay_display_mode(
+    state: &Arc<RwLock<RuntimeState>>,
+    attached: bool,
+    scale: f64,
+    disconnect_on_failure: bool,
 ) -> Result<(), String> {
     if state.read().await.lid_closed {
         if apply_external_only_clamshell_layout(state, disconnect_on_failure).await? {
-            let _ = logger::append_line(
-                "rust-daemon: enforced external-only display layout while lid is closed",
+            logger::info(
+                "rust-daemon",
+                "enforced external-only display layout while lid is closed",
             );
         } else {
-            let _ = logger::append_line(
-                "rust-daemon: skipped display replay while lid is closed and no external display is active",
+            logger::warn(
+                "rust-daemon",
+                "skipped display replay while lid is closed and no external display is active",
             );
         }
         return Ok(());
@@ -527,8 +720,9 @@ async fn replay_current_display_mode_with_disconnect(
             .map(layout_manages_only_internal_displays)
             .unwrap_or(true)
     {
-        let _ = logger::append_line(
-            "rust-daemon: skipped internal display replay while an external display is active",
+        logger::warn(
+            "rust-daemon",
+            "skipped internal display replay while an external display is active",
         );
         return Ok(());
     }
@@ -556,27 +750,58 @@ pub(crate) async fn handle_lid_closed_change(
     state: &Arc<RwLock<RuntimeState>>,
     lid_closed: bool,
 ) -> Result<(), String> {
-    let Some((attached, scale)) = record_lid_closed_state(state, lid_closed).await else {
-        let _ = logger::append_line(format!(
-            "rust-daemon: ignored duplicate lid {} signal",
-            if lid_closed { "closed" } else { "opened" }
-        ));
+    let Some((attached, scale, actions, policy)) = record_lid_closed_state(state, lid_closed).await
+    else {
+        logger::info(
+            "rust-daemon",
+            format!(
+                "ignored duplicate lid {} signal",
+                if lid_closed { "closed" } else { "opened" }
+            ),
+        );
         return Ok(());
     };
 
-    let result = apply_lid_display_state(state, lid_closed, attached, scale, true).await;
-    if let Err(err) = &result {
-        if is_display_session_deferral(err) {
-            queue_lid_display_retry(state.clone(), lid_closed);
+    let result = apply_posture_actions(state, actions, attached, scale).await;
+    crate::runtime::monitor::apply_policy_actions(state.clone(), policy).await;
+    run_transition_hooks(
+        state,
+        if lid_closed {
+            HookEvent::LidClosed
+        } else {
+            HookEvent::LidOpened
+        },
+    )
+    .await;
+    result
+}
+
+async fn apply_posture_actions(
+    state: &Arc<RwLock<RuntimeState>>,
+    actions: Vec<PostureAction>,
+    attached: bool,
+    scale: f64,
+) -> Result<(), String> {
+    // Lid changes while suspended carry no actions; resume replays instead.
+    for action in actions {
+        let closed = action == PostureAction::ApplyClamshellLayout;
+        let result = apply_lid_display_state(state, closed, attached, scale, true).await;
+        if let Err(err) = &result {
+            if is_display_session_deferral(err) {
+                queue_lid_display_retry(state.clone(), closed);
+            }
         }
+        result?;
     }
-    result
+    Ok(())
 }
 
 async fn record_lid_closed_state(
     state: &Arc<RwLock<RuntimeState>>,
     lid_closed: bool,
-) -> Option<(bool, f64)> {
+) -> Option<(bool, f64, Vec<PostureAction>, Vec<PolicyAction>)> {
+    let hardware = state.read().await.hardware.clone();
+    let context = hardware.policy_context();
     let mut guard = state.write().await;
     if guard.lid_closed == lid_closed {
         return None;
@@ -592,9 +817,16 @@ async fn record_lid_closed_state(
         },
         "rust-daemon",
     ));
+    let actions = guard.sync_posture(PostureTrigger::Lid);
+    let policy = policy::evaluate_rules(&mut guard, PolicyTrigger::Lid, &context);
     guard.touch();
-    persist_state(&guard);
-    Some((guard.status.keyboard_attached, guard.settings.default_scale))
+    guard.persist();
+    Some((
+        guard.status.keyboard_attached,
+        guard.settings.default_scale,
+        actions,
+        policy,
+    ))
 }
 
 async fn apply_lid_display_state(
@@ -607,20 +839,24 @@ async fn apply_lid_display_state(
     if lid_closed {
         if apply_external_only_clamshell_layout(state, false).await? {
             if log_transition {
-                let _ = logger::append_line(
-                    "rust-daemon: lid closed with external display; applied external-only layout",
+                logger::info(
+                    "rust-daemon",
+                    "lid closed with external display; applied external-only layout",
                 );
             }
         } else if log_transition {
-            let _ = logger::append_line(
-                "rust-daemon: lid closed without an active external display; no display layout change applied",
+            logger::info(
+                "rust-daemon",
+                "lid closed without an active external display; no display layout change applied",
             );
         }
         Ok(())
     } else {
         if log_transition {
-            let _ =
-                logger::append_line("rust-daemon: lid opened; replaying current dock display mode");
+            logger::info(
+                "rust-daemon",
+                "lid opened; replaying current dock display mode",
+            );
         }
         replay_current_display_mode_with_disconnect(state, attached, scale, false).await
     }
@@ -643,29 +879,36 @@ fn queue_lid_display_retry(state: Arc<RwLock<RuntimeState>>, lid_closed: bool) {
                 )
             };
             if current_lid_closed != lid_closed {
-                let _ = logger::append_line(
-                    "rust-daemon: cancelled lid display retry after lid state changed",
+                record_display_retry("lid", "cancelled");
+                logger::info(
+                    "rust-daemon",
+                    "cancelled lid display retry after lid state changed",
                 );
                 return;
             }
 
             match apply_lid_display_state(&state, lid_closed, attached, scale, false).await {
                 Ok(()) => {
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: lid display retry succeeded on attempt {attempt}"
-                    ));
+                    record_display_retry("lid", "succeeded");
+                    logger::info(
+                        "rust-daemon",
+                        format!("lid display retry succeeded on attempt {attempt}"),
+                    );
                     return;
                 }
                 Err(err) if is_display_session_deferral(&err) => {
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: lid display retry deferred on attempt {attempt}: {err}"
-                    ));
+                    record_display_retry("lid", "deferred");
+                    logger::info(
+                        "rust-daemon",
+                        format!("lid display retry deferred on attempt {attempt}: {err}"),
+                    );
                 }
                 Err(err) => {
-                    log::warn!("lid display retry failed: {err}");
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: lid display retry failed on attempt {attempt}: {err}"
-                    ));
+                    record_display_retry("lid", "failed");
+                    logger::warn(
+                        "rust-daemon",
+                        format!("lid display retry failed on attempt {attempt}: {err}"),
+                    );
                     return;
                 }
             }
@@ -685,21 +928,28 @@ fn queue_lifecycle_display_retry(state: Arc<RwLock<RuntimeState>>) {
             match replay_current_display_mode_with_disconnect(&state, attached, scale, false).await
             {
                 Ok(()) => {
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: lifecycle dock refresh retry succeeded on attempt {attempt}"
-                    ));
+                    record_display_retry("lifecycle", "succeeded");
+                    logger::info(
+                        "rust-daemon",
+                        format!("lifecycle dock refresh retry succeeded on attempt {attempt}"),
+                    );
                     return;
                 }
                 Err(err) if is_display_session_deferral(&err) => {
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: lifecycle dock refresh retry deferred on attempt {attempt}: {err}"
-                    ));
+                    record_display_retry("lifecycle", "deferred");
+                    logger::info(
+                        "rust-daemon",
+                        format!(
+                            "lifecycle dock refresh retry deferred on attempt {attempt}: {err}"
+                        ),
+                    );
                 }
                 Err(err) => {
-                    log::warn!("lifecycle dock refresh retry failed: {err}");
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: lifecycle dock refresh retry failed on attempt {attempt}: {err}"
-                    ));
+                    record_display_retry("lifecycle", "failed");
+                    logger::warn(
+                        "rust-daemon",
+                        format!("lifecycle dock refresh retry failed on attempt {attempt}: {err}"),
+                    );
                     return;
                 }
             }
@@ -707,6 +957,14 @@ fn queue_lifecycle_display_retry(state: Arc<RwLock<RuntimeState>>) {
     });
 }
 
+/// Counts one retry attempt; `outcome` is what that attempt led to.
+fn record_display_retry(kind: &str, outcome: &str) {
+    metrics::increment(
+        metrics::DISPLAY_RETRIES,
+        &[("kind", kind), ("outcome", outcome)],
+    );
+}
+
 pub(crate) fn is_display_session_deferral(message: &str) -> bool {
     message == "No session agent registered"
         || message.starts_with("Timed out ")
@@ -858,6 +1116,12 @@ async fn forward_session_command_with_disconnect(
         SessionResponse::DisplayLayout { .. } => {
             Err("Unexpected display-layout response for command request".into())
         }
+        SessionResponse::Metrics { .. } => {
+            Err("Unexpected metrics response for command request".into())
+        }
+        SessionResponse::HookOutcomes { .. } => {
+            Err("Unexpected hook-outcomes response for command request".into())
+        }
     }
 }
 
@@ -887,6 +1151,12 @@ async fn session_display_layout_result(
         SessionResponse::DisplayLayout { layout } => Ok(layout),
         SessionResponse::Error { message } => Err(message),
         SessionResponse::Ack => Err("Unexpected ack response for display-layout request".into()),
+        SessionResponse::Metrics { .. } => {
+            Err("Unexpected metrics response for display-layout request".into())
+        }
+        SessionResponse::HookOutcomes { .. } => {
+            Err("Unexpected hook-outcomes response for display-layout request".into())
+        }
     }
 }
 
@@ -905,12 +1175,12 @@ async fn apply_orientation(
         Ok(()) => {
             let mut guard = state.write().await;
             guard.status.orientation = orientation;
-            let _ = logger::append_line(format!(
-                "rust-daemon: applied orientation -> {:?}",
-                guard.status.orientation
-            ));
+            logger::info(
+                "rust-daemon",
+                format!("applied orientation -> {:?}", guard.status.orientation),
+            );
             guard.touch();
-            persist_state(&guard);
+            guard.persist();
             DaemonResponse::Ack
         }
         Err(message) => DaemonResponse::Error { message },
@@ -932,12 +1202,15 @@ async fn apply_display_layout_request(
         Ok(()) => {
             let mut guard = state.write().await;
             crate::runtime::probe::apply_layout_to_status(&mut guard.status, Some(&layout));
-            let _ = logger::append_line(format!(
-                "rust-daemon: applied display layout with {} displays",
-                guard.status.monitor_count
-            ));
+            logger::info(
+                "rust-daemon",
+                format!(
+                    "applied display layout with {} displays",
+                    guard.status.monitor_count
+                ),
+            );
             guard.touch();
-            persist_state(&guard);
+            guard.persist();
             DaemonResponse::Ack
         }
         Err(message) => DaemonResponse::Error { message },
@@ -969,6 +1242,20 @@ mod tests {
         state
     }
 
+    #[tokio::test]
+    async fn scraping_session_metrics_is_not_recorded_as_a_session_request() {
+        let socket_path = unique_test_socket_path("metrics-scrape");
+        let state = state_with_connected_session_agent(&socket_path).await;
+
+        collect_metrics(&state).await;
+
+        let scraped = metrics::snapshot().histograms.into_iter().any(|sample| {
+            sample.name == metrics::SESSION_REQUEST_DURATION
+                && sample.labels.get("command").map(String::as_str) == Some("get_metrics")
+        });
+        assert!(!scraped);
+    }
+
     #[tokio::test]
     async fn command_write_timeout_clears_dead_session_agent() {
         let socket_path = unique_test_socket_path("write-timeout-command");
@@ -1013,6 +1300,37 @@ mod tests {
         assert!(!guard.status.service_active);
     }
 
+    #[tokio::test]
+    async fn hook_failures_are_recorded_as_warning_events() {
+        let state = Arc::new(RwLock::new(RuntimeState::default()));
+        let outcomes = [
+            crate::models::HookOutcome {
+                hook: "/etc/zenbook-duo/hooks.d/10-audio".into(),
+                status: crate::models::HookStatus::Succeeded,
+                output: "switched sink".into(),
+                duration_ms: 12,
+            },
+            crate::models::HookOutcome {
+                hook: "/etc/zenbook-duo/hooks.d/20-tmux".into(),
+                status: crate::models::HookStatus::TimedOut,
+                output: String::new(),
+                duration_ms: 500,
+            },
+        ];
+
+        hook_runner::report_hook_outcomes(&state, HookEvent::Attach, "system", &outcomes, 500)
+            .await;
+
+        let guard = state.read().await;
+        assert_eq!(guard.recent_events.len(), 1);
+        let event = &guard.recent_events[0];
+        assert_eq!(event.severity, crate::models::EventSeverity::Warning);
+        assert_eq!(
+            event.message,
+            "Hook /etc/zenbook-duo/hooks.d/20-tmux failed on attach: timed out after 500 ms"
+        );
+    }
+
     #[tokio::test]
     async fn liveness_display_layout_probe_clears_dead_session_agent() {
         let socket_path = unique_test_socket_path("dead-session-agent");
@@ -1873,6 +2191,7 @@ mod tests {
             guard.status.keyboard_attached = true;
             guard.settings.default_scale = 1.5;
             guard.settings.saved_display_layout = Some(dual_display_layout_with_refresh(120.0));
+            guard.reset_posture();
         }
 
         timeout(
@@ -0,0 +1,136 @@
+use super::*;
+use crate::models::{HookEvent, HookInvocation, HookOutcome, HookStatus};
+use crate::runtime::hooks;
+use crate::runtime::simulation::TranscriptEntry;
+
+/// Runs are queued so scripts from two quick transitions never interleave.
+static HOOK_RUNS: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Runs the system hooks, then the user's through the session agent, for a
+/// transition whose policy actions have been applied. The scripts run in
+/// the background so a slow hook never holds up the monitor.
+pub(crate) async fn run_transition_hooks(state: &Arc<RwLock<RuntimeState>>, event: HookEvent) {
+    let (invocation, timeout_ms) = {
+        let guard = state.read().await;
+        // Simulated runs note the transition instead of running scripts.
+        if guard.hardware.is_simulated() {
+            guard.hardware.record(TranscriptEntry::Hooks { event });
+            return;
+        }
+        let context = guard.hardware.policy_context();
+        let status = &guard.status;
+        let invocation = HookInvocation {
+            event,
+            posture: guard.posture.current(),
+            keyboard_attached: status.keyboard_attached,
+            connection_type: status.connection_type.clone(),
+            monitor_count: status.monitor_count,
+            external_monitor: context.external_monitor,
+            lid_closed: guard.lid_closed,
+            on_ac_power: context.on_ac_power,
+            wifi_enabled: status.wifi_enabled,
+            bluetooth_enabled: status.bluetooth_enabled,
+        };
+        (invocation, guard.settings.hook_timeout_ms)
+    };
+
+    let state = state.clone();
+    tokio::spawn(async move {
+        let _queued = HOOK_RUNS.lock().await;
+
+        let system_invocation = invocation.clone();
+        let system = tokio::task::spawn_blocking(move || {
+            hooks::run_hooks(
+                Path::new(hooks::SYSTEM_HOOKS_DIR),
+                &system_invocation,
+                Duration::from_millis(timeout_ms),
+            )
+        })
+        .await
+        .unwrap_or_default();
+        report_hook_outcomes(&state, event, "system", &system, timeout_ms).await;
+
+        // The daemon sees the user's config through ZENBOOK_DUO_HOME, so an
+        // empty hooks directory needs no session round trip.
+        if !state.read().await.session_agent.connected
+            || !hooks::has_entries(&hooks::user_hooks_dir())
+        {
+            return;
+        }
+        let command = SessionCommand::RunHooks {
+            invocation,
+            timeout_ms,
+        };
+        match session_bridge::request_session(state.clone(), command, false).await {
+            Ok(SessionResponse::HookOutcomes { outcomes }) => {
+                report_hook_outcomes(&state, event, "user", &outcomes, timeout_ms).await;
+            }
+            Ok(SessionResponse::Error { message }) | Err(message) => {
+                logger::warn(
+                    "rust-daemon",
+                    format!("user hooks for {} not run: {}", event.label(), message),
+                );
+            }
+            Ok(other) => {
+                logger::warn(
+                    "rust-daemon",
+                    format!(
+                        "user hooks for {} not run: unexpected session response {other:?}",
+                        event.label()
+                    ),
+                );
+            }
+        }
+    });
+}
+
+/// Logs each hook and its output, and records failures as warning events.
+pub(super) async fn report_hook_outcomes(
+    state: &Arc<RwLock<RuntimeState>>,
+    event: HookEvent,
+    scope: &str,
+    outcomes: &[HookOutcome],
+    timeout_ms: u64,
+) {
+    let mut failures = Vec::new();
+    for outcome in outcomes {
+        let detail = outcome.status.describe(timeout_ms);
+        let summary = format!(
+            "{scope} hook {} on {} {} ({} ms)",
+            outcome.hook,
+            event.label(),
+            detail,
+            outcome.duration_ms
+        );
+        if outcome.status == HookStatus::Succeeded {
+            logger::info("rust-daemon", summary);
+        } else {
+            logger::warn("rust-daemon", summary);
+        }
+        for line in outcome.output.lines() {
+            logger::info("rust-daemon", format!("hook {}: {}", outcome.hook, line));
+        }
+        if outcome.status.is_failure() {
+            failures.push(format!(
+                "Hook {} failed on {}: {}",
+                outcome.hook,
+                event.label(),
+                detail
+            ));
+        }
+    }
+
+    if failures.is_empty() {
+        return;
+    }
+    let mut guard = state.write().await;
+    for message in failures {
+        guard.push_recent_event(HardwareEvent::warning(
+            EventCategory::Service,
+            message,
+            "rust-daemon",
+        ));
+    }
+    guard.touch();
+    guard.persist();
+}
@@ -6,6 +6,15 @@ impl NotificationSink {
     pub(crate) async fn runtime_error(state: &Arc<RwLock<RuntimeState>>, title: &str, message: &str) {
         notify_runtime_error(state, title, message).await;
     }
+
+    pub(crate) async fn keyboard_battery_low(state: &Arc<RwLock<RuntimeState>>, percent: u8) {
+        notify_runtime_error(
+            state,
+            "Zenbook Duo Keyboard Battery Low",
+            &format!("The keyboard has {percent}% battery left. Attach it to charge."),
+        )
+        .await;
+    }
 }
 
 pub(crate) async fn notify_runtime_error(
@@ -69,7 +78,7 @@ async fn should_emit_runtime_notification(
         key,
         emitted_at: now,
     });
-    persist_state(&guard);
+    guard.persist();
     true
 }
 
@@ -94,14 +103,20 @@ async fn send_runtime_notification_via_session(
         SessionResponse::DisplayLayout { .. } => {
             Err("Unexpected display-layout response for notification request".into())
         }
+        SessionResponse::Metrics { .. } => {
+            Err("Unexpected metrics response for notification request".into())
+        }
+        SessionResponse::HookOutcomes { .. } => {
+            Err("Unexpected hook-outcomes response for notification request".into())
+        }
     }
 }
 
 pub(super) fn send_runtime_notification_direct(title: &str, message: &str) -> Result<(), String> {
     let target_user = std::env::var("ZENBOOK_DUO_USER")
         .map_err(|_| "ZENBOOK_DUO_USER is not set for runtime notifications".to_string())?;
-    let target_uid = std::env::var("ZENBOOK_DUO_UID")
-        .map_err(|_| "ZENBOOK_DUO_UID is not set for runtime notifications".to_string())?;
+    let target_uid = paths::configured_session_uid()
+        .ok_or_else(|| "ZENBOOK_DUO_UID is not set for runtime notifications".to_string())?;
     let runtime_dir = format!("/run/user/{target_uid}");
     let bus_address = format!("unix:path={runtime_dir}/bus");
 
@@ -16,6 +16,27 @@ pub(super) async fn request_session(
     state: Arc<RwLock<RuntimeState>>,
     command: SessionCommand,
     disconnect_on_failure: bool,
+) -> Result<SessionResponse, String> {
+    let kind = command.kind();
+    // Scraping metrics must not show up in the histogram it scrapes.
+    let observed = !matches!(command, SessionCommand::GetMetrics);
+    let started = std::time::Instant::now();
+    let result = send_session_request(state, command, disconnect_on_failure).await;
+    if observed {
+        metrics::observe_result(
+            metrics::SESSION_REQUEST_DURATION,
+            &[("command", kind)],
+            started,
+            &result,
+        );
+    }
+    result
+}
+
+async fn send_session_request(
+    state: Arc<RwLock<RuntimeState>>,
+    command: SessionCommand,
+    disconnect_on_failure: bool,
 ) -> Result<SessionResponse, String> {
     let socket_path = {
         let guard = state.read().await;
@@ -48,6 +69,7 @@ pub(super) async fn request_session(
     };
     let (reader, mut writer) = stream.into_split();
 
+    let reply_timeout = command.reply_timeout();
     let line = serde_json::to_string(&Envelope::new(command))
         .map_err(|e| format!("Failed to encode session command: {e}"))?;
     match timeout(Duration::from_secs(3), writer.write_all(line.as_bytes())).await {
@@ -94,7 +116,7 @@ pub(super) async fn request_session(
     }
 
     let mut lines = BufReader::new(reader).lines();
-    let reply = match timeout(Duration::from_secs(3), lines.next_line()).await {
+    let reply = match timeout(reply_timeout, lines.next_line()).await {
         Err(_) => {
             if disconnect_on_failure {
                 mark_session_agent_disconnected(&state, "Timed out waiting for session response")
@@ -150,24 +172,24 @@ async fn mark_session_agent_disconnected(state: &Arc<RwLock<RuntimeState>>, reas
             format!("Session agent disconnected: {reason}"),
             "rust-daemon",
         ));
+        guard.publish_session_agent_change();
     }
     guard.touch();
-    persist_state(&guard);
-    let _ = logger::append_line(format!(
-        "rust-daemon: session agent disconnected: {}",
-        reason
-    ));
+    guard.persist();
+    logger::info(
+        "rust-daemon",
+        format!("session agent disconnected: {}", reason),
+    );
     drop(guard);
 
     if was_connected {
         if let Err(err) = ServiceController::queue_target_user_unit_restart("zenbook-duo-session-agent.service") {
-            log::warn!("failed to queue session agent restart: {err}");
-            let _ = logger::append_line(format!(
-                "rust-daemon: failed to queue session agent restart: {}",
-                err
-            ));
+            logger::warn(
+                "rust-daemon",
+                format!("failed to queue session agent restart: {}", err),
+            );
         } else {
-            let _ = logger::append_line("rust-daemon: queued session agent restart");
+            logger::info("rust-daemon", "queued session agent restart");
         }
     }
 
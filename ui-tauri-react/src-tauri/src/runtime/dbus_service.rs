@@ -0,0 +1,839 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::RwLock;
+use zbus::message::Header;
+use zbus::object_server::{InterfaceRef, SignalEmitter};
+use zbus::zvariant::Value;
+use zbus::{fdo, Connection};
+
+use crate::ipc::protocol::{DaemonEvent, DaemonRequest, DaemonResponse};
+use crate::models::{
+    ConnectionType, DisplayLayout, DuoSettings, DuoStatus, EventQuery, LogQuery, Orientation,
+};
+use crate::runtime::peer_auth::{self, AccessTier};
+use crate::runtime::{daemon, event_bus, logger, state::RuntimeState};
+
+pub const BUS_NAME: &str = "org.zenbook.Duo1";
+pub const OBJECT_PATH: &str = "/org/zenbook/Duo1";
+
+const CONTROL_ACTION: &str = "org.zenbook.duo1.control";
+const ADMIN_ACTION: &str = "org.zenbook.duo1.admin";
+/// `CheckAuthorizationFlags.AllowUserInteraction`.
+const POLKIT_ALLOW_USER_INTERACTION: u32 = 1;
+
+/// System-bus Interface for the daemon.
+///
+/// Methods go through the same `dispatch_request` path as the JSON-lines
+/// socket, so both front doors behave identically. Mutating methods are
+/// guarded by polkit instead of `SO_PEERCRED`, since bus callers are
+/// identified by their unique name.
+pub fn start(state: Arc<RwLock<RuntimeState>>) {
+    tokio::spawn(async move {
+        if let Err(err) = serve(state).await {
+            logger::warn("rust-daemon", format!("D-Bus service failed: {err}"));
+        }
+    });
+}
+
+async fn serve(state: Arc<RwLock<RuntimeState>>) -> Result<(), zbus::Error> {
+    let connection = zbus::connection::Builder::system()?
+        .name(BUS_NAME)?
+        .serve_at(
+            OBJECT_PATH,
+            DuoService {
+                state: state.clone(),
+            },
+        )?
+        .build()
+        .await?;
+    logger::info(
+        "rust-daemon",
+        format!("D-Bus service published as {BUS_NAME}"),
+    );
+
+    let interface = connection
+        .object_server()
+        .interface::<_, DuoService>(OBJECT_PATH)
+        .await?;
+    forward_status_changes(&interface, &state).await
+}
+
+/// Turns status snapshots from the event bus into `PropertiesChanged`
+/// signals, one per property that actually moved.
+async fn forward_status_changes(
+    interface: &InterfaceRef<DuoService>,
+    state: &Arc<RwLock<RuntimeState>>,
+) -> Result<(), zbus::Error> {
+    let (mut receiver, mut last) = {
+        let guard = state.read().await;
+        (
+            guard.event_bus.subscribe(),
+            event_bus::subscriber_status(&guard.status),
+        )
+    };
+
+    loop {
+        let status = match receiver.recv().await {
+            Ok(DaemonEvent::StatusChanged { status }) => status,
+            Ok(_) => continue,
+            Err(RecvError::Lagged(_)) => {
+                // Snapshots were dropped; announce everything so bus clients
+                // re-read instead of trusting a stale cache.
+                let current = event_bus::subscriber_status(&state.read().await.status);
+                emit_changes(interface, StatusProperty::ALL.to_vec()).await?;
+                last = current;
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+
+        let changed = StatusProperty::changed(&last, &status);
+        last = status;
+        emit_changes(interface, changed).await?;
+    }
+}
+
+async fn emit_changes(
+    interface: &InterfaceRef<DuoService>,
+    properties: Vec<StatusProperty>,
+) -> Result<(), zbus::Error> {
+    if properties.is_empty() {
+        return Ok(());
+    }
+    let service = interface.get().await;
+    let emitter = interface.signal_emitter();
+    for property in properties {
+        property.emit(&service, emitter).await?;
+    }
+    Ok(())
+}
+
+/// `DuoStatus` fields published as D-Bus properties.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatusProperty {
+    KeyboardAttached,
+    ConnectionType,
+    MonitorCount,
+    WifiEnabled,
+    BluetoothEnabled,
+    BacklightLevel,
+    DisplayBrightness,
+    MaxBrightness,
+    ServiceActive,
+    Orientation,
+    KeyboardBatteryPercent,
+    KeyboardBatteryCharging,
+}
+
+impl StatusProperty {
+    const ALL: [StatusProperty; 12] = [
+        StatusProperty::KeyboardAttached,
+        StatusProperty::ConnectionType,
+        StatusProperty::MonitorCount,
+        StatusProperty::WifiEnabled,
+        StatusProperty::BluetoothEnabled,
+        StatusProperty::BacklightLevel,
+        StatusProperty::DisplayBrightness,
+        StatusProperty::MaxBrightness,
+        StatusProperty::ServiceActive,
+        StatusProperty::Orientation,
+        StatusProperty::KeyboardBatteryPercent,
+        StatusProperty::KeyboardBatteryCharging,
+    ];
+
+    fn changed(old: &DuoStatus, new: &DuoStatus) -> Vec<StatusProperty> {
+        // Spelled out without `..`, so a field added to `DuoStatus` does not
+        // compile until it is published here too.
+        let DuoStatus {
+            keyboard_attached,
+            connection_type,
+            monitor_count,
+            wifi_enabled,
+            bluetooth_enabled,
+            backlight_level,
+            display_brightness,
+            max_brightness,
+            service_active,
+            orientation,
+            keyboard_battery_percent,
+            keyboard_battery_charging,
+        } = new;
+        [
+            (
+                StatusProperty::KeyboardAttached,
+                old.keyboard_attached == *keyboard_attached,
+            ),
+            (
+                StatusProperty::ConnectionType,
+                old.connection_type == *connection_type,
+            ),
+            (
+                StatusProperty::MonitorCount,
+                old.monitor_count == *monitor_count,
+            ),
+            (
+                StatusProperty::WifiEnabled,
+                old.wifi_enabled == *wifi_enabled,
+            ),
+            (
+                StatusProperty::BluetoothEnabled,
+                old.bluetooth_enabled == *bluetooth_enabled,
+            ),
+            (
+                StatusProperty::BacklightLevel,
+                old.backlight_level == *backlight_level,
+            ),
+            (
+                StatusProperty::DisplayBrightness,
+                old.display_brightness == *display_brightness,
+            ),
+            (
+                StatusProperty::MaxBrightness,
+                old.max_brightness == *max_brightness,
+            ),
+            (
+                StatusProperty::ServiceActive,
+                old.service_active == *service_active,
+            ),
+            (StatusProperty::Orientation, old.orientation == *orientation),
+            (
+                StatusProperty::KeyboardBatteryPercent,
+                old.keyboard_battery_percent == *keyboard_battery_percent,
+            ),
+            (
+                StatusProperty::KeyboardBatteryCharging,
+                old.keyboard_battery_charging == *keyboard_battery_charging,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, same)| !same)
+        .map(|(property, _)| property)
+        .collect()
+    }
+
+    async fn emit(self, service: &DuoService, emitter: &SignalEmitter<'_>) -> zbus::Result<()> {
+        match self {
+            StatusProperty::KeyboardAttached => service.keyboard_attached_changed(emitter).await,
+            StatusProperty::ConnectionType => service.connection_type_changed(emitter).await,
+            StatusProperty::MonitorCount => service.monitor_count_changed(emitter).await,
+            StatusProperty::WifiEnabled => service.wifi_enabled_changed(emitter).await,
+            StatusProperty::BluetoothEnabled => service.bluetooth_enabled_changed(emitter).await,
+            StatusProperty::BacklightLevel => service.backlight_level_changed(emitter).await,
+            StatusProperty::DisplayBrightness => service.display_brightness_changed(emitter).await,
+            StatusProperty::MaxBrightness => service.max_brightness_changed(emitter).await,
+            StatusProperty::ServiceActive => service.service_active_changed(emitter).await,
+            StatusProperty::Orientation => service.orientation_changed(emitter).await,
+            StatusProperty::KeyboardBatteryPercent => {
+                service.keyboard_battery_percent_changed(emitter).await
+            }
+            StatusProperty::KeyboardBatteryCharging => {
+                service.keyboard_battery_charging_changed(emitter).await
+            }
+        }
+    }
+}
+
+struct DuoService {
+    state: Arc<RwLock<RuntimeState>>,
+}
+
+#[zbus::interface(name = "org.zenbook.Duo1")]
+impl DuoService {
+    async fn ping(&self) {}
+
+    /// Returns the daemon version and its newest protocol version.
+    async fn get_version(&self) -> (String, u32) {
+        (
+            env!("CARGO_PKG_VERSION").to_string(),
+            crate::ipc::protocol::PROTOCOL_VERSION,
+        )
+    }
+
+    async fn set_backlight(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        level: u8,
+    ) -> fdo::Result<()> {
+        self.dispatch(&header, connection, DaemonRequest::SetBacklight { level })
+            .await
+            .and_then(expect_ack)
+    }
+
+    /// Accepts `normal`, `left`, `right` or `inverted`.
+    async fn set_orientation(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        orientation: String,
+    ) -> fdo::Result<()> {
+        let orientation = parse_orientation(&orientation)?;
+        self.dispatch(
+            &header,
+            connection,
+            DaemonRequest::SetOrientation { orientation },
+        )
+        .await
+        .and_then(expect_ack)
+    }
+
+    /// Returns the current layout as the same JSON the socket API uses.
+    async fn get_display_layout(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> fdo::Result<String> {
+        match self
+            .dispatch(&header, connection, DaemonRequest::GetDisplayLayout)
+            .await?
+        {
+            DaemonResponse::DisplayLayout { layout } => to_json(&layout),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn apply_display_layout(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        layout_json: String,
+    ) -> fdo::Result<()> {
+        let layout: DisplayLayout = from_json(&layout_json)?;
+        self.dispatch(
+            &header,
+            connection,
+            DaemonRequest::ApplyDisplayLayout { layout },
+        )
+        .await
+        .and_then(expect_ack)
+    }
+
+    async fn get_settings(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> fdo::Result<String> {
+        match self
+            .dispatch(&header, connection, DaemonRequest::GetSettings)
+            .await?
+        {
+            DaemonResponse::Settings { settings } => to_json(&settings),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn save_settings(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        settings_json: String,
+    ) -> fdo::Result<()> {
+        let settings: DuoSettings = from_json(&settings_json)?;
+        self.dispatch(
+            &header,
+            connection,
+            DaemonRequest::SaveSettings { settings },
+        )
+        .await
+        .and_then(expect_ack)
+    }
+
+    /// Returns `(name, i2c_id, connector, enabled)` per touchscreen.
+    async fn list_touchscreens(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> fdo::Result<Vec<(String, String, String, bool)>> {
+        match self
+            .dispatch(&header, connection, DaemonRequest::ListTouchscreens)
+            .await?
+        {
+            DaemonResponse::Touchscreens { devices } => Ok(devices
+                .into_iter()
+                .map(|device| (device.name, device.i2c_id, device.connector, device.enabled))
+                .collect()),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn set_touchscreen_enabled(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        connector: String,
+        enabled: bool,
+    ) -> fdo::Result<()> {
+        self.dispatch(
+            &header,
+            connection,
+            DaemonRequest::SetTouchscreenEnabled { connector, enabled },
+        )
+        .await
+        .and_then(expect_ack)
+    }
+
+    async fn usb_media_remap_start(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> fdo::Result<()> {
+        self.dispatch(&header, connection, DaemonRequest::UsbMediaRemapStart)
+            .await
+            .and_then(expect_ack)
+    }
+
+    async fn usb_media_remap_stop(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> fdo::Result<()> {
+        self.dispatch(&header, connection, DaemonRequest::UsbMediaRemapStop)
+            .await
+            .and_then(expect_ack)
+    }
+
+    async fn usb_media_remap_toggle_pause(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> fdo::Result<()> {
+        self.dispatch(&header, connection, DaemonRequest::UsbMediaRemapTogglePause)
+            .await
+            .and_then(expect_ack)
+    }
+
+    async fn usb_media_remap_set_paused(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        paused: bool,
+    ) -> fdo::Result<()> {
+        self.dispatch(
+            &header,
+            connection,
+            DaemonRequest::UsbMediaRemapSetPaused { paused },
+        )
+        .await
+        .and_then(expect_ack)
+    }
+
+    async fn restart_service(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> fdo::Result<()> {
+        self.dispatch(&header, connection, DaemonRequest::RestartService)
+            .await
+            .and_then(expect_ack)
+    }
+
+    async fn tail_logs(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        lines: u32,
+    ) -> fdo::Result<Vec<String>> {
+        let request = DaemonRequest::TailLogs {
+            lines: lines as usize,
+        };
+        match self.dispatch(&header, connection, request).await? {
+            DaemonResponse::Logs { lines } => Ok(lines),
+            DaemonResponse::LogRecords { records } => {
+                Ok(records.iter().map(ToString::to_string).collect())
+            }
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Returns the newest log records as a JSON array of `LogRecord`s,
+    /// oldest first.
+    async fn tail_log_records(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        lines: u32,
+    ) -> fdo::Result<String> {
+        let request = DaemonRequest::TailLogs {
+            lines: lines as usize,
+        };
+        match self.dispatch(&header, connection, request).await? {
+            DaemonResponse::LogRecords { records } => to_json(&records),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn clear_logs(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> fdo::Result<()> {
+        self.dispatch(&header, connection, DaemonRequest::ClearLogs)
+            .await
+            .and_then(expect_ack)
+    }
+
+    /// Returns recent hardware events as a JSON array, newest last.
+    async fn get_recent_events(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        limit: u32,
+    ) -> fdo::Result<String> {
+        let request = DaemonRequest::GetRecentEvents {
+            limit: limit as usize,
+        };
+        match self.dispatch(&header, connection, request).await? {
+            DaemonResponse::Events { events } => to_json(&events),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Searches the event journal. Takes an `EventQuery` as JSON (e.g.
+    /// `{"since":"2026-03-10T00:00:00Z","categories":["DISPLAY"]}`) and
+    /// returns the matching events as a JSON array, newest last.
+    async fn query_events(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        query_json: String,
+    ) -> fdo::Result<String> {
+        let query: EventQuery = from_json(&query_json)?;
+        match self
+            .dispatch(&header, connection, DaemonRequest::query_events(query))
+            .await?
+        {
+            DaemonResponse::Events { events } => to_json(&events),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Searches the component logs. Takes a `LogQuery` as JSON (e.g.
+    /// `{"components":["session-agent"],"minLevel":"warn"}`) and returns the
+    /// matching records as a JSON array, newest last.
+    async fn query_logs(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        query_json: String,
+    ) -> fdo::Result<String> {
+        let query: LogQuery = from_json(&query_json)?;
+        match self
+            .dispatch(&header, connection, DaemonRequest::query_logs(query, false))
+            .await?
+        {
+            DaemonResponse::LogRecords { records } => to_json(&records),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Runtime counters and latency histograms as a `MetricsSnapshot` JSON
+    /// object.
+    async fn get_metrics(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> fdo::Result<String> {
+        match self
+            .dispatch(&header, connection, DaemonRequest::GetMetrics)
+            .await?
+        {
+            DaemonResponse::Metrics { metrics } => to_json(&metrics),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Current device posture and its transition history as a
+    /// `PostureReport` JSON object.
+    async fn get_posture(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> fdo::Result<String> {
+        match self
+            .dispatch(&header, connection, DaemonRequest::GetPosture)
+            .await?
+        {
+            DaemonResponse::Posture { posture } => to_json(&posture),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    #[zbus(property)]
+    async fn keyboard_attached(&self) -> bool {
+        self.status().await.keyboard_attached
+    }
+
+    /// `usb`, `bluetooth` or `none`.
+    #[zbus(property)]
+    async fn connection_type(&self) -> String {
+        match self.status().await.connection_type {
+            ConnectionType::Usb => "usb",
+            ConnectionType::Bluetooth => "bluetooth",
+            ConnectionType::None => "none",
+        }
+        .to_string()
+    }
+
+    #[zbus(property)]
+    async fn monitor_count(&self) -> u32 {
+        self.status().await.monitor_count
+    }
+
+    #[zbus(property)]
+    async fn wifi_enabled(&self) -> bool {
+        self.status().await.wifi_enabled
+    }
+
+    #[zbus(property)]
+    async fn bluetooth_enabled(&self) -> bool {
+        self.status().await.bluetooth_enabled
+    }
+
+    #[zbus(property)]
+    async fn backlight_level(&self) -> u8 {
+        self.status().await.backlight_level
+    }
+
+    #[zbus(property)]
+    async fn display_brightness(&self) -> u32 {
+        self.status().await.display_brightness
+    }
+
+    #[zbus(property)]
+    async fn max_brightness(&self) -> u32 {
+        self.status().await.max_brightness
+    }
+
+    #[zbus(property)]
+    async fn service_active(&self) -> bool {
+        self.status().await.service_active
+    }
+
+    /// `normal`, `left`, `right` or `inverted`.
+    #[zbus(property)]
+    async fn orientation(&self) -> String {
+        orientation_name(&self.status().await.orientation).to_string()
+    }
+
+    /// 0-100, or -1 when the keyboard is docked or does not report one.
+    #[zbus(property)]
+    async fn keyboard_battery_percent(&self) -> i16 {
+        self.status()
+            .await
+            .keyboard_battery_percent
+            .map_or(-1, i16::from)
+    }
+
+    /// `charging`, `discharging` or `unknown`.
+    #[zbus(property)]
+    async fn keyboard_battery_charging(&self) -> String {
+        match self.status().await.keyboard_battery_charging {
+            Some(true) => "charging",
+            Some(false) => "discharging",
+            None => "unknown",
+        }
+        .to_string()
+    }
+}
+
+impl DuoService {
+    async fn status(&self) -> DuoStatus {
+        self.state.read().await.status.clone()
+    }
+
+    async fn dispatch(
+        &self,
+        header: &Header<'_>,
+        connection: &Connection,
+        request: DaemonRequest,
+    ) -> fdo::Result<DaemonResponse> {
+        authorize(connection, header, &request).await?;
+        match daemon::dispatch_request(request, self.state.clone()).await {
+            DaemonResponse::Error { message } => Err(fdo::Error::Failed(message)),
+            response => Ok(response),
+        }
+    }
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.PolicyKit1.Authority",
+    default_service = "org.freedesktop.PolicyKit1",
+    default_path = "/org/freedesktop/PolicyKit1/Authority"
+)]
+trait PolkitAuthority {
+    fn check_authorization(
+        &self,
+        subject: &(&str, HashMap<&str, Value<'_>>),
+        action_id: &str,
+        details: HashMap<&str, &str>,
+        flags: u32,
+        cancellation_id: &str,
+    ) -> zbus::Result<(bool, bool, HashMap<String, String>)>;
+}
+
+/// Read-only requests need no authorization; everything else is checked
+/// against the polkit action for its tier.
+fn polkit_action(tier: AccessTier) -> Option<&'static str> {
+    match tier {
+        AccessTier::ReadOnly => None,
+        AccessTier::UserControl => Some(CONTROL_ACTION),
+        AccessTier::Privileged => Some(ADMIN_ACTION),
+    }
+}
+
+async fn authorize(
+    connection: &Connection,
+    header: &Header<'_>,
+    request: &DaemonRequest,
+) -> fdo::Result<()> {
+    let Some(action) = polkit_action(peer_auth::required_tier(request)) else {
+        return Ok(());
+    };
+    let sender = header
+        .sender()
+        .ok_or_else(|| fdo::Error::AccessDenied("D-Bus caller has no unique name".into()))?;
+
+    let authority = PolkitAuthorityProxy::new(connection)
+        .await
+        .map_err(|e| fdo::Error::Failed(format!("Failed to reach polkit: {e}")))?;
+    let subject = (
+        "system-bus-name",
+        HashMap::from([("name", Value::from(sender.as_str()))]),
+    );
+    let (authorized, _, _) = authority
+        .check_authorization(
+            &subject,
+            action,
+            HashMap::new(),
+            POLKIT_ALLOW_USER_INTERACTION,
+            "",
+        )
+        .await
+        .map_err(|e| fdo::Error::Failed(format!("Failed to check polkit authorization: {e}")))?;
+
+    if authorized {
+        return Ok(());
+    }
+    logger::warn(
+        "rust-daemon",
+        format!(
+            "audit: denied {} over D-Bus from {sender} ({action})",
+            request.kind()
+        ),
+    );
+    Err(fdo::Error::AccessDenied(format!(
+        "Not authorized for {action}"
+    )))
+}
+
+fn expect_ack(response: DaemonResponse) -> fdo::Result<()> {
+    match response {
+        DaemonResponse::Ack => Ok(()),
+        other => Err(unexpected(other)),
+    }
+}
+
+fn unexpected(response: DaemonResponse) -> fdo::Error {
+    fdo::Error::Failed(format!("Unexpected daemon response: {response:?}"))
+}
+
+fn parse_orientation(value: &str) -> fdo::Result<Orientation> {
+    match value {
+        "normal" => Ok(Orientation::Normal),
+        "left" => Ok(Orientation::Left),
+        "right" => Ok(Orientation::Right),
+        "inverted" => Ok(Orientation::Inverted),
+        other => Err(fdo::Error::InvalidArgs(format!(
+            "Unknown orientation {other:?}; expected normal, left, right or inverted"
+        ))),
+    }
+}
+
+fn orientation_name(orientation: &Orientation) -> &'static str {
+    match orientation {
+        Orientation::Normal => "normal",
+        Orientation::Left => "left",
+        Orientation::Right => "right",
+        Orientation::Inverted => "inverted",
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> fdo::Result<String> {
+    serde_json::to_string(value)
+        .map_err(|e| fdo::Error::Failed(format!("Failed to encode reply: {e}")))
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(value: &str) -> fdo::Result<T> {
+    serde_json::from_str(value)
+        .map_err(|e| fdo::Error::InvalidArgs(format!("Invalid JSON argument: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_moved_fields_are_reported_as_changed() {
+        let old = DuoStatus::default();
+        let mut new = old.clone();
+        new.backlight_level = 3;
+        new.orientation = Orientation::Left;
+
+        assert_eq!(
+            StatusProperty::changed(&old, &new),
+            vec![StatusProperty::BacklightLevel, StatusProperty::Orientation]
+        );
+        assert!(StatusProperty::changed(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn every_status_field_is_published() {
+        let old = DuoStatus::default();
+        let new = DuoStatus {
+            keyboard_attached: true,
+            connection_type: ConnectionType::Usb,
+            monitor_count: 2,
+            wifi_enabled: true,
+            bluetooth_enabled: true,
+            backlight_level: 1,
+            display_brightness: 100,
+            max_brightness: 400,
+            service_active: true,
+            orientation: Orientation::Inverted,
+            keyboard_battery_percent: Some(80),
+            keyboard_battery_charging: Some(true),
+        };
+
+        assert_eq!(StatusProperty::changed(&old, &new), StatusProperty::ALL);
+    }
+
+    #[test]
+    fn mutating_methods_map_to_polkit_actions_by_tier() {
+        let action = |request: DaemonRequest| polkit_action(peer_auth::required_tier(&request));
+
+        assert_eq!(action(DaemonRequest::ListTouchscreens), None);
+        assert_eq!(
+            action(DaemonRequest::SetBacklight { level: 1 }),
+            Some(CONTROL_ACTION)
+        );
+        assert_eq!(action(DaemonRequest::RestartService), Some(ADMIN_ACTION));
+    }
+
+    #[test]
+    fn orientation_names_round_trip() {
+        for orientation in [
+            Orientation::Normal,
+            Orientation::Left,
+            Orientation::Right,
+            Orientation::Inverted,
+        ] {
+            assert_eq!(
+                parse_orientation(orientation_name(&orientation)).ok(),
+                Some(orientation)
+            );
+        }
+        assert!(parse_orientation("sideways").is_err());
+    }
+}
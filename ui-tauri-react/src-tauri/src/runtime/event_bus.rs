@@ -0,0 +1,125 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::broadcast;
+
+use crate::ipc::protocol::{DaemonEvent, SessionBackend};
+use crate::models::{DuoStatus, HardwareEvent};
+
+const EVENT_BUS_CAPACITY: usize = 256;
+
+/// Fan-out channel for push-based daemon events.
+///
+/// The bus travels with `RuntimeState` so every Module that already holds the
+/// state lock can publish without extra plumbing. Subscribers are long-lived
+/// router connections that asked for `DaemonRequest::Subscribe`.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<DaemonEvent>,
+    last_status: Arc<Mutex<Option<DuoStatus>>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self {
+            sender,
+            last_status: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl EventBus {
+    pub fn subscribe(&self) -> broadcast::Receiver<DaemonEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Publishes the status as subscribers see it from `GetStatus`, skipping
+    /// snapshots identical to the last one sent.
+    pub fn publish_status(&self, status: &DuoStatus) {
+        let status = subscriber_status(status);
+        {
+            let mut last = self.last_status.lock().expect("event bus status lock");
+            if last.as_ref() == Some(&status) {
+                return;
+            }
+            *last = Some(status.clone());
+        }
+        self.publish(DaemonEvent::StatusChanged { status });
+    }
+
+    pub fn publish_hardware_event(&self, event: &HardwareEvent) {
+        self.publish(DaemonEvent::HardwareEvent {
+            event: event.clone(),
+        });
+    }
+
+    pub fn publish_session_agent(&self, connected: bool, backend: Option<SessionBackend>) {
+        self.publish(DaemonEvent::SessionAgentChanged { connected, backend });
+    }
+
+    fn publish(&self, event: DaemonEvent) {
+        // Sending only fails when nobody is subscribed, which is the common case.
+        let _ = self.sender.send(event);
+    }
+}
+
+/// The daemon is reachable whenever it answers, so subscribers get the same
+/// `service_active` view as `GetStatus` callers.
+pub fn subscriber_status(status: &DuoStatus) -> DuoStatus {
+    let mut status = status.clone();
+    status.service_active = true;
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::protocol::SubscriptionCategory;
+    use crate::models::EventCategory;
+
+    #[test]
+    fn status_changes_are_published_once_per_distinct_snapshot() {
+        let bus = EventBus::default();
+        let mut receiver = bus.subscribe();
+        let mut status = DuoStatus::default();
+
+        bus.publish_status(&status);
+        bus.publish_status(&status);
+        status.backlight_level = 2;
+        bus.publish_status(&status);
+
+        match receiver.try_recv().expect("first status") {
+            DaemonEvent::StatusChanged { status } => {
+                assert!(status.service_active);
+                assert_eq!(status.backlight_level, 0);
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+        match receiver.try_recv().expect("second status") {
+            DaemonEvent::StatusChanged { status } => assert_eq!(status.backlight_level, 2),
+            other => panic!("unexpected event: {other:?}"),
+        }
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn events_match_requested_categories() {
+        let hardware = DaemonEvent::HardwareEvent {
+            event: HardwareEvent::info(EventCategory::Usb, "Keyboard attached", "test"),
+        };
+
+        assert!(hardware.matches(&[]));
+        assert!(hardware.matches(&[SubscriptionCategory::Hardware]));
+        assert!(!hardware.matches(&[
+            SubscriptionCategory::Status,
+            SubscriptionCategory::SessionAgent
+        ]));
+    }
+
+    #[test]
+    fn publishing_without_subscribers_is_harmless() {
+        let bus = EventBus::default();
+        bus.publish_session_agent(true, Some(SessionBackend::Gnome));
+        bus.publish_status(&DuoStatus::default());
+    }
+}
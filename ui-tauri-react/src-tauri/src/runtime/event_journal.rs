@@ -0,0 +1,407 @@
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tokio::sync::{mpsc, RwLock};
+
+use crate::models::{EventQuery, HardwareEvent};
+use crate::runtime::state::{RuntimeState, MAX_RECENT_EVENTS};
+use crate::runtime::{logger, paths};
+
+/// The active journal file is rotated once it grows past this size.
+pub const JOURNAL_MAX_BYTES: u64 = 2 * 1024 * 1024;
+/// Rotated generations kept next to the active file (`events.jsonl.1` is
+/// the newest, `events.jsonl.4` the oldest).
+pub const JOURNAL_ROTATED_FILES: usize = 4;
+pub const DEFAULT_QUERY_LIMIT: usize = 200;
+pub const MAX_QUERY_LIMIT: usize = 5000;
+
+/// Append-only JSON-lines log of every `HardwareEvent` the daemon records.
+#[derive(Debug, Clone)]
+pub struct EventJournal {
+    path: PathBuf,
+    max_bytes: u64,
+    rotated_files: usize,
+}
+
+impl EventJournal {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            max_bytes: JOURNAL_MAX_BYTES,
+            rotated_files: JOURNAL_ROTATED_FILES,
+        }
+    }
+
+    pub fn system() -> Self {
+        Self::new(paths::event_journal_path())
+    }
+
+    pub fn append(&self, events: &[HardwareEvent]) -> Result<(), String> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+
+        let mut lines = String::new();
+        for event in events {
+            let line = serde_json::to_string(event)
+                .map_err(|e| format!("Failed to serialize event: {e}"))?;
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open {}: {e}", self.path.display()))?;
+        file.write_all(lines.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {e}", self.path.display()))?;
+
+        let size = file
+            .metadata()
+            .map_err(|e| format!("Failed to inspect {}: {e}", self.path.display()))?
+            .len();
+        if size > self.max_bytes {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+
+    /// Matching events in chronological order. When more match than the
+    /// limit allows, the newest ones are kept. Files are read newest-first
+    /// and reading stops at the limit or the first event before `since`.
+    pub fn query(&self, query: &EventQuery) -> Result<Vec<HardwareEvent>, String> {
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_QUERY_LIMIT)
+            .min(MAX_QUERY_LIMIT);
+        let mut matches = Vec::new();
+        if limit == 0 {
+            return Ok(matches);
+        }
+        self.visit_newest_first(|event| {
+            if query.since.is_some_and(|since| event.timestamp < since) {
+                return false;
+            }
+            if query.matches(&event) {
+                matches.push(event);
+            }
+            matches.len() < limit
+        })?;
+        matches.reverse();
+        Ok(matches)
+    }
+
+    pub fn last_event(&self) -> Result<Option<HardwareEvent>, String> {
+        let mut last = None;
+        self.visit_newest_first(|event| {
+            last = Some(event);
+            false
+        })?;
+        Ok(last)
+    }
+
+    /// Hands events to `visit` from the newest backwards until it returns
+    /// false, skipping lines that do not parse (e.g. a line torn by a crash)
+    /// rather than failing the whole read.
+    fn visit_newest_first(
+        &self,
+        mut visit: impl FnMut(HardwareEvent) -> bool,
+    ) -> Result<(), String> {
+        for generation in 0..=self.rotated_files {
+            let path = self.generation_path(generation);
+            let mut finished = false;
+            logger::visit_lines_backwards(&path, |line| {
+                let Ok(event) = serde_json::from_str(line) else {
+                    return true;
+                };
+                finished = !visit(event);
+                !finished
+            })
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+            if finished {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn rotate(&self) -> Result<(), String> {
+        for generation in (1..self.rotated_files).rev() {
+            let from = self.generation_path(generation);
+            match fs::rename(&from, self.generation_path(generation + 1)) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(format!("Failed to rotate {}: {err}", from.display())),
+            }
+        }
+        fs::rename(&self.path, self.generation_path(1))
+            .map_err(|e| format!("Failed to rotate {}: {e}", self.path.display()))
+    }
+
+    fn generation_path(&self, generation: usize) -> PathBuf {
+        if generation == 0 {
+            return self.path.clone();
+        }
+        let mut name = self
+            .path
+            .file_name()
+            .map(OsString::from)
+            .unwrap_or_default();
+        name.push(format!(".{generation}"));
+        self.path.with_file_name(name)
+    }
+}
+
+/// Handle used by `RuntimeState::push_recent_event` to hand events to the
+/// journal task, keeping file I/O out from under the state lock. A default
+/// handle drops events, which is what non-daemon processes want.
+#[derive(Debug, Clone, Default)]
+pub struct JournalWriter {
+    sender: Option<mpsc::UnboundedSender<HardwareEvent>>,
+}
+
+impl JournalWriter {
+    pub fn record(&self, event: &HardwareEvent) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(event.clone());
+        }
+    }
+}
+
+/// Starts the journal writer, first appending events recorded before it ran
+/// (recoveries during state load, or the event list of a state file written
+/// before the journal existed) and reloading the in-memory tail from disk.
+pub async fn start(state: &Arc<RwLock<RuntimeState>>, journal: EventJournal) {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<HardwareEvent>();
+    {
+        let mut guard = state.write().await;
+        let pending = guard.recent_events.clone();
+        let startup = journal.clone();
+        let tail = tokio::task::spawn_blocking(move || seed(&startup, pending))
+            .await
+            .unwrap_or_else(|e| Err(format!("Failed to join event journal seeding: {e}")));
+        match tail {
+            Ok(tail) => guard.recent_events = tail,
+            Err(err) => report_failure(&err),
+        }
+        guard.journal = JournalWriter {
+            sender: Some(sender),
+        };
+    }
+
+    tokio::spawn(async move {
+        while let Some(event) = receiver.recv().await {
+            let mut batch = vec![event];
+            while let Ok(event) = receiver.try_recv() {
+                batch.push(event);
+            }
+            let journal = journal.clone();
+            let result = tokio::task::spawn_blocking(move || journal.append(&batch))
+                .await
+                .unwrap_or_else(|e| Err(format!("Failed to join event journal writer: {e}")));
+            if let Err(err) = result {
+                report_failure(&err);
+            }
+        }
+    });
+}
+
+fn seed(journal: &EventJournal, pending: Vec<HardwareEvent>) -> Result<Vec<HardwareEvent>, String> {
+    let newest = journal.last_event()?.map(|event| event.timestamp);
+    let unsaved: Vec<_> = pending
+        .into_iter()
+        .filter(|event| newest.is_none_or(|newest| event.timestamp > newest))
+        .collect();
+    journal.append(&unsaved)?;
+    journal.query(&EventQuery {
+        limit: Some(MAX_RECENT_EVENTS),
+        ..EventQuery::default()
+    })
+}
+
+fn report_failure(err: &str) {
+    logger::warn("rust-daemon", format!("event journal error: {err}"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EventCategory, EventSeverity};
+    use chrono::{Duration, TimeZone, Utc};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn unique_journal(label: &str) -> EventJournal {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        EventJournal::new(
+            std::env::temp_dir()
+                .join(format!("zenbook-duo-journal-{label}-{nanos}"))
+                .join("events.jsonl"),
+        )
+    }
+
+    fn event(minute: i64, category: EventCategory, severity: EventSeverity) -> HardwareEvent {
+        HardwareEvent {
+            timestamp: Utc.with_ymd_and_hms(2026, 3, 10, 9, 0, 0).unwrap()
+                + Duration::minutes(minute),
+            category,
+            severity,
+            message: format!("event {minute}"),
+            source: "test".into(),
+        }
+    }
+
+    fn cleanup(journal: &EventJournal) {
+        let _ = fs::remove_dir_all(journal.path.parent().expect("journal dir"));
+    }
+
+    #[test]
+    fn query_filters_and_keeps_newest_matches_in_order() {
+        let journal = unique_journal("query");
+        journal
+            .append(&[
+                event(0, EventCategory::Display, EventSeverity::Info),
+                event(1, EventCategory::Usb, EventSeverity::Warning),
+                event(2, EventCategory::Display, EventSeverity::Warning),
+                event(3, EventCategory::Display, EventSeverity::Error),
+            ])
+            .expect("append events");
+
+        let found = journal
+            .query(&EventQuery {
+                categories: vec![EventCategory::Display],
+                min_severity: Some(EventSeverity::Warning),
+                limit: Some(5),
+                ..EventQuery::default()
+            })
+            .expect("query");
+        let messages: Vec<_> = found.iter().map(|event| event.message.as_str()).collect();
+        assert_eq!(messages, vec!["event 2", "event 3"]);
+
+        let newest = journal
+            .query(&EventQuery {
+                limit: Some(1),
+                ..EventQuery::default()
+            })
+            .expect("query");
+        assert_eq!(newest[0].message, "event 3");
+
+        cleanup(&journal);
+    }
+
+    #[test]
+    fn rotation_keeps_history_queryable_and_bounded() {
+        let mut journal = unique_journal("rotate");
+        journal.max_bytes = 300;
+        journal.rotated_files = 2;
+
+        for minute in 0..30 {
+            journal
+                .append(&[event(minute, EventCategory::Usb, EventSeverity::Info)])
+                .expect("append event");
+        }
+
+        assert!(journal.generation_path(1).exists());
+        assert!(journal.generation_path(2).exists());
+        assert!(!journal.generation_path(3).exists());
+
+        let all = journal
+            .query(&EventQuery {
+                limit: Some(MAX_QUERY_LIMIT),
+                ..EventQuery::default()
+            })
+            .expect("query");
+        assert!(all.len() < 30, "oldest generations are dropped");
+        assert!(all
+            .windows(2)
+            .all(|pair| pair[0].timestamp < pair[1].timestamp));
+        assert_eq!(all.last().expect("newest").message, "event 29");
+
+        cleanup(&journal);
+    }
+
+    #[test]
+    fn query_stops_reading_once_the_window_is_filled() {
+        let journal = unique_journal("window");
+        journal
+            .append(&[
+                event(0, EventCategory::Usb, EventSeverity::Info),
+                event(1, EventCategory::Usb, EventSeverity::Info),
+            ])
+            .expect("append events");
+        // An unreadable older generation only fails queries that reach it.
+        fs::create_dir(journal.generation_path(1)).expect("create blocker");
+
+        let newest = journal
+            .query(&EventQuery {
+                limit: Some(1),
+                ..EventQuery::default()
+            })
+            .expect("limited query");
+        assert_eq!(newest[0].message, "event 1");
+
+        let since = journal
+            .query(&EventQuery {
+                since: Some(event(1, EventCategory::Usb, EventSeverity::Info).timestamp),
+                ..EventQuery::default()
+            })
+            .expect("bounded query");
+        assert_eq!(since.len(), 1);
+
+        assert!(journal.query(&EventQuery::default()).is_err());
+
+        cleanup(&journal);
+    }
+
+    #[test]
+    fn torn_lines_are_skipped() {
+        let journal = unique_journal("torn");
+        journal
+            .append(&[event(0, EventCategory::Usb, EventSeverity::Info)])
+            .expect("append event");
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&journal.path)
+            .expect("open journal");
+        file.write_all(b"{\"timestamp\":").expect("write torn line");
+
+        assert_eq!(
+            journal.query(&EventQuery::default()).expect("query").len(),
+            1
+        );
+
+        cleanup(&journal);
+    }
+
+    #[test]
+    fn seeding_appends_only_events_newer_than_the_journal() {
+        let journal = unique_journal("seed");
+        journal
+            .append(&[event(5, EventCategory::Usb, EventSeverity::Info)])
+            .expect("append event");
+
+        let tail = seed(
+            &journal,
+            vec![
+                event(1, EventCategory::Usb, EventSeverity::Info),
+                event(9, EventCategory::Service, EventSeverity::Warning),
+            ],
+        )
+        .expect("seed journal");
+
+        let messages: Vec<_> = tail.iter().map(|event| event.message.as_str()).collect();
+        assert_eq!(messages, vec!["event 5", "event 9"]);
+
+        cleanup(&journal);
+    }
+}
@@ -0,0 +1,292 @@
+use std::fs;
+use std::io::Read;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::models::{HookInvocation, HookOutcome, HookStatus, MAX_HOOKS_PER_RUN};
+
+/// Hooks the daemon runs as root. Only root-owned scripts are used.
+pub const SYSTEM_HOOKS_DIR: &str = "/etc/zenbook-duo/hooks.d";
+/// Keep the tail of a chatty hook's output, not all of it.
+const OUTPUT_LIMIT: usize = 4096;
+const WAIT_POLL: Duration = Duration::from_millis(20);
+
+/// Hooks the session agent runs as the desktop user.
+pub fn user_hooks_dir() -> PathBuf {
+    crate::commands::settings::config_base_dir()
+        .join("zenbook-duo")
+        .join("hooks.d")
+}
+
+/// Whether `dir` has any entries at all, as a cheap check before asking
+/// someone else to run it.
+pub fn has_entries(dir: &Path) -> bool {
+    fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_some())
+}
+
+/// Executable regular files in `dir`, in name order. Hidden files, editor
+/// backups and scripts that another user owns or that group or others can
+/// write are left out, since they would run with this process's privileges.
+/// The same goes for the whole directory, as anyone who can write to it can
+/// swap a script in.
+pub fn discover(dir: &Path) -> Vec<PathBuf> {
+    let uid = nix::unistd::Uid::current().as_raw();
+    let trusted = |metadata: &fs::Metadata| {
+        metadata.permissions().mode() & 0o022 == 0 && (metadata.uid() == uid || metadata.uid() == 0)
+    };
+    if !fs::metadata(dir).is_ok_and(|metadata| metadata.is_dir() && trusted(&metadata)) {
+        return Vec::new();
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut hooks: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            !name.starts_with('.') && !name.ends_with('~')
+        })
+        .filter(|entry| {
+            fs::metadata(entry.path()).is_ok_and(|metadata| {
+                metadata.is_file()
+                    && metadata.permissions().mode() & 0o111 != 0
+                    && trusted(&metadata)
+            })
+        })
+        .map(|entry| entry.path())
+        .collect();
+    hooks.sort();
+    hooks
+}
+
+/// Runs every hook in `dir` one after another, each with `timeout`.
+pub fn run_hooks(dir: &Path, invocation: &HookInvocation, timeout: Duration) -> Vec<HookOutcome> {
+    discover(dir)
+        .into_iter()
+        .enumerate()
+        .map(|(index, hook)| {
+            if index < MAX_HOOKS_PER_RUN {
+                run_hook(&hook, invocation, timeout)
+            } else {
+                HookOutcome {
+                    hook: hook.display().to_string(),
+                    status: HookStatus::Skipped,
+                    output: String::new(),
+                    duration_ms: 0,
+                }
+            }
+        })
+        .collect()
+}
+
+fn run_hook(hook: &Path, invocation: &HookInvocation, timeout: Duration) -> HookOutcome {
+    let started = Instant::now();
+    let deadline = started + timeout;
+    let finish = |status, output| HookOutcome {
+        hook: hook.display().to_string(),
+        status,
+        output,
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+
+    // Its own process group, so a timeout also takes down anything it forked.
+    let spawned = Command::new(hook)
+        .envs(invocation.environment())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(err) => {
+            return finish(
+                HookStatus::SpawnFailed {
+                    message: format!("Failed to run {}: {err}", hook.display()),
+                },
+                String::new(),
+            )
+        }
+    };
+
+    let output = capture_output(&mut child);
+    let status = match wait_until(&mut child, deadline) {
+        Some(status) if status.success() => HookStatus::Succeeded,
+        Some(status) => HookStatus::Failed {
+            code: status.code(),
+        },
+        None => HookStatus::TimedOut,
+    };
+    let output = collect_output(output, deadline.max(Instant::now()) + WAIT_POLL);
+    finish(status, output)
+}
+
+/// Reads stdout and stderr on their own threads so a hook that fills one
+/// pipe cannot stall while we wait for it.
+fn capture_output(child: &mut Child) -> mpsc::Receiver<(u8, Vec<u8>)> {
+    let (sender, receiver) = mpsc::channel();
+    let pipes: [(u8, Option<Box<dyn Read + Send>>); 2] = [
+        (0, child.stdout.take().map(|pipe| Box::new(pipe) as _)),
+        (1, child.stderr.take().map(|pipe| Box::new(pipe) as _)),
+    ];
+    for (stream, pipe) in pipes {
+        let Some(mut pipe) = pipe else { continue };
+        let sender = sender.clone();
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = pipe.read_to_end(&mut buffer);
+            let _ = sender.send((stream, buffer));
+        });
+    }
+    receiver
+}
+
+/// Output gathered by the reader threads. A hook that left a daemon
+/// holding its pipes open is not waited on past `deadline`.
+fn collect_output(receiver: mpsc::Receiver<(u8, Vec<u8>)>, deadline: Instant) -> String {
+    let mut streams = [Vec::new(), Vec::new()];
+    while let Ok((stream, buffer)) =
+        receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+    {
+        streams[stream as usize] = buffer;
+    }
+
+    let combined = streams.concat();
+    let tail = &combined[combined.len().saturating_sub(OUTPUT_LIMIT)..];
+    String::from_utf8_lossy(tail).trim().to_string()
+}
+
+fn wait_until(child: &mut Child, deadline: Instant) -> Option<ExitStatus> {
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Some(status),
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(WAIT_POLL),
+            Ok(None) => break,
+            Err(_) => return child.wait().ok().or(Some(ExitStatus::from_raw(1 << 8))),
+        }
+    }
+
+    // SAFETY: `kill` takes no pointers; a negative pid targets the group
+    // created for this hook with `process_group(0)`.
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+    let _ = child.wait();
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionType, DevicePosture, HookEvent};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    struct HooksDir {
+        path: PathBuf,
+    }
+
+    impl HooksDir {
+        fn new() -> Self {
+            let unique = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock")
+                .as_nanos();
+            let path = std::env::temp_dir().join(format!("zenbook-duo-hooks-{unique}"));
+            fs::create_dir_all(&path).expect("create hooks dir");
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).expect("chmod hooks dir");
+            Self { path }
+        }
+
+        fn script(&self, name: &str, body: &str, mode: u32) {
+            let path = self.path.join(name);
+            fs::write(&path, format!("#!/bin/sh\n{body}\n")).expect("write hook");
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode)).expect("chmod hook");
+        }
+    }
+
+    impl Drop for HooksDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    fn invocation() -> HookInvocation {
+        HookInvocation {
+            event: HookEvent::Detach,
+            posture: DevicePosture::DetachedBluetooth,
+            keyboard_attached: true,
+            connection_type: ConnectionType::Bluetooth,
+            monitor_count: 2,
+            external_monitor: false,
+            lid_closed: false,
+            on_ac_power: None,
+            wifi_enabled: true,
+            bluetooth_enabled: true,
+        }
+    }
+
+    #[test]
+    fn runs_executable_hooks_in_order_with_transition_environment() {
+        let dir = HooksDir::new();
+        dir.script(
+            "20-second",
+            "echo \"$DUO_EVENT $DUO_CONNECTION $DUO_MONITORS ${DUO_AC_POWER-unset}\"",
+            0o755,
+        );
+        dir.script("10-first", "echo to-stderr >&2; exit 3", 0o755);
+        dir.script("30-not-executable", "exit 0", 0o644);
+        dir.script("40-world-writable", "exit 0", 0o777);
+        dir.script("50-group-writable", "exit 0", 0o775);
+        dir.script(".hidden", "exit 0", 0o755);
+
+        let outcomes = run_hooks(&dir.path, &invocation(), Duration::from_secs(5));
+
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes[0].hook.ends_with("10-first"));
+        assert_eq!(outcomes[0].status, HookStatus::Failed { code: Some(3) });
+        assert_eq!(outcomes[0].output, "to-stderr");
+        assert!(outcomes[0].status.is_failure());
+        assert_eq!(outcomes[1].status, HookStatus::Succeeded);
+        assert_eq!(outcomes[1].output, "detach bluetooth 2 unset");
+    }
+
+    #[test]
+    fn group_writable_directory_has_no_hooks() {
+        let dir = HooksDir::new();
+        dir.script("10-hook", "exit 0", 0o755);
+        assert_eq!(discover(&dir.path).len(), 1);
+
+        fs::set_permissions(&dir.path, fs::Permissions::from_mode(0o775)).expect("chmod hooks dir");
+        assert!(discover(&dir.path).is_empty());
+    }
+
+    #[test]
+    fn kills_hooks_that_overrun_their_timeout() {
+        let dir = HooksDir::new();
+        dir.script("slow", "echo started; sleep 30 & wait", 0o755);
+
+        let started = Instant::now();
+        let outcomes = run_hooks(&dir.path, &invocation(), Duration::from_millis(200));
+
+        assert_eq!(outcomes[0].status, HookStatus::TimedOut);
+        assert_eq!(outcomes[0].output, "started");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn missing_directory_has_no_hooks() {
+        let outcomes = run_hooks(
+            Path::new("/nonexistent/zenbook-duo/hooks.d"),
+            &invocation(),
+            Duration::from_secs(1),
+        );
+
+        assert!(outcomes.is_empty());
+    }
+}
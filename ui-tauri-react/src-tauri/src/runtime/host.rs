@@ -21,6 +21,12 @@ impl CommandRunner for ProcessCommandRunner {
     }
 }
 
+impl<T: CommandRunner + ?Sized> CommandRunner for &T {
+    fn output(&self, program: &str, args: &[&str]) -> Result<Output, String> {
+        (**self).output(program, args)
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -0,0 +1,366 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::RwLock;
+
+use crate::models::{ConnectionType, DuoSettings};
+use crate::runtime::{logger, state::RuntimeState};
+
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Turns the keyboard backlight off after a stretch without keypresses and
+/// back on at the next one.
+///
+/// Like the persister it travels with `RuntimeState`. The Bluetooth hotkey
+/// thread reports keypresses straight to it; USB keypresses arrive from the
+/// remap helper as `KeyboardActivity` requests.
+#[derive(Clone, Default)]
+pub struct KeyboardIdle {
+    clock: Arc<Mutex<IdleClock>>,
+}
+
+impl std::fmt::Debug for KeyboardIdle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyboardIdle").finish_non_exhaustive()
+    }
+}
+
+impl KeyboardIdle {
+    fn clock(&self) -> std::sync::MutexGuard<'_, IdleClock> {
+        self.clock.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// A key went down. Returns the level to restore when the backlight is
+    /// off because of the timeout.
+    pub fn activity(&self) -> Option<u8> {
+        self.clock().activity(Instant::now())
+    }
+
+    /// Whether the backlight is off because of the timeout.
+    pub fn dimmed(&self) -> bool {
+        self.clock().dimmed_from.is_some()
+    }
+
+    /// The level the timeout turned off, which is what gets saved and
+    /// restored on resume in place of the 0 the keyboard is showing.
+    pub fn dimmed_from(&self) -> Option<u8> {
+        self.clock().dimmed_from
+    }
+
+    /// The timer stands still while the screen is locked and starts over
+    /// when it unlocks.
+    pub fn set_screen_locked(&self, locked: bool) {
+        self.clock().set_locked(locked, Instant::now());
+    }
+
+    /// Whether the Bluetooth hotkey watcher has the keyboard open, and so
+    /// will see the keypress that wakes the backlight.
+    pub fn set_bluetooth_watched(&self, watched: bool) {
+        self.clock().bluetooth_watched = watched;
+    }
+}
+
+#[derive(Debug)]
+struct IdleClock {
+    last_activity: Instant,
+    /// The level the timeout turned off.
+    dimmed_from: Option<u8>,
+    locked: bool,
+    bluetooth_watched: bool,
+}
+
+impl Default for IdleClock {
+    fn default() -> Self {
+        Self {
+            last_activity: Instant::now(),
+            dimmed_from: None,
+            locked: false,
+            bluetooth_watched: false,
+        }
+    }
+}
+
+impl IdleClock {
+    fn activity(&mut self, now: Instant) -> Option<u8> {
+        self.last_activity = now;
+        self.dimmed_from.take()
+    }
+
+    fn set_locked(&mut self, locked: bool, now: Instant) {
+        if self.locked && !locked {
+            self.last_activity = now;
+        }
+        self.locked = locked;
+    }
+
+    /// Whether the backlight, now at `level`, should go off. When it should,
+    /// it counts as dimmed from here on, so a keypress racing the write still
+    /// finds the level to restore.
+    fn due(&mut self, level: u8, timeout: Option<Duration>, now: Instant) -> bool {
+        if self.dimmed_from.is_some() && level != 0 {
+            // Something else turned the backlight on; start counting again.
+            self.dimmed_from = None;
+            self.last_activity = now;
+        }
+        let Some(timeout) = timeout else {
+            return false;
+        };
+        let due = !self.locked
+            && self.dimmed_from.is_none()
+            && level != 0
+            && now.duration_since(self.last_activity) >= timeout;
+        if due {
+            self.dimmed_from = Some(level);
+        }
+        due
+    }
+}
+
+/// The idle timeout for the keyboard's current transport. `None` when it is
+/// switched off, or when nothing would see the keypress that wakes it.
+fn idle_timeout(
+    settings: &DuoSettings,
+    connection: &ConnectionType,
+    usb_keypresses_seen: bool,
+    bluetooth_keypresses_seen: bool,
+) -> Option<Duration> {
+    let secs = match connection {
+        ConnectionType::Usb if usb_keypresses_seen => settings.backlight_idle_timeout_usb_secs,
+        ConnectionType::Bluetooth if bluetooth_keypresses_seen => {
+            settings.backlight_idle_timeout_bluetooth_secs
+        }
+        _ => 0,
+    };
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+pub fn start(state: Arc<RwLock<RuntimeState>>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            tick(&state).await;
+        }
+    });
+}
+
+async fn tick(state: &Arc<RwLock<RuntimeState>>) {
+    let (idle, hardware) = {
+        let guard = state.read().await;
+        (guard.keyboard_idle.clone(), guard.hardware.clone())
+    };
+    // Checked before taking the lock below, as finding the helper can mean
+    // a walk through /proc.
+    let remap_running = hardware.usb_media_remap_running();
+
+    // Held until the status says 0, so a keypress that wakes the backlight
+    // in the meantime restores it after this write rather than before.
+    let mut guard = state.write().await;
+    let level = guard.status.backlight_level;
+    let connection = guard.status.connection_type.clone();
+    // The remap helper only runs, and so only reports keypresses, while the
+    // keyboard is docked over USB.
+    let usb_keypresses_seen = connection == ConnectionType::Usb && remap_running;
+    let bluetooth_keypresses_seen = idle.clock().bluetooth_watched;
+    let timeout = idle_timeout(
+        &guard.settings,
+        &connection,
+        usb_keypresses_seen,
+        bluetooth_keypresses_seen,
+    );
+    if !idle.clock().due(level, timeout, Instant::now()) {
+        return;
+    }
+
+    if let Err(err) = hardware.set_backlight(0) {
+        idle.clock().dimmed_from = None;
+        logger::warn(
+            "rust-daemon",
+            format!("failed to turn off idle keyboard backlight: {err}"),
+        );
+        return;
+    }
+    guard.status.backlight_level = 0;
+    guard.touch();
+    guard.persist();
+    logger::info(
+        "rust-daemon",
+        format!(
+            "keyboard idle for {}s, backlight off (was {level})",
+            timeout.unwrap_or_default().as_secs()
+        ),
+    );
+}
+
+/// Restores the backlight the timeout turned off, unless it has been set
+/// since.
+pub async fn wake(state: &Arc<RwLock<RuntimeState>>) {
+    let (idle, hardware) = {
+        let guard = state.read().await;
+        (guard.keyboard_idle.clone(), guard.hardware.clone())
+    };
+    if let Some(level) = idle.activity() {
+        restore(state, &hardware, level).await;
+    }
+}
+
+/// [`wake`] for the Bluetooth hotkey thread, which cannot await.
+pub fn wake_blocking(state: &Arc<RwLock<RuntimeState>>, idle: &KeyboardIdle) {
+    let Some(level) = idle.activity() else {
+        return;
+    };
+    let (hardware, current) = {
+        let guard = state.blocking_read();
+        (guard.hardware.clone(), guard.status.backlight_level)
+    };
+    if current != 0 {
+        return;
+    }
+    match hardware.set_backlight(level) {
+        Ok(()) => record_restored(&mut state.blocking_write(), level),
+        Err(err) => logger::warn(
+            "rust-daemon",
+            format!("failed to restore keyboard backlight after idle: {err}"),
+        ),
+    }
+}
+
+async fn restore(
+    state: &Arc<RwLock<RuntimeState>>,
+    hardware: &crate::runtime::backend::HardwareBackend,
+    level: u8,
+) {
+    if state.read().await.status.backlight_level != 0 {
+        return;
+    }
+    match hardware.set_backlight(level) {
+        Ok(()) => record_restored(&mut *state.write().await, level),
+        Err(err) => logger::warn(
+            "rust-daemon",
+            format!("failed to restore keyboard backlight after idle: {err}"),
+        ),
+    }
+}
+
+fn record_restored(state: &mut RuntimeState, level: u8) {
+    state.status.backlight_level = level;
+    state.touch();
+    state.persist();
+    logger::info(
+        "rust-daemon",
+        format!("keypress after idle, backlight back to {level}"),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::backend::HardwareBackend;
+    use crate::runtime::simulation::{SimHardware, SimMachine};
+
+    const TIMEOUT: Option<Duration> = Some(Duration::from_secs(30));
+
+    #[test]
+    fn dims_after_the_timeout_and_restores_on_the_next_keypress() {
+        let start = Instant::now();
+        let mut clock = IdleClock {
+            last_activity: start,
+            ..IdleClock::default()
+        };
+
+        assert!(!clock.due(2, TIMEOUT, start + Duration::from_secs(29)));
+        assert!(clock.due(2, TIMEOUT, start + Duration::from_secs(30)));
+        assert_eq!(clock.dimmed_from, Some(2));
+        assert!(!clock.due(0, TIMEOUT, start + Duration::from_secs(60)));
+
+        assert_eq!(clock.activity(start + Duration::from_secs(61)), Some(2));
+        assert_eq!(clock.activity(start + Duration::from_secs(62)), None);
+        assert!(!clock.due(2, TIMEOUT, start + Duration::from_secs(91)));
+        assert!(clock.due(2, TIMEOUT, start + Duration::from_secs(92)));
+    }
+
+    #[test]
+    fn locked_screen_holds_the_timer_and_unlocking_restarts_it() {
+        let start = Instant::now();
+        let mut clock = IdleClock {
+            last_activity: start,
+            ..IdleClock::default()
+        };
+
+        clock.set_locked(true, start + Duration::from_secs(10));
+        assert!(!clock.due(3, TIMEOUT, start + Duration::from_secs(300)));
+        clock.set_locked(false, start + Duration::from_secs(300));
+        assert!(!clock.due(3, TIMEOUT, start + Duration::from_secs(329)));
+        assert!(clock.due(3, TIMEOUT, start + Duration::from_secs(330)));
+    }
+
+    #[test]
+    fn backlight_turned_on_elsewhere_is_not_restored_over() {
+        let start = Instant::now();
+        let mut clock = IdleClock {
+            last_activity: start,
+            dimmed_from: Some(3),
+            ..IdleClock::default()
+        };
+
+        assert!(!clock.due(1, TIMEOUT, start + Duration::from_secs(5)));
+        assert_eq!(clock.activity(start + Duration::from_secs(6)), None);
+        assert!(!clock.due(0, TIMEOUT, start + Duration::from_secs(600)));
+    }
+
+    #[tokio::test]
+    async fn dimming_saves_the_level_it_turned_off() {
+        let hardware = Arc::new(SimHardware::new(&SimMachine::default()));
+        hardware.detach_keyboard(true);
+        let state = Arc::new(RwLock::new(RuntimeState {
+            status: hardware.status(),
+            settings: DuoSettings {
+                backlight_idle_timeout_bluetooth_secs: 30,
+                ..DuoSettings::default()
+            },
+            hardware: HardwareBackend::simulated(hardware.clone()),
+            ..RuntimeState::default()
+        }));
+        {
+            let mut guard = state.write().await;
+            guard.status.backlight_level = 2;
+            guard.keyboard_idle.set_bluetooth_watched(true);
+            guard.keyboard_idle.clock().last_activity -= Duration::from_secs(31);
+        }
+
+        tick(&state).await;
+
+        let guard = state.read().await;
+        assert_eq!(guard.status.backlight_level, 0);
+        assert_eq!(guard.keyboard_idle.dimmed_from(), Some(2));
+        let saved: RuntimeState =
+            serde_json::from_str(&guard.to_json().expect("json")).expect("state");
+        assert_eq!(saved.status.backlight_level, 2);
+    }
+
+    #[test]
+    fn timeout_follows_the_transport_and_needs_a_keypress_source() {
+        let settings = DuoSettings {
+            backlight_idle_timeout_usb_secs: 0,
+            backlight_idle_timeout_bluetooth_secs: 45,
+            ..DuoSettings::default()
+        };
+
+        assert_eq!(
+            idle_timeout(&settings, &ConnectionType::Bluetooth, false, true),
+            Some(Duration::from_secs(45))
+        );
+        assert_eq!(
+            idle_timeout(&settings, &ConnectionType::Bluetooth, true, false),
+            None
+        );
+        assert_eq!(
+            idle_timeout(&settings, &ConnectionType::Usb, true, true),
+            None
+        );
+        assert_eq!(
+            idle_timeout(&settings, &ConnectionType::None, true, true),
+            None
+        );
+    }
+}
@@ -1,31 +1,756 @@
-use std::fs::{self, OpenOptions};
-use std::io::Write;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
+use crate::models::{LogFilter, LogLevel, LogQuery, LogRecord};
 use crate::runtime::paths;
 
-fn ensure_log_parent() -> Result<(), String> {
-    let path = paths::log_file_path();
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("Failed to create log dir: {e}"))?;
+pub const DEFAULT_MAX_BYTES: u64 = 1024 * 1024;
+pub const DEFAULT_KEEP_FILES: usize = 3;
+pub const DEFAULT_QUERY_LIMIT: usize = 200;
+pub const MAX_QUERY_LIMIT: usize = 5000;
+const READ_BLOCK_BYTES: usize = 8 * 1024;
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+/// Logger settings, read once per process from the environment so the
+/// systemd units can set them:
+///
+/// - `ZENBOOK_DUO_LOG_LEVEL`: `debug`, `info` (default), `warn` or `error`
+/// - `ZENBOOK_DUO_LOG_FORMAT`: `text` (default) or `json` for JSON lines
+/// - `ZENBOOK_DUO_LOG_JOURNALD`: `1` to also send records to journald
+/// - `ZENBOOK_DUO_LOG_MAX_BYTES` / `ZENBOOK_DUO_LOG_KEEP`: rotate the file
+///   past this size, keeping this many older files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogConfig {
+    pub min_level: LogLevel,
+    pub format: LogFormat,
+    pub journald: bool,
+    pub max_bytes: u64,
+    pub keep_files: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            min_level: LogLevel::Info,
+            format: LogFormat::Text,
+            journald: false,
+            max_bytes: DEFAULT_MAX_BYTES,
+            keep_files: DEFAULT_KEEP_FILES,
+        }
+    }
+}
+
+impl LogConfig {
+    pub fn from_env() -> Self {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let defaults = Self::default();
+        Self {
+            min_level: lookup("ZENBOOK_DUO_LOG_LEVEL")
+                .and_then(|raw| LogLevel::parse(&raw))
+                .unwrap_or(defaults.min_level),
+            format: match lookup("ZENBOOK_DUO_LOG_FORMAT").as_deref() {
+                Some("json") => LogFormat::Json,
+                _ => defaults.format,
+            },
+            journald: matches!(
+                lookup("ZENBOOK_DUO_LOG_JOURNALD").as_deref(),
+                Some("1" | "true" | "yes")
+            ),
+            max_bytes: lookup("ZENBOOK_DUO_LOG_MAX_BYTES")
+                .and_then(|raw| raw.parse().ok())
+                .unwrap_or(defaults.max_bytes),
+            keep_files: lookup("ZENBOOK_DUO_LOG_KEEP")
+                .and_then(|raw| raw.parse().ok())
+                .unwrap_or(defaults.keep_files),
+        }
+    }
+
+    pub fn global() -> &'static LogConfig {
+        static CONFIG: OnceLock<LogConfig> = OnceLock::new();
+        CONFIG.get_or_init(LogConfig::from_env)
+    }
+}
+
+/// A log file plus the settings it is written with. The daemon, session
+/// agent and UI share `daemon.log`; the remap helper keeps its own file next
+/// to its pid file.
+#[derive(Debug, Clone)]
+pub struct LogSink {
+    path: PathBuf,
+    config: LogConfig,
+}
+
+impl LogSink {
+    pub fn new(path: PathBuf, config: LogConfig) -> Self {
+        Self { path, config }
+    }
+
+    /// A sink at `path` with the process-wide settings.
+    pub fn at(path: PathBuf) -> Self {
+        Self::new(path, LogConfig::global().clone())
+    }
+
+    pub fn system() -> Self {
+        Self::at(paths::log_file_path())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write(&self, record: &LogRecord) -> Result<(), String> {
+        if record.level < self.config.min_level {
+            return Ok(());
+        }
+        if self.config.journald {
+            // journald is best-effort; the file stays the source of truth.
+            let _ = send_to_journald(record);
+        }
+
+        let line = match self.config.format {
+            LogFormat::Text => record.to_string(),
+            LogFormat::Json => serde_json::to_string(record)
+                .map_err(|e| format!("Failed to serialize log record: {e}"))?,
+        };
+        self.append(&line)
+    }
+
+    fn append(&self, line: &str) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create log dir: {e}"))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open {}: {e}", self.path.display()))?;
+        writeln!(file, "{line}")
+            .map_err(|e| format!("Failed to write {}: {e}", self.path.display()))?;
+
+        let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        if size > self.config.max_bytes {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+
+    /// `daemon.log` becomes `daemon.log.1`, which becomes `daemon.log.2`, and
+    /// so on; the oldest file past `keep_files` is dropped.
+    fn rotate(&self) -> Result<(), String> {
+        if self.config.keep_files == 0 {
+            return fs::write(&self.path, "")
+                .map_err(|e| format!("Failed to truncate {}: {e}", self.path.display()));
+        }
+        for generation in (1..self.config.keep_files).rev() {
+            let from = self.generation_path(generation);
+            match fs::rename(&from, self.generation_path(generation + 1)) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(format!("Failed to rotate {}: {err}", from.display())),
+            }
+        }
+        fs::rename(&self.path, self.generation_path(1))
+            .map_err(|e| format!("Failed to rotate {}: {e}", self.path.display()))
+    }
+
+    fn generation_path(&self, generation: usize) -> PathBuf {
+        if generation == 0 {
+            return self.path.clone();
+        }
+        let mut name = self
+            .path
+            .file_name()
+            .map(OsString::from)
+            .unwrap_or_default();
+        name.push(format!(".{generation}"));
+        self.path.with_file_name(name)
+    }
+
+    /// The last `count` records across the current and rotated files,
+    /// oldest first.
+    pub fn tail(&self, count: usize) -> Vec<LogRecord> {
+        let everything = LogQuery::default()
+            .compile()
+            .expect("an empty log query always compiles");
+        let mut records = self.newest(&everything, count).unwrap_or_default();
+        records.reverse();
+        records
+    }
+
+    /// Up to `limit` matching records, newest first. Files are read
+    /// backwards a block at a time, and the scan stops once the limit is
+    /// reached or the records predate the filter's window.
+    pub fn newest(&self, filter: &LogFilter, limit: usize) -> Result<Vec<LogRecord>, String> {
+        let mut found = Vec::new();
+        if limit == 0 {
+            return Ok(found);
+        }
+        for generation in 0..=self.config.keep_files {
+            let path = self.generation_path(generation);
+            let mut finished = false;
+            visit_lines_backwards(&path, |line| {
+                let Some(record) = LogRecord::parse_line(line) else {
+                    return true;
+                };
+                if filter.is_before_window(&record) {
+                    finished = true;
+                    return false;
+                }
+                if filter.matches(&record) {
+                    found.push(record);
+                    finished = found.len() >= limit;
+                }
+                !finished
+            })
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+            if finished {
+                break;
+            }
+        }
+        Ok(found)
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create log dir: {e}"))?;
+        }
+        for generation in 1..=self.config.keep_files {
+            let _ = fs::remove_file(self.generation_path(generation));
+        }
+        fs::write(&self.path, "")
+            .map_err(|e| format!("Failed to clear {}: {e}", self.path.display()))
+    }
+}
+
+/// Calls `visit` with each line of `path`, last line first, reading the file
+/// backwards in blocks so only the visited part is ever held in memory.
+/// Stops as soon as `visit` returns false. A missing file has no lines.
+pub(crate) fn visit_lines_backwards(
+    path: &Path,
+    mut visit: impl FnMut(&str) -> bool,
+) -> io::Result<()> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    let mut position = file.metadata()?.len();
+    let mut block = vec![0u8; READ_BLOCK_BYTES];
+    // Start of the line that straddles the previous block boundary.
+    let mut partial = Vec::new();
+    while position > 0 {
+        let len = READ_BLOCK_BYTES.min(position as usize);
+        position -= len as u64;
+        file.seek(SeekFrom::Start(position))?;
+        file.read_exact(&mut block[..len])?;
+
+        let mut chunk = block[..len].to_vec();
+        chunk.append(&mut partial);
+        let mut end = chunk.len();
+        while let Some(newline) = chunk[..end].iter().rposition(|byte| *byte == b'\n') {
+            let line = &chunk[newline + 1..end];
+            if !line.is_empty() && !visit(&String::from_utf8_lossy(line)) {
+                return Ok(());
+            }
+            end = newline;
+        }
+        chunk.truncate(end);
+        partial = chunk;
+    }
+    if !partial.is_empty() {
+        visit(&String::from_utf8_lossy(&partial));
     }
     Ok(())
 }
 
+/// Searches several logs at once (the runtime log and the remap helper's
+/// log) and merges the matches chronologically, keeping the newest ones when
+/// more match than the query's limit allows.
+pub fn query(sinks: &[LogSink], filter: &LogFilter) -> Result<Vec<LogRecord>, String> {
+    let limit = filter
+        .query()
+        .limit
+        .unwrap_or(DEFAULT_QUERY_LIMIT)
+        .min(MAX_QUERY_LIMIT);
+    let mut records = Vec::new();
+    for sink in sinks {
+        records.extend(sink.newest(filter, limit)?);
+    }
+    records.sort_by_key(|record| record.timestamp);
+    if records.len() > limit {
+        records.drain(..records.len() - limit);
+    }
+    Ok(records)
+}
+
+/// Picks up records appended to a set of logs after it was created, for
+/// `QueryLogs { follow }`. Rotation is detected by the file's inode changing,
+/// in which case the rest of the rotated files is read before the new one.
+#[derive(Debug)]
+pub struct LogFollower {
+    files: Vec<FollowedFile>,
+}
+
+#[derive(Debug)]
+struct FollowedFile {
+    sink: LogSink,
+    inode: Option<u64>,
+    offset: u64,
+}
+
+impl LogFollower {
+    /// Starts at the current end of every log.
+    pub fn new(sinks: Vec<LogSink>) -> Self {
+        let files = sinks
+            .into_iter()
+            .map(|sink| {
+                let metadata = fs::metadata(sink.path()).ok();
+                FollowedFile {
+                    inode: metadata.as_ref().map(MetadataExt::ino),
+                    offset: metadata.map_or(0, |metadata| metadata.len()),
+                    sink,
+                }
+            })
+            .collect();
+        Self { files }
+    }
+
+    /// Complete lines appended since the last poll, in time order.
+    pub fn poll(&mut self) -> Vec<LogRecord> {
+        let mut records = Vec::new();
+        for file in &mut self.files {
+            file.poll(&mut records);
+        }
+        records.sort_by_key(|record| record.timestamp);
+        records
+    }
+}
+
+impl FollowedFile {
+    fn poll(&mut self, records: &mut Vec<LogRecord>) {
+        let current = fs::metadata(self.sink.path()).ok();
+        let current_inode = current.as_ref().map(MetadataExt::ino);
+        if current_inode != self.inode {
+            // Finish the file we were following, then read any generations
+            // rotated after it, oldest first.
+            let followed = (1..=self.sink.config.keep_files).find(|generation| {
+                self.inode.is_some()
+                    && fs::metadata(self.sink.generation_path(*generation))
+                        .is_ok_and(|metadata| Some(metadata.ino()) == self.inode)
+            });
+            if let Some(followed) = followed {
+                read_appended(
+                    &self.sink.generation_path(followed),
+                    &mut self.offset,
+                    records,
+                );
+                for generation in (1..followed).rev() {
+                    read_appended(&self.sink.generation_path(generation), &mut 0, records);
+                }
+            }
+            self.inode = current_inode;
+            self.offset = 0;
+        } else if current
+            .as_ref()
+            .is_some_and(|metadata| metadata.len() < self.offset)
+        {
+            // Truncated in place by `ClearLogs`.
+            self.offset = 0;
+        }
+        if self.inode.is_some() {
+            read_appended(self.sink.path(), &mut self.offset, records);
+        }
+    }
+}
+
+/// Reads whole lines from `offset` onwards; a trailing line that is still
+/// being written is left for the next poll.
+fn read_appended(path: &Path, offset: &mut u64, records: &mut Vec<LogRecord>) {
+    let Ok(mut file) = File::open(path) else {
+        return;
+    };
+    let mut appended = Vec::new();
+    if file.seek(SeekFrom::Start(*offset)).is_err() || file.read_to_end(&mut appended).is_err() {
+        return;
+    }
+    let Some(complete) = appended.iter().rposition(|byte| *byte == b'\n') else {
+        return;
+    };
+    *offset += complete as u64 + 1;
+    records.extend(
+        String::from_utf8_lossy(&appended[..complete])
+            .lines()
+            .filter_map(LogRecord::parse_line),
+    );
+}
+
+/// Native journald protocol: `KEY=value` lines, with values that contain a
+/// newline sent as `KEY\n<u64 little-endian length><bytes>\n`.
+fn send_to_journald(record: &LogRecord) -> io::Result<()> {
+    let priority = match record.level {
+        LogLevel::Debug => "7",
+        LogLevel::Info => "6",
+        LogLevel::Warn => "4",
+        LogLevel::Error => "3",
+    };
+    let mut payload = Vec::new();
+    push_journal_field(&mut payload, "MESSAGE", &record.message);
+    push_journal_field(&mut payload, "PRIORITY", priority);
+    push_journal_field(&mut payload, "SYSLOG_IDENTIFIER", &record.component);
+    for (key, value) in &record.fields {
+        push_journal_field(&mut payload, &journal_field_name(key), value);
+    }
+
+    let socket = UnixDatagram::unbound()?;
+    socket.send_to(&payload, JOURNALD_SOCKET)?;
+    Ok(())
+}
+
+fn push_journal_field(payload: &mut Vec<u8>, key: &str, value: &str) {
+    payload.extend_from_slice(key.as_bytes());
+    if value.contains('\n') {
+        payload.push(b'\n');
+        payload.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        payload.extend_from_slice(value.as_bytes());
+    } else {
+        payload.push(b'=');
+        payload.extend_from_slice(value.as_bytes());
+    }
+    payload.push(b'\n');
+}
+
+/// journald only accepts `[A-Z0-9_]` field names that do not start with `_`.
+fn journal_field_name(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("ZENBOOK_DUO_{name}")
+}
+
+/// Writes a record to the shared log and mirrors it to the `log` facade so
+/// `RUST_LOG` still shows it on stderr.
+pub fn write(record: LogRecord) {
+    let level = match record.level {
+        LogLevel::Debug => log::Level::Debug,
+        LogLevel::Info => log::Level::Info,
+        LogLevel::Warn => log::Level::Warn,
+        LogLevel::Error => log::Level::Error,
+    };
+    log::log!(target: record.component.as_str(), level, "{}", record.message);
+    if let Err(err) = LogSink::system().write(&record) {
+        eprintln!("{}: failed to write log record: {err}", record.component);
+    }
+}
+
+pub fn debug(component: &str, message: impl Into<String>) {
+    write(LogRecord::new(LogLevel::Debug, component, message));
+}
+
+pub fn info(component: &str, message: impl Into<String>) {
+    write(LogRecord::new(LogLevel::Info, component, message));
+}
+
+pub fn warn(component: &str, message: impl Into<String>) {
+    write(LogRecord::new(LogLevel::Warn, component, message));
+}
+
+pub fn error(component: &str, message: impl Into<String>) {
+    write(LogRecord::new(LogLevel::Error, component, message));
+}
+
+/// Logs a free-form `component: message` line, as sent by `AppendLog`
+/// clients. Lines that are already structured keep their level and fields.
 pub fn append_line(message: impl AsRef<str>) -> Result<(), String> {
-    ensure_log_parent()?;
-    let path = paths::log_file_path();
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&path)
-        .map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    let Some(mut record) = LogRecord::parse_line(message.as_ref()) else {
+        return Ok(());
+    };
+    if record.timestamp.is_none() {
+        record.timestamp = Some(chrono::Utc::now());
+    }
+    LogSink::system().write(&record)
+}
 
-    writeln!(file, "{}", message.as_ref())
-        .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+pub fn tail(count: usize) -> Vec<LogRecord> {
+    LogSink::system().tail(count)
 }
 
 pub fn clear() -> Result<(), String> {
-    ensure_log_parent()?;
-    let path = paths::log_file_path();
-    fs::write(&path, "").map_err(|e| format!("Failed to clear {}: {e}", path.display()))
+    LogSink::system().clear()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn unique_sink(label: &str, config: LogConfig) -> LogSink {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        LogSink::new(
+            std::env::temp_dir()
+                .join(format!("zenbook-duo-logger-{label}-{nanos}"))
+                .join("daemon.log"),
+            config,
+        )
+    }
+
+    fn cleanup(sink: &LogSink) {
+        let _ = fs::remove_dir_all(sink.path().parent().expect("log dir"));
+    }
+
+    #[test]
+    fn config_reads_environment_overrides() {
+        let env: HashMap<&str, &str> = [
+            ("ZENBOOK_DUO_LOG_LEVEL", "warn"),
+            ("ZENBOOK_DUO_LOG_FORMAT", "json"),
+            ("ZENBOOK_DUO_LOG_JOURNALD", "1"),
+            ("ZENBOOK_DUO_LOG_KEEP", "5"),
+        ]
+        .into_iter()
+        .collect();
+
+        let config = LogConfig::from_lookup(|key| env.get(key).map(|value| value.to_string()));
+
+        assert_eq!(config.min_level, LogLevel::Warn);
+        assert_eq!(config.format, LogFormat::Json);
+        assert!(config.journald);
+        assert_eq!(config.keep_files, 5);
+        assert_eq!(config.max_bytes, DEFAULT_MAX_BYTES);
+    }
+
+    #[test]
+    fn records_below_the_minimum_level_are_dropped() {
+        let sink = unique_sink(
+            "level",
+            LogConfig {
+                min_level: LogLevel::Warn,
+                ..LogConfig::default()
+            },
+        );
+
+        sink.write(&LogRecord::new(LogLevel::Info, "rust-daemon", "chatty"))
+            .expect("write info");
+        sink.write(&LogRecord::new(LogLevel::Error, "rust-daemon", "broken").field("code", 5))
+            .expect("write error");
+
+        let records = sink.tail(10);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message, "broken");
+        assert_eq!(records[0].fields["code"], "5");
+
+        cleanup(&sink);
+    }
+
+    #[test]
+    fn rotation_keeps_a_bounded_number_of_files_and_tail_spans_them() {
+        let sink = unique_sink(
+            "rotate",
+            LogConfig {
+                format: LogFormat::Json,
+                max_bytes: 400,
+                keep_files: 2,
+                ..LogConfig::default()
+            },
+        );
+
+        for index in 0..40 {
+            sink.write(&LogRecord::new(
+                LogLevel::Info,
+                "rust-daemon",
+                format!("line {index}"),
+            ))
+            .expect("write record");
+        }
+
+        assert!(sink.generation_path(1).exists());
+        assert!(sink.generation_path(2).exists());
+        assert!(!sink.generation_path(3).exists());
+
+        let tail = sink.tail(8);
+        let messages: Vec<_> = tail.iter().map(|record| record.message.as_str()).collect();
+        assert_eq!(messages.len(), 8);
+        assert_eq!(messages.last(), Some(&"line 39"));
+        assert!(tail
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+
+        sink.clear().expect("clear log");
+        assert!(sink.tail(10).is_empty());
+        assert!(!sink.generation_path(1).exists());
+
+        cleanup(&sink);
+    }
+
+    #[test]
+    fn tail_parses_legacy_lines_alongside_structured_ones() {
+        let sink = unique_sink("legacy", LogConfig::default());
+        fs::create_dir_all(sink.path().parent().expect("log dir")).expect("create log dir");
+        fs::write(sink.path(), "rust-daemon: initialized runtime state\n").expect("write legacy");
+        sink.write(&LogRecord::new(
+            LogLevel::Warn,
+            "session-agent",
+            "lost compositor",
+        ))
+        .expect("write record");
+
+        let records = sink.tail(10);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].timestamp, None);
+        assert_eq!(records[0].component, "rust-daemon");
+        assert_eq!(records[1].level, LogLevel::Warn);
+        assert_eq!(records[1].component, "session-agent");
+
+        cleanup(&sink);
+    }
+
+    fn record_at(minute: u32, component: &str, level: LogLevel, message: &str) -> LogRecord {
+        let mut record = LogRecord::new(level, component, message);
+        record.timestamp = Some(
+            chrono::TimeZone::with_ymd_and_hms(&chrono::Utc, 2026, 3, 10, 9, minute, 0).unwrap(),
+        );
+        record
+    }
+
+    #[test]
+    fn backwards_reader_yields_every_line_across_block_boundaries() {
+        let sink = unique_sink("backwards", LogConfig::default());
+        fs::create_dir_all(sink.path().parent().expect("log dir")).expect("create log dir");
+        let lines: Vec<String> = (0..2000)
+            .map(|index| format!("line number {index}"))
+            .collect();
+        fs::write(sink.path(), lines.join("\n")).expect("write log");
+
+        let mut seen = Vec::new();
+        visit_lines_backwards(sink.path(), |line| {
+            seen.push(line.to_string());
+            true
+        })
+        .expect("read backwards");
+        seen.reverse();
+        assert_eq!(seen, lines);
+
+        cleanup(&sink);
+    }
+
+    #[test]
+    fn query_merges_logs_and_stops_at_the_window() {
+        let daemon = unique_sink("query-daemon", LogConfig::default());
+        let helper = unique_sink("query-helper", LogConfig::default());
+        for (minute, level, message) in [
+            (0, LogLevel::Warn, "before resume"),
+            (10, LogLevel::Info, "resumed"),
+            (20, LogLevel::Warn, "lost compositor"),
+        ] {
+            daemon
+                .write(&record_at(minute, "session-agent", level, message))
+                .expect("write daemon log");
+        }
+        helper
+            .write(&record_at(15, "usb-remap", LogLevel::Error, "no device"))
+            .expect("write helper log");
+
+        let filter = LogQuery {
+            since: Some(
+                chrono::TimeZone::with_ymd_and_hms(&chrono::Utc, 2026, 3, 10, 9, 5, 0).unwrap(),
+            ),
+            min_level: Some(LogLevel::Warn),
+            ..LogQuery::default()
+        }
+        .compile()
+        .expect("compile query");
+        let found = query(&[daemon.clone(), helper.clone()], &filter).expect("query logs");
+        let messages: Vec<_> = found.iter().map(|record| record.message.as_str()).collect();
+        assert_eq!(messages, vec!["no device", "lost compositor"]);
+
+        let session_only = LogQuery {
+            components: vec!["session-agent".into()],
+            limit: Some(1),
+            ..LogQuery::default()
+        }
+        .compile()
+        .expect("compile query");
+        let found = query(&[daemon.clone(), helper.clone()], &session_only).expect("query logs");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].message, "lost compositor");
+
+        cleanup(&daemon);
+        cleanup(&helper);
+    }
+
+    #[test]
+    fn follower_reads_appended_lines_through_rotation() {
+        let sink = unique_sink(
+            "follow",
+            LogConfig {
+                max_bytes: 200,
+                ..LogConfig::default()
+            },
+        );
+        sink.write(&LogRecord::new(LogLevel::Info, "rust-daemon", "old"))
+            .expect("write record");
+        let mut follower = LogFollower::new(vec![sink.clone()]);
+        assert!(follower.poll().is_empty());
+
+        for index in 0..6 {
+            sink.write(&LogRecord::new(
+                LogLevel::Info,
+                "rust-daemon",
+                format!("new {index}"),
+            ))
+            .expect("write record");
+        }
+        assert!(sink.generation_path(1).exists(), "the log rotated");
+
+        let messages: Vec<_> = follower
+            .poll()
+            .into_iter()
+            .map(|record| record.message)
+            .collect();
+        assert_eq!(
+            messages,
+            (0..6)
+                .map(|index| format!("new {index}"))
+                .collect::<Vec<_>>()
+        );
+        assert!(follower.poll().is_empty());
+
+        cleanup(&sink);
+    }
+
+    #[test]
+    fn journald_fields_use_the_native_encoding() {
+        let mut payload = Vec::new();
+        push_journal_field(&mut payload, "MESSAGE", "one line");
+        push_journal_field(&mut payload, "MESSAGE", "two\nlines");
+
+        let mut expected = b"MESSAGE=one line\nMESSAGE\n".to_vec();
+        expected.extend_from_slice(&9u64.to_le_bytes());
+        expected.extend_from_slice(b"two\nlines\n");
+        assert_eq!(payload, expected);
+        assert_eq!(journal_field_name("retry-count"), "ZENBOOK_DUO_RETRY_COUNT");
+    }
 }
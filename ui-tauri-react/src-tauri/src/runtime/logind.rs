@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use tokio::sync::RwLock;
@@ -5,7 +6,18 @@ use tokio::sync::RwLock;
 use crate::models::{EventCategory, HardwareEvent};
 use crate::runtime::{logger, state::RuntimeState};
 
+const LOGIN1: &str = "org.freedesktop.login1";
+const LOGIN1_SESSION: &str = "org.freedesktop.login1.Session";
+const LOGIN1_SEAT: &str = "org.freedesktop.login1.Seat";
+const SEAT0_PATH: &str = "/org/freedesktop/login1/seat/seat0";
+
 pub fn start(state: Arc<RwLock<RuntimeState>>) {
+    let locks = state.clone();
+    tokio::spawn(async move {
+        if let Err(err) = watch_session_locks(locks).await {
+            logger::warn("rust-daemon", format!("session lock watcher failed: {err}"));
+        }
+    });
     tokio::spawn(async move {
         if let Err(err) = watch_logind(state.clone()).await {
             log::warn!("logind runtime watcher failed: {err}");
@@ -15,7 +27,7 @@ pub fn start(state: Arc<RwLock<RuntimeState>>) {
                 &format!("Logind watcher failed: {err}"),
             )
             .await;
-            let _ = logger::append_line(format!("rust-daemon: logind watcher failed: {err}"));
+            logger::warn("rust-daemon", format!("logind watcher failed: {err}"));
         }
     });
 }
@@ -44,33 +56,6 @@ async fn watch_logind(state: Arc<RwLock<RuntimeState>>) -> Result<(), zbus::Erro
     while let Some(signal) = stream.next().await {
         let args = signal.args().ok();
         if let Some(args) = args {
-            if args.changed_properties().contains_key("LockedHint") {
-                let locked = args
-                    .changed_properties()
-                    .get("LockedHint")
-                    .and_then(|value| value.downcast_ref::<bool>().ok());
-                let message = match locked {
-                    Some(true) => "Screen locked",
-                    Some(false) => "Screen unlocked",
-                    None => "Screen lock state changed",
-                };
-
-                let mut guard = state.write().await;
-                guard.push_recent_event(HardwareEvent::info(
-                    EventCategory::Display,
-                    message,
-                    "rust-daemon",
-                ));
-                guard.touch();
-                if let Err(err) = guard.save() {
-                    log::warn!("failed to save logind runtime state: {err}");
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: failed to persist logind state: {err}"
-                    ));
-                }
-                let _ = logger::append_line(format!("rust-daemon: {}", message));
-            }
-
             if let Some(lid_closed) = args
                 .changed_properties()
                 .get("LidClosed")
@@ -80,9 +65,10 @@ async fn watch_logind(state: Arc<RwLock<RuntimeState>>) -> Result<(), zbus::Erro
                     crate::runtime::daemon::handle_lid_closed_change(&state, lid_closed).await
                 {
                     if crate::runtime::daemon::is_display_session_deferral(&err) {
-                        let _ = logger::append_line(format!(
-                            "rust-daemon: lid state display update deferred: {err}"
-                        ));
+                        logger::info(
+                            "rust-daemon",
+                            format!("lid state display update deferred: {err}"),
+                        );
                         continue;
                     }
 
@@ -93,9 +79,10 @@ async fn watch_logind(state: Arc<RwLock<RuntimeState>>) -> Result<(), zbus::Erro
                         &format!("Lid state display update failed: {err}"),
                     )
                     .await;
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: lid state display update failed: {err}"
-                    ));
+                    logger::warn(
+                        "rust-daemon",
+                        format!("lid state display update failed: {err}"),
+                    );
                 }
             }
         }
@@ -104,12 +91,161 @@ async fn watch_logind(state: Arc<RwLock<RuntimeState>>) -> Result<(), zbus::Erro
     Ok(())
 }
 
+/// `LockedHint` lives on each session object rather than the manager, so
+/// this follows whichever session is in the foreground on seat0: its lock
+/// state is read at startup and whenever the seat switches sessions, and
+/// otherwise tracked from its property changes. Background sessions, other
+/// seats and sessions that have ended are ignored.
+async fn watch_session_locks(state: Arc<RwLock<RuntimeState>>) -> Result<(), zbus::Error> {
+    let connection = zbus::Connection::system().await?;
+    let rule = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender(LOGIN1)?
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .build();
+    let mut stream = zbus::MessageStream::for_match_rule(rule, &connection, None).await?;
+
+    let mut lock = ActiveSessionLock::default();
+    let session = active_session(&connection).await;
+    let locked = session_locked(&connection, session.as_deref()).await;
+    if lock.switch_to(session, locked) == Some(true) {
+        state.read().await.keyboard_idle.set_screen_locked(true);
+        logger::info("rust-daemon", "screen locked at startup");
+    }
+
+    use futures_util::StreamExt;
+    while let Some(message) = stream.next().await {
+        let message = message?;
+        let Some(path) = message.header().path().map(|path| path.to_string()) else {
+            continue;
+        };
+        let Ok((interface, changed, invalidated)) = message.body().deserialize::<(
+            String,
+            HashMap<String, zbus::zvariant::OwnedValue>,
+            Vec<String>,
+        )>() else {
+            continue;
+        };
+
+        let change = if interface == LOGIN1_SEAT && path == SEAT0_PATH {
+            if !changed.contains_key("ActiveSession")
+                && !invalidated.iter().any(|name| name == "ActiveSession")
+            {
+                continue;
+            }
+            let session = active_session(&connection).await;
+            let locked = session_locked(&connection, session.as_deref()).await;
+            lock.switch_to(session, locked)
+        } else if interface == LOGIN1_SESSION {
+            let Some(locked) = changed
+                .get("LockedHint")
+                .and_then(|value| value.downcast_ref::<bool>().ok())
+            else {
+                continue;
+            };
+            lock.record(&path, locked)
+        } else {
+            continue;
+        };
+        let Some(screen_locked) = change else {
+            continue;
+        };
+
+        let message = if screen_locked {
+            "Screen locked"
+        } else {
+            "Screen unlocked"
+        };
+        let mut guard = state.write().await;
+        guard.keyboard_idle.set_screen_locked(screen_locked);
+        guard.push_recent_event(HardwareEvent::info(
+            EventCategory::Display,
+            message,
+            "rust-daemon",
+        ));
+        guard.touch();
+        guard.persist();
+        logger::info("rust-daemon", message);
+    }
+
+    Ok(())
+}
+
+/// The object path of seat0's foreground session, if it has one.
+async fn active_session(connection: &zbus::Connection) -> Option<String> {
+    let value = login1_property(connection, SEAT0_PATH, LOGIN1_SEAT, "ActiveSession").await?;
+    let (_, path) = <(String, zbus::zvariant::OwnedObjectPath)>::try_from(value).ok()?;
+    // logind reports "/" when the seat has no session in the foreground.
+    (path.as_str() != "/").then(|| path.to_string())
+}
+
+async fn session_locked(connection: &zbus::Connection, session: Option<&str>) -> bool {
+    let Some(session) = session else {
+        return false;
+    };
+    login1_property(connection, session, LOGIN1_SESSION, "LockedHint")
+        .await
+        .and_then(|value| value.downcast_ref::<bool>().ok())
+        .unwrap_or(false)
+}
+
+async fn login1_property(
+    connection: &zbus::Connection,
+    path: &str,
+    interface: &'static str,
+    name: &str,
+) -> Option<zbus::zvariant::OwnedValue> {
+    let proxy = zbus::fdo::PropertiesProxy::builder(connection)
+        .destination(LOGIN1)
+        .ok()?
+        .path(path.to_string())
+        .ok()?
+        .build()
+        .await
+        .ok()?;
+    let interface = interface.try_into().ok()?;
+    proxy.get(interface, name).await.ok()
+}
+
+/// The lock state of seat0's foreground session.
+#[derive(Debug, Default)]
+struct ActiveSessionLock {
+    session: Option<String>,
+    locked: bool,
+}
+
+impl ActiveSessionLock {
+    /// The seat switched to `session`, which is `locked`. Returns whether the
+    /// screen is now locked, when that changed.
+    fn switch_to(&mut self, session: Option<String>, locked: bool) -> Option<bool> {
+        self.session = session;
+        self.set(locked)
+    }
+
+    /// Records a session's `LockedHint`, which only counts for the session
+    /// in the foreground.
+    fn record(&mut self, session: &str, locked: bool) -> Option<bool> {
+        if self.session.as_deref() != Some(session) {
+            return None;
+        }
+        self.set(locked)
+    }
+
+    fn set(&mut self, locked: bool) -> Option<bool> {
+        let changed = self.locked != locked;
+        self.locked = locked;
+        changed.then_some(locked)
+    }
+}
+
 async fn sync_initial_lid_closed_state(state: &Arc<RwLock<RuntimeState>>, lid_closed: bool) {
     if let Err(err) = crate::runtime::daemon::handle_lid_closed_change(state, lid_closed).await {
         if crate::runtime::daemon::is_display_session_deferral(&err) {
-            let _ = logger::append_line(format!(
-                "rust-daemon: initial lid state display update deferred: {err}"
-            ));
+            logger::info(
+                "rust-daemon",
+                format!("initial lid state display update deferred: {err}"),
+            );
             return;
         }
 
@@ -120,9 +256,10 @@ async fn sync_initial_lid_closed_state(state: &Arc<RwLock<RuntimeState>>, lid_cl
             &format!("Initial lid state display update failed: {err}"),
         )
         .await;
-        let _ = logger::append_line(format!(
-            "rust-daemon: initial lid state display update failed: {err}"
-        ));
+        logger::warn(
+            "rust-daemon",
+            format!("initial lid state display update failed: {err}"),
+        );
     }
 }
 
@@ -138,4 +275,24 @@ mod tests {
 
         assert!(state.read().await.lid_closed);
     }
+
+    #[test]
+    fn only_the_foreground_session_locks_the_screen() {
+        let session = |id: &str| format!("/org/freedesktop/login1/session/{id}");
+        let mut lock = ActiveSessionLock::default();
+
+        assert_eq!(lock.switch_to(Some(session("_32")), false), None);
+        assert_eq!(lock.record(&session("c1"), true), None);
+        assert_eq!(lock.record(&session("_32"), true), Some(true));
+        assert_eq!(lock.record(&session("_32"), true), None);
+
+        // Switching to another user's unlocked session unlocks the screen,
+        // and the session left behind no longer counts.
+        assert_eq!(lock.switch_to(Some(session("c1")), false), Some(false));
+        assert_eq!(lock.record(&session("_32"), false), None);
+        assert_eq!(lock.record(&session("_32"), true), None);
+
+        assert_eq!(lock.switch_to(None, false), None);
+        assert_eq!(lock.record(&session("c1"), true), None);
+    }
 }
@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use tokio::sync::RwLock;
+
+use crate::models::{CounterSample, HistogramSample, MetricsSnapshot};
+use crate::runtime::{atomic_file, logger, state::RuntimeState};
+
+pub const COMMAND_DURATION: &str = "zenbook_duo_command_duration_seconds";
+pub const SESSION_REQUEST_DURATION: &str = "zenbook_duo_session_request_duration_seconds";
+pub const DOCK_REPLAY_DURATION: &str = "zenbook_duo_dock_replay_duration_seconds";
+pub const HID_WRITE_DURATION: &str = "zenbook_duo_hid_write_duration_seconds";
+pub const HID_READ_DURATION: &str = "zenbook_duo_hid_read_duration_seconds";
+pub const DISPLAY_RETRIES: &str = "zenbook_duo_display_retries_total";
+
+const TEXTFILE_ENV: &str = "ZENBOOK_DUO_METRICS_TEXTFILE";
+const TEXTFILE_INTERVAL: Duration = Duration::from_secs(15);
+
+type SeriesKey = (&'static str, BTreeMap<String, String>);
+
+/// Process-wide counters and histograms. Each process (daemon, session
+/// agent) keeps its own; the daemon merges the agent's in on request.
+#[derive(Default)]
+struct Registry {
+    counters: BTreeMap<SeriesKey, u64>,
+    histograms: BTreeMap<SeriesKey, HistogramSample>,
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+fn key(name: &'static str, labels: &[(&str, &str)]) -> SeriesKey {
+    let labels = labels
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    (name, labels)
+}
+
+pub fn increment(name: &'static str, labels: &[(&str, &str)]) {
+    if let Ok(mut registry) = registry().lock() {
+        *registry.counters.entry(key(name, labels)).or_default() += 1;
+    }
+}
+
+pub fn observe(name: &'static str, labels: &[(&str, &str)], elapsed: Duration) {
+    if let Ok(mut registry) = registry().lock() {
+        let (name, labels) = key(name, labels);
+        registry
+            .histograms
+            .entry((name, labels.clone()))
+            .or_insert_with(|| HistogramSample::empty(name, labels))
+            .observe(elapsed.as_secs_f64());
+    }
+}
+
+/// Records how long `started` ago the call began, labelled with whether
+/// `result` succeeded.
+pub fn observe_result<T, E>(
+    name: &'static str,
+    labels: &[(&str, &str)],
+    started: Instant,
+    result: &Result<T, E>,
+) {
+    let mut labels = labels.to_vec();
+    labels.push(("outcome", outcome(result)));
+    observe(name, &labels, started.elapsed());
+}
+
+pub fn outcome<T, E>(result: &Result<T, E>) -> &'static str {
+    if result.is_ok() {
+        "ok"
+    } else {
+        "error"
+    }
+}
+
+pub fn snapshot() -> MetricsSnapshot {
+    let Ok(registry) = registry().lock() else {
+        return MetricsSnapshot::default();
+    };
+    MetricsSnapshot {
+        counters: registry
+            .counters
+            .iter()
+            .map(|((name, labels), value)| CounterSample {
+                name: name.to_string(),
+                labels: labels.clone(),
+                value: *value,
+            })
+            .collect(),
+        histograms: registry.histograms.values().cloned().collect(),
+    }
+}
+
+pub fn textfile_path() -> Option<PathBuf> {
+    std::env::var_os(TEXTFILE_ENV)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Periodically rewrites the Prometheus textfile named by
+/// `ZENBOOK_DUO_METRICS_TEXTFILE`, if set. The file is replaced atomically so
+/// the collector never scrapes a half-written file.
+pub fn start(state: Arc<RwLock<RuntimeState>>) {
+    let Some(path) = textfile_path() else {
+        return;
+    };
+    logger::info(
+        "rust-daemon",
+        format!("writing metrics textfile to {}", path.display()),
+    );
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TEXTFILE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let text = crate::runtime::daemon::collect_metrics(&state)
+                .await
+                .to_prometheus();
+            let path = path.clone();
+            let result =
+                tokio::task::spawn_blocking(move || atomic_file::replace(&path, text.as_bytes()))
+                    .await
+                    .unwrap_or_else(|e| Err(format!("Failed to join metrics textfile write: {e}")));
+            if let Err(err) = result {
+                logger::warn(
+                    "rust-daemon",
+                    format!("metrics textfile write failed: {err}"),
+                );
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_accumulates_series_by_labels() {
+        increment("test_registry_total", &[("kind", "a")]);
+        increment("test_registry_total", &[("kind", "a")]);
+        increment("test_registry_total", &[("kind", "b")]);
+        observe_result(
+            "test_registry_seconds",
+            &[("program", "gdctl")],
+            Instant::now(),
+            &Err::<(), _>("boom"),
+        );
+
+        let snapshot = snapshot();
+        let counts: Vec<_> = snapshot
+            .counters
+            .iter()
+            .filter(|counter| counter.name == "test_registry_total")
+            .map(|counter| (counter.labels["kind"].as_str(), counter.value))
+            .collect();
+        assert_eq!(counts, vec![("a", 2), ("b", 1)]);
+
+        let histogram = snapshot
+            .histograms
+            .iter()
+            .find(|histogram| histogram.name == "test_registry_seconds")
+            .expect("histogram recorded");
+        assert_eq!(histogram.labels["outcome"], "error");
+        assert_eq!(histogram.count, 1);
+    }
+}
@@ -1,21 +1,38 @@
+pub mod ambient_light;
+pub mod atomic_file;
+pub mod attach_debounce;
+pub mod backend;
 pub(crate) mod bluetooth_hotkeys;
 pub mod client;
 pub mod compositor;
 pub mod daemon;
+pub mod dbus_service;
+pub mod event_bus;
+pub mod event_journal;
+pub mod hooks;
 pub mod host;
+pub mod keyboard_idle;
 pub mod logger;
 pub mod logind;
+pub mod metrics;
 pub mod monitor;
 pub mod paths;
+pub mod persistence;
+pub mod peer_auth;
 pub mod policy;
+pub mod posture;
 pub mod probe;
+pub mod radio;
 pub mod router;
 pub mod runtime_dir;
+pub mod schema;
 pub mod service_control;
 pub mod session;
 pub mod session_agent;
 pub(crate) mod session_display_planner;
 pub(crate) mod session_ipc;
 pub(crate) mod session_watchers;
+pub mod simulation;
 pub mod state;
+pub mod uevent;
 pub mod version;
@@ -2,64 +2,284 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use chrono::Utc;
-use tokio::sync::RwLock;
-use tokio::time::sleep;
-
-use crate::models::{ConnectionType, EventCategory, HardwareEvent};
+use tokio::sync::{mpsc, RwLock};
+use tokio::time::{sleep, sleep_until};
+
+use crate::ipc::protocol::{DaemonRequest, DaemonResponse};
+use crate::models::{
+    ConnectionType, DuoStatus, EventCategory, HardwareEvent, HookEvent, PostureTrigger,
+};
+use crate::runtime::attach_debounce::{AttachDebouncer, AttachState, Debounced, SystemClock};
 use crate::runtime::logger;
 use crate::runtime::policy::PolicyAction;
+use crate::runtime::probe::StatusRefresh;
+use crate::runtime::radio::Radio;
+use crate::runtime::simulation::TranscriptEntry;
 use crate::runtime::state::RuntimeState;
+use crate::runtime::uevent::UeventSocket;
+
+/// Full re-probe interval while uevents drive the monitor. It catches what
+/// the kernel does not announce, such as a compositor-side rotation or a
+/// session agent that went away, and paces USB remap auto-start retries.
+const SAFETY_NET_INTERVAL: Duration = Duration::from_secs(15);
+/// Polling interval when the uevent socket is unavailable.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// An attach fires a burst of uevents across subsystems; wait this long for
+/// the burst to settle so it costs a single refresh.
+const UEVENT_SETTLE: Duration = Duration::from_millis(50);
 
 pub fn start(state: Arc<RwLock<RuntimeState>>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let uevents_open = match UeventSocket::open() {
+        Ok(socket) => {
+            crate::runtime::uevent::start(socket, sender);
+            true
+        }
+        Err(err) => {
+            logger::warn(
+                "rust-daemon",
+                format!("hotplug events unavailable, polling status every second: {err}"),
+            );
+            false
+        }
+    };
+
+    let (radio_sender, radio_receiver) = mpsc::unbounded_channel();
+    crate::runtime::radio::start_watcher(radio_sender);
+    spawn_monitor(state, receiver, uevents_open, radio_receiver);
+}
+
+/// Runs the monitor on refreshes from `receiver` in place of the uevent and
+/// rfkill watchers, which is how `--simulate` delivers hotplug events.
+pub fn start_with(
+    state: Arc<RwLock<RuntimeState>>,
+    receiver: mpsc::UnboundedReceiver<StatusRefresh>,
+) {
+    let (_, radio_receiver) = mpsc::unbounded_channel();
+    spawn_monitor(state, receiver, true, radio_receiver);
+}
+
+fn spawn_monitor(
+    state: Arc<RwLock<RuntimeState>>,
+    mut receiver: mpsc::UnboundedReceiver<StatusRefresh>,
+    mut uevents_open: bool,
+    mut radio_receiver: mpsc::UnboundedReceiver<StatusRefresh>,
+) {
+    let mut radios_open = true;
+
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        let mut debouncer =
+            AttachDebouncer::new(AttachState::of(&state.read().await.status), SystemClock);
+        let mut interval = tokio::time::interval(if uevents_open {
+            SAFETY_NET_INTERVAL
+        } else {
+            POLL_INTERVAL
+        });
         loop {
-            interval.tick().await;
-
-            let mut next_status = crate::runtime::probe::current_status();
-            let session_connected = {
-                let guard = state.read().await;
-                guard.session_agent.connected
+            let settle_at = debouncer
+                .deadline(attach_settle_window(&state).await)
+                .map(tokio::time::Instant::from_std);
+            let refresh = tokio::select! {
+                _ = interval.tick() => StatusRefresh::ALL,
+                _ = sleep_until(settle_at.unwrap_or_else(tokio::time::Instant::now)),
+                    if settle_at.is_some() =>
+                {
+                    StatusRefresh {
+                        keyboard: true,
+                        ..StatusRefresh::default()
+                    }
+                }
+                received = receiver.recv(), if uevents_open => match received {
+                    Some(refresh) => settle_uevents(&mut receiver, refresh).await,
+                    None => {
+                        logger::warn(
+                            "rust-daemon",
+                            "hotplug listener exited, polling status every second",
+                        );
+                        uevents_open = false;
+                        interval = tokio::time::interval(POLL_INTERVAL);
+                        continue;
+                    }
+                },
+                received = radio_receiver.recv(), if radios_open => match received {
+                    Some(refresh) => refresh,
+                    None => {
+                        radios_open = false;
+                        continue;
+                    }
+                },
             };
-            next_status.service_active = session_connected;
 
-            if let Some(layout) =
-                crate::runtime::daemon::session_display_layout_for_liveness(state.clone()).await
+            refresh_status(&state, refresh, &mut debouncer).await;
+            reconcile_usb_media_remap(state.clone()).await;
+        }
+    });
+}
+
+async fn settle_uevents(
+    receiver: &mut mpsc::UnboundedReceiver<StatusRefresh>,
+    mut refresh: StatusRefresh,
+) -> StatusRefresh {
+    while let Ok(Some(next)) = tokio::time::timeout(UEVENT_SETTLE, receiver.recv()).await {
+        refresh = refresh.union(next);
+    }
+    refresh
+}
+
+async fn attach_settle_window(state: &Arc<RwLock<RuntimeState>>) -> Duration {
+    Duration::from_millis(state.read().await.settings.attach_settle_ms)
+}
+
+async fn refresh_status(
+    state: &Arc<RwLock<RuntimeState>>,
+    refresh: StatusRefresh,
+    debouncer: &mut AttachDebouncer<SystemClock>,
+) {
+    let (mut probed, session_connected, window, hardware) = {
+        let guard = state.read().await;
+        (
+            guard.status.clone(),
+            guard.session_agent.connected,
+            Duration::from_millis(guard.settings.attach_settle_ms),
+            guard.hardware.clone(),
+        )
+    };
+    debouncer.resync(AttachState::of(&probed));
+    hardware.refresh_status(&mut probed, refresh);
+    probed.service_active = session_connected;
+
+    // Policy only ever sees the settled keyboard state; raw flaps stay here.
+    let mut flapping = None;
+    if refresh.keyboard {
+        match debouncer.observe(AttachState::of(&probed), window) {
+            Debounced::Pending { .. } => logger::info(
+                "rust-daemon",
+                format!(
+                    "keyboard connection changed to {}, waiting {}ms for it to settle",
+                    connection_label(&probed.connection_type),
+                    window.as_millis()
+                ),
+            ),
+            Debounced::Settled { suppressed, .. } | Debounced::Reverted { suppressed }
+                if suppressed > 0 =>
             {
-                crate::runtime::probe::apply_layout_to_status(&mut next_status, Some(&layout));
-                next_status.service_active = true;
+                flapping = Some(suppressed);
             }
+            _ => {}
+        }
+        debouncer.stable().apply_to(&mut probed);
+    }
 
-            let mut guard = state.write().await;
-            let previous = guard.status.clone();
-
-            if previous != next_status {
-                let updated = next_status.clone();
-                let _ = logger::append_line(format!(
-                    "rust-daemon: status transition attached={} monitors={} wifi={} bluetooth={} connection={}",
-                    updated.keyboard_attached,
-                    updated.monitor_count,
-                    updated.wifi_enabled,
-                    updated.bluetooth_enabled,
-                    connection_label(&updated.connection_type),
-                ));
-                guard.status = next_status;
-                let actions =
-                    crate::runtime::policy::apply_transition_policy(&mut guard, &previous);
-                push_status_events(&mut guard, &previous, &updated);
-                guard.touch();
-                if let Err(err) = guard.save() {
-                    log::warn!("failed to save monitored runtime state: {err}");
-                }
-                drop(guard);
-                apply_policy_actions(state.clone(), actions).await;
-            } else {
-                drop(guard);
+    if refresh.displays {
+        match crate::runtime::daemon::session_display_layout_for_liveness(state.clone()).await {
+            Some(layout) => {
+                crate::runtime::probe::apply_layout_to_status(&mut probed, Some(&layout));
+                probed.service_active = true;
             }
+            None => crate::runtime::probe::apply_layout_to_status(
+                &mut probed,
+                hardware.display_layout().ok().as_ref(),
+            ),
+        }
+    }
 
-            reconcile_usb_media_remap(state.clone()).await;
+    let mut guard = state.write().await;
+    if let Some(suppressed) = flapping {
+        logger::info(
+            "rust-daemon",
+            format!("suppressed {suppressed} keyboard attach/detach transitions while settling"),
+        );
+        guard.push_recent_event(HardwareEvent::warning(
+            EventCategory::Keyboard,
+            format!("Ignored {suppressed} keyboard attach/detach transitions while the connection settled"),
+            "rust-daemon",
+        ));
+        guard.touch();
+    }
+    let previous = guard.status.clone();
+    let next_status = merge_refreshed(&previous, &probed, refresh);
+
+    if previous != next_status && battery_only_change(&previous, &next_status) {
+        // Battery reports move no posture or policy, so they skip the
+        // transition: just the new level and any low battery warning.
+        guard.status = next_status;
+        let updated = guard.status.clone();
+        let low_battery = record_low_battery(&mut guard, &previous, &updated);
+        guard.touch();
+        guard.persist();
+        drop(guard);
+        if let Some(percent) = low_battery {
+            crate::runtime::daemon::NotificationSink::keyboard_battery_low(state, percent).await;
         }
-    });
+    } else if previous != next_status {
+        let updated = next_status.clone();
+        logger::info(
+            "rust-daemon",
+            format!(
+                "status transition attached={} monitors={} wifi={} bluetooth={} connection={}",
+                updated.keyboard_attached,
+                updated.monitor_count,
+                updated.wifi_enabled,
+                updated.bluetooth_enabled,
+                connection_label(&updated.connection_type),
+            ),
+        );
+        guard.status = next_status;
+        let actions = crate::runtime::policy::apply_transition_policy(&mut guard, &previous);
+        push_status_events(&mut guard, &previous, &updated);
+        let low_battery = record_low_battery(&mut guard, &previous, &updated);
+        // Keyboard moves keep the lid axis, so they never carry display
+        // actions; the dock replay comes from the transition policy.
+        guard.sync_posture(PostureTrigger::Keyboard);
+        guard.touch();
+        guard.persist();
+        drop(guard);
+        if let Some(percent) = low_battery {
+            crate::runtime::daemon::NotificationSink::keyboard_battery_low(state, percent).await;
+        }
+        apply_policy_actions(state.clone(), actions).await;
+        if keyboard_reconnected(&previous, &updated) {
+            reconcile_backlight_level(state).await;
+        }
+
+        let attached = crate::runtime::policy::display_mode_attached(&updated);
+        if attached != crate::runtime::policy::display_mode_attached(&previous) {
+            let event = if attached {
+                HookEvent::Attach
+            } else {
+                HookEvent::Detach
+            };
+            crate::runtime::daemon::run_transition_hooks(state, event).await;
+        }
+    }
+}
+
+/// Copies the refreshed fields from `probed` onto the live status, so writes
+/// that landed while the probe ran (a backlight change, say) are kept.
+fn merge_refreshed(current: &DuoStatus, probed: &DuoStatus, refresh: StatusRefresh) -> DuoStatus {
+    let mut next = current.clone();
+    next.service_active = probed.service_active;
+    if refresh.keyboard {
+        next.keyboard_attached = probed.keyboard_attached;
+        next.connection_type = probed.connection_type.clone();
+    }
+    if refresh.radios {
+        next.wifi_enabled = probed.wifi_enabled;
+        next.bluetooth_enabled = probed.bluetooth_enabled;
+    }
+    if refresh.displays {
+        next.monitor_count = probed.monitor_count;
+        next.orientation = probed.orientation.clone();
+    }
+    if refresh.brightness {
+        next.display_brightness = probed.display_brightness;
+        next.max_brightness = probed.max_brightness;
+    }
+    if refresh.battery {
+        next.keyboard_battery_percent = probed.keyboard_battery_percent;
+        next.keyboard_battery_charging = probed.keyboard_battery_charging;
+    }
+    next
 }
 
 pub(crate) fn queue_usb_media_remap_resume_retry(state: Arc<RwLock<RuntimeState>>) {
@@ -78,19 +298,104 @@ pub(crate) async fn reset_usb_media_remap_retry_cooldown(state: &Arc<RwLock<Runt
     let mut guard = state.write().await;
     clear_usb_media_remap_retry_cooldown(&mut guard);
     guard.touch();
-    if let Err(err) = guard.save() {
-        log::warn!("failed to save usb media remap retry reset: {err}");
+    guard.persist();
+}
+
+/// Whether `next` differs from `previous` in the keyboard battery alone.
+fn battery_only_change(previous: &DuoStatus, next: &DuoStatus) -> bool {
+    let mut unchanged = next.clone();
+    unchanged.keyboard_battery_percent = previous.keyboard_battery_percent;
+    unchanged.keyboard_battery_charging = previous.keyboard_battery_charging;
+    unchanged == *previous
+}
+
+/// Records a low battery warning when `updated` crossed a threshold, and
+/// returns the level to notify about.
+fn record_low_battery(
+    state: &mut RuntimeState,
+    previous: &DuoStatus,
+    updated: &DuoStatus,
+) -> Option<u8> {
+    let percent = keyboard_battery_threshold_crossed(
+        &state.settings.keyboard_battery_thresholds,
+        previous,
+        updated,
+    )?;
+    state.push_recent_event(HardwareEvent::warning(
+        EventCategory::Keyboard,
+        format!("Keyboard battery low ({percent}%)"),
+        "rust-daemon",
+    ));
+    Some(percent)
+}
+
+/// The battery level to warn about when `next` has just drained to or below
+/// one of `thresholds`. A keyboard that reconnects already low warns again;
+/// one that is charging does not.
+fn keyboard_battery_threshold_crossed(
+    thresholds: &[u8],
+    previous: &DuoStatus,
+    next: &DuoStatus,
+) -> Option<u8> {
+    let percent = next.keyboard_battery_percent?;
+    if next.keyboard_battery_charging == Some(true) {
+        return None;
+    }
+    let crossed = thresholds.iter().any(|threshold| {
+        percent <= *threshold
+            && previous
+                .keyboard_battery_percent
+                .is_none_or(|before| before > *threshold)
+    });
+    crossed.then_some(percent)
+}
+
+/// A keyboard appeared or switched transports, and may have reset its
+/// backlight on the way.
+fn keyboard_reconnected(previous: &DuoStatus, next: &DuoStatus) -> bool {
+    next.keyboard_attached
+        && (!previous.keyboard_attached || previous.connection_type != next.connection_type)
+}
+
+/// Replaces the cached backlight level with the one the keyboard reports.
+/// The cached level stays when the keyboard cannot be asked.
+pub(crate) async fn reconcile_backlight_level(state: &Arc<RwLock<RuntimeState>>) {
+    let hardware = state.read().await.hardware.clone();
+    let reported = hardware.read_backlight();
+
+    let mut guard = state.write().await;
+    let cached = guard.status.backlight_level;
+    match reported {
+        Ok(level) if level != cached => {
+            guard.status.backlight_level = level;
+            guard.push_recent_event(HardwareEvent::info(
+                EventCategory::Keyboard,
+                format!("Keyboard reports backlight level {level} (recorded {cached})"),
+                "rust-daemon",
+            ));
+            guard.touch();
+            guard.persist();
+            logger::info(
+                "rust-daemon",
+                format!("backlight level reconciled from keyboard: {cached} -> {level}"),
+            );
+        }
+        Ok(_) => {}
+        Err(err) => logger::debug(
+            "rust-daemon",
+            format!("keeping cached backlight level {cached}: {err}"),
+        ),
     }
 }
 
 pub(crate) async fn reconcile_usb_media_remap(state: Arc<RwLock<RuntimeState>>) {
     const AUTO_START_RETRY_COOLDOWN_SECS: i64 = 15;
 
-    let (should_run, is_running) = {
+    let (should_run, is_running, hardware) = {
         let guard = state.read().await;
         let should_run = usb_media_remap_should_run(&guard);
-        let is_running = crate::commands::usb_media_remap::get_status().running;
-        (should_run, is_running)
+        let is_running = guard.hardware.usb_media_remap_running();
+        (should_run, is_running, guard.hardware.clone())
     };
 
     if should_run == is_running {
@@ -115,10 +420,13 @@ pub(crate) async fn reconcile_usb_media_remap(state: Arc<RwLock<RuntimeState>>)
                         .unwrap_or(true)
                     {
                         guard.usb_media_remap_reconcile.last_backoff_log_at = Some(now);
-                        let _ = logger::append_line(format!(
-                            "rust-daemon: usb media remap auto-start backing off for {}s after repeated failures",
-                            AUTO_START_RETRY_COOLDOWN_SECS
-                        ));
+                        logger::warn(
+                            "rust-daemon",
+                            format!(
+                                "usb media remap auto-start backing off for {}s after repeated failures",
+                                AUTO_START_RETRY_COOLDOWN_SECS
+                            ),
+                        );
                     }
                     return;
                 }
@@ -127,7 +435,7 @@ pub(crate) async fn reconcile_usb_media_remap(state: Arc<RwLock<RuntimeState>>)
             guard.usb_media_remap_reconcile.last_backoff_log_at = None;
         }
 
-        match crate::commands::usb_media_remap::start_remap() {
+        match hardware.start_usb_media_remap() {
             Ok(()) => {
                 let mut should_log = false;
                 {
@@ -146,8 +454,7 @@ pub(crate) async fn reconcile_usb_media_remap(state: Arc<RwLock<RuntimeState>>)
                     }
                 }
                 if should_log {
-                    let _ =
-                        logger::append_line("rust-daemon: reconciled usb media remap -> started");
+                    logger::info("rust-daemon", "reconciled usb media remap -> started");
                 }
             }
             Err(err) => {
@@ -158,13 +465,13 @@ pub(crate) async fn reconcile_usb_media_remap(state: Arc<RwLock<RuntimeState>>)
                     &format!("USB media remap auto-start failed: {err}"),
                 )
                 .await;
-                let _ = logger::append_line(format!(
-                    "rust-daemon: usb media remap auto-start failed: {}",
-                    err
-                ));
+                logger::warn(
+                    "rust-daemon",
+                    format!("usb media remap auto-start failed: {}", err),
+                );
             }
         }
-    } else if let Err(err) = crate::commands::usb_media_remap::stop_remap() {
+    } else if let Err(err) = hardware.stop_usb_media_remap() {
         log::warn!("failed to auto-stop usb media remap: {err}");
         crate::runtime::daemon::notify_runtime_error(
             &state,
@@ -172,22 +479,24 @@ pub(crate) async fn reconcile_usb_media_remap(state: Arc<RwLock<RuntimeState>>)
             &format!("USB media remap auto-stop failed: {err}"),
         )
         .await;
-        let _ = logger::append_line(format!(
-            "rust-daemon: usb media remap auto-stop failed: {}",
-            err
-        ));
+        logger::warn(
+            "rust-daemon",
+            format!("usb media remap auto-stop failed: {}", err),
+        );
     } else {
         let mut guard = state.write().await;
         clear_usb_media_remap_retry_cooldown(&mut guard);
         drop(guard);
-        let _ = logger::append_line("rust-daemon: reconciled usb media remap -> stopped");
+        logger::info("rust-daemon", "reconciled usb media remap -> stopped");
     }
 }
 
 fn usb_media_remap_should_run(state: &RuntimeState) -> bool {
     state.status.keyboard_attached
         && matches!(state.status.connection_type, ConnectionType::Usb)
-        && state.settings.usb_media_remap_enabled
+        && state
+            .usb_media_remap_policy
+            .unwrap_or(state.settings.usb_media_remap_enabled)
 }
 
 fn clear_usb_media_remap_retry_cooldown(state: &mut RuntimeState) {
@@ -195,11 +504,18 @@ fn clear_usb_media_remap_retry_cooldown(state: &mut RuntimeState) {
     state.usb_media_remap_reconcile.last_backoff_log_at = None;
 }
 
-async fn apply_policy_actions(state: Arc<RwLock<RuntimeState>>, actions: Vec<PolicyAction>) {
+pub(crate) async fn apply_policy_actions(
+    state: Arc<RwLock<RuntimeState>>,
+    actions: Vec<PolicyAction>,
+) {
+    let hardware = state.read().await.hardware.clone();
     for action in actions {
+        hardware.record(TranscriptEntry::PolicyAction {
+            action: action.clone(),
+        });
         match action {
             PolicyAction::SetWifi(enabled) => {
-                if let Err(err) = crate::runtime::policy::set_wifi_enabled(enabled) {
+                if let Err(err) = hardware.set_radio(Radio::Wifi, enabled) {
                     log::warn!("failed to set wifi policy action: {err}");
                     crate::runtime::daemon::notify_runtime_error(
                         &state,
@@ -207,19 +523,19 @@ async fn apply_policy_actions(state: Arc<RwLock<RuntimeState>>, actions: Vec<Pol
                         &format!("Wi-Fi policy action failed: {err}"),
                     )
                     .await;
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: wifi policy action failed (enabled={}): {}",
-                        enabled, err
-                    ));
+                    logger::warn(
+                        "rust-daemon",
+                        format!("wifi policy action failed (enabled={}): {}", enabled, err),
+                    );
                 } else {
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: applied wifi policy action -> {}",
-                        enabled
-                    ));
+                    logger::info(
+                        "rust-daemon",
+                        format!("applied wifi policy action -> {}", enabled),
+                    );
                 }
             }
             PolicyAction::SetBluetooth(enabled) => {
-                if let Err(err) = crate::runtime::policy::set_bluetooth_enabled(enabled) {
+                if let Err(err) = hardware.set_radio(Radio::Bluetooth, enabled) {
                     log::warn!("failed to set bluetooth policy action: {err}");
                     crate::runtime::daemon::notify_runtime_error(
                         &state,
@@ -227,19 +543,22 @@ async fn apply_policy_actions(state: Arc<RwLock<RuntimeState>>, actions: Vec<Pol
                         &format!("Bluetooth policy action failed: {err}"),
                     )
                     .await;
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: bluetooth policy action failed (enabled={}): {}",
-                        enabled, err
-                    ));
+                    logger::warn(
+                        "rust-daemon",
+                        format!(
+                            "bluetooth policy action failed (enabled={}): {}",
+                            enabled, err
+                        ),
+                    );
                 } else {
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: applied bluetooth policy action -> {}",
-                        enabled
-                    ));
+                    logger::info(
+                        "rust-daemon",
+                        format!("applied bluetooth policy action -> {}", enabled),
+                    );
                 }
             }
             PolicyAction::SetBacklight(level) => {
-                if let Err(err) = crate::hardware::hid::set_backlight(level) {
+                if let Err(err) = hardware.set_backlight(level) {
                     log::warn!("failed to set backlight policy action: {err}");
                     crate::runtime::daemon::notify_runtime_error(
                         &state,
@@ -247,10 +566,10 @@ async fn apply_policy_actions(state: Arc<RwLock<RuntimeState>>, actions: Vec<Pol
                         &format!("Backlight policy action failed: {err}"),
                     )
                     .await;
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: backlight policy action failed (level={}): {}",
-                        level, err
-                    ));
+                    logger::warn(
+                        "rust-daemon",
+                        format!("backlight policy action failed (level={}): {}", level, err),
+                    );
                 } else {
                     {
                         let mut guard = state.write().await;
@@ -261,14 +580,12 @@ async fn apply_policy_actions(state: Arc<RwLock<RuntimeState>>, actions: Vec<Pol
                             "rust-daemon",
                         ));
                         guard.touch();
-                        if let Err(err) = guard.save() {
-                            log::warn!("failed to save backlight policy state: {err}");
-                        }
+                        guard.persist();
                     }
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: applied backlight policy action -> {}",
-                        level
-                    ));
+                    logger::info(
+                        "rust-daemon",
+                        format!("applied backlight policy action -> {}", level),
+                    );
                 }
             }
             PolicyAction::ApplyDisplayMode { attached, scale } => {
@@ -283,19 +600,88 @@ async fn apply_policy_actions(state: Arc<RwLock<RuntimeState>>, actions: Vec<Pol
                         &format!("Display-mode policy action failed: {err}"),
                     )
                     .await;
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: display-mode policy action failed (attached={}, scale={}): {}",
-                        attached, scale, err
-                    ));
+                    logger::warn(
+                        "rust-daemon",
+                        format!(
+                            "display-mode policy action failed (attached={}, scale={}): {}",
+                            attached, scale, err
+                        ),
+                    );
                 } else {
-                    let _ = logger::append_line(format!(
-                        "rust-daemon: applied display-mode policy action (attached={}, scale={})",
-                        attached, scale
-                    ));
+                    logger::info(
+                        "rust-daemon",
+                        format!(
+                            "applied display-mode policy action (attached={}, scale={})",
+                            attached, scale
+                        ),
+                    );
                 }
             }
+            PolicyAction::ActivateProfile(id) => {
+                if let Err(err) = activate_profile(&state, &id).await {
+                    log::warn!("failed to apply profile policy action: {err}");
+                    crate::runtime::daemon::notify_runtime_error(
+                        &state,
+                        "Zenbook Duo Runtime Error",
+                        &format!("Profile policy action failed: {err}"),
+                    )
+                    .await;
+                    logger::warn(
+                        "rust-daemon",
+                        format!("profile policy action failed (id={}): {}", id, err),
+                    );
+                } else {
+                    logger::info(
+                        "rust-daemon",
+                        format!("applied profile policy action -> {}", id),
+                    );
+                }
+            }
+            PolicyAction::SetUsbMediaRemap(enabled) => {
+                {
+                    let mut guard = state.write().await;
+                    guard.usb_media_remap_policy = Some(enabled);
+                    guard.touch();
+                    guard.persist();
+                }
+                logger::info(
+                    "rust-daemon",
+                    format!("applied usb media remap policy action -> {}", enabled),
+                );
+                reconcile_usb_media_remap(state.clone()).await;
+            }
+        }
+    }
+}
+
+/// Applies a saved profile through the same handlers the UI's
+/// `activate_profile` reaches over IPC. Those handlers can themselves run
+/// policy actions, so the dispatch is boxed.
+async fn activate_profile(state: &Arc<RwLock<RuntimeState>>, id: &str) -> Result<(), String> {
+    let profile = crate::commands::profiles::find_profile(id)?;
+    let mut requests = vec![
+        DaemonRequest::SetBacklight {
+            level: profile.backlight_level,
+        },
+        DaemonRequest::SetOrientation {
+            orientation: profile.orientation,
+        },
+    ];
+    if let Some(layout) = profile.display_layout {
+        requests.push(DaemonRequest::ApplyDisplayLayout { layout });
+    }
+
+    for request in requests {
+        let kind = request.kind();
+        if let DaemonResponse::Error { message } = Box::pin(
+            crate::runtime::daemon::dispatch_request(request, state.clone()),
+        )
+        .await
+        {
+            return Err(format!("{kind} failed: {message}"));
         }
     }
+    Ok(())
 }
 
 fn push_status_events(
@@ -399,6 +785,8 @@ fn orientation_label(orientation: &crate::models::Orientation) -> &'static str {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::runtime::backend::HardwareBackend;
+    use crate::runtime::simulation::{SimHardware, SimMachine};
 
     #[test]
     fn usb_media_remap_only_runs_for_enabled_usb_keyboard() {
@@ -420,6 +808,152 @@ mod tests {
         assert!(!usb_media_remap_should_run(&state));
     }
 
+    #[test]
+    fn merge_refreshed_only_takes_requested_fields() {
+        let current = DuoStatus {
+            keyboard_attached: true,
+            connection_type: ConnectionType::Usb,
+            monitor_count: 2,
+            wifi_enabled: true,
+            backlight_level: 3,
+            ..DuoStatus::default()
+        };
+        let probed = DuoStatus {
+            keyboard_attached: false,
+            connection_type: ConnectionType::None,
+            monitor_count: 1,
+            wifi_enabled: false,
+            backlight_level: 0,
+            service_active: true,
+            ..DuoStatus::default()
+        };
+
+        let next = merge_refreshed(
+            &current,
+            &probed,
+            StatusRefresh {
+                keyboard: true,
+                ..StatusRefresh::default()
+            },
+        );
+
+        assert!(!next.keyboard_attached);
+        assert_eq!(next.connection_type, ConnectionType::None);
+        assert!(next.service_active);
+        assert_eq!(next.monitor_count, 2);
+        assert!(next.wifi_enabled);
+        assert_eq!(next.backlight_level, 3);
+    }
+
+    #[tokio::test]
+    async fn backlight_level_follows_the_keyboard_when_it_can_report() {
+        let hardware = Arc::new(SimHardware::new(&SimMachine::default()));
+        hardware.set_backlight(1).expect("backlight");
+        let state = Arc::new(RwLock::new(RuntimeState {
+            hardware: HardwareBackend::simulated(hardware.clone()),
+            ..RuntimeState::default()
+        }));
+        state.write().await.status.backlight_level = 3;
+
+        reconcile_backlight_level(&state).await;
+        assert_eq!(state.read().await.status.backlight_level, 1);
+
+        hardware.detach_keyboard(false);
+        state.write().await.status.backlight_level = 2;
+        reconcile_backlight_level(&state).await;
+        assert_eq!(state.read().await.status.backlight_level, 2);
+    }
+
+    #[tokio::test]
+    async fn keyboard_refresh_after_resume_keeps_the_resumed_connection() {
+        let hardware = Arc::new(SimHardware::new(&SimMachine::default()));
+        let state = Arc::new(RwLock::new(RuntimeState {
+            status: hardware.status(),
+            hardware: HardwareBackend::simulated(hardware.clone()),
+            ..RuntimeState::default()
+        }));
+        let mut debouncer =
+            AttachDebouncer::new(AttachState::of(&state.read().await.status), SystemClock);
+
+        // The keyboard left the dock while suspended; resume re-probes.
+        hardware.detach_keyboard(true);
+        state.write().await.status = hardware.status();
+
+        let keyboard = StatusRefresh {
+            keyboard: true,
+            ..StatusRefresh::default()
+        };
+        refresh_status(&state, keyboard, &mut debouncer).await;
+
+        let guard = state.read().await;
+        assert_eq!(guard.status.connection_type, ConnectionType::Bluetooth);
+        assert!(guard.recent_events.is_empty());
+    }
+
+    #[test]
+    fn keyboard_battery_warns_once_per_threshold_while_draining() {
+        let draining = |percent| DuoStatus {
+            keyboard_battery_percent: percent,
+            keyboard_battery_charging: Some(false),
+            ..DuoStatus::default()
+        };
+        let charging = DuoStatus {
+            keyboard_battery_charging: Some(true),
+            ..draining(Some(15))
+        };
+        let crossed = |before: &DuoStatus, after: &DuoStatus| {
+            keyboard_battery_threshold_crossed(&[20, 10], before, after)
+        };
+
+        assert_eq!(crossed(&draining(Some(21)), &draining(Some(20))), Some(20));
+        assert_eq!(crossed(&draining(Some(20)), &draining(Some(19))), None);
+        assert_eq!(crossed(&draining(Some(12)), &draining(Some(9))), Some(9));
+        assert_eq!(crossed(&draining(None), &draining(Some(15))), Some(15));
+        assert_eq!(crossed(&draining(None), &charging), None);
+        assert_eq!(crossed(&draining(Some(15)), &draining(None)), None);
+        assert_eq!(crossed(&draining(None), &draining(Some(60))), None);
+    }
+
+    #[test]
+    fn battery_reports_alone_are_told_apart_from_transitions() {
+        let bluetooth = DuoStatus {
+            keyboard_attached: false,
+            connection_type: ConnectionType::Bluetooth,
+            keyboard_battery_percent: Some(40),
+            ..DuoStatus::default()
+        };
+        let drained = DuoStatus {
+            keyboard_battery_percent: Some(39),
+            keyboard_battery_charging: Some(false),
+            ..bluetooth.clone()
+        };
+        let docked = DuoStatus {
+            keyboard_attached: true,
+            connection_type: ConnectionType::Usb,
+            ..drained.clone()
+        };
+
+        assert!(battery_only_change(&bluetooth, &drained));
+        assert!(!battery_only_change(&drained, &docked));
+    }
+
+    #[test]
+    fn reconnects_are_new_keyboards_or_transport_switches() {
+        let status = |attached, connection_type| DuoStatus {
+            keyboard_attached: attached,
+            connection_type,
+            ..DuoStatus::default()
+        };
+        let usb = status(true, ConnectionType::Usb);
+        let bluetooth = status(true, ConnectionType::Bluetooth);
+        let detached = status(false, ConnectionType::None);
+
+        assert!(keyboard_reconnected(&detached, &usb));
+        assert!(keyboard_reconnected(&usb, &bluetooth));
+        assert!(!keyboard_reconnected(&usb, &usb));
+        assert!(!keyboard_reconnected(&usb, &detached));
+    }
+
     #[test]
     fn clearing_retry_cooldown_preserves_last_start_log() {
         let mut state = RuntimeState::default();
@@ -1,14 +1,28 @@
 use nix::unistd::Uid;
 use std::path::PathBuf;
+use std::sync::OnceLock;
 
 pub const APP_DIR_NAME: &str = "zenbook-duo";
 pub const DAEMON_SOCKET_NAME: &str = "daemon.sock";
 pub const SESSION_SOCKET_NAME: &str = "session-agent.sock";
 pub const STATE_FILE_NAME: &str = "state.json";
 pub const LOG_FILE_NAME: &str = "daemon.log";
+pub const EVENT_JOURNAL_FILE_NAME: &str = "events.jsonl";
+
+static SYSTEM_RUNTIME_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Moves the state file, log and event journal under `dir` for the rest of
+/// the process. `--simulate` uses it so a run never writes over the
+/// installed daemon's files. Only the first call takes effect.
+pub fn set_system_runtime_dir(dir: PathBuf) {
+    let _ = SYSTEM_RUNTIME_DIR.set(dir);
+}
 
 pub fn system_runtime_dir() -> PathBuf {
-    PathBuf::from("/var/lib").join(APP_DIR_NAME)
+    SYSTEM_RUNTIME_DIR
+        .get()
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from("/var/lib").join(APP_DIR_NAME))
 }
 
 pub fn daemon_socket_path() -> PathBuf {
@@ -23,6 +37,34 @@ pub fn log_file_path() -> PathBuf {
     system_runtime_dir().join(LOG_FILE_NAME)
 }
 
+pub fn event_journal_path() -> PathBuf {
+    system_runtime_dir().join(EVENT_JOURNAL_FILE_NAME)
+}
+
+/// The desktop user's uid that the service unit hands the daemon in
+/// `ZENBOOK_DUO_UID`. The socket owner and peer authorization both read it
+/// here so they cannot disagree.
+pub fn configured_session_uid() -> Option<u32> {
+    env_id("ZENBOOK_DUO_UID")
+}
+
+/// The desktop user's gid from `ZENBOOK_DUO_GID`.
+pub fn configured_session_gid() -> Option<u32> {
+    env_id("ZENBOOK_DUO_GID")
+}
+
+/// The user this process acts for: the configured session user, or whoever
+/// runs it.
+pub fn session_owner_uid() -> u32 {
+    configured_session_uid().unwrap_or_else(|| Uid::current().as_raw())
+}
+
+fn env_id(name: &str) -> Option<u32> {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse::<u32>().ok())
+}
+
 pub fn user_runtime_dir(uid: u32) -> PathBuf {
     PathBuf::from(format!("/run/user/{uid}/{APP_DIR_NAME}"))
 }
@@ -0,0 +1,288 @@
+use std::fmt;
+use std::fs;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::Path;
+
+use nix::unistd::Uid;
+use tokio::net::UnixStream;
+
+use crate::ipc::protocol::DaemonRequest;
+
+/// Identity of the process on the other end of a daemon connection, as
+/// reported by `SO_PEERCRED` when the connection was accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerCredentials {
+    pub uid: u32,
+    pub gid: u32,
+    pub pid: Option<i32>,
+}
+
+impl PeerCredentials {
+    pub fn of(stream: &UnixStream) -> Option<Self> {
+        stream.peer_cred().ok().map(|cred| Self {
+            uid: cred.uid(),
+            gid: cred.gid(),
+            pid: cred.pid(),
+        })
+    }
+}
+
+impl fmt::Display for PeerCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "uid={} gid={}", self.uid, self.gid)?;
+        match self.pid {
+            Some(pid) => write!(f, " pid={pid}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// How much a request can change. Tiers are ordered, so a caller granted a
+/// tier may also issue every request below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AccessTier {
+    ReadOnly,
+    UserControl,
+    Privileged,
+}
+
+impl AccessTier {
+    pub fn label(self) -> &'static str {
+        match self {
+            AccessTier::ReadOnly => "read-only",
+            AccessTier::UserControl => "user-control",
+            AccessTier::Privileged => "privileged",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Authorization {
+    Allowed,
+    /// The session owner reaching for a privileged request. It still runs,
+    /// since the desktop UI offers these actions, but leaves an audit line.
+    Audited,
+    Denied(String),
+}
+
+pub fn required_tier(request: &DaemonRequest) -> AccessTier {
+    match request {
+        DaemonRequest::Ping
+        | DaemonRequest::Hello { .. }
+        | DaemonRequest::GetStatus
+        | DaemonRequest::GetVersion
+        | DaemonRequest::GetDisplayLayout
+        | DaemonRequest::GetSettings
+        | DaemonRequest::UsbMediaRemapStatus
+        | DaemonRequest::TailLogs { .. }
+        | DaemonRequest::GetRecentEvents { .. }
+        | DaemonRequest::QueryEvents { .. }
+        | DaemonRequest::QueryLogs { .. }
+        | DaemonRequest::GetMetrics
+        | DaemonRequest::GetPosture
+        | DaemonRequest::ListTouchscreens
+        | DaemonRequest::Subscribe { .. } => AccessTier::ReadOnly,
+        DaemonRequest::SaveSettings { .. }
+        | DaemonRequest::SetBacklight { .. }
+        | DaemonRequest::SetOrientation { .. }
+        | DaemonRequest::ApplyDisplayLayout { .. }
+        | DaemonRequest::UsbMediaRemapStart
+        | DaemonRequest::UsbMediaRemapStop
+        | DaemonRequest::UsbMediaRemapTogglePause
+        | DaemonRequest::UsbMediaRemapSetPaused { .. }
+        | DaemonRequest::RegisterSessionAgent { .. }
+        | DaemonRequest::KeyboardActivity
+        | DaemonRequest::ManualBrightness
+        | DaemonRequest::AppendLog { .. } => AccessTier::UserControl,
+        DaemonRequest::HandleLifecycle { .. }
+        | DaemonRequest::RestartService
+        | DaemonRequest::ClearLogs
+        | DaemonRequest::SetTouchscreenEnabled { .. } => AccessTier::Privileged,
+    }
+}
+
+/// Root and the daemon's own user are trusted fully, the desktop user the
+/// daemon serves may control the hardware, and anyone else who can reach the
+/// socket through its group may only look.
+pub fn caller_tier(peer: Option<&PeerCredentials>, owner_uid: u32) -> AccessTier {
+    match peer {
+        Some(peer) if peer.uid == 0 || peer.uid == Uid::effective().as_raw() => {
+            AccessTier::Privileged
+        }
+        Some(peer) if peer.uid == owner_uid => AccessTier::UserControl,
+        _ => AccessTier::ReadOnly,
+    }
+}
+
+pub fn authorize(
+    peer: Option<&PeerCredentials>,
+    request: &DaemonRequest,
+    owner_uid: u32,
+) -> Authorization {
+    if let DaemonRequest::RegisterSessionAgent { socket_path, .. } = request {
+        if let Err(message) = check_session_socket_owner(peer, Path::new(socket_path)) {
+            return Authorization::Denied(message);
+        }
+    }
+
+    let required = required_tier(request);
+    let granted = caller_tier(peer, owner_uid);
+    if granted >= required {
+        Authorization::Allowed
+    } else if granted == AccessTier::UserControl {
+        Authorization::Audited
+    } else {
+        Authorization::Denied(format!(
+            "Permission denied: {} requires {} access",
+            request.kind(),
+            required.label()
+        ))
+    }
+}
+
+/// The daemon connects back to whatever socket a session agent registers, so
+/// only the user owning that socket may point the daemon at it.
+fn check_session_socket_owner(peer: Option<&PeerCredentials>, path: &Path) -> Result<(), String> {
+    let peer = peer.ok_or_else(|| {
+        "Permission denied: session agent registration requires peer credentials".to_string()
+    })?;
+    let metadata = fs::symlink_metadata(path).map_err(|e| {
+        format!(
+            "Failed to inspect session agent socket {}: {e}",
+            path.display()
+        )
+    })?;
+    if !metadata.file_type().is_socket() {
+        return Err(format!(
+            "Permission denied: session agent path {} is not a socket",
+            path.display()
+        ));
+    }
+    if metadata.uid() != peer.uid {
+        return Err(format!(
+            "Permission denied: session agent socket {} is owned by uid {}, not uid {}",
+            path.display(),
+            metadata.uid(),
+            peer.uid
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use crate::ipc::protocol::SessionBackend;
+
+    const OWNER: u32 = 4242;
+
+    fn peer(uid: u32) -> PeerCredentials {
+        PeerCredentials {
+            uid,
+            gid: uid,
+            pid: Some(1),
+        }
+    }
+
+    fn unique_temp_path(label: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        std::env::temp_dir().join(format!("zenbook-duo-peer-auth-{label}-{nanos}"))
+    }
+
+    fn registration(socket_path: &Path) -> DaemonRequest {
+        DaemonRequest::RegisterSessionAgent {
+            session_id: "2".into(),
+            backend: SessionBackend::Gnome,
+            socket_path: socket_path.display().to_string(),
+        }
+    }
+
+    #[test]
+    fn owner_controls_hardware_and_privileged_calls_are_audited() {
+        let owner = peer(OWNER);
+
+        assert_eq!(
+            authorize(
+                Some(&owner),
+                &DaemonRequest::SetBacklight { level: 2 },
+                OWNER
+            ),
+            Authorization::Allowed
+        );
+        assert_eq!(
+            authorize(Some(&owner), &DaemonRequest::RestartService, OWNER),
+            Authorization::Audited
+        );
+    }
+
+    #[test]
+    fn other_socket_users_are_limited_to_read_only_requests() {
+        let stranger = peer(OWNER + 1);
+
+        assert_eq!(
+            authorize(Some(&stranger), &DaemonRequest::GetStatus, OWNER),
+            Authorization::Allowed
+        );
+        match authorize(Some(&stranger), &DaemonRequest::ClearLogs, OWNER) {
+            Authorization::Denied(message) => {
+                assert_eq!(
+                    message,
+                    "Permission denied: clear_logs requires privileged access"
+                )
+            }
+            other => panic!("unexpected authorization: {other:?}"),
+        }
+        assert!(matches!(
+            authorize(None, &DaemonRequest::SetBacklight { level: 1 }, OWNER),
+            Authorization::Denied(_)
+        ));
+    }
+
+    #[test]
+    fn root_is_privileged() {
+        assert_eq!(caller_tier(Some(&peer(0)), OWNER), AccessTier::Privileged);
+    }
+
+    #[test]
+    fn session_agent_registration_requires_socket_ownership() {
+        let socket_path = unique_temp_path("agent.sock");
+        let _listener = UnixListener::bind(&socket_path).expect("bind agent socket");
+        let current = Uid::current().as_raw();
+
+        assert_eq!(
+            authorize(Some(&peer(current)), &registration(&socket_path), current),
+            Authorization::Allowed
+        );
+        match authorize(
+            Some(&peer(current + 1)),
+            &registration(&socket_path),
+            current + 1,
+        ) {
+            Authorization::Denied(message) => assert!(message.contains("is owned by uid")),
+            other => panic!("unexpected authorization: {other:?}"),
+        }
+
+        let _ = fs::remove_file(&socket_path);
+    }
+
+    #[test]
+    fn session_agent_registration_rejects_non_sockets() {
+        let file_path = unique_temp_path("agent.txt");
+        fs::write(&file_path, b"not a socket").expect("write temp file");
+        let current = Uid::current().as_raw();
+
+        match authorize(Some(&peer(current)), &registration(&file_path), current) {
+            Authorization::Denied(message) => assert!(message.contains("is not a socket")),
+            other => panic!("unexpected authorization: {other:?}"),
+        }
+
+        let _ = fs::remove_file(&file_path);
+    }
+}
@@ -0,0 +1,228 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+
+use tokio::sync::{mpsc, oneshot, RwLock};
+use tokio::time::{sleep, Duration};
+
+use crate::runtime::state::RuntimeState;
+use crate::runtime::{logger, paths};
+
+/// How long dirty notifications are coalesced before the state hits disk.
+pub const WRITE_BEHIND_DELAY: Duration = Duration::from_millis(250);
+
+enum PersistCommand {
+    Dirty,
+    Flush(oneshot::Sender<()>),
+}
+
+/// Handle to the write-behind persistence task.
+///
+/// Like the event bus it travels with `RuntimeState`, so code holding the
+/// write lock only queues a notification; serialization and disk I/O happen
+/// on the persistence task after the lock is released. A default handle is
+/// not connected to any task, and the state falls back to saving in place.
+#[derive(Debug, Clone, Default)]
+pub struct Persister {
+    sender: Option<mpsc::UnboundedSender<PersistCommand>>,
+}
+
+impl Persister {
+    /// Queues a write. Returns false when no persistence task is running.
+    pub fn mark_dirty(&self) -> bool {
+        self.sender
+            .as_ref()
+            .is_some_and(|sender| sender.send(PersistCommand::Dirty).is_ok())
+    }
+
+    /// Writes any pending changes now and waits for the write to finish.
+    /// Used before suspend and shutdown, where a queued write could be lost.
+    pub async fn flush(&self) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        let (ack, done) = oneshot::channel();
+        if sender.send(PersistCommand::Flush(ack)).is_ok() {
+            let _ = done.await;
+        }
+    }
+}
+
+/// Starts the persistence task for the daemon's state file and attaches its
+/// handle to the state.
+pub async fn start(state: &Arc<RwLock<RuntimeState>>) {
+    start_at(state, paths::state_file_path(), WRITE_BEHIND_DELAY).await;
+}
+
+pub(crate) async fn start_at(state: &Arc<RwLock<RuntimeState>>, path: PathBuf, delay: Duration) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    state.write().await.persister = Persister {
+        sender: Some(sender),
+    };
+    tokio::spawn(run(Arc::downgrade(state), receiver, path, delay));
+}
+
+async fn run(
+    state: Weak<RwLock<RuntimeState>>,
+    mut receiver: mpsc::UnboundedReceiver<PersistCommand>,
+    path: PathBuf,
+    delay: Duration,
+) {
+    while let Some(command) = receiver.recv().await {
+        let mut waiters = Vec::new();
+        let dirty = match command {
+            PersistCommand::Flush(ack) => {
+                waiters.push(ack);
+                false
+            }
+            PersistCommand::Dirty => {
+                coalesce(&mut receiver, &mut waiters, delay).await;
+                true
+            }
+        };
+        // A flush may still find writes queued behind it.
+        let dirty = dirty || drain(&mut receiver, &mut waiters);
+
+        if dirty {
+            let Some(state) = state.upgrade() else {
+                return;
+            };
+            write_snapshot(&state, &path).await;
+        }
+        for ack in waiters {
+            let _ = ack.send(());
+        }
+    }
+}
+
+/// Absorbs further dirty notifications until the delay runs out or someone
+/// asks for a flush.
+async fn coalesce(
+    receiver: &mut mpsc::UnboundedReceiver<PersistCommand>,
+    waiters: &mut Vec<oneshot::Sender<()>>,
+    delay: Duration,
+) {
+    let deadline = sleep(delay);
+    tokio::pin!(deadline);
+    loop {
+        tokio::select! {
+            _ = &mut deadline => return,
+            command = receiver.recv() => match command {
+                Some(PersistCommand::Dirty) => {}
+                Some(PersistCommand::Flush(ack)) => {
+                    waiters.push(ack);
+                    return;
+                }
+                None => return,
+            },
+        }
+    }
+}
+
+fn drain(
+    receiver: &mut mpsc::UnboundedReceiver<PersistCommand>,
+    waiters: &mut Vec<oneshot::Sender<()>>,
+) -> bool {
+    let mut dirty = false;
+    while let Ok(command) = receiver.try_recv() {
+        match command {
+            PersistCommand::Dirty => dirty = true,
+            PersistCommand::Flush(ack) => waiters.push(ack),
+        }
+    }
+    dirty
+}
+
+async fn write_snapshot(state: &Arc<RwLock<RuntimeState>>, path: &Path) {
+    // Serializing only needs a read lock; the file write runs without any.
+    let json = state.read().await.to_json();
+    let result = match json {
+        Ok(json) => {
+            let path = path.to_path_buf();
+            tokio::task::spawn_blocking(move || RuntimeState::write_json(&path, &json))
+                .await
+                .unwrap_or_else(|e| Err(format!("Failed to join state writer: {e}")))
+        }
+        Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+        logger::warn("rust-daemon", format!("failed to persist state: {err}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::atomic_file;
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn unique_state_path(label: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        std::env::temp_dir()
+            .join(format!("zenbook-duo-persist-{label}-{nanos}"))
+            .join("state.json")
+    }
+
+    #[tokio::test]
+    async fn dirty_notifications_are_coalesced_into_one_write() {
+        let path = unique_state_path("coalesce");
+        let state = Arc::new(RwLock::new(RuntimeState::default()));
+        start_at(&state, path.clone(), Duration::from_millis(50)).await;
+
+        for level in 1..=3 {
+            let mut guard = state.write().await;
+            guard.status.backlight_level = level;
+            guard.persist();
+        }
+        assert!(!path.exists(), "writes wait for the coalescing delay");
+
+        sleep(Duration::from_millis(200)).await;
+        let saved: RuntimeState =
+            serde_json::from_str(&fs::read_to_string(&path).expect("read state"))
+                .expect("parse state");
+        assert_eq!(saved.status.backlight_level, 3);
+        assert!(
+            !atomic_file::backup_path(&path).exists(),
+            "a single write leaves no backup behind"
+        );
+
+        let _ = fs::remove_dir_all(path.parent().expect("state dir"));
+    }
+
+    #[tokio::test]
+    async fn flush_writes_pending_changes_immediately() {
+        let path = unique_state_path("flush");
+        let state = Arc::new(RwLock::new(RuntimeState::default()));
+        start_at(&state, path.clone(), Duration::from_secs(60)).await;
+
+        let persister = {
+            let mut guard = state.write().await;
+            guard.lid_closed = true;
+            guard.persist();
+            guard.persister.clone()
+        };
+        persister.flush().await;
+
+        let saved: RuntimeState =
+            serde_json::from_str(&fs::read_to_string(&path).expect("read state"))
+                .expect("parse state");
+        assert!(saved.lid_closed);
+
+        let _ = fs::remove_dir_all(path.parent().expect("state dir"));
+    }
+
+    #[tokio::test]
+    async fn flush_without_pending_changes_does_not_write() {
+        let path = unique_state_path("idle");
+        let state = Arc::new(RwLock::new(RuntimeState::default()));
+        start_at(&state, path.clone(), Duration::from_millis(10)).await;
+
+        let persister = state.read().await.persister.clone();
+        persister.flush().await;
+
+        assert!(!path.exists());
+    }
+}
@@ -1,20 +1,55 @@
-use crate::models::{ConnectionType, DuoStatus, EventCategory, HardwareEvent};
-use crate::runtime::host::{CommandRunner, ProcessCommandRunner};
+use serde::Serialize;
+
+use crate::hardware::sysfs;
+use crate::models::{
+    ConnectionType, DuoStatus, EventCategory, HardwareEvent, PolicyConditions, PolicyRuleAction,
+    PolicyTrigger, RadioSetting,
+};
+use crate::runtime::host::CommandRunner;
+use crate::runtime::logger;
+use crate::runtime::radio::{Radio, Radios};
 use crate::runtime::state::RuntimeState;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
 pub enum PolicyAction {
     SetWifi(bool),
     SetBluetooth(bool),
     SetBacklight(u8),
     ApplyDisplayMode { attached: bool, scale: f64 },
+    ActivateProfile(String),
+    SetUsbMediaRemap(bool),
+}
+
+/// Facts rule conditions can test that `RuntimeState` does not track.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PolicyContext {
+    pub on_ac_power: Option<bool>,
+    pub external_monitor: bool,
+}
+
+impl PolicyContext {
+    pub fn probe() -> Self {
+        Self {
+            on_ac_power: sysfs::on_ac_power(),
+            external_monitor: sysfs::external_display_connected(),
+        }
+    }
 }
 
 pub fn apply_transition_policy(
     state: &mut RuntimeState,
     previous: &DuoStatus,
 ) -> Vec<PolicyAction> {
-    let mut actions = Vec::new();
+    let context = state.hardware.policy_context();
+    apply_transition_policy_with(state, previous, &context)
+}
+
+pub fn apply_transition_policy_with(
+    state: &mut RuntimeState,
+    previous: &DuoStatus,
+    context: &PolicyContext,
+) -> Vec<PolicyAction> {
     let was_attached = display_mode_attached(previous);
     let is_attached = display_mode_attached(&state.status);
 
@@ -23,7 +58,7 @@ pub fn apply_transition_policy(
             state.remembered_wifi_enabled = Some(state.status.wifi_enabled);
             state.remembered_bluetooth_enabled = Some(state.status.bluetooth_enabled);
         }
-        return actions;
+        return Vec::new();
     }
 
     if !is_attached {
@@ -31,65 +66,146 @@ pub fn apply_transition_policy(
         state.remembered_bluetooth_enabled = Some(previous.bluetooth_enabled);
     }
 
-    if is_attached {
-        if let Some(wifi_enabled) = state.remembered_wifi_enabled {
-            actions.push(PolicyAction::SetWifi(wifi_enabled));
+    let trigger = if is_attached {
+        PolicyTrigger::Attach
+    } else {
+        PolicyTrigger::Detach
+    };
+    evaluate_rules(state, trigger, context)
+}
+
+/// Runs every rule in `settings.policyRules` that matches `trigger` and the
+/// current state, in order, and returns the actions they resolve to. The
+/// status is updated as radios are switched so later rules see the result.
+pub fn evaluate_rules(
+    state: &mut RuntimeState,
+    trigger: PolicyTrigger,
+    context: &PolicyContext,
+) -> Vec<PolicyAction> {
+    let rules: Vec<_> = state
+        .settings
+        .policy_rules
+        .iter()
+        .filter(|rule| rule.trigger == trigger)
+        .cloned()
+        .collect();
+
+    let mut actions = Vec::new();
+    for rule in rules {
+        if !conditions_match(&rule.when, state, context) {
+            continue;
+        }
+        logger::debug(
+            "rust-daemon",
+            format!(
+                "policy rule '{}' matched on {}",
+                rule.label(),
+                trigger.label()
+            ),
+        );
+        for action in &rule.actions {
+            if let Some(action) = resolve_action(state, trigger, action) {
+                actions.push(action);
+            }
+        }
+    }
+    actions
+}
+
+fn conditions_match(
+    conditions: &PolicyConditions,
+    state: &RuntimeState,
+    context: &PolicyContext,
+) -> bool {
+    fn holds(expected: Option<bool>, actual: Option<bool>) -> bool {
+        expected.is_none_or(|expected| actual == Some(expected))
+    }
+
+    conditions
+        .connection_type
+        .as_ref()
+        .is_none_or(|connection_type| *connection_type == state.status.connection_type)
+        && holds(conditions.on_ac_power, context.on_ac_power)
+        && holds(conditions.external_monitor, Some(context.external_monitor))
+        && holds(conditions.lid_closed, Some(state.lid_closed))
+        && holds(conditions.wifi_enabled, Some(state.status.wifi_enabled))
+        && holds(
+            conditions.bluetooth_enabled,
+            Some(state.status.bluetooth_enabled),
+        )
+}
+
+fn resolve_action(
+    state: &mut RuntimeState,
+    trigger: PolicyTrigger,
+    action: &PolicyRuleAction,
+) -> Option<PolicyAction> {
+    match action {
+        PolicyRuleAction::Wifi { state: setting } => {
+            let enabled = radio_target(*setting, state.remembered_wifi_enabled)?;
             state.push_recent_event(HardwareEvent::info(
                 EventCategory::Network,
-                if wifi_enabled {
-                    "Restoring Wi-Fi on attach"
-                } else {
-                    "Keeping Wi-Fi disabled on attach"
-                },
+                radio_message("Wi-Fi", *setting, enabled, trigger),
                 "rust-daemon",
             ));
-            state.status.wifi_enabled = wifi_enabled;
+            state.status.wifi_enabled = enabled;
+            Some(PolicyAction::SetWifi(enabled))
         }
-
-        if let Some(bluetooth_enabled) = state.remembered_bluetooth_enabled {
-            actions.push(PolicyAction::SetBluetooth(bluetooth_enabled));
+        PolicyRuleAction::Bluetooth { state: setting } => {
+            let enabled = radio_target(*setting, state.remembered_bluetooth_enabled)?;
             state.push_recent_event(HardwareEvent::info(
                 EventCategory::Bluetooth,
-                if bluetooth_enabled {
-                    "Restoring Bluetooth on attach"
-                } else {
-                    "Keeping Bluetooth disabled on attach"
-                },
+                radio_message("Bluetooth", *setting, enabled, trigger),
                 "rust-daemon",
             ));
-            state.status.bluetooth_enabled = bluetooth_enabled;
+            state.status.bluetooth_enabled = enabled;
+            Some(PolicyAction::SetBluetooth(enabled))
         }
-
-        actions.push(PolicyAction::SetBacklight(state.settings.default_backlight));
-        actions.push(PolicyAction::ApplyDisplayMode {
-            attached: true,
+        PolicyRuleAction::Backlight { level } => Some(PolicyAction::SetBacklight(
+            level.unwrap_or(state.settings.default_backlight).min(3),
+        )),
+        PolicyRuleAction::DisplayMode => Some(PolicyAction::ApplyDisplayMode {
+            attached: display_mode_attached(&state.status),
             scale: state.settings.default_scale,
-        });
-    } else {
-        actions.push(PolicyAction::SetBluetooth(true));
-        actions.push(PolicyAction::ApplyDisplayMode {
-            attached: false,
-            scale: state.settings.default_scale,
-        });
-        state.status.bluetooth_enabled = true;
-        state.push_recent_event(HardwareEvent::info(
-            EventCategory::Bluetooth,
-            "Enabled Bluetooth on detach",
-            "rust-daemon",
-        ));
+        }),
+        PolicyRuleAction::Profile { id } => Some(PolicyAction::ActivateProfile(id.clone())),
+        PolicyRuleAction::Remap { enabled } => Some(PolicyAction::SetUsbMediaRemap(*enabled)),
     }
+}
 
-    actions
+/// `Restore` has nothing to restore until the keyboard has been docked once.
+fn radio_target(setting: RadioSetting, remembered: Option<bool>) -> Option<bool> {
+    match setting {
+        RadioSetting::On => Some(true),
+        RadioSetting::Off => Some(false),
+        RadioSetting::Restore => remembered,
+    }
+}
+
+fn radio_message(
+    radio: &str,
+    setting: RadioSetting,
+    enabled: bool,
+    trigger: PolicyTrigger,
+) -> String {
+    let trigger = trigger.label();
+    match (setting, enabled) {
+        (RadioSetting::Restore, true) => format!("Restoring {radio} on {trigger}"),
+        (RadioSetting::Restore, false) => format!("Keeping {radio} disabled on {trigger}"),
+        (_, true) => format!("Enabled {radio} on {trigger}"),
+        (_, false) => format!("Disabled {radio} on {trigger}"),
+    }
 }
 
-fn display_mode_attached(status: &DuoStatus) -> bool {
+pub fn display_mode_attached(status: &DuoStatus) -> bool {
     matches!(status.connection_type, ConnectionType::Usb)
 }
 
 pub fn set_wifi_enabled(enabled: bool) -> Result<(), String> {
-    set_wifi_enabled_with(&ProcessCommandRunner, enabled)
+    Radios::system().set_enabled(Radio::Wifi, enabled)
 }
 
+/// `nmcli` fallback for systems where `/dev/rfkill` cannot be written.
 pub fn set_wifi_enabled_with(host: &impl CommandRunner, enabled: bool) -> Result<(), String> {
     let target = if enabled { "on" } else { "off" };
     let output = host.output("nmcli", &["radio", "wifi", target])?;
@@ -101,9 +217,10 @@ pub fn set_wifi_enabled_with(host: &impl CommandRunner, enabled: bool) -> Result
 }
 
 pub fn set_bluetooth_enabled(enabled: bool) -> Result<(), String> {
-    set_bluetooth_enabled_with(&ProcessCommandRunner, enabled)
+    Radios::system().set_enabled(Radio::Bluetooth, enabled)
 }
 
+/// `rfkill` tool fallback for systems where `/dev/rfkill` cannot be written.
 pub fn set_bluetooth_enabled_with(host: &impl CommandRunner, enabled: bool) -> Result<(), String> {
     let action = if enabled { "unblock" } else { "block" };
     let output = host.output("rfkill", &[action, "bluetooth"])?;
@@ -201,6 +318,100 @@ mod tests {
         assert!(actions.is_empty());
     }
 
+    fn rules(json: &str) -> Vec<crate::models::PolicyRule> {
+        serde_json::from_str(json).expect("policy rules")
+    }
+
+    #[test]
+    fn custom_rules_leave_wifi_alone_and_use_a_detached_backlight() {
+        let mut state = RuntimeState {
+            status: status(false, true, true),
+            ..RuntimeState::default()
+        };
+        state.settings.policy_rules = rules(
+            r#"[
+                { "trigger": "detach", "when": { "bluetoothEnabled": false },
+                  "actions": [{ "type": "bluetooth", "state": "on" }] },
+                { "trigger": "detach", "actions": [{ "type": "backlight", "level": 1 }] }
+            ]"#,
+        );
+
+        let actions = apply_transition_policy_with(
+            &mut state,
+            &status(true, true, true),
+            &PolicyContext::default(),
+        );
+
+        assert_eq!(actions, vec![PolicyAction::SetBacklight(1)]);
+        assert!(state.recent_events.is_empty());
+        assert_eq!(state.remembered_wifi_enabled, Some(true));
+    }
+
+    #[test]
+    fn conditions_check_power_monitor_and_connection() {
+        let mut state = RuntimeState {
+            status: DuoStatus {
+                connection_type: ConnectionType::Bluetooth,
+                ..status(false, true, true)
+            },
+            ..RuntimeState::default()
+        };
+        state.settings.policy_rules = rules(
+            r#"[
+                { "trigger": "detach", "when": { "onAcPower": true, "externalMonitor": true },
+                  "actions": [{ "type": "profile", "id": "presentation" }] },
+                { "trigger": "detach", "when": { "connectionType": "bluetooth" },
+                  "actions": [{ "type": "remap", "enabled": false }] },
+                { "trigger": "detach", "when": { "connectionType": "none" },
+                  "actions": [{ "type": "wifi", "state": "off" }] }
+            ]"#,
+        );
+        let docked = PolicyContext {
+            on_ac_power: Some(true),
+            external_monitor: true,
+        };
+
+        let actions = evaluate_rules(&mut state, PolicyTrigger::Detach, &docked);
+        assert_eq!(
+            actions,
+            vec![
+                PolicyAction::ActivateProfile("presentation".into()),
+                PolicyAction::SetUsbMediaRemap(false),
+            ]
+        );
+
+        let unknown_power = PolicyContext {
+            on_ac_power: None,
+            external_monitor: true,
+        };
+        let actions = evaluate_rules(&mut state, PolicyTrigger::Detach, &unknown_power);
+        assert_eq!(actions, vec![PolicyAction::SetUsbMediaRemap(false)]);
+    }
+
+    #[test]
+    fn lid_rules_match_on_lid_state() {
+        let mut state = RuntimeState {
+            lid_closed: true,
+            ..RuntimeState::default()
+        };
+        state.settings.policy_rules = rules(
+            r#"[
+                { "trigger": "lid", "when": { "lidClosed": true },
+                  "actions": [{ "type": "bluetooth", "state": "off" }] },
+                { "trigger": "lid", "when": { "lidClosed": false },
+                  "actions": [{ "type": "bluetooth", "state": "on" }] }
+            ]"#,
+        );
+
+        let actions = evaluate_rules(&mut state, PolicyTrigger::Lid, &PolicyContext::default());
+
+        assert_eq!(actions, vec![PolicyAction::SetBluetooth(false)]);
+        assert!(!state.status.bluetooth_enabled);
+        assert!(
+            evaluate_rules(&mut state, PolicyTrigger::Resume, &PolicyContext::default()).is_empty()
+        );
+    }
+
     #[test]
     fn updates_remembered_radios_while_still_attached() {
         let mut state = RuntimeState {
@@ -0,0 +1,308 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+
+use crate::models::{
+    DevicePosture, PostureInputs, PostureReport, PostureTransition, PostureTrigger,
+};
+
+const MAX_POSTURE_HISTORY: usize = 64;
+
+/// Side effects the daemon runs when a posture is left or entered.
+///
+/// Only the lid axis carries actions here. Attach/detach side effects (radios,
+/// backlight, dock layout) come from the transition policy, and suspend and
+/// resume are handled by the lifecycle hooks, which replay the display mode
+/// for whatever posture the machine wakes up in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostureAction {
+    /// Entry action of the clamshell postures: external displays only.
+    ApplyClamshellLayout,
+    /// Exit action of the clamshell postures: bring the panels back.
+    ReplayDisplayMode,
+}
+
+/// The transitions one update went through, oldest first, and the actions
+/// of each in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostureChange {
+    pub transitions: Vec<PostureTransition>,
+    pub actions: Vec<PostureAction>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PostureMachine {
+    current: DevicePosture,
+    since: DateTime<Utc>,
+    /// The posture `Suspended` was entered from, which a lid or keyboard
+    /// change that arrives without a resume carries on from.
+    before_suspend: DevicePosture,
+    history: VecDeque<PostureTransition>,
+}
+
+impl Default for PostureMachine {
+    fn default() -> Self {
+        Self::new(DevicePosture::default(), Utc::now())
+    }
+}
+
+impl PostureMachine {
+    pub fn new(initial: DevicePosture, at: DateTime<Utc>) -> Self {
+        Self {
+            current: initial,
+            since: at,
+            before_suspend: initial,
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn current(&self) -> DevicePosture {
+        self.current
+    }
+
+    /// Moves to the posture `inputs` describe, if it differs, and returns the
+    /// exit actions of the old posture followed by the entry actions of the
+    /// new one. When both the lid and the keyboard moved since the last
+    /// update, the keyboard step is taken first and the lid step from there,
+    /// so every recorded transition is one the posture allows.
+    pub fn update(
+        &mut self,
+        inputs: &PostureInputs,
+        trigger: PostureTrigger,
+        at: DateTime<Utc>,
+    ) -> Option<PostureChange> {
+        let next = DevicePosture::derive(inputs);
+        if next == self.current {
+            return None;
+        }
+
+        let steps = if self.current.allows(next) {
+            vec![(next, trigger)]
+        } else {
+            let via = DevicePosture::derive(&PostureInputs {
+                lid_closed: self.current.lid_closed(),
+                ..inputs.clone()
+            });
+            vec![(via, PostureTrigger::Keyboard), (next, PostureTrigger::Lid)]
+        };
+        let mut change = PostureChange {
+            transitions: Vec::new(),
+            actions: Vec::new(),
+        };
+        for (to, trigger) in steps {
+            let (transition, actions) = self.step(to, trigger, at);
+            change.transitions.push(transition);
+            change.actions.extend(actions);
+        }
+        Some(change)
+    }
+
+    fn step(
+        &mut self,
+        to: DevicePosture,
+        trigger: PostureTrigger,
+        at: DateTime<Utc>,
+    ) -> (PostureTransition, Vec<PostureAction>) {
+        let from = self.current;
+        // Leaving `Suspended` for anything but a resume means the resume was
+        // missed; act as if the machine never went to sleep.
+        let acting_from = if from == DevicePosture::Suspended && trigger != PostureTrigger::Resume {
+            self.before_suspend
+        } else {
+            from
+        };
+        let mut actions = exit_actions(acting_from, to);
+        actions.extend(entry_actions(to, acting_from));
+
+        if to == DevicePosture::Suspended {
+            self.before_suspend = from;
+        }
+        let transition = PostureTransition {
+            from,
+            to,
+            trigger,
+            at,
+        };
+        self.current = to;
+        self.since = at;
+        self.history.push_back(transition.clone());
+        while self.history.len() > MAX_POSTURE_HISTORY {
+            self.history.pop_front();
+        }
+        (transition, actions)
+    }
+
+    pub fn report(&self) -> PostureReport {
+        PostureReport {
+            posture: self.current,
+            since: self.since,
+            history: self.history.iter().cloned().collect(),
+        }
+    }
+}
+
+fn exit_actions(from: DevicePosture, to: DevicePosture) -> Vec<PostureAction> {
+    if from.lid_closed() && !to.lid_closed() && to != DevicePosture::Suspended {
+        vec![PostureAction::ReplayDisplayMode]
+    } else {
+        Vec::new()
+    }
+}
+
+fn entry_actions(to: DevicePosture, from: DevicePosture) -> Vec<PostureAction> {
+    if to.lid_closed() && !from.lid_closed() && from != DevicePosture::Suspended {
+        vec![PostureAction::ApplyClamshellLayout]
+    } else {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConnectionType;
+
+    fn inputs(keyboard_attached: bool, lid_closed: bool, suspended: bool) -> PostureInputs {
+        PostureInputs {
+            keyboard_attached,
+            connection_type: if keyboard_attached {
+                ConnectionType::Usb
+            } else {
+                ConnectionType::None
+            },
+            lid_closed,
+            suspended,
+        }
+    }
+
+    #[test]
+    fn lid_moves_carry_clamshell_entry_and_exit_actions() {
+        let mut machine = PostureMachine::new(DevicePosture::Docked, Utc::now());
+
+        let closed = machine
+            .update(&inputs(true, true, false), PostureTrigger::Lid, Utc::now())
+            .expect("lid closed");
+        assert_eq!(closed.transitions.len(), 1);
+        assert_eq!(closed.transitions[0].to, DevicePosture::ClamshellDocked);
+        assert_eq!(closed.actions, vec![PostureAction::ApplyClamshellLayout]);
+
+        let opened = machine
+            .update(&inputs(true, false, false), PostureTrigger::Lid, Utc::now())
+            .expect("lid opened");
+        assert_eq!(opened.actions, vec![PostureAction::ReplayDisplayMode]);
+        assert_eq!(machine.current(), DevicePosture::Docked);
+    }
+
+    #[test]
+    fn keyboard_and_sleep_moves_carry_no_display_actions() {
+        let mut machine = PostureMachine::new(DevicePosture::Clamshell, Utc::now());
+
+        let docked = machine
+            .update(
+                &inputs(true, true, false),
+                PostureTrigger::Keyboard,
+                Utc::now(),
+            )
+            .expect("docked");
+        assert!(docked.actions.is_empty());
+
+        let suspended = machine
+            .update(
+                &inputs(true, true, true),
+                PostureTrigger::Suspend,
+                Utc::now(),
+            )
+            .expect("suspended");
+        assert!(suspended.actions.is_empty());
+
+        // The lid opened while asleep; resume replays on its own.
+        let resumed = machine
+            .update(
+                &inputs(true, false, false),
+                PostureTrigger::Resume,
+                Utc::now(),
+            )
+            .expect("resumed");
+        assert_eq!(resumed.transitions[0].to, DevicePosture::Docked);
+        assert!(resumed.actions.is_empty());
+    }
+
+    #[test]
+    fn diagonal_moves_step_through_the_keyboard_change_first() {
+        let mut machine = PostureMachine::new(DevicePosture::Docked, Utc::now());
+
+        let change = machine
+            .update(&inputs(false, true, false), PostureTrigger::Lid, Utc::now())
+            .expect("clamshell");
+        let steps: Vec<_> = change
+            .transitions
+            .iter()
+            .map(|transition| (transition.from, transition.to, transition.trigger))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (
+                    DevicePosture::Docked,
+                    DevicePosture::DetachedNoKeyboard,
+                    PostureTrigger::Keyboard
+                ),
+                (
+                    DevicePosture::DetachedNoKeyboard,
+                    DevicePosture::Clamshell,
+                    PostureTrigger::Lid
+                ),
+            ]
+        );
+        assert!(change
+            .transitions
+            .iter()
+            .all(|transition| transition.from.allows(transition.to)));
+        assert_eq!(change.actions, vec![PostureAction::ApplyClamshellLayout]);
+        assert_eq!(machine.report().history.len(), 2);
+    }
+
+    #[test]
+    fn lid_change_after_a_missed_resume_leaves_suspended_with_its_actions() {
+        let mut machine = PostureMachine::new(DevicePosture::Docked, Utc::now());
+        machine
+            .update(
+                &inputs(true, false, true),
+                PostureTrigger::Suspend,
+                Utc::now(),
+            )
+            .expect("suspended");
+
+        // No resume arrives; the next lid close still applies clamshell.
+        let closed = machine
+            .update(&inputs(true, true, false), PostureTrigger::Lid, Utc::now())
+            .expect("clamshell");
+        assert_eq!(closed.transitions[0].from, DevicePosture::Suspended);
+        assert_eq!(machine.current(), DevicePosture::ClamshellDocked);
+        assert_eq!(closed.actions, vec![PostureAction::ApplyClamshellLayout]);
+    }
+
+    #[test]
+    fn unchanged_inputs_do_not_grow_history() {
+        let mut machine = PostureMachine::new(DevicePosture::Docked, Utc::now());
+        assert!(machine
+            .update(
+                &inputs(true, false, false),
+                PostureTrigger::Keyboard,
+                Utc::now()
+            )
+            .is_none());
+
+        for round in 0..MAX_POSTURE_HISTORY {
+            let attached = round % 2 == 0;
+            machine.update(
+                &inputs(!attached, false, false),
+                PostureTrigger::Keyboard,
+                Utc::now(),
+            );
+        }
+        let report = machine.report();
+        assert_eq!(report.history.len(), MAX_POSTURE_HISTORY);
+        assert_eq!(report.posture, machine.current());
+    }
+}
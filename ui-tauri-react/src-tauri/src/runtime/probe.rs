@@ -1,5 +1,6 @@
-use crate::hardware::{display_layout, sysfs};
-use crate::runtime::host::{CommandRunner, ProcessCommandRunner};
+use crate::hardware::{display_layout, keyboard_battery, sysfs};
+use crate::runtime::host::CommandRunner;
+use crate::runtime::radio::{Radio, Radios};
 use crate::models::{ConnectionType, DisplayLayout, DuoStatus, Orientation};
 
 pub fn current_status() -> DuoStatus {
@@ -7,9 +8,10 @@ pub fn current_status() -> DuoStatus {
     let connection_type = sysfs::detect_connection_type();
     status.keyboard_attached = keyboard_attached(&connection_type);
     status.connection_type = connection_type;
-    let host = ProcessCommandRunner;
-    status.wifi_enabled = wifi_enabled_with(&host);
-    status.bluetooth_enabled = bluetooth_enabled_with(&host);
+    let radios = Radios::system();
+    status.wifi_enabled = radios.enabled(Radio::Wifi);
+    status.bluetooth_enabled = radios.enabled(Radio::Bluetooth);
+    apply_keyboard_battery(&mut status);
     apply_layout_to_status(
         &mut status,
         display_layout::get_display_layout().ok().as_ref(),
@@ -17,6 +19,75 @@ pub fn current_status() -> DuoStatus {
     status
 }
 
+/// Which `DuoStatus` fields a refresh recomputes. Hotplug events only touch
+/// the fields their subsystem can change; the safety-net poll asks for all.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatusRefresh {
+    /// `keyboard_attached` and `connection_type`, from hidraw.
+    pub keyboard: bool,
+    /// `wifi_enabled` and `bluetooth_enabled`.
+    pub radios: bool,
+    /// `monitor_count` and `orientation`, from the display layout.
+    pub displays: bool,
+    /// `display_brightness` and `max_brightness`.
+    pub brightness: bool,
+    /// `keyboard_battery_percent` and `keyboard_battery_charging`.
+    pub battery: bool,
+}
+
+impl StatusRefresh {
+    pub const ALL: StatusRefresh = StatusRefresh {
+        keyboard: true,
+        radios: true,
+        displays: true,
+        brightness: true,
+        battery: true,
+    };
+
+    pub fn is_empty(&self) -> bool {
+        *self == StatusRefresh::default()
+    }
+
+    pub fn union(self, other: StatusRefresh) -> StatusRefresh {
+        StatusRefresh {
+            keyboard: self.keyboard || other.keyboard,
+            radios: self.radios || other.radios,
+            displays: self.displays || other.displays,
+            brightness: self.brightness || other.brightness,
+            battery: self.battery || other.battery,
+        }
+    }
+}
+
+/// Recomputes the fields `refresh` names and leaves the rest untouched.
+/// Displays are left to the caller, which prefers the session agent's layout
+/// over querying the compositor from here.
+pub fn refresh_status(status: &mut DuoStatus, refresh: StatusRefresh) {
+    if refresh.keyboard {
+        let connection_type = sysfs::detect_connection_type();
+        status.keyboard_attached = keyboard_attached(&connection_type);
+        status.connection_type = connection_type;
+    }
+    if refresh.radios {
+        let radios = Radios::system();
+        status.wifi_enabled = radios.enabled(Radio::Wifi);
+        status.bluetooth_enabled = radios.enabled(Radio::Bluetooth);
+    }
+    if refresh.brightness {
+        status.display_brightness = sysfs::read_display_brightness();
+        status.max_brightness = sysfs::read_max_brightness();
+    }
+    if refresh.battery {
+        apply_keyboard_battery(status);
+    }
+}
+
+fn apply_keyboard_battery(status: &mut DuoStatus) {
+    let battery = keyboard_battery::read();
+    status.keyboard_battery_percent = battery.map(|battery| battery.percent);
+    status.keyboard_battery_charging = battery.and_then(|battery| battery.charging);
+}
+
 pub fn apply_layout_to_status(status: &mut DuoStatus, layout: Option<&DisplayLayout>) {
     status.monitor_count = monitor_count(layout, status.monitor_count);
     status.orientation = inferred_orientation(layout).unwrap_or(status.orientation.clone());
@@ -27,9 +98,10 @@ pub fn keyboard_attached(connection_type: &ConnectionType) -> bool {
 }
 
 pub fn wifi_enabled() -> bool {
-    wifi_enabled_with(&ProcessCommandRunner)
+    Radios::system().enabled(Radio::Wifi)
 }
 
+/// `nmcli` fallback for systems where `/dev/rfkill` cannot be read.
 pub fn wifi_enabled_with(host: &impl CommandRunner) -> bool {
     host.output("nmcli", &["radio", "wifi"])
         .ok()
@@ -39,9 +111,10 @@ pub fn wifi_enabled_with(host: &impl CommandRunner) -> bool {
 }
 
 pub fn bluetooth_enabled() -> bool {
-    bluetooth_enabled_with(&ProcessCommandRunner)
+    Radios::system().enabled(Radio::Bluetooth)
 }
 
+/// `rfkill` tool fallback for systems where `/dev/rfkill` cannot be read.
 pub fn bluetooth_enabled_with(host: &impl CommandRunner) -> bool {
     host.output("rfkill", &["-n", "-o", "SOFT", "list", "bluetooth"])
         .ok()
@@ -105,6 +178,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn status_refresh_union_keeps_every_requested_field() {
+        let keyboard = StatusRefresh {
+            keyboard: true,
+            ..StatusRefresh::default()
+        };
+        let radios = StatusRefresh {
+            radios: true,
+            ..StatusRefresh::default()
+        };
+
+        let merged = keyboard.union(radios);
+        assert!(merged.keyboard && merged.radios);
+        assert!(!merged.displays && !merged.brightness);
+        assert!(StatusRefresh::default().is_empty());
+        assert_eq!(StatusRefresh::ALL.union(keyboard), StatusRefresh::ALL);
+    }
+
     #[test]
     fn usb_connection_counts_as_attached_keyboard() {
         assert!(keyboard_attached(&ConnectionType::Usb));
@@ -0,0 +1,343 @@
+use std::sync::Mutex;
+
+use tokio::sync::mpsc;
+
+use crate::hardware::rfkill::{
+    self, DevRfkill, RfkillControl, RfkillMonitor, RfkillOp, RfkillType,
+};
+use crate::runtime::host::{CommandRunner, ProcessCommandRunner};
+use crate::runtime::logger;
+use crate::runtime::probe::StatusRefresh;
+
+const NETWORK_MANAGER_SERVICE: &str = "org.freedesktop.NetworkManager";
+const NETWORK_MANAGER_PATH: &str = "/org/freedesktop/NetworkManager";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Radio {
+    Wifi,
+    Bluetooth,
+}
+
+impl Radio {
+    fn rfkill_type(self) -> RfkillType {
+        match self {
+            Radio::Wifi => RfkillType::Wlan,
+            Radio::Bluetooth => RfkillType::Bluetooth,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Radio::Wifi => "Wi-Fi",
+            Radio::Bluetooth => "Bluetooth",
+        }
+    }
+}
+
+/// NetworkManager's own Wi-Fi switch. It can be off while rfkill is not, so
+/// when NetworkManager runs it has to agree before Wi-Fi counts as enabled.
+pub trait WirelessSwitch {
+    /// `None` when NetworkManager is not running.
+    fn wireless_enabled(&self) -> Option<bool>;
+    fn set_wireless_enabled(&self, enabled: bool) -> Result<(), String>;
+}
+
+/// `WirelessEnabled` on NetworkManager over the system bus.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NetworkManager;
+
+static SYSTEM_BUS: Mutex<Option<zbus::blocking::Connection>> = Mutex::new(None);
+
+/// The daemon's blocking system bus connection, opened on first use.
+pub(crate) fn system_bus() -> Result<zbus::blocking::Connection, zbus::Error> {
+    let mut bus = SYSTEM_BUS.lock().unwrap_or_else(|e| e.into_inner());
+    match bus.as_ref() {
+        Some(connection) => Ok(connection.clone()),
+        None => {
+            let connection = zbus::blocking::Connection::system()?;
+            *bus = Some(connection.clone());
+            Ok(connection)
+        }
+    }
+}
+
+impl NetworkManager {
+    fn proxy(&self) -> Result<zbus::blocking::Proxy<'static>, zbus::Error> {
+        let connection = system_bus()?;
+        zbus::blocking::proxy::Builder::new(&connection)
+            .destination(NETWORK_MANAGER_SERVICE)?
+            .path(NETWORK_MANAGER_PATH)?
+            .interface(NETWORK_MANAGER_SERVICE)?
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+    }
+}
+
+impl WirelessSwitch for NetworkManager {
+    fn wireless_enabled(&self) -> Option<bool> {
+        self.proxy()
+            .and_then(|proxy| proxy.get_property::<bool>("WirelessEnabled"))
+            .ok()
+    }
+
+    fn set_wireless_enabled(&self, enabled: bool) -> Result<(), String> {
+        self.proxy()
+            .map_err(|e| format!("Failed to reach NetworkManager: {e}"))?
+            .set_property("WirelessEnabled", enabled)
+            .map_err(|e| format!("Failed to set NetworkManager WirelessEnabled: {e}"))
+    }
+}
+
+/// Wi-Fi and Bluetooth switches. `/dev/rfkill` is authoritative and works
+/// with any network stack; NetworkManager is consulted for Wi-Fi when it is
+/// running, and the `nmcli`/`rfkill` tools through the [`CommandRunner`] are
+/// the fallback when the device node is unavailable.
+pub struct Radios<K, N, H> {
+    rfkill: K,
+    network_manager: N,
+    host: H,
+}
+
+impl Radios<DevRfkill, NetworkManager, ProcessCommandRunner> {
+    pub fn system() -> Self {
+        Self::new(DevRfkill, NetworkManager, ProcessCommandRunner)
+    }
+}
+
+impl<K: RfkillControl, N: WirelessSwitch, H: CommandRunner> Radios<K, N, H> {
+    pub fn new(rfkill: K, network_manager: N, host: H) -> Self {
+        Self {
+            rfkill,
+            network_manager,
+            host,
+        }
+    }
+
+    pub fn enabled(&self, radio: Radio) -> bool {
+        let unblocked = self
+            .rfkill
+            .devices()
+            .ok()
+            .and_then(|devices| rfkill::radio_unblocked(&devices, radio.rfkill_type()));
+
+        match (radio, unblocked) {
+            (Radio::Wifi, Some(unblocked)) => {
+                unblocked && self.network_manager.wireless_enabled().unwrap_or(true)
+            }
+            (Radio::Wifi, None) => self
+                .network_manager
+                .wireless_enabled()
+                .unwrap_or_else(|| crate::runtime::probe::wifi_enabled_with(&self.host)),
+            (Radio::Bluetooth, Some(unblocked)) => unblocked,
+            (Radio::Bluetooth, None) => crate::runtime::probe::bluetooth_enabled_with(&self.host),
+        }
+    }
+
+    pub fn set_enabled(&self, radio: Radio, enabled: bool) -> Result<(), String> {
+        let native = self.rfkill.set_soft_blocked(radio.rfkill_type(), !enabled);
+
+        if radio == Radio::Wifi && self.network_manager.wireless_enabled().is_some() {
+            if let Err(err) = &native {
+                logger::debug(
+                    "rust-daemon",
+                    format!("rfkill Wi-Fi switch unavailable, using NetworkManager: {err}"),
+                );
+            }
+            return self.network_manager.set_wireless_enabled(enabled);
+        }
+
+        let Err(native_err) = native else {
+            return Ok(());
+        };
+        let fallback = match radio {
+            Radio::Wifi => crate::runtime::policy::set_wifi_enabled_with(&self.host, enabled),
+            Radio::Bluetooth => {
+                crate::runtime::policy::set_bluetooth_enabled_with(&self.host, enabled)
+            }
+        };
+        fallback.map_err(|err| {
+            format!(
+                "Failed to switch {} {}: {native_err}; fallback: {err}",
+                radio.label(),
+                if enabled { "on" } else { "off" }
+            )
+        })
+    }
+}
+
+/// Forwards a radios refresh for every switch change reported by
+/// `/dev/rfkill`. Soft-block changes made through the device node do not
+/// always produce a uevent, so this complements the uevent listener.
+pub fn start_watcher(sender: mpsc::UnboundedSender<StatusRefresh>) {
+    let mut monitor = match RfkillMonitor::open() {
+        Ok(monitor) => monitor,
+        Err(err) => {
+            logger::info(
+                "rust-daemon",
+                format!("rfkill watcher unavailable, radios follow uevents only: {err}"),
+            );
+            return;
+        }
+    };
+
+    let refresh = StatusRefresh {
+        radios: true,
+        ..StatusRefresh::default()
+    };
+    let spawned = std::thread::Builder::new()
+        .name("zenbook-duo-rfkill".into())
+        .spawn(move || loop {
+            match monitor.next_event() {
+                // The initial burst of `Add` events describes switches the
+                // first probe already saw.
+                Ok(event) if event.op == RfkillOp::Add => {}
+                Ok(_) => {
+                    if sender.send(refresh).is_err() {
+                        return;
+                    }
+                }
+                Err(err) => {
+                    logger::warn("rust-daemon", format!("rfkill watcher stopped: {err}"));
+                    return;
+                }
+            }
+        });
+    if let Err(err) = spawned {
+        logger::warn(
+            "rust-daemon",
+            format!("failed to spawn rfkill watcher: {err}"),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::rfkill::RfkillDevice;
+    use crate::runtime::host::tests::FakeCommandRunner;
+    use std::cell::RefCell;
+
+    #[derive(Default)]
+    struct FakeRfkill {
+        devices: Option<Vec<RfkillDevice>>,
+        writes: RefCell<Vec<(RfkillType, bool)>>,
+    }
+
+    impl FakeRfkill {
+        fn with(devices: &[(RfkillType, bool)]) -> Self {
+            Self {
+                devices: Some(
+                    devices
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, (kind, soft_blocked))| RfkillDevice {
+                            idx: idx as u32,
+                            kind: *kind,
+                            soft_blocked: *soft_blocked,
+                            hard_blocked: false,
+                        })
+                        .collect(),
+                ),
+                writes: RefCell::default(),
+            }
+        }
+    }
+
+    impl RfkillControl for &FakeRfkill {
+        fn devices(&self) -> Result<Vec<RfkillDevice>, String> {
+            self.devices
+                .clone()
+                .ok_or_else(|| "Failed to open /dev/rfkill: permission denied".into())
+        }
+
+        fn set_soft_blocked(&self, kind: RfkillType, blocked: bool) -> Result<(), String> {
+            if self.devices.is_none() {
+                return Err("Failed to write /dev/rfkill: permission denied".into());
+            }
+            self.writes.borrow_mut().push((kind, blocked));
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeNetworkManager {
+        enabled: Option<bool>,
+        writes: RefCell<Vec<bool>>,
+    }
+
+    impl WirelessSwitch for &FakeNetworkManager {
+        fn wireless_enabled(&self) -> Option<bool> {
+            self.enabled
+        }
+
+        fn set_wireless_enabled(&self, enabled: bool) -> Result<(), String> {
+            self.writes.borrow_mut().push(enabled);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn reads_radios_from_rfkill_without_running_commands() {
+        let rfkill = FakeRfkill::with(&[(RfkillType::Wlan, false), (RfkillType::Bluetooth, true)]);
+        let network_manager = FakeNetworkManager::default();
+        let host = FakeCommandRunner::new([]);
+        let radios = Radios::new(&rfkill, &network_manager, &host);
+
+        assert!(radios.enabled(Radio::Wifi));
+        assert!(!radios.enabled(Radio::Bluetooth));
+        assert!(host.calls().is_empty());
+    }
+
+    #[test]
+    fn network_manager_switch_overrides_unblocked_wifi() {
+        let rfkill = FakeRfkill::with(&[(RfkillType::Wlan, false)]);
+        let network_manager = FakeNetworkManager {
+            enabled: Some(false),
+            ..FakeNetworkManager::default()
+        };
+        let host = FakeCommandRunner::new([]);
+        let radios = Radios::new(&rfkill, &network_manager, &host);
+
+        assert!(!radios.enabled(Radio::Wifi));
+
+        radios.set_enabled(Radio::Wifi, true).expect("wifi on");
+        assert_eq!(*rfkill.writes.borrow(), vec![(RfkillType::Wlan, false)]);
+        assert_eq!(*network_manager.writes.borrow(), vec![true]);
+    }
+
+    #[test]
+    fn writes_bluetooth_through_rfkill() {
+        let rfkill = FakeRfkill::with(&[(RfkillType::Bluetooth, false)]);
+        let network_manager = FakeNetworkManager::default();
+        let host = FakeCommandRunner::new([]);
+        let radios = Radios::new(&rfkill, &network_manager, &host);
+
+        radios
+            .set_enabled(Radio::Bluetooth, false)
+            .expect("bluetooth off");
+
+        assert_eq!(*rfkill.writes.borrow(), vec![(RfkillType::Bluetooth, true)]);
+        assert!(host.calls().is_empty());
+    }
+
+    #[test]
+    fn falls_back_to_commands_without_rfkill_access() {
+        let rfkill = FakeRfkill::default();
+        let network_manager = FakeNetworkManager::default();
+        let host = FakeCommandRunner::new([
+            Ok(FakeCommandRunner::success("unblocked\n")),
+            Ok(FakeCommandRunner::failure(
+                "rfkill: cannot open /dev/rfkill",
+            )),
+        ]);
+        let radios = Radios::new(&rfkill, &network_manager, &host);
+
+        assert!(radios.enabled(Radio::Bluetooth));
+        let err = radios
+            .set_enabled(Radio::Bluetooth, true)
+            .expect_err("both paths fail");
+        assert!(err.contains("permission denied"));
+        assert!(err.contains("cannot open /dev/rfkill"));
+        assert_eq!(host.calls().len(), 2);
+    }
+}
@@ -1,20 +1,73 @@
 use std::sync::Arc;
 
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use serde::Serialize;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
 use tokio::net::UnixStream;
-use tokio::sync::RwLock;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{Mutex, RwLock};
+use tokio::task::{JoinError, JoinSet};
+use tokio::time::{interval, Duration};
 
-use crate::ipc::protocol::{DaemonRequest, DaemonResponse, Envelope, PROTOCOL_VERSION};
-use crate::runtime::{daemon, state::RuntimeState};
+use crate::ipc::protocol::{
+    is_supported_version, protocol_mismatch_message, DaemonEvent, DaemonRequest, DaemonResponse,
+    Envelope, SubscriptionCategory, PROTOCOL_VERSION,
+};
+use crate::models::LogQuery;
+use crate::runtime::logger::LogFollower;
+use crate::runtime::peer_auth::{self, Authorization, PeerCredentials};
+use crate::runtime::{daemon, event_bus, logger, paths, state::RuntimeState};
+
+/// Upper bound on tagged requests dispatched concurrently per connection.
+/// Further requests wait for a slot, which pushes back on the client.
+const MAX_IN_FLIGHT: usize = 32;
+/// How often a following `QueryLogs` connection checks the logs for new lines.
+const LOG_FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+type SharedWriter<W> = Arc<Mutex<W>>;
 
 /// Daemon request-router Interface.
 ///
 /// This Module owns the wire-level loop and protocol error semantics. The
 /// daemon Implementation owns the command behavior behind `dispatch_request`.
+///
+/// Requests without an `id` are answered in order, one at a time, exactly as
+/// older clients expect. Requests with an `id` are dispatched concurrently and
+/// their responses carry the same `id`, so a slow command does not hold up the
+/// ones pipelined behind it.
+///
+/// Each request is checked against the caller's `SO_PEERCRED` identity before
+/// it reaches `dispatch_request`; see `peer_auth` for the tiers.
 pub async fn handle_client(stream: UnixStream, state: Arc<RwLock<RuntimeState>>) -> Result<(), String> {
-    let (reader, mut writer) = stream.into_split();
-    let mut lines = BufReader::new(reader).lines();
+    let peer = PeerCredentials::of(&stream);
+    if peer.is_none() {
+        log::warn!("daemon client has no peer credentials; treating it as read-only");
+    }
+    let (reader, writer) = stream.into_split();
+    let lines = BufReader::new(reader).lines();
+    let writer = Arc::new(Mutex::new(writer));
+    let mut in_flight = JoinSet::new();
+
+    let result = serve(lines, writer, state, peer, &mut in_flight).await;
 
+    // Let tagged requests that are already running finish and answer rather
+    // than aborting them halfway through a hardware change.
+    while let Some(joined) = in_flight.join_next().await {
+        log_dispatch_result(joined);
+    }
+    result
+}
+
+async fn serve<R, W>(
+    mut lines: Lines<R>,
+    writer: SharedWriter<W>,
+    state: Arc<RwLock<RuntimeState>>,
+    peer: Option<PeerCredentials>,
+    in_flight: &mut JoinSet<Result<(), String>>,
+) -> Result<(), String>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWriteExt + Unpin + Send + 'static,
+{
     while let Some(line) = lines
         .next_line()
         .await
@@ -23,13 +76,36 @@ pub async fn handle_client(stream: UnixStream, state: Arc<RwLock<RuntimeState>>)
         let envelope: Envelope<DaemonRequest> =
             serde_json::from_str(&line).map_err(|e| format!("Invalid daemon request JSON: {e}"))?;
 
-        if envelope.protocol_version != PROTOCOL_VERSION {
+        // Answer every request in the dialect it was sent in, so older clients
+        // keep working against a newer daemon.
+        let version = envelope.protocol_version;
+        let id = envelope.id;
+        if !is_supported_version(version) {
+            write_response(
+                &writer,
+                PROTOCOL_VERSION,
+                id,
+                DaemonResponse::Error {
+                    message: protocol_mismatch_message(version),
+                },
+            )
+            .await?;
+            continue;
+        }
+
+        if let Some(feature) = envelope
+            .payload
+            .required_feature()
+            .filter(|feature| feature.introduced_in() > version)
+        {
             write_response(
-                &mut writer,
+                &writer,
+                version,
+                id,
                 DaemonResponse::Error {
                     message: format!(
-                        "Protocol mismatch: expected {}, got {}",
-                        PROTOCOL_VERSION, envelope.protocol_version
+                        "{feature:?} requires protocol version {}, request used {version}",
+                        feature.introduced_in()
                     ),
                 },
             )
@@ -37,25 +113,400 @@ pub async fn handle_client(stream: UnixStream, state: Arc<RwLock<RuntimeState>>)
             continue;
         }
 
-        let response = daemon::dispatch_request(envelope.payload, state.clone()).await;
-        write_response(&mut writer, response).await?;
+        match peer_auth::authorize(peer.as_ref(), &envelope.payload, paths::session_owner_uid()) {
+            Authorization::Allowed => {}
+            Authorization::Audited => audit(peer.as_ref(), &envelope.payload, "allowed"),
+            Authorization::Denied(message) => {
+                audit(peer.as_ref(), &envelope.payload, "denied");
+                write_response(&writer, version, id, DaemonResponse::Error { message }).await?;
+                continue;
+            }
+        }
+
+        if let DaemonRequest::Subscribe { categories } = envelope.payload {
+            return stream_events(lines, writer, state, categories, version, id).await;
+        }
+        if let DaemonRequest::QueryLogs {
+            since,
+            until,
+            components,
+            min_level,
+            contains,
+            pattern,
+            limit,
+            follow: true,
+        } = envelope.payload
+        {
+            let query = LogQuery {
+                since,
+                until,
+                components,
+                min_level,
+                contains,
+                pattern,
+                limit,
+            };
+            return stream_logs(lines, writer, query, version, id).await;
+        }
+
+        if id.is_none() {
+            let response = daemon::dispatch_request(envelope.payload, state.clone()).await;
+            write_response(&writer, version, None, response).await?;
+            continue;
+        }
+
+        while let Some(joined) = in_flight.try_join_next() {
+            log_dispatch_result(joined);
+        }
+        if in_flight.len() >= MAX_IN_FLIGHT {
+            if let Some(joined) = in_flight.join_next().await {
+                log_dispatch_result(joined);
+            }
+        }
+
+        let writer = writer.clone();
+        let state = state.clone();
+        in_flight.spawn(async move {
+            let response = daemon::dispatch_request(envelope.payload, state).await;
+            write_response(&writer, version, id, response).await
+        });
     }
 
     Ok(())
 }
 
+fn audit(peer: Option<&PeerCredentials>, request: &DaemonRequest, outcome: &str) {
+    let caller = peer.map_or_else(|| "unknown peer".to_string(), ToString::to_string);
+    let required = peer_auth::required_tier(request).label();
+    logger::info(
+        "rust-daemon",
+        format!(
+            "audit: {outcome} {} ({required}) from {caller}",
+            request.kind()
+        ),
+    );
+}
+
+fn log_dispatch_result(joined: Result<Result<(), String>, JoinError>) {
+    match joined {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => log::warn!("pipelined daemon response failed: {err}"),
+        Err(err) => log::warn!("pipelined daemon request task failed: {err}"),
+    }
+}
+
+/// Serves a subscribed connection until the client hangs up.
+///
+/// The receiver is taken before the ack so nothing published in between is
+/// lost, and status subscribers get the current snapshot up front instead of
+/// having to race a separate `GetStatus`.
+async fn stream_events<R, W>(
+    mut lines: Lines<R>,
+    writer: SharedWriter<W>,
+    state: Arc<RwLock<RuntimeState>>,
+    categories: Vec<SubscriptionCategory>,
+    version: u32,
+    id: Option<u64>,
+) -> Result<(), String>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    let (mut receiver, snapshot) = {
+        let guard = state.read().await;
+        (
+            guard.event_bus.subscribe(),
+            DaemonEvent::StatusChanged {
+                status: event_bus::subscriber_status(&guard.status),
+            },
+        )
+    };
+
+    write_response(&writer, version, id, DaemonResponse::Ack).await?;
+    if snapshot.matches(&categories) {
+        write_event(&writer, version, snapshot).await?;
+    }
+
+    loop {
+        tokio::select! {
+            event = receiver.recv() => match event {
+                Ok(event) => {
+                    if event.matches(&categories) {
+                        write_event(&writer, version, event).await?;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("daemon event subscriber lagged; dropped {skipped} events");
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            line = lines.next_line() => match line {
+                Ok(Some(_)) => continue,
+                Ok(None) | Err(_) => return Ok(()),
+            },
+        }
+    }
+}
+
+/// Serves `QueryLogs { follow }` until the client hangs up: first the
+/// matches so far, then a `LogRecords` batch whenever matching lines are
+/// appended to any of the logs.
+async fn stream_logs<R, W>(
+    mut lines: Lines<R>,
+    writer: SharedWriter<W>,
+    query: LogQuery,
+    version: u32,
+    id: Option<u64>,
+) -> Result<(), String>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    let filter = match query.clone().compile() {
+        Ok(filter) => filter,
+        Err(message) => {
+            return write_response(&writer, version, id, DaemonResponse::Error { message }).await
+        }
+    };
+    // Positions are taken before the initial query, so a line written in
+    // between may be sent twice but is never lost.
+    let mut follower = LogFollower::new(daemon::log_sinks());
+    let initial = daemon::query_logs(query).await;
+    let failed = matches!(initial, DaemonResponse::Error { .. });
+    write_response(&writer, version, id, initial).await?;
+    if failed {
+        return Ok(());
+    }
+
+    let mut poll = interval(LOG_FOLLOW_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = poll.tick() => {
+                let (returned, appended) = tokio::task::spawn_blocking(move || {
+                    let appended = follower.poll();
+                    (follower, appended)
+                })
+                .await
+                .map_err(|e| format!("Failed to join log follower: {e}"))?;
+                follower = returned;
+                let records: Vec<_> = appended
+                    .into_iter()
+                    .filter(|record| filter.matches(record))
+                    .collect();
+                if !records.is_empty() {
+                    write_response(&writer, version, id, DaemonResponse::LogRecords { records })
+                        .await?;
+                }
+            }
+            line = lines.next_line() => match line {
+                Ok(Some(_)) => continue,
+                Ok(None) | Err(_) => return Ok(()),
+            },
+        }
+    }
+}
+
 async fn write_response<W: AsyncWriteExt + Unpin>(
-    writer: &mut W,
+    writer: &Mutex<W>,
+    version: u32,
+    id: Option<u64>,
     response: DaemonResponse,
 ) -> Result<(), String> {
-    let line = serde_json::to_string(&Envelope::new(response))
-        .map_err(|e| format!("Failed to encode daemon response: {e}"))?;
+    let envelope = Envelope::with_version(version, response.for_version(version)).with_id(id);
+    write_envelope(writer, envelope, "daemon response").await
+}
+
+async fn write_event<W: AsyncWriteExt + Unpin>(
+    writer: &Mutex<W>,
+    version: u32,
+    event: DaemonEvent,
+) -> Result<(), String> {
+    write_envelope(writer, Envelope::with_version(version, event), "daemon event").await
+}
+
+/// Writes one envelope as a single line. The lock is held for the whole line
+/// so concurrent responses never interleave.
+async fn write_envelope<W: AsyncWriteExt + Unpin, T: Serialize>(
+    writer: &Mutex<W>,
+    envelope: Envelope<T>,
+    label: &str,
+) -> Result<(), String> {
+    let mut line = serde_json::to_string(&envelope)
+        .map_err(|e| format!("Failed to encode {label}: {e}"))?;
+    line.push('\n');
     writer
-        .write_all(line.as_bytes())
+        .lock()
         .await
-        .map_err(|e| format!("Failed to write daemon response: {e}"))?;
-    writer
-        .write_all(b"\n")
+        .write_all(line.as_bytes())
         .await
-        .map_err(|e| format!("Failed to terminate daemon response: {e}"))
+        .map_err(|e| format!("Failed to write {label}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EventCategory, HardwareEvent};
+    use tokio::time::{timeout, Duration};
+
+    #[tokio::test]
+    async fn subscribe_acks_then_streams_snapshot_and_published_events() {
+        let (server, client) = UnixStream::pair().expect("socket pair");
+        let state = Arc::new(RwLock::new(RuntimeState::default()));
+        let router = tokio::spawn(handle_client(server, state.clone()));
+
+        let (reader, mut writer) = client.into_split();
+        let request = serde_json::to_string(&Envelope::new(DaemonRequest::Subscribe {
+            categories: vec![SubscriptionCategory::Status, SubscriptionCategory::Hardware],
+        }))
+        .expect("encode subscribe");
+        writer.write_all(request.as_bytes()).await.expect("write subscribe");
+        writer.write_all(b"\n").await.expect("terminate subscribe");
+
+        let mut lines = BufReader::new(reader).lines();
+
+        let ack: Envelope<DaemonResponse> =
+            serde_json::from_str(&next_line(&mut lines).await).expect("decode ack");
+        assert!(matches!(ack.payload, DaemonResponse::Ack));
+
+        let snapshot: Envelope<DaemonEvent> =
+            serde_json::from_str(&next_line(&mut lines).await).expect("decode snapshot");
+        match snapshot.payload {
+            DaemonEvent::StatusChanged { status } => assert!(status.service_active),
+            other => panic!("unexpected snapshot: {other:?}"),
+        }
+
+        {
+            let mut guard = state.write().await;
+            guard.publish_session_agent_change();
+            guard.push_recent_event(HardwareEvent::info(
+                EventCategory::Usb,
+                "Keyboard attached",
+                "test",
+            ));
+        }
+
+        let event: Envelope<DaemonEvent> =
+            serde_json::from_str(&next_line(&mut lines).await).expect("decode event");
+        match event.payload {
+            DaemonEvent::HardwareEvent { event } => assert_eq!(event.message, "Keyboard attached"),
+            other => panic!("session-agent events were not requested: {other:?}"),
+        }
+
+        drop(writer);
+        drop(lines);
+        timeout(Duration::from_secs(1), router)
+            .await
+            .expect("router exits after client hangs up")
+            .expect("join router")
+            .expect("router result");
+    }
+
+    #[tokio::test]
+    async fn older_dialect_is_answered_in_kind_and_newer_features_are_refused() {
+        let (server, client) = UnixStream::pair().expect("socket pair");
+        let state = Arc::new(RwLock::new(RuntimeState::default()));
+        let router = tokio::spawn(handle_client(server, state));
+
+        let (reader, mut writer) = client.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        send(&mut writer, Envelope::with_version(1, DaemonRequest::Ping)).await;
+        let pong: Envelope<DaemonResponse> =
+            serde_json::from_str(&next_line(&mut lines).await).expect("decode pong");
+        assert_eq!(pong.protocol_version, 1);
+        assert!(matches!(pong.payload, DaemonResponse::Pong));
+
+        send(
+            &mut writer,
+            Envelope::with_version(1, DaemonRequest::Subscribe { categories: Vec::new() }),
+        )
+        .await;
+        let refused: Envelope<DaemonResponse> =
+            serde_json::from_str(&next_line(&mut lines).await).expect("decode refusal");
+        match refused.payload {
+            DaemonResponse::Error { message } => assert!(message.contains("requires protocol version 2")),
+            other => panic!("unexpected response: {other:?}"),
+        }
+
+        send(&mut writer, Envelope::with_version(99, DaemonRequest::Ping)).await;
+        let mismatch: Envelope<DaemonResponse> =
+            serde_json::from_str(&next_line(&mut lines).await).expect("decode mismatch");
+        match mismatch.payload {
+            DaemonResponse::Error { message } => assert_eq!(
+                crate::ipc::protocol::parse_protocol_mismatch(&message),
+                Some(PROTOCOL_VERSION)
+            ),
+            other => panic!("unexpected response: {other:?}"),
+        }
+
+        send(
+            &mut writer,
+            Envelope::new(DaemonRequest::Hello {
+                min_version: 1,
+                max_version: 12,
+                features: Vec::new(),
+            }),
+        )
+        .await;
+        let hello: Envelope<DaemonResponse> =
+            serde_json::from_str(&next_line(&mut lines).await).expect("decode hello");
+        match hello.payload {
+            DaemonResponse::Hello { handshake } => {
+                assert_eq!(handshake.negotiated_version, PROTOCOL_VERSION);
+                assert_eq!(handshake.min_version, crate::ipc::protocol::MIN_PROTOCOL_VERSION);
+            }
+            other => panic!("unexpected response: {other:?}"),
+        }
+
+        drop(writer);
+        router.await.expect("join router").expect("router result");
+    }
+
+    #[tokio::test]
+    async fn tagged_requests_are_answered_with_their_ids() {
+        let (server, client) = UnixStream::pair().expect("socket pair");
+        let state = Arc::new(RwLock::new(RuntimeState::default()));
+        let router = tokio::spawn(handle_client(server, state));
+
+        let (reader, mut writer) = client.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        send(&mut writer, Envelope::new(DaemonRequest::GetStatus).with_id(Some(7))).await;
+        send(&mut writer, Envelope::new(DaemonRequest::Ping).with_id(Some(8))).await;
+
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let response: Envelope<DaemonResponse> =
+                serde_json::from_str(&next_line(&mut lines).await).expect("decode response");
+            match (response.id, response.payload) {
+                (Some(7), DaemonResponse::Status { .. }) | (Some(8), DaemonResponse::Pong) => {}
+                other => panic!("response paired with the wrong id: {other:?}"),
+            }
+            ids.push(response.id);
+        }
+        ids.sort();
+        assert_eq!(ids, vec![Some(7), Some(8)]);
+
+        send(&mut writer, Envelope::new(DaemonRequest::Ping)).await;
+        let untagged: Envelope<DaemonResponse> =
+            serde_json::from_str(&next_line(&mut lines).await).expect("decode untagged");
+        assert_eq!(untagged.id, None);
+
+        drop(writer);
+        router.await.expect("join router").expect("router result");
+    }
+
+    async fn send<W: AsyncWriteExt + Unpin>(writer: &mut W, envelope: Envelope<DaemonRequest>) {
+        let line = serde_json::to_string(&envelope).expect("encode request");
+        writer.write_all(line.as_bytes()).await.expect("write request");
+        writer.write_all(b"\n").await.expect("terminate request");
+    }
+
+    async fn next_line<R: AsyncBufRead + Unpin>(lines: &mut Lines<R>) -> String {
+        timeout(Duration::from_secs(1), lines.next_line())
+            .await
+            .expect("line before timeout")
+            .expect("read line")
+            .expect("stream open")
+    }
 }
@@ -75,26 +75,13 @@ This usually means a root-owned helper created it; restart the system daemon or
 }
 
 fn target_identity() -> (u32, u32) {
-    let uid = std::env::var("ZENBOOK_DUO_UID")
-        .ok()
-        .and_then(|value| value.parse::<u32>().ok())
-        .unwrap_or_else(|| Uid::current().as_raw());
-    let gid = std::env::var("ZENBOOK_DUO_GID")
-        .ok()
-        .and_then(|value| value.parse::<u32>().ok())
-        .unwrap_or(uid);
-    (uid, gid)
+    let uid = paths::session_owner_uid();
+    (uid, paths::configured_session_gid().unwrap_or(uid))
 }
 
 fn target_identity_opt() -> Option<(u32, u32)> {
-    let uid = std::env::var("ZENBOOK_DUO_UID")
-        .ok()
-        .and_then(|value| value.parse::<u32>().ok())?;
-    let gid = std::env::var("ZENBOOK_DUO_GID")
-        .ok()
-        .and_then(|value| value.parse::<u32>().ok())
-        .unwrap_or(uid);
-    Some((uid, gid))
+    let uid = paths::configured_session_uid()?;
+    Some((uid, paths::configured_session_gid().unwrap_or(uid)))
 }
 
 fn owner_from_metadata(path: &Path) -> Option<(u32, u32)> {
@@ -0,0 +1,321 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::models::{PROFILES_SCHEMA_VERSION, SETTINGS_SCHEMA_VERSION};
+use crate::runtime::state::STATE_SCHEMA_VERSION;
+
+/// Key every persisted document carries its schema version under. Documents
+/// written before versioning existed have no key and count as version 0.
+pub const SCHEMA_VERSION_FIELD: &str = "schemaVersion";
+
+/// The JSON documents the app and daemon persist across releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Document {
+    RuntimeState,
+    Settings,
+    Profiles,
+}
+
+/// One step in a document's history. `migrations()[n]` upgrades a version `n`
+/// document to version `n + 1`; steps must be idempotent because a document
+/// that went through a downgrade is migrated again on the next upgrade.
+type Migration = fn(&mut Map<String, Value>);
+
+const STATE_MIGRATIONS: &[Migration] = &[state_v0_to_v1];
+
+const SETTINGS_MIGRATIONS: &[Migration] = &[settings_v0_to_v1];
+
+const PROFILES_MIGRATIONS: &[Migration] = &[profiles_v0_to_v1];
+
+impl Document {
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Document::RuntimeState => "state.json",
+            Document::Settings => "settings.json",
+            Document::Profiles => "profiles.json",
+        }
+    }
+
+    pub fn current_version(self) -> u32 {
+        match self {
+            Document::RuntimeState => STATE_SCHEMA_VERSION,
+            Document::Settings => SETTINGS_SCHEMA_VERSION,
+            Document::Profiles => PROFILES_SCHEMA_VERSION,
+        }
+    }
+
+    fn migrations(self) -> &'static [Migration] {
+        match self {
+            Document::RuntimeState => STATE_MIGRATIONS,
+            Document::Settings => SETTINGS_MIGRATIONS,
+            Document::Profiles => PROFILES_MIGRATIONS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Upgraded {
+    pub value: Value,
+    pub from_version: u32,
+}
+
+impl Upgraded {
+    /// True when the document came from a release newer than this one and was
+    /// passed through untouched.
+    pub fn is_newer(&self, document: Document) -> bool {
+        self.from_version > document.current_version()
+    }
+}
+
+pub fn version_of(value: &Value) -> u32 {
+    value.as_object().map(version_in).unwrap_or(0)
+}
+
+fn version_in(object: &Map<String, Value>) -> u32 {
+    object
+        .get(SCHEMA_VERSION_FIELD)
+        .and_then(Value::as_u64)
+        .and_then(|version| u32::try_from(version).ok())
+        .unwrap_or(0)
+}
+
+/// Runs every migration between the document's version and the current one.
+/// Documents from a newer release are returned as-is; callers decide what to
+/// do with fields they cannot represent.
+pub fn upgrade(document: Document, value: Value) -> Result<Upgraded, String> {
+    let Value::Object(mut object) = value else {
+        return Err(format!(
+            "Failed to migrate {}: document is not a JSON object",
+            document.file_name()
+        ));
+    };
+
+    let from_version = version_in(&object);
+    let current = document.current_version();
+    if from_version < current {
+        for migration in &document.migrations()[from_version as usize..current as usize] {
+            migration(&mut object);
+        }
+        object.insert(SCHEMA_VERSION_FIELD.into(), Value::from(current));
+    }
+
+    Ok(Upgraded {
+        value: Value::Object(object),
+        from_version,
+    })
+}
+
+/// Lists the fields of `original` that are missing from `reserialized`, i.e.
+/// what would be lost by parsing a document and writing it back. Keys holding
+/// `null` are ignored since optional fields may be skipped on output.
+pub fn unknown_fields(original: &Value, reserialized: &Value) -> Vec<String> {
+    let mut missing = Vec::new();
+    collect_unknown_fields("", original, reserialized, &mut missing);
+    missing
+}
+
+fn collect_unknown_fields(prefix: &str, original: &Value, kept: &Value, out: &mut Vec<String>) {
+    match (original, kept) {
+        (Value::Object(original), Value::Object(kept)) => {
+            for (key, value) in original {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                match kept.get(key) {
+                    Some(kept_value) => collect_unknown_fields(&path, value, kept_value, out),
+                    None if value.is_null() => {}
+                    None => out.push(path),
+                }
+            }
+        }
+        (Value::Array(original), Value::Array(kept)) => {
+            for (index, (value, kept_value)) in original.iter().zip(kept).enumerate() {
+                collect_unknown_fields(&format!("{prefix}[{index}]"), value, kept_value, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Refuses to replace a document written by a newer release when doing so
+/// would drop fields this release does not know about. Downgrading and then
+/// saving a setting must not wipe out what the newer release stored.
+pub fn guard_overwrite(document: Document, path: &Path, next: &Value) -> Result<(), String> {
+    let Ok(raw) = fs::read_to_string(path) else {
+        return Ok(());
+    };
+    let Ok(on_disk) = serde_json::from_str::<Value>(&raw) else {
+        return Ok(());
+    };
+
+    let version = version_of(&on_disk);
+    let current = document.current_version();
+    if version <= current {
+        return Ok(());
+    }
+
+    let lost = unknown_fields(&on_disk, next);
+    if lost.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "Refusing to overwrite {} (schema version {version}, this release supports up to {current}): it would drop {}",
+        path.display(),
+        lost.join(", ")
+    ))
+}
+
+/// Settings files from before `setupCompleted` existed belong to installs that
+/// already went through setup, so upgrading must not show the wizard again.
+fn settings_v0_to_v1(settings: &mut Map<String, Value>) {
+    settings
+        .entry("setupCompleted")
+        .or_insert(Value::Bool(true));
+}
+
+/// The daemon keeps a copy of the settings inside its state file.
+fn state_v0_to_v1(state: &mut Map<String, Value>) {
+    if let Some(settings @ Value::Object(_)) = state.get_mut("settings") {
+        if let Ok(upgraded) = upgrade(Document::Settings, settings.take()) {
+            *settings = upgraded.value;
+        }
+    }
+}
+
+/// Unversioned profile lists already match the version 1 layout.
+fn profiles_v0_to_v1(_profiles: &mut Map<String, Value>) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn unique_temp_path(label: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        std::env::temp_dir().join(format!("zenbook-duo-schema-{label}-{nanos}.json"))
+    }
+
+    #[test]
+    fn every_document_has_one_migration_per_version() {
+        for document in [
+            Document::RuntimeState,
+            Document::Settings,
+            Document::Profiles,
+        ] {
+            assert_eq!(
+                document.migrations().len() as u32,
+                document.current_version(),
+                "{}",
+                document.file_name()
+            );
+        }
+    }
+
+    #[test]
+    fn settings_v0_to_v1_marks_existing_installs_as_set_up() {
+        let upgraded = upgrade(Document::Settings, json!({ "defaultBacklight": 2 }))
+            .expect("upgrade settings");
+
+        assert_eq!(upgraded.from_version, 0);
+        assert_eq!(upgraded.value["setupCompleted"], json!(true));
+        assert_eq!(upgraded.value["defaultBacklight"], json!(2));
+        assert_eq!(version_of(&upgraded.value), 1);
+
+        let unfinished = upgrade(Document::Settings, json!({ "setupCompleted": false }))
+            .expect("upgrade settings");
+        assert_eq!(unfinished.value["setupCompleted"], json!(false));
+    }
+
+    #[test]
+    fn state_v0_to_v1_migrates_embedded_settings() {
+        let upgraded = upgrade(
+            Document::RuntimeState,
+            json!({ "lidClosed": true, "settings": { "theme": "dark" } }),
+        )
+        .expect("upgrade state");
+
+        assert_eq!(upgraded.value["lidClosed"], json!(true));
+        assert_eq!(upgraded.value["settings"]["setupCompleted"], json!(true));
+        assert_eq!(version_of(&upgraded.value["settings"]), 1);
+        assert_eq!(version_of(&upgraded.value), 1);
+    }
+
+    #[test]
+    fn profiles_v0_to_v1_only_stamps_the_version() {
+        let original = json!({ "profiles": [{ "id": "docked" }] });
+
+        let upgraded = upgrade(Document::Profiles, original.clone()).expect("upgrade profiles");
+
+        assert_eq!(upgraded.value["profiles"], original["profiles"]);
+        assert_eq!(version_of(&upgraded.value), 1);
+    }
+
+    #[test]
+    fn documents_from_newer_releases_pass_through_untouched() {
+        let newer = json!({ "schemaVersion": 7, "futureField": [1, 2] });
+
+        let upgraded = upgrade(Document::Settings, newer.clone()).expect("upgrade settings");
+
+        assert_eq!(upgraded.value, newer);
+        assert!(upgraded.is_newer(Document::Settings));
+        assert!(upgrade(Document::Settings, json!([1])).is_err());
+    }
+
+    #[test]
+    fn unknown_fields_reports_nested_and_array_paths() {
+        let original = json!({
+            "theme": "dark",
+            "skipped": null,
+            "future": 1,
+            "profiles": [{ "id": "a", "hotkey": "F1" }],
+            "nested": { "kept": 1, "added": 2 }
+        });
+        let kept = json!({
+            "theme": "dark",
+            "profiles": [{ "id": "a" }],
+            "nested": { "kept": 1 }
+        });
+
+        assert_eq!(
+            unknown_fields(&original, &kept),
+            vec!["future", "nested.added", "profiles[0].hotkey"]
+        );
+    }
+
+    #[test]
+    fn guard_refuses_to_drop_fields_from_newer_documents() {
+        let path = unique_temp_path("guard");
+        let next = json!({ "schemaVersion": 1, "theme": "light" });
+
+        assert!(guard_overwrite(Document::Settings, &path, &next).is_ok());
+
+        fs::write(&path, r#"{"schemaVersion": 1, "theme": "dark", "old": 1}"#)
+            .expect("write current document");
+        assert!(guard_overwrite(Document::Settings, &path, &next).is_ok());
+
+        fs::write(&path, r#"{"schemaVersion": 2, "theme": "dark"}"#)
+            .expect("write compatible newer document");
+        assert!(guard_overwrite(Document::Settings, &path, &next).is_ok());
+
+        fs::write(
+            &path,
+            r#"{"schemaVersion": 2, "theme": "dark", "accent": "teal"}"#,
+        )
+        .expect("write newer document");
+        let message = guard_overwrite(Document::Settings, &path, &next)
+            .expect_err("newer fields must not be dropped");
+        assert!(message.contains("schema version 2"));
+        assert!(message.ends_with("it would drop accent"));
+
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -3,6 +3,8 @@ use std::process::{Command, Output};
 
 use nix::unistd::{Gid, Uid};
 
+use crate::runtime::paths;
+
 /// Adapter for systemd service-control behavior used by daemon requests and
 /// session lifecycle recovery.
 pub(crate) struct ServiceController;
@@ -54,7 +56,7 @@ fn restart_target_user_unit(unit: &str) -> Result<(), String> {
 }
 
 fn target_user_systemctl_command() -> Command {
-    let uid = target_uid();
+    let uid = paths::session_owner_uid();
     let gid = target_gid();
     let mut command = Command::new("systemctl");
     command
@@ -117,18 +119,8 @@ fn queue_system_unit_restart(unit: &str) -> Result<(), String> {
         .map_err(|e| format!("Failed to queue restart for {unit}: {e}"))
 }
 
-fn target_uid() -> u32 {
-    std::env::var("ZENBOOK_DUO_UID")
-        .ok()
-        .and_then(|value| value.parse::<u32>().ok())
-        .unwrap_or_else(|| Uid::current().as_raw())
-}
-
 fn target_gid() -> u32 {
-    std::env::var("ZENBOOK_DUO_GID")
-        .ok()
-        .and_then(|value| value.parse::<u32>().ok())
-        .unwrap_or_else(|| Gid::current().as_raw())
+    paths::configured_session_gid().unwrap_or_else(|| Gid::current().as_raw())
 }
 
 fn unit_not_found(output: &Output) -> bool {
@@ -85,7 +85,7 @@ async fn register_with_daemon(backend: SessionBackend) -> Result<(), String> {
     }
 }
 
-pub(crate) fn dispatch_session_command(payload: SessionCommand) -> SessionResponse {
+pub(crate) async fn dispatch_session_command(payload: SessionCommand) -> SessionResponse {
     match payload {
         SessionCommand::GetDisplayLayout => {
             match crate::hardware::display_layout::get_display_layout() {
@@ -122,6 +122,25 @@ pub(crate) fn dispatch_session_command(payload: SessionCommand) -> SessionRespon
             Err(message) => SessionResponse::Error { message },
         },
         SessionCommand::OpenEmojiPicker => SessionResponse::Ack,
+        SessionCommand::GetMetrics => SessionResponse::Metrics {
+            metrics: crate::runtime::metrics::snapshot(),
+        },
+        SessionCommand::RunHooks {
+            invocation,
+            timeout_ms,
+        } => {
+            // Hooks can run for seconds; keep them off the connection's thread.
+            let outcomes = tokio::task::spawn_blocking(move || {
+                crate::runtime::hooks::run_hooks(
+                    &crate::runtime::hooks::user_hooks_dir(),
+                    &invocation,
+                    Duration::from_millis(timeout_ms),
+                )
+            })
+            .await
+            .unwrap_or_default();
+            SessionResponse::HookOutcomes { outcomes }
+        }
     }
 }
 
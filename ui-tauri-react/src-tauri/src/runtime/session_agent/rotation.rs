@@ -216,14 +216,10 @@ pub(super) fn display_orientation_from_sensor_value(
 }
 
 
-fn runtime_log_info(message: impl AsRef<str>) {
-    let text = message.as_ref();
-    let _ = crate::runtime::logger::append_line(format!("session-agent: {text}"));
-    log::info!("{text}");
+fn runtime_log_info(message: impl Into<String>) {
+    crate::runtime::logger::info("session-agent", message);
 }
 
-fn runtime_log_warn(message: impl AsRef<str>) {
-    let text = message.as_ref();
-    let _ = crate::runtime::logger::append_line(format!("session-agent: {text}"));
-    log::warn!("{text}");
+fn runtime_log_warn(message: impl Into<String>) {
+    crate::runtime::logger::warn("session-agent", message);
 }
@@ -19,7 +19,7 @@ pub(crate) async fn handle_session_command(stream: UnixStream) -> Result<(), Str
     {
         let envelope: Envelope<SessionCommand> = serde_json::from_str(&line)
             .map_err(|e| format!("Invalid session command JSON: {e}"))?;
-        let response = session_agent::dispatch_session_command(envelope.payload);
+        let response = session_agent::dispatch_session_command(envelope.payload).await;
         let line = serde_json::to_string(&Envelope::new(response))
             .map_err(|e| format!("Failed to encode session response: {e}"))?;
         writer
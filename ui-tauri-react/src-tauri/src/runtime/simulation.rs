@@ -0,0 +1,311 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tokio::sync::{mpsc, RwLock};
+use tokio::time::{sleep, sleep_until, Duration, Instant};
+
+mod hardware;
+mod scenario;
+mod session;
+pub use hardware::SimHardware;
+pub use scenario::{
+    Scenario, ScenarioEvent, ScenarioStep, SimMachine, TranscriptEntry, TranscriptRecord,
+};
+
+use crate::ipc::protocol::LifecyclePhase;
+use crate::runtime::backend::HardwareBackend;
+use crate::runtime::daemon::{self, DisplayReplayPolicy};
+use crate::runtime::persistence::{self, WRITE_BEHIND_DELAY};
+use crate::runtime::state::{RuntimeState, SessionAgentState};
+use crate::runtime::{logger, monitor, paths};
+
+const USAGE: &str = "Usage: zenbook-duo-daemon --simulate <scenario.json> [--transcript <file>]";
+
+/// `zenbook-duo-daemon --simulate`: replays a scenario and writes the
+/// transcript as JSON lines to `--transcript`, or stdout. The log and state
+/// file go to a scratch directory instead of the installed daemon's.
+pub async fn run(args: &[String]) -> Result<(), String> {
+    let (scenario_path, transcript_path) = parse_args(args)?;
+    let scenario = Scenario::load(&scenario_path)?;
+
+    let scratch = std::env::temp_dir().join(format!("zenbook-duo-simulate-{}", std::process::id()));
+    fs::create_dir_all(&scratch)
+        .map_err(|e| format!("Failed to create {}: {e}", scratch.display()))?;
+    eprintln!(
+        "simulating {} (log and state in {})",
+        scenario_path.display(),
+        scratch.display()
+    );
+
+    let transcript = simulate(scenario, &scratch).await?;
+    let mut output = String::new();
+    for record in &transcript {
+        let line = serde_json::to_string(record)
+            .map_err(|e| format!("Failed to encode transcript: {e}"))?;
+        output.push_str(&line);
+        output.push('\n');
+    }
+    match transcript_path {
+        Some(path) => fs::write(&path, output)
+            .map_err(|e| format!("Failed to write transcript {}: {e}", path.display())),
+        None => {
+            print!("{output}");
+            Ok(())
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<(PathBuf, Option<PathBuf>), String> {
+    match args {
+        [scenario] => Ok((PathBuf::from(scenario), None)),
+        [scenario, flag, transcript] if flag == "--transcript" => {
+            Ok((PathBuf::from(scenario), Some(PathBuf::from(transcript))))
+        }
+        _ => Err(USAGE.into()),
+    }
+}
+
+/// Replays `scenario` through the daemon's own monitor, lid, lifecycle and
+/// policy code against [`SimHardware`] and a simulated session agent, and
+/// returns everything that happened. `scratch` holds the agent's socket and
+/// the state file, and becomes the runtime directory for the log and event
+/// journal unless an earlier run already claimed it.
+pub async fn simulate(scenario: Scenario, scratch: &Path) -> Result<Vec<TranscriptRecord>, String> {
+    paths::set_system_runtime_dir(scratch.to_path_buf());
+    let hardware = Arc::new(SimHardware::new(&scenario.initial));
+    let socket = scratch.join(paths::SESSION_SOCKET_NAME);
+    session::start(hardware.clone(), &socket)?;
+
+    let mut initial = RuntimeState {
+        settings: scenario.settings,
+        lid_closed: scenario.initial.lid_closed,
+        status: hardware.status(),
+        session_agent: SessionAgentState {
+            connected: true,
+            socket_path: Some(socket.display().to_string()),
+            ..SessionAgentState::default()
+        },
+        hardware: HardwareBackend::simulated(hardware.clone()),
+        ..RuntimeState::default()
+    };
+    initial.status.service_active = true;
+    initial.reset_posture();
+    let state = Arc::new(RwLock::new(initial));
+    persistence::start_at(
+        &state,
+        scratch.join(paths::STATE_FILE_NAME),
+        WRITE_BEHIND_DELAY,
+    )
+    .await;
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    hardware.connect(sender);
+    monitor::start_with(state.clone(), receiver);
+
+    let started = Instant::now();
+    for step in scenario.events {
+        sleep_until(started + Duration::from_millis(step.at_ms)).await;
+        hardware.record(TranscriptEntry::Event {
+            event: step.event.clone(),
+        });
+        if let Err(err) = replay(&state, &hardware, &step.event).await {
+            logger::warn(
+                "rust-daemon",
+                format!("simulated {} failed: {err}", step.event.label()),
+            );
+        }
+    }
+    sleep(Duration::from_millis(scenario.tail_ms)).await;
+
+    let persister = state.read().await.persister.clone();
+    persister.flush().await;
+    let _ = fs::remove_file(&socket);
+    Ok(hardware.transcript())
+}
+
+/// Moves the simulated hardware and delivers what the daemon would hear
+/// about it: uevents through the monitor, and logind's lid signal and the
+/// sleep hooks' lifecycle requests straight to their handlers.
+async fn replay(
+    state: &Arc<RwLock<RuntimeState>>,
+    hardware: &SimHardware,
+    event: &ScenarioEvent,
+) -> Result<(), String> {
+    match event {
+        ScenarioEvent::Attach => hardware.attach_keyboard(),
+        ScenarioEvent::Detach { bluetooth } => hardware.detach_keyboard(*bluetooth),
+        ScenarioEvent::LidClose | ScenarioEvent::LidOpen => {
+            let closed = *event == ScenarioEvent::LidClose;
+            return daemon::handle_lid_closed_change(state, closed).await;
+        }
+        ScenarioEvent::Suspend => {
+            return DisplayReplayPolicy::handle_lifecycle(state, LifecyclePhase::Pre).await
+        }
+        ScenarioEvent::Resume => {
+            return DisplayReplayPolicy::handle_lifecycle(state, LifecyclePhase::Post).await
+        }
+        ScenarioEvent::MonitorPlug => hardware.plug_monitor(),
+        ScenarioEvent::MonitorUnplug => hardware.unplug_monitor(),
+        ScenarioEvent::AcPower { online } => hardware.set_ac_power(*online),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    struct Scratch {
+        path: PathBuf,
+    }
+
+    impl Scratch {
+        fn new() -> Self {
+            let unique = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("clock")
+                .as_nanos();
+            let path = std::env::temp_dir().join(format!("zenbook-duo-sim-{unique}"));
+            fs::create_dir_all(&path).expect("create scratch dir");
+            Self { path }
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    async fn summaries(scenario: &str) -> Vec<String> {
+        let scratch = Scratch::new();
+        let scenario = Scenario::parse(scenario).expect("scenario");
+        simulate(scenario, &scratch.path)
+            .await
+            .expect("simulate")
+            .iter()
+            .map(|record| record.entry.summary())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn simulation_keeps_off_the_installed_daemons_files() {
+        summaries(r#"{"events": []}"#).await;
+
+        assert!(!paths::state_file_path().starts_with("/var/lib"));
+        assert!(!paths::event_journal_path().starts_with("/var/lib"));
+    }
+
+    /// `expected` appears in `transcript` in this order, other entries aside.
+    fn assert_in_order(transcript: &[String], expected: &[&str]) {
+        let mut remaining = transcript.iter();
+        for line in expected {
+            assert!(
+                remaining.any(|entry| entry == line),
+                "missing {line:?} in order; transcript:\n{}",
+                transcript.join("\n")
+            );
+        }
+    }
+
+    #[test]
+    fn scenarios_fill_defaults_and_sort_events() {
+        let scenario = Scenario::parse(
+            r#"{
+                "initial": { "externalMonitors": 1 },
+                "events": [
+                    { "atMs": 500, "type": "resume" },
+                    { "atMs": 100, "type": "detach", "bluetooth": true },
+                    { "atMs": 300, "type": "ac_power", "online": false }
+                ]
+            }"#,
+        )
+        .expect("scenario");
+
+        assert_eq!(scenario.initial.external_monitors, 1);
+        assert!(scenario.initial.wifi);
+        assert_eq!(
+            scenario
+                .events
+                .iter()
+                .map(|step| step.event.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                ScenarioEvent::Detach { bluetooth: true },
+                ScenarioEvent::AcPower { online: false },
+                ScenarioEvent::Resume,
+            ]
+        );
+        assert!(Scenario::parse(r#"{ "events": [{ "atMs": 0, "type": "teleport" }] }"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn dock_cycle_replays_display_mode_and_remap_through_fakes() {
+        let transcript = summaries(
+            r#"{
+                "settings": { "attachSettleMs": 0 },
+                "tailMs": 300,
+                "events": [
+                    { "atMs": 100, "type": "detach", "bluetooth": true },
+                    { "atMs": 500, "type": "attach" }
+                ]
+            }"#,
+        )
+        .await;
+
+        assert_in_order(
+            &transcript,
+            &[
+                "hardware usb_media_remap start",
+                "event detach bluetooth",
+                "posture docked -> detached_bluetooth (keyboard)",
+                "policy apply_display_mode attached=false",
+                "session set_dock_mode attached=false",
+                "hooks detach",
+                "hardware usb_media_remap stop",
+                "event attach",
+                "posture detached_bluetooth -> docked (keyboard)",
+                "policy apply_display_mode attached=true",
+                "session set_dock_mode attached=true",
+                "hooks attach",
+                "hardware usb_media_remap start",
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn lid_close_with_external_monitor_goes_external_only_and_survives_suspend() {
+        let transcript = summaries(
+            r#"{
+                "settings": { "attachSettleMs": 0 },
+                "initial": { "externalMonitors": 1 },
+                "tailMs": 300,
+                "events": [
+                    { "atMs": 100, "type": "lid_close" },
+                    { "atMs": 400, "type": "suspend" },
+                    { "atMs": 600, "type": "resume" }
+                ]
+            }"#,
+        )
+        .await;
+
+        assert_in_order(
+            &transcript,
+            &[
+                "event lid_close",
+                "posture docked -> clamshell_docked (lid)",
+                "session apply_display_layout HDMI-A-1",
+                "hooks lid_closed",
+                "event suspend",
+                "hardware usb_media_remap stop",
+                "hardware backlight 0",
+                "posture clamshell_docked -> suspended (suspend)",
+                "event resume",
+                "posture suspended -> clamshell_docked (resume)",
+                "hooks resume",
+            ],
+        );
+    }
+}
@@ -0,0 +1,467 @@
+use std::process::Output;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use tokio::sync::mpsc;
+
+use super::{SimMachine, TranscriptEntry, TranscriptRecord};
+use crate::hardware::duo::{PRIMARY_INTERNAL_CONNECTOR, SECONDARY_INTERNAL_CONNECTOR};
+use crate::hardware::rfkill::{RfkillControl, RfkillDevice, RfkillType};
+use crate::ipc::protocol::{SessionCommand, SessionResponse};
+use crate::models::{
+    ConnectionType, DisplayInfo, DisplayLayout, DisplayMode, DuoStatus, RefreshPolicy,
+};
+use crate::runtime::host::CommandRunner;
+use crate::runtime::policy::PolicyContext;
+use crate::runtime::probe::{self, StatusRefresh};
+use crate::runtime::radio::WirelessSwitch;
+
+const PANEL_WIDTH: u32 = 2880;
+const PANEL_HEIGHT: u32 = 1800;
+const EXTERNAL_WIDTH: u32 = 2560;
+const EXTERNAL_HEIGHT: u32 = 1440;
+const DISPLAY_BRIGHTNESS: u32 = 400;
+
+/// In-memory stand-in for the keyboard, lid, radios, displays and remap
+/// helper. The scenario runner moves it; the daemon reads and writes it
+/// through [`HardwareBackend`](crate::runtime::backend::HardwareBackend)
+/// and the simulated session agent, and every write lands in the
+/// transcript. Changes the real hardware would announce with a uevent are
+/// sent to the monitor as status refreshes.
+pub struct SimHardware {
+    machine: Mutex<Machine>,
+    transcript: Mutex<Vec<TranscriptRecord>>,
+    refreshes: Mutex<Option<mpsc::UnboundedSender<StatusRefresh>>>,
+    started: Instant,
+}
+
+struct Machine {
+    connection: ConnectionType,
+    /// A detached keyboard that reconnects over Bluetooth when the radio is on.
+    paired: bool,
+    externals: Vec<String>,
+    /// Connectors the compositor has switched on, in layout order.
+    enabled: Vec<String>,
+    wifi_blocked: bool,
+    bluetooth_blocked: bool,
+    on_ac_power: Option<bool>,
+    backlight: u8,
+    display_brightness: u32,
+    remap_running: bool,
+}
+
+impl Machine {
+    fn connected(&self, connector: &str) -> bool {
+        connector == PRIMARY_INTERNAL_CONNECTOR
+            || connector == SECONDARY_INTERNAL_CONNECTOR
+            || self.externals.iter().any(|external| external == connector)
+    }
+
+    /// Brings a paired keyboard back, or drops it, to follow the radio.
+    fn follow_bluetooth_radio(&mut self) -> bool {
+        let previous = self.connection.clone();
+        match self.connection {
+            ConnectionType::None if self.paired && !self.bluetooth_blocked => {
+                self.connection = ConnectionType::Bluetooth;
+            }
+            ConnectionType::Bluetooth if self.bluetooth_blocked => {
+                self.connection = ConnectionType::None;
+            }
+            _ => {}
+        }
+        self.connection != previous
+    }
+}
+
+impl SimHardware {
+    pub fn new(initial: &SimMachine) -> Self {
+        let externals: Vec<String> = (1..=initial.external_monitors)
+            .map(|n| format!("HDMI-A-{n}"))
+            .collect();
+        let mut enabled = vec![PRIMARY_INTERNAL_CONNECTOR.to_string()];
+        if initial.keyboard != ConnectionType::Usb {
+            enabled.push(SECONDARY_INTERNAL_CONNECTOR.to_string());
+        }
+        enabled.extend(externals.iter().cloned());
+
+        Self {
+            machine: Mutex::new(Machine {
+                paired: initial.keyboard == ConnectionType::Bluetooth,
+                connection: initial.keyboard.clone(),
+                externals,
+                enabled,
+                wifi_blocked: !initial.wifi,
+                bluetooth_blocked: !initial.bluetooth,
+                on_ac_power: initial.on_ac_power,
+                backlight: 0,
+                display_brightness: DISPLAY_BRIGHTNESS,
+                remap_running: false,
+            }),
+            transcript: Mutex::new(Vec::new()),
+            refreshes: Mutex::new(None),
+            started: Instant::now(),
+        }
+    }
+
+    /// Where hotplug refreshes go once the monitor is running.
+    pub fn connect(&self, sender: mpsc::UnboundedSender<StatusRefresh>) {
+        *self.refreshes.lock().unwrap_or_else(|e| e.into_inner()) = Some(sender);
+    }
+
+    pub fn record(&self, entry: TranscriptEntry) {
+        let at_ms = self.started.elapsed().as_millis() as u64;
+        self.transcript
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(TranscriptRecord { at_ms, entry });
+    }
+
+    pub fn transcript(&self) -> Vec<TranscriptRecord> {
+        self.transcript
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn machine(&self) -> std::sync::MutexGuard<'_, Machine> {
+        self.machine.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn announce(&self, refresh: StatusRefresh) {
+        if let Some(sender) = self
+            .refreshes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+        {
+            let _ = sender.send(refresh);
+        }
+    }
+
+    fn write(&self, write: String) {
+        self.record(TranscriptEntry::Hardware { write });
+    }
+
+    pub fn status(&self) -> DuoStatus {
+        let mut status = DuoStatus {
+            backlight_level: self.machine().backlight,
+            ..DuoStatus::default()
+        };
+        self.refresh_status(&mut status, StatusRefresh::ALL);
+        probe::apply_layout_to_status(&mut status, Some(&self.display_layout()));
+        status
+    }
+
+    pub fn refresh_status(&self, status: &mut DuoStatus, refresh: StatusRefresh) {
+        let machine = self.machine();
+        if refresh.keyboard {
+            status.keyboard_attached = probe::keyboard_attached(&machine.connection);
+            status.connection_type = machine.connection.clone();
+        }
+        if refresh.radios {
+            status.wifi_enabled = !machine.wifi_blocked;
+            status.bluetooth_enabled = !machine.bluetooth_blocked;
+        }
+        if refresh.brightness {
+            status.display_brightness = machine.display_brightness;
+            status.max_brightness = DISPLAY_BRIGHTNESS;
+        }
+    }
+
+    /// The enabled displays: the panels stacked, externals to their right.
+    pub fn display_layout(&self) -> DisplayLayout {
+        let machine = self.machine();
+        let mut external_x = PANEL_WIDTH as i32;
+        let displays = machine
+            .enabled
+            .iter()
+            .map(|connector| {
+                if connector == PRIMARY_INTERNAL_CONNECTOR {
+                    display(connector, 0, 0, PANEL_WIDTH, PANEL_HEIGHT, true)
+                } else if connector == SECONDARY_INTERNAL_CONNECTOR {
+                    display(
+                        connector,
+                        0,
+                        PANEL_HEIGHT as i32,
+                        PANEL_WIDTH,
+                        PANEL_HEIGHT,
+                        false,
+                    )
+                } else {
+                    let x = external_x;
+                    external_x += EXTERNAL_WIDTH as i32;
+                    display(connector, x, 0, EXTERNAL_WIDTH, EXTERNAL_HEIGHT, false)
+                }
+            })
+            .collect();
+        DisplayLayout { displays }
+    }
+
+    pub fn policy_context(&self) -> PolicyContext {
+        let machine = self.machine();
+        PolicyContext {
+            on_ac_power: machine.on_ac_power,
+            external_monitor: !machine.externals.is_empty(),
+        }
+    }
+
+    pub fn set_backlight(&self, level: u8) -> Result<(), String> {
+        self.machine().backlight = level;
+        self.write(format!("backlight {level}"));
+        Ok(())
+    }
+
+    pub fn set_panel_brightness(&self, percent: u8) -> Result<u32, String> {
+        let value = (DISPLAY_BRIGHTNESS * u32::from(percent.min(100)) / 100).max(1);
+        self.machine().display_brightness = value;
+        self.write(format!("panel_brightness {percent}%"));
+        Ok(value)
+    }
+
+    /// What the keyboard would answer to GET_FEATURE; nothing when it is gone.
+    pub fn read_backlight(&self) -> Result<u8, String> {
+        let machine = self.machine();
+        match machine.connection {
+            ConnectionType::None => Err("Zenbook Duo keyboard not connected".into()),
+            _ => Ok(machine.backlight),
+        }
+    }
+
+    pub fn remap_running(&self) -> bool {
+        self.machine().remap_running
+    }
+
+    pub fn set_remap_running(&self, running: bool) -> Result<(), String> {
+        let mut machine = self.machine();
+        if machine.remap_running != running {
+            machine.remap_running = running;
+            drop(machine);
+            self.write(format!(
+                "usb_media_remap {}",
+                if running { "start" } else { "stop" }
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn attach_keyboard(&self) {
+        let mut machine = self.machine();
+        machine.connection = ConnectionType::Usb;
+        machine.paired = false;
+        drop(machine);
+        self.announce(StatusRefresh {
+            keyboard: true,
+            ..StatusRefresh::default()
+        });
+    }
+
+    pub fn detach_keyboard(&self, bluetooth: bool) {
+        let mut machine = self.machine();
+        machine.connection = ConnectionType::None;
+        machine.paired = bluetooth;
+        machine.follow_bluetooth_radio();
+        drop(machine);
+        self.announce(StatusRefresh {
+            keyboard: true,
+            ..StatusRefresh::default()
+        });
+    }
+
+    /// New outputs come up enabled, as compositors do by default.
+    pub fn plug_monitor(&self) {
+        let mut machine = self.machine();
+        let connector = (1..)
+            .map(|n| format!("HDMI-A-{n}"))
+            .find(|connector| !machine.externals.contains(connector))
+            .unwrap_or_default();
+        machine.externals.push(connector.clone());
+        machine.enabled.push(connector);
+        drop(machine);
+        self.announce(StatusRefresh {
+            displays: true,
+            ..StatusRefresh::default()
+        });
+    }
+
+    pub fn unplug_monitor(&self) {
+        let mut machine = self.machine();
+        let Some(connector) = machine.externals.pop() else {
+            return;
+        };
+        machine.enabled.retain(|enabled| *enabled != connector);
+        if machine.enabled.is_empty() {
+            // The compositor falls back to the panel when nothing is left.
+            machine.enabled.push(PRIMARY_INTERNAL_CONNECTOR.to_string());
+        }
+        drop(machine);
+        self.announce(StatusRefresh {
+            displays: true,
+            ..StatusRefresh::default()
+        });
+    }
+
+    pub fn set_ac_power(&self, online: bool) {
+        self.machine().on_ac_power = Some(online);
+    }
+
+    /// What the simulated session agent answers. Layout changes are
+    /// announced like the DRM uevent a real mode set produces.
+    pub fn handle_session_command(&self, command: &SessionCommand) -> SessionResponse {
+        let enabled = match command {
+            SessionCommand::GetDisplayLayout => {
+                return SessionResponse::DisplayLayout {
+                    layout: self.display_layout(),
+                }
+            }
+            SessionCommand::SetDockMode { attached, .. } => {
+                let machine = self.machine();
+                let mut enabled = vec![PRIMARY_INTERNAL_CONNECTOR.to_string()];
+                if !attached {
+                    enabled.push(SECONDARY_INTERNAL_CONNECTOR.to_string());
+                }
+                enabled.extend(
+                    machine
+                        .enabled
+                        .iter()
+                        .filter(|connector| machine.externals.contains(connector))
+                        .cloned(),
+                );
+                enabled
+            }
+            SessionCommand::ApplyDisplayLayout { layout } => {
+                let machine = self.machine();
+                let enabled: Vec<String> = layout
+                    .displays
+                    .iter()
+                    .map(|display| display.connector.clone())
+                    .filter(|connector| machine.connected(connector))
+                    .collect();
+                if enabled.is_empty() {
+                    return SessionResponse::Error {
+                        message: "Layout enables no connected display".into(),
+                    };
+                }
+                enabled
+            }
+            SessionCommand::RunHooks { .. } => {
+                return SessionResponse::HookOutcomes {
+                    outcomes: Vec::new(),
+                }
+            }
+            SessionCommand::GetMetrics => {
+                return SessionResponse::Error {
+                    message: "Metrics are not simulated".into(),
+                }
+            }
+            SessionCommand::SetOrientation { .. }
+            | SessionCommand::ShowNotification { .. }
+            | SessionCommand::OpenEmojiPicker => return SessionResponse::Ack,
+        };
+
+        let mut machine = self.machine();
+        if machine.enabled != enabled {
+            machine.enabled = enabled;
+            drop(machine);
+            self.announce(StatusRefresh {
+                displays: true,
+                ..StatusRefresh::default()
+            });
+        }
+        SessionResponse::Ack
+    }
+}
+
+fn display(connector: &str, x: i32, y: i32, width: u32, height: u32, primary: bool) -> DisplayInfo {
+    let mode = DisplayMode {
+        mode_id: format!("{width}x{height}@60"),
+        backend_mode_id: None,
+        width,
+        height,
+        refresh_rate: 60.0,
+    };
+    DisplayInfo {
+        connector: connector.to_string(),
+        width,
+        height,
+        refresh_rate: mode.refresh_rate,
+        scale: 1.0,
+        x,
+        y,
+        transform: 0,
+        primary,
+        current_mode: mode.clone(),
+        available_modes: vec![mode],
+        refresh_policy: RefreshPolicy::Fixed,
+        supports_dynamic_refresh: false,
+    }
+}
+
+impl RfkillControl for &SimHardware {
+    fn devices(&self) -> Result<Vec<RfkillDevice>, String> {
+        let machine = self.machine();
+        Ok(vec![
+            RfkillDevice {
+                idx: 0,
+                kind: RfkillType::Wlan,
+                soft_blocked: machine.wifi_blocked,
+                hard_blocked: false,
+            },
+            RfkillDevice {
+                idx: 1,
+                kind: RfkillType::Bluetooth,
+                soft_blocked: machine.bluetooth_blocked,
+                hard_blocked: false,
+            },
+        ])
+    }
+
+    fn set_soft_blocked(&self, kind: RfkillType, blocked: bool) -> Result<(), String> {
+        let mut machine = self.machine();
+        let (label, switch) = match kind {
+            RfkillType::Wlan => ("wlan", &mut machine.wifi_blocked),
+            RfkillType::Bluetooth => ("bluetooth", &mut machine.bluetooth_blocked),
+            other => return Err(format!("No simulated rfkill switch for {other:?}")),
+        };
+        if *switch == blocked {
+            return Ok(());
+        }
+        *switch = blocked;
+        let keyboard = machine.follow_bluetooth_radio();
+        drop(machine);
+
+        self.write(format!(
+            "rfkill {label} {}",
+            if blocked { "block" } else { "unblock" }
+        ));
+        self.announce(StatusRefresh {
+            radios: true,
+            keyboard,
+            ..StatusRefresh::default()
+        });
+        Ok(())
+    }
+}
+
+/// The simulation has no NetworkManager; rfkill alone decides Wi-Fi.
+impl WirelessSwitch for &SimHardware {
+    fn wireless_enabled(&self) -> Option<bool> {
+        None
+    }
+
+    fn set_wireless_enabled(&self, _enabled: bool) -> Result<(), String> {
+        Err("NetworkManager is not simulated".into())
+    }
+}
+
+/// Nothing is executed: a command the daemon falls back to is recorded and
+/// fails, so the transcript shows which path would have shelled out.
+impl CommandRunner for SimHardware {
+    fn output(&self, program: &str, args: &[&str]) -> Result<Output, String> {
+        self.record(TranscriptEntry::Hardware {
+            write: format!("command {program} {}", args.join(" ")),
+        });
+        Err(format!(
+            "Failed to run {program}: commands are not run in simulation"
+        ))
+    }
+}
@@ -0,0 +1,204 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ipc::protocol::SessionCommand;
+use crate::models::{ConnectionType, DevicePosture, DuoSettings, HookEvent, PostureTrigger};
+use crate::runtime::policy::PolicyAction;
+
+const DEFAULT_TAIL_MS: u64 = 2000;
+
+/// A timed sequence of hardware events for `zenbook-duo-daemon --simulate`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scenario {
+    /// Daemon settings for the run; the defaults when left out.
+    #[serde(default)]
+    pub settings: DuoSettings,
+    #[serde(default)]
+    pub initial: SimMachine,
+    pub events: Vec<ScenarioStep>,
+    /// How long to keep running after the last event, so settle windows and
+    /// display retries can play out.
+    #[serde(default = "default_tail_ms")]
+    pub tail_ms: u64,
+}
+
+fn default_tail_ms() -> u64 {
+    DEFAULT_TAIL_MS
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let raw = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read scenario {}: {e}", path.display()))?;
+        Self::parse(&raw).map_err(|e| format!("{e} in {}", path.display()))
+    }
+
+    /// Parses a scenario and puts its events in time order.
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let mut scenario: Scenario =
+            serde_json::from_str(raw).map_err(|e| format!("Invalid scenario JSON: {e}"))?;
+        scenario.events.sort_by_key(|step| step.at_ms);
+        Ok(scenario)
+    }
+}
+
+/// The simulated machine when the run starts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SimMachine {
+    pub keyboard: ConnectionType,
+    pub lid_closed: bool,
+    pub external_monitors: u32,
+    pub wifi: bool,
+    pub bluetooth: bool,
+    pub on_ac_power: Option<bool>,
+}
+
+impl Default for SimMachine {
+    fn default() -> Self {
+        Self {
+            keyboard: ConnectionType::Usb,
+            lid_closed: false,
+            external_monitors: 0,
+            wifi: true,
+            bluetooth: true,
+            on_ac_power: Some(true),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScenarioStep {
+    /// Milliseconds after the run started.
+    pub at_ms: u64,
+    #[serde(flatten)]
+    pub event: ScenarioEvent,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScenarioEvent {
+    Attach,
+    /// With `bluetooth` the keyboard stays paired and reconnects over
+    /// Bluetooth whenever the radio is on.
+    Detach {
+        #[serde(default)]
+        bluetooth: bool,
+    },
+    LidClose,
+    LidOpen,
+    Suspend,
+    Resume,
+    MonitorPlug,
+    MonitorUnplug,
+    AcPower {
+        online: bool,
+    },
+}
+
+impl ScenarioEvent {
+    pub fn label(&self) -> String {
+        match self {
+            ScenarioEvent::Attach => "attach".into(),
+            ScenarioEvent::Detach { bluetooth: true } => "detach bluetooth".into(),
+            ScenarioEvent::Detach { bluetooth: false } => "detach".into(),
+            ScenarioEvent::LidClose => "lid_close".into(),
+            ScenarioEvent::LidOpen => "lid_open".into(),
+            ScenarioEvent::Suspend => "suspend".into(),
+            ScenarioEvent::Resume => "resume".into(),
+            ScenarioEvent::MonitorPlug => "monitor_plug".into(),
+            ScenarioEvent::MonitorUnplug => "monitor_unplug".into(),
+            ScenarioEvent::AcPower { online } => format!("ac_power online={online}"),
+        }
+    }
+}
+
+/// Something the daemon did, or had done to it, during a simulated run.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TranscriptEntry {
+    /// A scenario step, recorded as it is replayed.
+    Event {
+        event: ScenarioEvent,
+    },
+    Posture {
+        from: DevicePosture,
+        to: DevicePosture,
+        trigger: PostureTrigger,
+    },
+    PolicyAction {
+        action: PolicyAction,
+    },
+    SessionCommand {
+        command: SessionCommand,
+    },
+    /// A write to the simulated hardware: backlight, rfkill, remap helper.
+    Hardware {
+        write: String,
+    },
+    /// Hook scripts that would have run; the simulation never runs them.
+    Hooks {
+        event: HookEvent,
+    },
+}
+
+impl TranscriptEntry {
+    /// One line per entry, for reading a run at a glance and for tests that
+    /// only care about the order of things.
+    pub fn summary(&self) -> String {
+        match self {
+            TranscriptEntry::Event { event } => format!("event {}", event.label()),
+            TranscriptEntry::Posture { from, to, trigger } => format!(
+                "posture {} -> {} ({})",
+                from.label(),
+                to.label(),
+                trigger.label()
+            ),
+            TranscriptEntry::PolicyAction { action } => format!(
+                "policy {}",
+                match action {
+                    PolicyAction::SetWifi(enabled) => format!("set_wifi {enabled}"),
+                    PolicyAction::SetBluetooth(enabled) => format!("set_bluetooth {enabled}"),
+                    PolicyAction::SetBacklight(level) => format!("set_backlight {level}"),
+                    PolicyAction::ApplyDisplayMode { attached, .. } => {
+                        format!("apply_display_mode attached={attached}")
+                    }
+                    PolicyAction::ActivateProfile(id) => format!("activate_profile {id}"),
+                    PolicyAction::SetUsbMediaRemap(enabled) => {
+                        format!("set_usb_media_remap {enabled}")
+                    }
+                }
+            ),
+            TranscriptEntry::SessionCommand { command } => match command {
+                SessionCommand::SetDockMode { attached, .. } => {
+                    format!("session set_dock_mode attached={attached}")
+                }
+                SessionCommand::ApplyDisplayLayout { layout } => format!(
+                    "session apply_display_layout {}",
+                    layout
+                        .displays
+                        .iter()
+                        .map(|display| display.connector.as_str())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                ),
+                other => format!("session {}", other.kind()),
+            },
+            TranscriptEntry::Hardware { write } => format!("hardware {write}"),
+            TranscriptEntry::Hooks { event } => format!("hooks {}", event.label()),
+        }
+    }
+}
+
+/// A transcript line as written to `--transcript`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptRecord {
+    pub at_ms: u64,
+    #[serde(flatten)]
+    pub entry: TranscriptEntry,
+}
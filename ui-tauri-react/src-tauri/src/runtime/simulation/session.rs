@@ -0,0 +1,49 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+use super::{SimHardware, TranscriptEntry};
+use crate::ipc::protocol::{Envelope, SessionCommand, SessionResponse};
+
+/// Serves the session agent protocol on `socket`, so the daemon's display
+/// and notification paths run unchanged against the simulated compositor.
+pub(super) fn start(hardware: Arc<SimHardware>, socket: &Path) -> Result<(), String> {
+    let _ = fs::remove_file(socket);
+    let listener = UnixListener::bind(socket)
+        .map_err(|e| format!("Failed to bind simulated session socket: {e}"))?;
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(serve(stream, hardware.clone()));
+        }
+    });
+    Ok(())
+}
+
+async fn serve(stream: UnixStream, hardware: Arc<SimHardware>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let response = match serde_json::from_str::<Envelope<SessionCommand>>(&line) {
+            Ok(envelope) => {
+                let command = envelope.payload;
+                hardware.record(TranscriptEntry::SessionCommand {
+                    command: command.clone(),
+                });
+                hardware.handle_session_command(&command)
+            }
+            Err(e) => SessionResponse::Error {
+                message: format!("Invalid session command JSON: {e}"),
+            },
+        };
+        let Ok(mut reply) = serde_json::to_string(&Envelope::new(response)) else {
+            return;
+        };
+        reply.push('\n');
+        if writer.write_all(reply.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
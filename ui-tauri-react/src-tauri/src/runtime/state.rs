@@ -1,14 +1,33 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fs;
+use std::io;
+use std::path::Path;
 
 use crate::ipc::protocol::SessionBackend;
-use crate::models::{DuoSettings, DuoStatus, HardwareEvent};
-use crate::runtime::paths;
+use crate::models::{
+    DevicePosture, DuoSettings, DuoStatus, EventCategory, HardwareEvent, PostureInputs,
+    PostureTrigger,
+};
+use crate::runtime::ambient_light::AmbientLight;
+use crate::runtime::backend::HardwareBackend;
+use crate::runtime::event_bus::EventBus;
+use crate::runtime::event_journal::JournalWriter;
+use crate::runtime::keyboard_idle::KeyboardIdle;
+use crate::runtime::persistence::Persister;
+use crate::runtime::posture::{PostureAction, PostureMachine};
+use crate::runtime::schema::{self, Document};
+use crate::runtime::simulation::TranscriptEntry;
+use crate::runtime::{atomic_file, logger, paths};
+
+pub const STATE_SCHEMA_VERSION: u32 = 1;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RuntimeState {
+    #[serde(default = "state_schema_version")]
+    pub schema_version: u32,
     pub status: DuoStatus,
     pub settings: DuoSettings,
     pub session_agent: SessionAgentState,
@@ -18,29 +37,72 @@ pub struct RuntimeState {
     pub last_runtime_notification: Option<RuntimeNotificationState>,
     #[serde(default)]
     pub lid_closed: bool,
+    /// Set by a policy rule's remap action; overrides `usbMediaRemapEnabled`
+    /// until another rule changes it.
+    #[serde(default)]
+    pub usb_media_remap_policy: Option<bool>,
     pub remembered_wifi_enabled: Option<bool>,
     pub remembered_bluetooth_enabled: Option<bool>,
     pub last_updated: DateTime<Utc>,
+    /// In-memory tail of the event journal, which is the durable copy. The
+    /// state file keeps an empty list so releases that require the key can
+    /// still read it.
+    #[serde(default, serialize_with = "serialize_without_events")]
     pub recent_events: Vec<HardwareEvent>,
+    #[serde(skip)]
+    pub event_bus: EventBus,
+    #[serde(skip)]
+    pub persister: Persister,
+    #[serde(skip)]
+    pub journal: JournalWriter,
+    /// Rebuilt from the hardware on every start, so it is never persisted.
+    #[serde(skip)]
+    pub posture: PostureMachine,
+    #[serde(skip)]
+    pub hardware: HardwareBackend,
+    #[serde(skip)]
+    pub keyboard_idle: KeyboardIdle,
+    #[serde(skip)]
+    pub ambient_light: AmbientLight,
 }
 
 impl Default for RuntimeState {
     fn default() -> Self {
         Self {
+            schema_version: STATE_SCHEMA_VERSION,
             status: DuoStatus::default(),
             settings: DuoSettings::default(),
             session_agent: SessionAgentState::default(),
             usb_media_remap_reconcile: UsbMediaRemapReconcileState::default(),
             last_runtime_notification: None,
             lid_closed: false,
+            usb_media_remap_policy: None,
             remembered_wifi_enabled: None,
             remembered_bluetooth_enabled: None,
             last_updated: Utc::now(),
             recent_events: Vec::new(),
+            event_bus: EventBus::default(),
+            persister: Persister::default(),
+            journal: JournalWriter::default(),
+            posture: PostureMachine::default(),
+            hardware: HardwareBackend::default(),
+            keyboard_idle: KeyboardIdle::default(),
+            ambient_light: AmbientLight::default(),
         }
     }
 }
 
+fn serialize_without_events<S: serde::Serializer>(
+    _events: &[HardwareEvent],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(std::iter::empty::<HardwareEvent>())
+}
+
+fn state_schema_version() -> u32 {
+    STATE_SCHEMA_VERSION
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct SessionAgentState {
@@ -68,15 +130,71 @@ pub struct RuntimeNotificationState {
 pub const MAX_RECENT_EVENTS: usize = 500;
 
 impl RuntimeState {
+    /// Marks the state as modified and lets subscribers know if the status moved.
     pub fn touch(&mut self) {
         self.last_updated = Utc::now();
+        self.event_bus.publish_status(&self.status);
     }
 
     pub fn push_recent_event(&mut self, event: HardwareEvent) {
+        self.event_bus.publish_hardware_event(&event);
+        self.journal.record(&event);
         self.recent_events.push(event);
         self.trim_recent_events();
     }
 
+    fn posture_inputs(&self, suspended: bool) -> PostureInputs {
+        PostureInputs {
+            keyboard_attached: self.status.keyboard_attached,
+            connection_type: self.status.connection_type.clone(),
+            lid_closed: self.lid_closed,
+            suspended,
+        }
+    }
+
+    /// Starts the posture history over from what the hardware looks like now.
+    pub fn reset_posture(&mut self) {
+        let posture = DevicePosture::derive(&self.posture_inputs(false));
+        self.posture = PostureMachine::new(posture, Utc::now());
+    }
+
+    /// Moves the posture machine to match `status` and `lid_closed`, records
+    /// the transition and returns the exit/entry actions the caller must run.
+    pub fn sync_posture(&mut self, trigger: PostureTrigger) -> Vec<PostureAction> {
+        // Any lid or keyboard input means the machine is awake, even when the
+        // resume after a suspend never arrived.
+        let suspended = trigger == PostureTrigger::Suspend;
+        let inputs = self.posture_inputs(suspended);
+        let Some(change) = self.posture.update(&inputs, trigger, Utc::now()) else {
+            return Vec::new();
+        };
+
+        for transition in &change.transitions {
+            let message = format!(
+                "Posture changed from {} to {}",
+                transition.from.label(),
+                transition.to.label()
+            );
+            logger::info("rust-daemon", message.as_str());
+            self.push_recent_event(HardwareEvent::info(
+                EventCategory::Display,
+                message,
+                "rust-daemon",
+            ));
+            self.hardware.record(TranscriptEntry::Posture {
+                from: transition.from,
+                to: transition.to,
+                trigger: transition.trigger,
+            });
+        }
+        change.actions
+    }
+
+    pub fn publish_session_agent_change(&self) {
+        self.event_bus
+            .publish_session_agent(self.session_agent.connected, self.session_agent.backend);
+    }
+
     pub fn trim_recent_events(&mut self) {
         if self.recent_events.len() > MAX_RECENT_EVENTS {
             let overflow = self.recent_events.len() - MAX_RECENT_EVENTS;
@@ -84,21 +202,272 @@ impl RuntimeState {
         }
     }
 
+    /// Loads the persisted state, falling back to the `.bak` copy when the
+    /// main file is torn or unparsable. Any recovery is recorded as a warning
+    /// event so it shows up in the UI instead of silently resetting.
     pub fn load() -> Self {
-        fs::read_to_string(paths::state_file_path())
-            .ok()
-            .and_then(|raw| serde_json::from_str(&raw).ok())
-            .unwrap_or_default()
+        Self::load_from(&paths::state_file_path())
     }
 
     pub fn save(&self) -> Result<(), String> {
-        if let Some(parent) = paths::state_file_path().parent() {
-            fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create runtime state dir: {e}"))?;
+        self.save_to(&paths::state_file_path())
+    }
+
+    /// Schedules the state to be written by the persistence task, or saves it
+    /// right away when no task is running (e.g. before the daemon starts one).
+    pub fn persist(&self) {
+        if self.persister.mark_dirty() {
+            return;
+        }
+        if let Err(err) = self.save() {
+            logger::warn("rust-daemon", format!("failed to persist state: {err}"));
         }
-        let json = serde_json::to_string_pretty(self)
+    }
+
+    fn load_from(path: &Path) -> Self {
+        let primary_error = match read_state(path) {
+            Ok(state) => return state,
+            Err(StateReadError::Missing) => None,
+            Err(StateReadError::Corrupt(message)) => Some(message),
+        };
+
+        let backup = atomic_file::backup_path(path);
+        match (read_state(&backup), primary_error) {
+            (Ok(mut state), primary_error) => {
+                let reason = primary_error.unwrap_or_else(|| "it was missing".into());
+                state.record_recovery(format!(
+                    "Recovered runtime state from {} because {} {reason}",
+                    backup.display(),
+                    path.display()
+                ));
+                state
+            }
+            (Err(_), None) => Self::default(),
+            (Err(_), Some(primary_error)) => {
+                let mut state = Self::default();
+                state.record_recovery(format!(
+                    "Runtime state was reset because {} {primary_error} and no usable backup exists",
+                    path.display()
+                ));
+                state
+            }
+        }
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), String> {
+        Self::write_json(path, &self.to_json()?)
+    }
+
+    pub(crate) fn to_json(&self) -> Result<String, String> {
+        let mut value = serde_json::to_value(self)
             .map_err(|e| format!("Failed to serialize runtime state: {e}"))?;
-        fs::write(paths::state_file_path(), json)
+        // An idle-dimmed keyboard comes back at the user's level, not at 0.
+        if let Some(level) = self.keyboard_idle.dimmed_from() {
+            value["status"]["backlightLevel"] = level.into();
+        }
+        serde_json::to_string_pretty(&value)
+            .map_err(|e| format!("Failed to serialize runtime state: {e}"))
+    }
+
+    pub(crate) fn write_json(path: &Path, json: &str) -> Result<(), String> {
+        atomic_file::write_with_backup(path, json.as_bytes())
             .map_err(|e| format!("Failed to write runtime state: {e}"))
     }
+
+    fn record_recovery(&mut self, message: String) {
+        logger::warn("rust-daemon", message.as_str());
+        self.push_recent_event(HardwareEvent::warning(
+            EventCategory::Service,
+            message,
+            "rust-daemon",
+        ));
+    }
+}
+
+enum StateReadError {
+    Missing,
+    Corrupt(String),
+}
+
+fn read_state(path: &Path) -> Result<RuntimeState, StateReadError> {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Err(StateReadError::Missing),
+        Err(err) => return Err(StateReadError::Corrupt(format!("could not be read ({err})"))),
+    };
+    let upgraded = serde_json::from_str(&raw)
+        .map_err(|err| StateReadError::Corrupt(format!("could not be parsed ({err})")))
+        .and_then(|value| {
+            schema::upgrade(Document::RuntimeState, value).map_err(StateReadError::Corrupt)
+        })?;
+    let mut state: RuntimeState = serde_json::from_value(upgraded.value.clone())
+        .map_err(|err| StateReadError::Corrupt(format!("could not be parsed ({err})")))?;
+
+    if upgraded.is_newer(Document::RuntimeState) {
+        set_aside_newer_state(path, &raw, &upgraded.value, &mut state);
+    }
+    Ok(state)
+}
+
+/// State written by a newer daemon is the daemon's own cache, so rather than
+/// refusing to run we keep an untouched copy next to it before our next save
+/// drops the fields this release cannot represent.
+fn set_aside_newer_state(path: &Path, raw: &str, original: &Value, state: &mut RuntimeState) {
+    let reserialized = serde_json::to_value(&*state).unwrap_or(Value::Null);
+    let lost = schema::unknown_fields(original, &reserialized);
+    if lost.is_empty() {
+        return;
+    }
+
+    let copy = path.with_file_name(format!(
+        "{}.schema-v{}",
+        Document::RuntimeState.file_name(),
+        state.schema_version
+    ));
+    let message = match fs::write(&copy, raw) {
+        Ok(()) => format!(
+            "Runtime state was written by a newer release (schema version {}); kept a copy at {} because this release drops {}",
+            state.schema_version,
+            copy.display(),
+            lost.join(", ")
+        ),
+        Err(err) => format!(
+            "Runtime state was written by a newer release (schema version {}) and {} could not be preserved: {err}",
+            state.schema_version,
+            lost.join(", ")
+        ),
+    };
+    state.schema_version = STATE_SCHEMA_VERSION;
+    state.record_recovery(message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EventSeverity;
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn unique_state_path(label: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        std::env::temp_dir()
+            .join(format!("zenbook-duo-state-{label}-{nanos}"))
+            .join("state.json")
+    }
+
+    fn saved_state(path: &Path, lid_closed: bool) {
+        let state = RuntimeState {
+            lid_closed,
+            remembered_wifi_enabled: Some(true),
+            ..RuntimeState::default()
+        };
+        state.save_to(path).expect("save state");
+    }
+
+    #[test]
+    fn truncated_state_is_recovered_from_backup_with_warning() {
+        let path = unique_state_path("truncated");
+        saved_state(&path, true);
+        saved_state(&path, false);
+        fs::write(&path, "{\"status\": {").expect("truncate state");
+
+        let state = RuntimeState::load_from(&path);
+
+        assert!(state.lid_closed, "backup holds the previous save");
+        assert_eq!(state.remembered_wifi_enabled, Some(true));
+        let event = state.recent_events.last().expect("recovery event");
+        assert_eq!(event.severity, EventSeverity::Warning);
+        assert!(event.message.contains("Recovered runtime state"));
+
+        let _ = fs::remove_dir_all(path.parent().expect("state dir"));
+    }
+
+    #[test]
+    fn missing_state_without_backup_starts_fresh_silently() {
+        let path = unique_state_path("missing");
+
+        let state = RuntimeState::load_from(&path);
+
+        assert!(state.recent_events.is_empty());
+    }
+
+    #[test]
+    fn unreadable_state_without_backup_resets_with_warning() {
+        let path = unique_state_path("garbage");
+        fs::create_dir_all(path.parent().expect("state dir")).expect("create state dir");
+        fs::write(&path, "not json").expect("write garbage");
+
+        let state = RuntimeState::load_from(&path);
+
+        assert!(!state.lid_closed);
+        let event = state.recent_events.last().expect("reset event");
+        assert_eq!(event.severity, EventSeverity::Warning);
+        assert!(event.message.contains("was reset"));
+
+        let _ = fs::remove_dir_all(path.parent().expect("state dir"));
+    }
+
+    #[test]
+    fn unversioned_state_is_migrated_on_load() {
+        let path = unique_state_path("legacy");
+        fs::create_dir_all(path.parent().expect("state dir")).expect("create state dir");
+        let mut legacy = serde_json::to_value(RuntimeState {
+            lid_closed: true,
+            ..RuntimeState::default()
+        })
+        .expect("serialize state");
+        legacy
+            .as_object_mut()
+            .expect("object")
+            .remove("schemaVersion");
+        fs::write(&path, legacy.to_string()).expect("write legacy state");
+
+        let state = RuntimeState::load_from(&path);
+
+        assert!(state.lid_closed);
+        assert_eq!(state.schema_version, STATE_SCHEMA_VERSION);
+        assert!(state.recent_events.is_empty());
+
+        let _ = fs::remove_dir_all(path.parent().expect("state dir"));
+    }
+
+    #[test]
+    fn newer_state_is_copied_aside_before_fields_are_dropped() {
+        let path = unique_state_path("newer");
+        fs::create_dir_all(path.parent().expect("state dir")).expect("create state dir");
+        let mut newer = serde_json::to_value(RuntimeState::default()).expect("serialize state");
+        newer["schemaVersion"] = serde_json::json!(STATE_SCHEMA_VERSION + 1);
+        newer["postureHistory"] = serde_json::json!(["laptop"]);
+        fs::write(&path, newer.to_string()).expect("write newer state");
+
+        let state = RuntimeState::load_from(&path);
+
+        assert_eq!(state.schema_version, STATE_SCHEMA_VERSION);
+        let event = state.recent_events.last().expect("warning event");
+        assert!(event.message.contains("postureHistory"));
+        let copy = path.with_file_name(format!("state.json.schema-v{}", STATE_SCHEMA_VERSION + 1));
+        assert_eq!(
+            fs::read_to_string(copy).expect("read preserved copy"),
+            newer.to_string()
+        );
+
+        let _ = fs::remove_dir_all(path.parent().expect("state dir"));
+    }
+
+    #[test]
+    fn lid_input_after_suspend_without_resume_wakes_the_posture() {
+        let mut state = RuntimeState::default();
+        state.reset_posture();
+        state.sync_posture(PostureTrigger::Suspend);
+        assert_eq!(state.posture.current(), DevicePosture::Suspended);
+
+        state.lid_closed = true;
+        let actions = state.sync_posture(PostureTrigger::Lid);
+
+        assert_eq!(state.posture.current(), DevicePosture::Clamshell);
+        assert_eq!(actions, vec![PostureAction::ApplyClamshellLayout]);
+    }
 }
@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+use tokio::sync::mpsc;
+
+use crate::hardware::hid;
+use crate::runtime::logger;
+use crate::runtime::probe::StatusRefresh;
+
+/// Multicast group the kernel itself broadcasts uevents on. Group 2 carries
+/// udevd's re-broadcasts, which need libudev's framing and arrive later.
+const KERNEL_UEVENT_GROUP: u32 = 1;
+const RECEIVE_BUFFER_BYTES: usize = 16 * 1024;
+
+/// One kernel uevent: `ACTION@DEVPATH` followed by `KEY=VALUE` pairs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uevent {
+    pub action: String,
+    pub devpath: String,
+    pub properties: BTreeMap<String, String>,
+}
+
+impl Uevent {
+    pub fn parse(datagram: &[u8]) -> Option<Self> {
+        let mut fields = datagram
+            .split(|byte| *byte == 0)
+            .filter(|field| !field.is_empty())
+            .map(String::from_utf8_lossy);
+        let header = fields.next()?;
+        let (action, devpath) = header.split_once('@')?;
+        let properties = fields
+            .filter_map(|field| {
+                field
+                    .split_once('=')
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+            })
+            .collect();
+        Some(Self {
+            action: action.to_string(),
+            devpath: devpath.to_string(),
+            properties,
+        })
+    }
+
+    pub fn subsystem(&self) -> Option<&str> {
+        self.properties.get("SUBSYSTEM").map(String::as_str)
+    }
+
+    /// Status fields this event can change. Subsystems the monitor does not
+    /// track map to an empty refresh and are dropped.
+    pub fn refresh(&self) -> StatusRefresh {
+        match self.subsystem() {
+            // The keyboard's battery comes and goes with its Bluetooth link.
+            Some("hidraw" | "input") => StatusRefresh {
+                keyboard: true,
+                battery: true,
+                ..StatusRefresh::default()
+            },
+            Some("rfkill") => StatusRefresh {
+                radios: true,
+                ..StatusRefresh::default()
+            },
+            Some("drm") => StatusRefresh {
+                displays: true,
+                ..StatusRefresh::default()
+            },
+            Some("backlight") => StatusRefresh {
+                brightness: true,
+                ..StatusRefresh::default()
+            },
+            Some("power_supply") => StatusRefresh {
+                battery: true,
+                ..StatusRefresh::default()
+            },
+            _ => StatusRefresh::default(),
+        }
+    }
+
+    /// A HID or USB device came or went, so a cached keyboard transport may
+    /// now point at a device that no longer exists.
+    pub fn replugs_hid_device(&self) -> bool {
+        matches!(self.action.as_str(), "add" | "remove")
+            && matches!(self.subsystem(), Some("hidraw" | "usb"))
+    }
+}
+
+/// Kernel uevent netlink socket, read with blocking `recv`.
+pub struct UeventSocket {
+    fd: OwnedFd,
+}
+
+impl UeventSocket {
+    pub fn open() -> Result<Self, String> {
+        let raw = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_KOBJECT_UEVENT,
+            )
+        };
+        if raw < 0 {
+            return Err(format!(
+                "Failed to open uevent socket: {}",
+                io::Error::last_os_error()
+            ));
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        let mut address: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        address.nl_groups = KERNEL_UEVENT_GROUP;
+        let result = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                &address as *const libc::sockaddr_nl as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if result != 0 {
+            return Err(format!(
+                "Failed to bind uevent socket: {}",
+                io::Error::last_os_error()
+            ));
+        }
+        Ok(Self { fd })
+    }
+
+    pub fn receive(&self) -> io::Result<Uevent> {
+        let mut buffer = vec![0u8; RECEIVE_BUFFER_BYTES];
+        loop {
+            let read = unsafe {
+                libc::recv(
+                    self.fd.as_raw_fd(),
+                    buffer.as_mut_ptr() as *mut libc::c_void,
+                    buffer.len(),
+                    0,
+                )
+            };
+            if read < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(err);
+            }
+            if let Some(event) = Uevent::parse(&buffer[..read as usize]) {
+                return Ok(event);
+            }
+        }
+    }
+}
+
+/// Forwards the refresh each relevant uevent asks for until the receiver is
+/// dropped. Runs on its own thread because `receive` blocks.
+pub fn start(socket: UeventSocket, sender: mpsc::UnboundedSender<StatusRefresh>) {
+    let spawned = std::thread::Builder::new()
+        .name("zenbook-duo-uevents".into())
+        .spawn(move || loop {
+            match socket.receive() {
+                Ok(event) => {
+                    if event.replugs_hid_device() {
+                        hid::invalidate_transport();
+                    }
+                    let refresh = event.refresh();
+                    if refresh.is_empty() {
+                        continue;
+                    }
+                    if sender.send(refresh).is_err() {
+                        return;
+                    }
+                }
+                Err(err) => {
+                    // ENOBUFS means the kernel dropped events while we were
+                    // slow; ask for everything rather than guess what was lost.
+                    if err.raw_os_error() == Some(libc::ENOBUFS) {
+                        hid::invalidate_transport();
+                        if sender.send(StatusRefresh::ALL).is_err() {
+                            return;
+                        }
+                        continue;
+                    }
+                    logger::warn("rust-daemon", format!("uevent listener stopped: {err}"));
+                    return;
+                }
+            }
+        });
+    if let Err(err) = spawned {
+        logger::warn(
+            "rust-daemon",
+            format!("failed to spawn uevent listener: {err}"),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_kernel_uevent_datagram() {
+        let datagram = b"add@/devices/pci0000:00/0000:00:14.0/usb3/3-6/3-6:1.4/0003:0B05:1B2C.0007/hidraw/hidraw3\0\
+ACTION=add\0DEVPATH=/devices/pci0000:00/0000:00:14.0/usb3/3-6/3-6:1.4/0003:0B05:1B2C.0007/hidraw/hidraw3\0\
+SUBSYSTEM=hidraw\0MAJOR=238\0MINOR=3\0DEVNAME=hidraw3\0SEQNUM=4211\0";
+
+        let event = Uevent::parse(datagram).expect("uevent");
+        assert_eq!(event.action, "add");
+        assert!(event.devpath.ends_with("/hidraw3"));
+        assert_eq!(event.subsystem(), Some("hidraw"));
+        assert_eq!(event.properties["DEVNAME"], "hidraw3");
+    }
+
+    #[test]
+    fn rejects_datagrams_without_an_action_header() {
+        assert_eq!(Uevent::parse(b"libudev\0\xfe\xed\xca\xfe"), None);
+        assert_eq!(Uevent::parse(b""), None);
+    }
+
+    #[test]
+    fn maps_subsystems_to_the_status_fields_they_affect() {
+        let event = |subsystem: &str| {
+            Uevent::parse(format!("change@/devices/x\0SUBSYSTEM={subsystem}\0").as_bytes())
+                .expect("uevent")
+        };
+
+        assert!(event("hidraw").refresh().keyboard);
+        assert!(event("input").refresh().keyboard);
+        assert!(event("rfkill").refresh().radios);
+        assert!(event("drm").refresh().displays);
+        assert!(event("backlight").refresh().brightness);
+        assert!(event("power_supply").refresh().battery);
+        assert!(event("sound").refresh().is_empty());
+    }
+
+    #[test]
+    fn hid_hotplug_invalidates_the_keyboard_transport() {
+        let event = |action: &str, subsystem: &str| {
+            Uevent::parse(format!("{action}@/devices/x\0SUBSYSTEM={subsystem}\0").as_bytes())
+                .expect("uevent")
+        };
+
+        assert!(event("add", "hidraw").replugs_hid_device());
+        assert!(event("remove", "usb").replugs_hid_device());
+        assert!(!event("change", "hidraw").replugs_hid_device());
+        assert!(!event("add", "drm").replugs_hid_device());
+    }
+}
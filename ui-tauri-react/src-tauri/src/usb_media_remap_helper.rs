@@ -1,4 +1,3 @@
-use chrono::Local;
 use evdev::uinput::VirtualDeviceBuilder;
 use evdev::{AttributeSet, Device, EventType, InputEvent, Key};
 use nix::fcntl::{fcntl, FcntlArg, Flock, FlockArg, OFlag};
@@ -7,15 +6,22 @@ use nix::unistd::Pid;
 use signal_hook::flag;
 use std::env;
 use std::fs::{self, OpenOptions};
-use std::io::Write;
 use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, SyncSender};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::runtime::paths;
+use crate::ipc::protocol::DaemonRequest;
+use crate::models::{LogLevel, LogRecord};
+use crate::runtime::logger::LogSink;
+use crate::runtime::{client, paths};
+
+/// The daemon's idle timeout counts in seconds, so one report a second is
+/// enough to keep it from firing while someone types.
+const ACTIVITY_REPORT_INTERVAL: Duration = Duration::from_secs(1);
 
 pub fn run_from_env() -> Result<(), String> {
     run_with_args(env::args().skip(1))
@@ -100,6 +106,18 @@ where
         .map_err(|e| format!("Failed to register SIGINT handler: {e}"))?;
 
     let pause_file = base_dir.join("usb_media_remap.paused");
+    let activity = spawn_reporter(
+        "keyboard-activity",
+        DaemonRequest::KeyboardActivity,
+        "keyboard activity",
+        ACTIVITY_REPORT_INTERVAL,
+    );
+    let manual_brightness = spawn_reporter(
+        "manual-brightness",
+        DaemonRequest::ManualBrightness,
+        "manual brightness change",
+        Duration::ZERO,
+    );
 
     while !terminate.load(Ordering::Relaxed) {
         let events = match device.fetch_events() {
@@ -116,13 +134,17 @@ where
             if terminate.load(Ordering::Relaxed) {
                 break;
             }
+            if event.event_type() == EventType::KEY && event.value() == 1 {
+                // Full means a report is already on its way.
+                let _ = activity.try_send(());
+            }
             if paused {
                 // Pass through all KEY events without remapping.
                 if event.event_type() == EventType::KEY {
                     emit_key(&mut uinput, Key::new(event.code()), event.value())?;
                 }
             } else {
-                handle_event(&mut uinput, &args, event)?;
+                handle_event(&mut uinput, &args, &manual_brightness, event)?;
             }
         }
     }
@@ -132,26 +154,49 @@ where
     Ok(())
 }
 
+/// Tells the daemon about `what` from a thread of its own, so a slow or
+/// missing daemon never holds up key delivery. Each send is followed by
+/// `pause`, and anything sent meanwhile is folded into the next report.
+fn spawn_reporter(
+    name: &str,
+    request: DaemonRequest,
+    what: &'static str,
+    pause: Duration,
+) -> SyncSender<()> {
+    let (sender, receiver) = mpsc::sync_channel(1);
+    let spawned = std::thread::Builder::new()
+        .name(name.into())
+        .spawn(move || {
+            while receiver.recv().is_ok() {
+                if let Err(err) = client::request(request.clone()) {
+                    log_line(LogLevel::Debug, &format!("Failed to report {what}: {err}"));
+                }
+                std::thread::sleep(pause);
+            }
+        });
+    if let Err(err) = spawned {
+        log_error(&format!("Failed to spawn {what} reporter: {err}"));
+    }
+    sender
+}
+
 pub fn log_error(message: &str) {
     eprintln!("USB-REMAP - ERROR: {}", message);
-    log_line("ERROR", message);
+    log_line(LogLevel::Error, message);
 }
 
 pub fn log_info(message: &str) {
     eprintln!("USB-REMAP - INFO: {}", message);
-    log_line("INFO", message);
+    log_line(LogLevel::Info, message);
 }
 
-fn log_line(level: &str, message: &str) {
+fn log_line(level: LogLevel, message: &str) {
     // Best-effort: derive the log location from --pid-file (or default).
     let pid_file = pid_file_from_env_args();
     let base_dir = base_dir_from_pid_file(&pid_file);
     let _ = ensure_dir(&base_dir);
-    let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S");
-    let log_path = base_dir.join("duo.log");
-    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(log_path) {
-        let _ = writeln!(file, "{} - USB-REMAP - {}: {}", timestamp, level, message);
-    }
+    let record = LogRecord::new(level, "usb-remap", message);
+    let _ = LogSink::at(base_dir.join("duo.log")).write(&record);
 }
 
 #[derive(Debug, Clone)]
@@ -204,6 +249,7 @@ where
 fn handle_event(
     uinput: &mut evdev::uinput::VirtualDevice,
     args: &Args,
+    manual_brightness: &SyncSender<()>,
     event: InputEvent,
 ) -> Result<(), String> {
     if event.event_type() != EventType::KEY {
@@ -230,7 +276,7 @@ fn handle_event(
     }
 
     if let Some((mapped, direction)) = brightness_key_mapping(key) {
-        return handle_brightness_key(uinput, mapped, value, direction);
+        return handle_brightness_key(uinput, manual_brightness, mapped, value, direction);
     }
 
     let mapped = match key {
@@ -253,11 +299,15 @@ fn brightness_key_mapping(key: Key) -> Option<(Key, &'static str)> {
 
 fn handle_brightness_key(
     uinput: &mut evdev::uinput::VirtualDevice,
+    manual_brightness: &SyncSender<()>,
     key: Key,
     value: i32,
     direction: &str,
 ) -> Result<(), String> {
     let before = if value == 1 {
+        // Keeps the daemon's ambient light control off the panels for a
+        // while. Full means a report is already on its way.
+        let _ = manual_brightness.try_send(());
         read_primary_brightness().ok()
     } else {
         None
@@ -374,7 +424,8 @@ fn find_keyboard_device() -> Option<PathBuf> {
 }
 
 fn cycle_backlight() {
-    // Backlight state is shared with the main app via files next to the pid file.
+    // The lock and debounce stamp live next to the pid file; the level comes
+    // from the keyboard, or the daemon's state file when it cannot say.
     let pid_file = pid_file_from_env_args();
     let base_dir = base_dir_from_pid_file(&pid_file);
     if ensure_dir(&base_dir).is_err() {
@@ -382,7 +433,6 @@ fn cycle_backlight() {
     }
 
     let kbl_lock_path = base_dir.join("kb_backlight_lock");
-    let kbl_level_path = base_dir.join("kb_backlight_level");
     let kbl_last_cycle_path = base_dir.join("kb_backlight_last_cycle");
 
     let lock = match OpenOptions::new()
@@ -412,14 +462,10 @@ fn cycle_backlight() {
 
     let _ = fs::write(&kbl_last_cycle_path, now.to_string());
 
-    let level = fs::read_to_string(&kbl_level_path)
-        .ok()
-        .and_then(|s| s.trim().parse::<u8>().ok())
-        .unwrap_or(0);
+    let level = crate::hardware::hid::read_backlight()
+        .unwrap_or_else(|_| crate::hardware::sysfs::read_backlight_level());
     let next = (level + 1) % 4;
-    if crate::commands::backlight::set_backlight_daemon_first(next).is_ok() {
-        let _ = fs::write(&kbl_level_path, next.to_string());
-    }
+    let _ = crate::commands::backlight::set_backlight_daemon_first(next);
 }
 
 fn open_emoji_picker(user: Option<&str>) {
@@ -0,0 +1,53 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::events::{push_event, EventBuffer};
+use crate::ipc::protocol::DaemonEvent;
+use crate::runtime::client;
+
+static STREAM_CONNECTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the push-based daemon stream is live; the file watcher only falls
+/// back to `state.json` change notifications while it is not.
+pub fn stream_connected() -> bool {
+    STREAM_CONNECTED.load(Ordering::Relaxed)
+}
+
+pub fn start(app: AppHandle, buffer: EventBuffer) {
+    std::thread::spawn(move || loop {
+        match client::subscribe(Vec::new()) {
+            Ok(subscription) => {
+                STREAM_CONNECTED.store(true, Ordering::Relaxed);
+                for event in subscription {
+                    match event {
+                        Ok(event) => forward_event(&app, &buffer, event),
+                        Err(err) => {
+                            log::warn!("daemon event stream error: {err}");
+                            break;
+                        }
+                    }
+                }
+                STREAM_CONNECTED.store(false, Ordering::Relaxed);
+            }
+            Err(err) => {
+                log::debug!("daemon event stream unavailable: {err}");
+            }
+        }
+
+        std::thread::sleep(Duration::from_secs(5));
+    });
+}
+
+fn forward_event(app: &AppHandle, buffer: &EventBuffer, event: DaemonEvent) {
+    match event {
+        DaemonEvent::StatusChanged { .. } | DaemonEvent::SessionAgentChanged { .. } => {
+            let _ = app.emit("duo://status-changed", ());
+        }
+        DaemonEvent::HardwareEvent { event } => {
+            push_event(buffer, event);
+            let _ = app.emit("duo://hardware-event", ());
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use tauri::{AppHandle, Emitter};
 
+use super::daemon_events;
 use crate::commands::events::{push_event, EventBuffer};
 use crate::models::{EventCategory, HardwareEvent};
 use crate::runtime::paths;
@@ -43,7 +44,7 @@ pub fn start(app: AppHandle, buffer: EventBuffer) {
                             .unwrap_or_default();
 
                         match filename.as_str() {
-                            "state.json" => {
+                            "state.json" if !daemon_events::stream_connected() => {
                                 let _ = app.emit("duo://status-changed", ());
                                 let hw_event = HardwareEvent::info(
                                     EventCategory::Service,
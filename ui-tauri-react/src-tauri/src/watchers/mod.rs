@@ -1,9 +1,10 @@
+pub mod daemon_events;
 pub mod file_watcher;
 
 use crate::commands::events::EventBuffer;
 use tauri::AppHandle;
 
 pub fn start_all_watchers(app: &AppHandle, buffer: EventBuffer) {
-    file_watcher::start(app.clone(), buffer.clone());
-    let _ = buffer;
+    daemon_events::start(app.clone(), buffer.clone());
+    file_watcher::start(app.clone(), buffer);
 }
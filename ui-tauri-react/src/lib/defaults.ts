@@ -12,6 +12,8 @@ export const DEFAULT_DUO_STATUS: DuoStatus = {
   maxBrightness: 1,
   serviceActive: false,
   orientation: "normal",
+  keyboardBatteryPercent: null,
+  keyboardBatteryCharging: null,
 };
 
 export const DEFAULT_DUO_SETTINGS: DuoSettings = {
@@ -26,6 +28,43 @@ export const DEFAULT_DUO_SETTINGS: DuoSettings = {
   setupCompleted: false,
   touchscreenDisabled: [],
   savedDisplayLayout: null,
+  attachSettleMs: 1000,
+  policyRules: [
+    {
+      name: "Restore docked state",
+      trigger: "attach",
+      actions: [
+        { type: "wifi", state: "restore" },
+        { type: "bluetooth", state: "restore" },
+        { type: "backlight" },
+        { type: "display_mode" },
+      ],
+    },
+    {
+      name: "Keep the keyboard reachable",
+      trigger: "detach",
+      actions: [{ type: "bluetooth", state: "on" }, { type: "display_mode" }],
+    },
+  ],
+  hookTimeoutMs: 10000,
+  keyboardBatteryThresholds: [20, 10],
+  backlightIdleTimeoutUsbSecs: 0,
+  backlightIdleTimeoutBluetoothSecs: 60,
+  ambientLightEnabled: false,
+  ambientBrightnessCurve: [
+    { lux: 0, value: 10 },
+    { lux: 50, value: 30 },
+    { lux: 300, value: 60 },
+    { lux: 1000, value: 85 },
+    { lux: 5000, value: 100 },
+  ],
+  ambientKeyboardCurve: [
+    { lux: 0, value: 3 },
+    { lux: 10, value: 2 },
+    { lux: 60, value: 1 },
+    { lux: 250, value: 0 },
+  ],
+  ambientOverrideCooldownSecs: 300,
 };
 
 export function withDuoSettingsDefaults(settings: Partial<DuoSettings>): DuoSettings {
@@ -36,5 +75,11 @@ export function withDuoSettingsDefaults(settings: Partial<DuoSettings>): DuoSett
     invertSensorRotation: settings.invertSensorRotation ?? DEFAULT_DUO_SETTINGS.invertSensorRotation,
     touchscreenDisabled: settings.touchscreenDisabled ?? DEFAULT_DUO_SETTINGS.touchscreenDisabled,
     savedDisplayLayout: settings.savedDisplayLayout ?? DEFAULT_DUO_SETTINGS.savedDisplayLayout,
+    policyRules: settings.policyRules ?? DEFAULT_DUO_SETTINGS.policyRules,
+    keyboardBatteryThresholds:
+      settings.keyboardBatteryThresholds ?? DEFAULT_DUO_SETTINGS.keyboardBatteryThresholds,
+    ambientBrightnessCurve:
+      settings.ambientBrightnessCurve ?? DEFAULT_DUO_SETTINGS.ambientBrightnessCurve,
+    ambientKeyboardCurve: settings.ambientKeyboardCurve ?? DEFAULT_DUO_SETTINGS.ambientKeyboardCurve,
   };
 }
@@ -56,6 +56,8 @@ const initialState: AppState = {
     appProtocolVersion: 0,
     daemonVersion: null,
     daemonProtocolVersion: null,
+    negotiatedProtocolVersion: null,
+    unavailableFeatures: [],
     serviceAvailable: false,
   },
   loading: true,
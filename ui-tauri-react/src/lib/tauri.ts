@@ -17,10 +17,15 @@ import type {
   UsbMediaRemapStatus,
   TouchscreenDevice,
   VersionInfo,
+  LogQuery,
+  LogRecord,
+  MetricsSnapshot,
+  PostureReport,
 } from "@/types/duo";
 
 // Status
 export const getStatus = () => invoke<DuoStatus>("get_status");
+export const getPosture = () => invoke<PostureReport>("get_posture");
 
 // Backlight
 export const getBacklight = () => invoke<number>("get_backlight");
@@ -39,6 +44,7 @@ export const setOrientation = (orientation: Orientation) =>
 export const isServiceActive = () => invoke<boolean>("is_service_active");
 export const getVersionInfo = () => invoke<VersionInfo>("get_version_info");
 export const restartService = () => invoke<void>("restart_service");
+export const getMetrics = () => invoke<MetricsSnapshot>("get_metrics");
 
 // Settings
 export const loadSettings = () => invoke<DuoSettings>("load_settings");
@@ -70,6 +76,8 @@ export const saveDisplayLayoutPreference = (layout: DisplayLayout) =>
 export const readLog = (lines: number) =>
   invoke<string[]>("read_log", { lines });
 export const clearLog = () => invoke<void>("clear_log");
+export const queryLogs = (query: LogQuery) =>
+  invoke<LogRecord[]>("query_logs", { query });
 
 // Profiles
 export const listProfiles = () => invoke<Profile[]>("list_profiles");
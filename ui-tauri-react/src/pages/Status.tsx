@@ -97,6 +97,14 @@ export default function Status() {
                 </span>
               </div>
             </StatusRow>
+            {store.status.keyboardBatteryPercent != null && (
+              <StatusRow label="Battery">
+                <span className="font-mono text-xs tabular-nums">
+                  {store.status.keyboardBatteryPercent}%
+                  {store.status.keyboardBatteryCharging && " (charging)"}
+                </span>
+              </StatusRow>
+            )}
             <StatusRow label="Backlight">
               <div className="flex items-center gap-2">
                 <div className="flex gap-[3px]">
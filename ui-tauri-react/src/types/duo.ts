@@ -16,6 +16,8 @@ export interface DuoStatus {
   maxBrightness: number;
   serviceActive: boolean;
   orientation: Orientation;
+  keyboardBatteryPercent: number | null;
+  keyboardBatteryCharging: boolean | null;
 }
 
 export interface DisplayInfo {
@@ -47,6 +49,7 @@ export interface DisplayMode {
 }
 
 export interface DuoSettings {
+  schemaVersion?: number;
   defaultBacklight: number;
   defaultScale: number;
   autoDualScreen: boolean;
@@ -58,6 +61,49 @@ export interface DuoSettings {
   setupCompleted: boolean;
   touchscreenDisabled: string[];
   savedDisplayLayout: DisplayLayout | null;
+  attachSettleMs: number;
+  policyRules: PolicyRule[];
+  hookTimeoutMs: number;
+  keyboardBatteryThresholds: number[];
+  backlightIdleTimeoutUsbSecs: number;
+  backlightIdleTimeoutBluetoothSecs: number;
+  ambientLightEnabled: boolean;
+  ambientBrightnessCurve: LightCurvePoint[];
+  ambientKeyboardCurve: LightCurvePoint[];
+  ambientOverrideCooldownSecs: number;
+}
+
+export interface LightCurvePoint {
+  lux: number;
+  value: number;
+}
+
+export type PolicyTrigger = "attach" | "detach" | "lid" | "resume";
+
+export interface PolicyConditions {
+  connectionType?: ConnectionType;
+  onAcPower?: boolean;
+  externalMonitor?: boolean;
+  lidClosed?: boolean;
+  wifiEnabled?: boolean;
+  bluetoothEnabled?: boolean;
+}
+
+export type RadioSetting = "on" | "off" | "restore";
+
+export type PolicyRuleAction =
+  | { type: "wifi"; state: RadioSetting }
+  | { type: "bluetooth"; state: RadioSetting }
+  | { type: "backlight"; level?: number }
+  | { type: "display_mode" }
+  | { type: "profile"; id: string }
+  | { type: "remap"; enabled: boolean };
+
+export interface PolicyRule {
+  name?: string;
+  trigger: PolicyTrigger;
+  when?: PolicyConditions;
+  actions: PolicyRuleAction[];
 }
 
 export interface TouchscreenDevice {
@@ -78,9 +124,24 @@ export interface VersionInfo {
   appProtocolVersion: number;
   daemonVersion?: string | null;
   daemonProtocolVersion?: number | null;
+  negotiatedProtocolVersion?: number | null;
+  unavailableFeatures?: ProtocolFeature[];
   serviceAvailable: boolean;
 }
 
+export type ProtocolFeature =
+  | "handshake"
+  | "event_subscription"
+  | "pipelining"
+  | "event_journal"
+  | "structured_logs"
+  | "log_query"
+  | "metrics"
+  | "posture"
+  | "keyboard_activity"
+  | "ambient_light"
+  | "remap_pause";
+
 export interface Profile {
   id: string;
   name: string;
@@ -99,6 +160,69 @@ export interface HardwareEvent {
   source: string;
 }
 
+export type LogLevel = "debug" | "info" | "warn" | "error";
+
+export interface LogRecord {
+  timestamp?: string | null;
+  level: LogLevel;
+  component: string;
+  message: string;
+  fields?: Record<string, string>;
+}
+
+export interface LogQuery {
+  since?: string;
+  until?: string;
+  components?: string[];
+  minLevel?: LogLevel;
+  contains?: string;
+  pattern?: string;
+  limit?: number;
+}
+
+export interface CounterSample {
+  name: string;
+  labels: Record<string, string>;
+  value: number;
+}
+
+/** `bucketCounts[i]` counts observations in the i-th latency bucket (seconds). */
+export interface HistogramSample {
+  name: string;
+  labels: Record<string, string>;
+  bucketCounts: number[];
+  count: number;
+  sumSeconds: number;
+}
+
+export interface MetricsSnapshot {
+  counters: CounterSample[];
+  histograms: HistogramSample[];
+}
+
+export type DevicePosture =
+  | "docked"
+  | "detached_bluetooth"
+  | "detached_no_keyboard"
+  | "clamshell"
+  | "clamshell_docked"
+  | "suspended";
+
+export type PostureTrigger = "startup" | "keyboard" | "lid" | "suspend" | "resume";
+
+export interface PostureTransition {
+  from: DevicePosture;
+  to: DevicePosture;
+  trigger: PostureTrigger;
+  at: string;
+}
+
+export interface PostureReport {
+  posture: DevicePosture;
+  since: string;
+  history: PostureTransition[];
+}
+
 // Diagnostics
 export interface EvdevDevice {
   eventPath: string;
@@ -112,6 +112,10 @@ sudo rm -f /etc/systemd/user/zenbook-duo-session-agent.service
 sudo rm -f /usr/lib/systemd/system-sleep/duo
 sudo rm -f /usr/lib/systemd/system-sleep/zenbook-duo-rust-lifecycle
 
+# Remove D-Bus service policy
+sudo rm -f /usr/share/dbus-1/system.d/org.zenbook.Duo1.conf
+sudo rm -f /usr/share/polkit-1/actions/org.zenbook.Duo1.policy
+
 # Reload systemd
 sudo systemctl daemon-reload
 run_user_systemctl_best_effort daemon-reload
@@ -145,6 +149,7 @@ fi
 # ============================================================================
 
 sudo rm -f /usr/local/bin/duo
+sudo rm -f /usr/local/bin/zenbook-duoctl
 sudo rm -rf /usr/local/libexec/zenbook-duo
 sudo rm -rf /var/lib/zenbook-duo
 rm -rf /tmp/duo